@@ -0,0 +1,172 @@
+use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime};
+
+use http::response::Parts;
+use http::{Request, Uri};
+use hyper::body::Bytes;
+use hyper::Body;
+
+use crate::connection::{HttpProtocol, ReWrkConnection};
+use crate::runtime::{create_connector, Error};
+
+#[derive(Debug, thiserror::Error)]
+/// A [BenchConnection] could not be established.
+pub enum BenchConnectionError {
+    #[error("The target URI is invalid: {0}")]
+    /// The target URI is invalid or could not be resolved.
+    InvalidTarget(#[from] Error),
+    #[error("Failed to connect to {addr}: {error}")]
+    /// The connection, TLS or HTTP handshake failed.
+    Connect {
+        /// The resolved address of the target.
+        addr: SocketAddr,
+        /// The error which caused the failure.
+        error: anyhow::Error,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The number of bytes transferred over a connection.
+pub struct IoCounters {
+    /// The number of bytes read from the connection.
+    pub read: u64,
+    /// The number of bytes written to the connection.
+    pub written: u64,
+}
+
+#[derive(Debug)]
+/// A response to a request sent on a [BenchConnection].
+pub struct TimedResponse {
+    /// The head of the response.
+    pub head: Parts,
+    /// The full response body.
+    pub body: Bytes,
+    /// The time the request was sent.
+    pub timestamp: SystemTime,
+    /// The time taken to send the request and read the full response.
+    ///
+    /// This excludes [Self::client_backpressure].
+    pub latency: Duration,
+    /// The time spent waiting for the connection to be ready to send
+    /// the request, i.e. for a HTTP/2 stream to become available.
+    pub client_backpressure: Duration,
+    /// The bytes transferred over the socket by the request and response.
+    ///
+    /// With HTTP/2 these are estimated from the size of the stream's frames.
+    pub io: IoCounters,
+    /// The bytes of the request and response before TLS encryption.
+    ///
+    /// For plain HTTP connections this is the same as [Self::io], the
+    /// difference between them is the overhead added by TLS.
+    pub plaintext_io: IoCounters,
+}
+
+/// A single benchmarking connection which can be used without the
+/// benchmark workers or a [Producer](crate::Producer).
+///
+/// Requests are timed the same way as they are in a benchmark,
+/// which makes this useful for one-off probes like synthetic monitoring.
+///
+/// ```no_run
+/// use http::{Request, Uri};
+/// use hyper::Body;
+/// use rewrk_core::{BenchConnection, HttpProtocol};
+///
+/// # async fn probe() -> anyhow::Result<()> {
+/// let uri = Uri::from_static("http://127.0.0.1:8080/");
+/// let mut conn = BenchConnection::connect(uri.clone(), HttpProtocol::HTTP1).await?;
+///
+/// let request = Request::builder().uri(uri).body(Body::empty())?;
+/// let response = conn.send(request).await?;
+/// println!("{} in {:?}", response.head.status, response.latency);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BenchConnection {
+    addr: SocketAddr,
+    conn: ReWrkConnection,
+    connect_latency: Duration,
+}
+
+impl IoCounters {
+    /// The counters which have been added since `earlier`.
+    pub(crate) fn since(self, earlier: IoCounters) -> IoCounters {
+        IoCounters {
+            read: self.read.saturating_sub(earlier.read),
+            written: self.written.saturating_sub(earlier.written),
+        }
+    }
+}
+
+impl BenchConnection {
+    /// Connect to the given URI using the given protocol.
+    ///
+    /// The connection is sent the same default headers as a benchmark.
+    pub async fn connect(
+        uri: Uri,
+        protocol: HttpProtocol,
+    ) -> Result<Self, BenchConnectionError> {
+        let connector = create_connector(uri, protocol)?;
+        let addr = connector.addr();
+
+        let start = Instant::now();
+        let conn = connector
+            .connect()
+            .await
+            .map_err(|error| BenchConnectionError::Connect { addr, error })?;
+
+        Ok(Self {
+            addr,
+            conn,
+            connect_latency: start.elapsed(),
+        })
+    }
+
+    /// The resolved address of the target.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The time taken to establish the connection, including
+    /// the TLS and HTTP handshakes.
+    pub fn connect_latency(&self) -> Duration {
+        self.connect_latency
+    }
+
+    /// The total number of bytes transferred over the connection's socket.
+    pub fn io_counters(&self) -> IoCounters {
+        let usage = self.conn.usage();
+        IoCounters {
+            read: usage.get_received_count(),
+            written: usage.get_written_count(),
+        }
+    }
+
+    /// Send a request and read the full response.
+    ///
+    /// The request host, scheme and port are replaced with the target's.
+    pub async fn send(
+        &mut self,
+        request: Request<Body>,
+    ) -> Result<TimedResponse, hyper::Error> {
+        let client_backpressure = self.conn.ready().await?;
+        let marker = self.conn.io_marker();
+        let timestamp = SystemTime::now();
+        let start = Instant::now();
+
+        let (head, body) = self.conn.execute_req(request).await?;
+
+        let latency = start.elapsed();
+        let io = self.conn.take_request_io(marker);
+
+        Ok(TimedResponse {
+            head,
+            body,
+            timestamp,
+            latency,
+            client_backpressure,
+            io: io.socket,
+            plaintext_io: io.plaintext,
+        })
+    }
+}
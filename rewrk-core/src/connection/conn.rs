@@ -1,33 +1,86 @@
-use std::future::Future;
 use std::net::SocketAddr;
+use std::num::NonZeroU32;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{ready, Context, Poll};
+use std::time::SystemTime;
 
+use futures_util::Stream;
+use http::header::HeaderName;
 use http::response::Parts;
-use http::{header, HeaderValue, Request, Response, Uri};
-use hyper::body::Bytes;
+use http::{header, HeaderMap, HeaderValue, Request, Uri};
+use hyper::body::{Bytes, HttpBody};
 use hyper::client::conn;
-use hyper::client::conn::SendRequest;
+use hyper::client::conn::{ResponseFuture, SendRequest};
 use hyper::Body;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 use tokio::task::JoinHandle;
-use tokio::time::{timeout_at, Duration, Instant};
+use tokio::time::{timeout, timeout_at, Duration, Instant};
 
-use crate::connection::{HttpProtocol, Scheme};
-use crate::utils::IoUsageTracker;
+use crate::connection::{HttpProtocol, IoCounters, RequestTemplate, Scheme, Transport};
+use crate::recording::ConnectPhases;
+use crate::retry::Backoff;
+use crate::utils::{IoUsageTracker, RateLimiter};
+use crate::validator::{BodyMode, ResponseValidator, ValidationError};
 
 /// The maximum number of attempts to try connect before aborting.
 const RETRY_MAX_DEFAULT: usize = 3;
+/// The default time allowed to open the TCP stream or custom transport.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The default time allowed to complete the TLS and HTTP handshakes.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The default backoff between connect attempts.
+pub const DEFAULT_CONNECT_BACKOFF: Backoff = Backoff::Fixed(Duration::from_millis(500));
+/// The default `User-Agent` header sent with every request.
+const DEFAULT_USER_AGENT: &str = concat!("rewrk-core/", env!("CARGO_PKG_VERSION"));
+/// The size of a HTTP/2 frame header.
+const H2_FRAME_HEADER_SIZE: u64 = 9;
+/// The default maximum size of a HTTP/2 frame payload.
+const H2_MAX_FRAME_SIZE: u64 = 16_384;
+
+#[derive(Debug, thiserror::Error)]
+/// A phase of establishing a connection did not complete in time.
+pub enum ConnectError {
+    #[error("Timed out opening the connection after {0:?}")]
+    /// The TCP stream or custom transport was not opened within the connect timeout.
+    ConnectTimeout(Duration),
+    #[error("Timed out completing the TLS and HTTP handshakes after {0:?}")]
+    /// The TLS or HTTP handshake did not complete within the handshake timeout.
+    HandshakeTimeout(Duration),
+}
+
+/// A single completed attempt to connect, successful or not.
+pub(crate) struct ConnectAttempt<'a> {
+    /// The wall clock time the attempt started.
+    pub timestamp: SystemTime,
+    /// The time the attempt took.
+    pub duration: Duration,
+    /// The time spent in each phase of the attempt.
+    pub phases: ConnectPhases,
+    /// The error which failed the attempt, if any.
+    pub error: Option<&'a anyhow::Error>,
+}
 
 #[derive(Clone)]
 /// The initial HTTP connector for benchmarking.
 pub struct ReWrkConnector {
     uri: Uri,
     host_header: HeaderValue,
-    addr: SocketAddr,
+    addrs: Arc<[SocketAddr]>,
+    next_addr: Arc<AtomicUsize>,
     protocol: HttpProtocol,
     scheme: Scheme,
     host: String,
     retry_max: usize,
+    connect_timeout: Duration,
+    handshake_timeout: Duration,
+    connect_backoff: Backoff,
+    default_headers: HeaderMap,
+    max_connect_rate: Option<u32>,
+    connect_limiter: Option<RateLimiter>,
+    transport: Option<Arc<dyn Transport>>,
 }
 
 impl ReWrkConnector {
@@ -43,33 +96,220 @@ impl ReWrkConnector {
         Self {
             uri,
             host_header,
-            addr,
+            addrs: Arc::new([addr]),
+            next_addr: Arc::default(),
             protocol,
             scheme,
             host: host.into(),
             retry_max: RETRY_MAX_DEFAULT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            connect_backoff: DEFAULT_CONNECT_BACKOFF,
+            default_headers: default_headers(),
+            max_connect_rate: None,
+            connect_limiter: None,
+            transport: None,
         }
     }
 
+    /// Set the maximum number of new connections which can be
+    /// established per second.
+    ///
+    /// The limit is shared between all clones of the connector.
+    pub fn set_max_connect_rate(&mut self, conns_per_sec: NonZeroU32) {
+        self.max_connect_rate = Some(conns_per_sec.get());
+        self.connect_limiter = Some(RateLimiter::per_second(conns_per_sec));
+    }
+
+    /// The maximum number of new connections established per second, if limited.
+    pub fn max_connect_rate(&self) -> Option<u32> {
+        self.max_connect_rate
+    }
+
+    /// Set the transport new connections are established over.
+    ///
+    /// By default connections are made over TCP to the resolved address.
+    pub fn set_transport(&mut self, transport: Arc<dyn Transport>) {
+        self.transport = Some(transport);
+    }
+
+    /// Set the addresses of the server new connections are made to.
+    ///
+    /// Each new connection is made to the next address in turn, so the
+    /// connections are spread evenly across every address the host resolves
+    /// to. The rotation is shared between all clones of the connector.
+    ///
+    /// # Panics
+    ///
+    /// If no addresses are given.
+    pub fn set_addrs(&mut self, addrs: Vec<SocketAddr>) {
+        assert!(
+            !addrs.is_empty(),
+            "The connector needs at least one address"
+        );
+        self.addrs = addrs.into();
+    }
+
+    /// Set the `Host` header sent with every request.
+    pub fn set_host_header(&mut self, host: HeaderValue) {
+        self.host_header = host;
+    }
+
+    /// Set a header which is added to every request.
+    ///
+    /// Headers already set on the request by the producer take priority
+    /// over the default headers.
+    pub fn set_default_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.default_headers.insert(name, value);
+    }
+
+    /// Removes a header from the set of default headers.
+    pub fn remove_default_header(&mut self, name: &HeaderName) {
+        self.default_headers.remove(name);
+    }
+
+    /// The headers which are added to every request.
+    pub fn default_headers(&self) -> &HeaderMap {
+        &self.default_headers
+    }
+
+    /// The base URI of the connector.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// The HTTP protocol used by the connector.
+    pub fn protocol(&self) -> HttpProtocol {
+        self.protocol
+    }
+
+    /// The socket address of the server the next connection is made to.
+    pub fn addr(&self) -> SocketAddr {
+        let next = self.next_addr.load(Ordering::Relaxed);
+        self.addrs[next % self.addrs.len()]
+    }
+
+    /// Takes the address of a new connection, rotating through the addresses.
+    fn take_addr(&self) -> SocketAddr {
+        let next = self.next_addr.fetch_add(1, Ordering::Relaxed);
+        self.addrs[next % self.addrs.len()]
+    }
+
     /// Set a new max retry attempt.
     pub fn set_retry_max(&mut self, max: usize) {
         self.retry_max = max;
     }
 
+    /// The maximum number of connect retry attempts.
+    pub fn retry_max(&self) -> usize {
+        self.retry_max
+    }
+
+    /// Set the time allowed to open the TCP stream, or the custom transport,
+    /// on each connect attempt.
+    ///
+    /// Attempts which take longer fail with [ConnectError::ConnectTimeout].
+    pub fn set_connect_timeout(&mut self, dur: Duration) {
+        self.connect_timeout = dur;
+    }
+
+    /// The time allowed to open the TCP stream on each connect attempt.
+    pub fn connect_timeout_duration(&self) -> Duration {
+        self.connect_timeout
+    }
+
+    /// Set the time allowed to complete the TLS and HTTP handshakes
+    /// on each connect attempt.
+    ///
+    /// Attempts which take longer fail with [ConnectError::HandshakeTimeout].
+    pub fn set_handshake_timeout(&mut self, dur: Duration) {
+        self.handshake_timeout = dur;
+    }
+
+    /// The time allowed to complete the TLS and HTTP handshakes on each connect attempt.
+    pub fn handshake_timeout(&self) -> Duration {
+        self.handshake_timeout
+    }
+
+    /// Set the backoff between failed connect attempts.
+    pub fn set_connect_backoff(&mut self, backoff: Backoff) {
+        self.connect_backoff = backoff;
+    }
+
+    /// The backoff between failed connect attempts.
+    pub fn connect_backoff(&self) -> Backoff {
+        self.connect_backoff
+    }
+
     /// Establish a new connection using the given connector.
     ///
     /// This will attempt to connect to the URI within the given duration.
     /// If the timeout elapses, `None` is returned.
+    ///
+    /// If a connect rate limit is set, the time spent waiting for the
+    /// limiter is not counted towards the timeout.
     pub async fn connect_timeout(
         &self,
         dur: Duration,
     ) -> anyhow::Result<Option<ReWrkConnection>> {
-        let deadline = Instant::now() + dur;
+        self.connect_timeout_with_phases(dur, &mut ConnectPhases::default())
+            .await
+    }
+
+    /// Attempts to connect to the URI within the given duration,
+    /// timing each phase of the last attempt.
+    pub(crate) async fn connect_timeout_with_phases(
+        &self,
+        dur: Duration,
+        phases: &mut ConnectPhases,
+    ) -> anyhow::Result<Option<ReWrkConnection>> {
+        self.connect_until(Some(Instant::now() + dur), phases, |_| {})
+            .await
+    }
+
+    /// Attempts to connect to the URI, retrying failed attempts,
+    /// timing each phase of the last attempt.
+    ///
+    /// Each attempt is bounded by the connect and handshake timeouts and
+    /// passed to `on_attempt` once it completes.
+    pub(crate) async fn connect_with_retries(
+        &self,
+        phases: &mut ConnectPhases,
+        on_attempt: impl FnMut(ConnectAttempt),
+    ) -> anyhow::Result<ReWrkConnection> {
+        let connection = self.connect_until(None, phases, on_attempt).await?;
+        Ok(connection.expect("Connecting without a deadline always completes"))
+    }
+
+    async fn connect_until(
+        &self,
+        deadline: Option<Instant>,
+        phases: &mut ConnectPhases,
+        mut on_attempt: impl FnMut(ConnectAttempt),
+    ) -> anyhow::Result<Option<ReWrkConnection>> {
+        self.wait_for_connect_slot().await;
+
         let mut last_error: Option<anyhow::Error> = None;
         let mut attempts_left = self.retry_max;
 
         loop {
-            let result = timeout_at(deadline, self.connect()).await;
+            phases.start_attempt();
+            let timestamp = SystemTime::now();
+            let start = Instant::now();
+            let attempt = self.connect_with_phases(phases);
+            let result = match deadline {
+                Some(deadline) => timeout_at(deadline, attempt).await,
+                None => Ok(attempt.await),
+            };
+
+            if let Ok(ref result) = result {
+                on_attempt(ConnectAttempt {
+                    timestamp,
+                    duration: start.elapsed(),
+                    phases: *phases,
+                    error: result.as_ref().err(),
+                });
+            }
 
             match result {
                 Err(_) => {
@@ -86,101 +326,533 @@ impl ReWrkConnector {
 
                     attempts_left -= 1;
                     last_error = Some(e);
-                    tokio::time::sleep(Duration::from_millis(500)).await;
+                    let attempts = phases.attempts as u32;
+                    tokio::time::sleep(self.connect_backoff.delay(attempts)).await;
+                    self.wait_for_connect_slot().await;
                 },
                 Ok(Ok(connection)) => return Ok(Some(connection)),
             }
         }
     }
 
+    /// Waits for the connect rate limiter if one is set.
+    async fn wait_for_connect_slot(&self) {
+        if let Some(limiter) = self.connect_limiter.as_ref() {
+            limiter.acquire().await;
+        }
+    }
+
     /// Establish a new connection using the given connector.
     ///
-    /// This method has no timeout and will block until the connection
-    /// is established.
+    /// This makes a single attempt, which fails if opening the connection or
+    /// the handshakes exceed the connect or handshake timeouts respectively.
     pub async fn connect(&self) -> anyhow::Result<ReWrkConnection> {
+        self.connect_with_phases(&mut ConnectPhases::default())
+            .await
+    }
+
+    async fn connect_with_phases(
+        &self,
+        phases: &mut ConnectPhases,
+    ) -> anyhow::Result<ReWrkConnection> {
         let mut conn_builder = conn::Builder::new();
 
         if self.protocol.is_http2() {
             conn_builder.http2_only(true);
         }
 
-        let stream = TcpStream::connect(self.addr).await?;
+        let start = Instant::now();
+        match self.transport.as_ref() {
+            Some(transport) => {
+                let stream = timeout(self.connect_timeout, transport.connect())
+                    .await
+                    .map_err(|_| self.connect_timed_out())??;
+                phases.tcp = Some(start.elapsed());
+                self.establish_timeout(conn_builder, stream, phases).await
+            },
+            None => {
+                let addr = self.take_addr();
+                phases.peer_addr = Some(addr);
+                let connect = TcpStream::connect(addr);
+                let stream = timeout(self.connect_timeout, connect)
+                    .await
+                    .map_err(|_| self.connect_timed_out())??;
+                phases.tcp = Some(start.elapsed());
+                if let Ok(addr) = stream.peer_addr() {
+                    phases.peer_addr = Some(addr);
+                }
+                self.establish_timeout(conn_builder, stream, phases).await
+            },
+        }
+    }
+
+    fn connect_timed_out(&self) -> ConnectError {
+        ConnectError::ConnectTimeout(self.connect_timeout)
+    }
 
+    /// Performs the TLS and HTTP handshakes within the handshake timeout.
+    async fn establish_timeout<S>(
+        &self,
+        conn_builder: conn::Builder,
+        stream: S,
+        phases: &mut ConnectPhases,
+    ) -> anyhow::Result<ReWrkConnection>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let establish = self.establish(conn_builder, stream, phases);
+        timeout(self.handshake_timeout, establish)
+            .await
+            .map_err(|_| ConnectError::HandshakeTimeout(self.handshake_timeout))?
+    }
+
+    /// Performs the TLS and HTTP handshakes over an opened stream.
+    async fn establish<S>(
+        &self,
+        conn_builder: conn::Builder,
+        stream: S,
+        phases: &mut ConnectPhases,
+    ) -> anyhow::Result<ReWrkConnection>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         let usage_tracker = IoUsageTracker::new();
         let stream = usage_tracker.wrap_stream(stream);
 
-        let stream = match self.scheme {
-            Scheme::Http => handshake(conn_builder, stream).await?,
+        let (stream, plaintext_tracker) = match self.scheme {
+            Scheme::Http => {
+                let start = Instant::now();
+                let stream = handshake(conn_builder, stream).await?;
+                phases.handshake = Some(start.elapsed());
+                (stream, usage_tracker.clone())
+            },
             Scheme::Https(ref tls_connector) => {
+                let start = Instant::now();
                 let stream = tls_connector.connect(&self.host, stream).await?;
-                handshake(conn_builder, stream).await?
+                phases.tls = Some(start.elapsed());
+
+                let plaintext_tracker = IoUsageTracker::new();
+                let stream = plaintext_tracker.wrap_stream(stream);
+
+                let start = Instant::now();
+                let stream = handshake(conn_builder, stream).await?;
+                phases.handshake = Some(start.elapsed());
+                (stream, plaintext_tracker)
             },
         };
 
-        Ok(ReWrkConnection::new(
-            self.uri.clone(),
+        let template = RequestTemplate::new(
+            &self.uri,
             self.host_header.clone(),
+            &self.default_headers,
+        );
+        Ok(ReWrkConnection::new(
+            template,
+            phases.peer_addr,
+            self.protocol,
             stream,
             usage_tracker,
+            plaintext_tracker,
         ))
     }
 }
 
 /// An established HTTP connection for benchmarking.
 pub struct ReWrkConnection {
-    uri: Uri,
-    host_header: HeaderValue,
+    template: RequestTemplate,
+    peer_addr: Option<SocketAddr>,
+    protocol: HttpProtocol,
     stream: HttpStream,
     io_tracker: IoUsageTracker,
+    /// Counts the bytes passed to and from the TLS layer, this is
+    /// the same tracker as `io_tracker` for plain HTTP connections.
+    plaintext_tracker: IoUsageTracker,
+    /// The estimated bytes of the HTTP/2 streams executed since last taken.
+    stream_io: IoCounters,
+    /// The phase timings of the last response received.
+    last_response: ResponsePhases,
+    /// The validator response bodies are streamed to, unless they're buffered.
+    body_validator: Option<Arc<dyn ResponseValidator>>,
+    /// The error of the last streamed response body chunk rejected.
+    last_body_error: Option<ValidationError>,
 }
 
 impl ReWrkConnection {
     #[inline]
     /// Creates a new live connection from an existing stream
     fn new(
-        uri: Uri,
-        host_header: HeaderValue,
+        template: RequestTemplate,
+        peer_addr: Option<SocketAddr>,
+        protocol: HttpProtocol,
         stream: HttpStream,
         io_tracker: IoUsageTracker,
+        plaintext_tracker: IoUsageTracker,
     ) -> Self {
         Self {
-            uri,
-            host_header,
+            template,
+            peer_addr,
+            protocol,
             stream,
             io_tracker,
+            plaintext_tracker,
+            stream_io: IoCounters::default(),
+            last_response: ResponsePhases::default(),
+            body_validator: None,
+            last_body_error: None,
         }
     }
 
+    /// The address of the peer the connection is made to.
+    ///
+    /// This is `None` when a custom [Transport] is set.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    #[inline]
+    /// The phase timings of the last response received by [Self::execute_req].
+    pub(crate) fn response_phases(&self) -> ResponsePhases {
+        self.last_response
+    }
+
+    /// Reads response bodies as the validator's [BodyMode] requires.
+    ///
+    /// Unless the validator buffers bodies, they're read chunk by chunk
+    /// and [Self::execute_req] returns an empty body.
+    pub(crate) fn set_body_validator(&mut self, validator: Arc<dyn ResponseValidator>) {
+        self.body_validator =
+            (validator.body_mode() != BodyMode::Buffered).then_some(validator);
+    }
+
+    #[inline]
+    /// Takes the error of the chunk of the last streamed response body
+    /// rejected by the body validator, if any.
+    pub(crate) fn take_body_error(&mut self) -> Option<ValidationError> {
+        self.last_body_error.take()
+    }
+
     #[inline]
     pub(crate) fn usage(&self) -> &IoUsageTracker {
         &self.io_tracker
     }
 
+    /// The total bytes transferred over the socket and the HTTP layer.
+    ///
+    /// The marker should be passed to [Self::take_request_io] once the
+    /// requests it should cover have completed.
+    pub(crate) fn io_marker(&mut self) -> IoMarker {
+        self.stream_io = IoCounters::default();
+        IoMarker {
+            socket: counters(&self.io_tracker),
+            plaintext: counters(&self.plaintext_tracker),
+        }
+    }
+
+    /// Takes the bytes attributed to the requests executed since the marker
+    /// was created.
+    ///
+    /// With HTTP/2 the socket is shared with connection level frames, i.e.
+    /// window updates and pings, so the plaintext bytes of each stream are
+    /// estimated from the size of its frames instead. The TLS overhead
+    /// measured over the same period is added to get the socket bytes.
+    pub(crate) fn take_request_io(&mut self, marker: IoMarker) -> RequestIo {
+        let socket = counters(&self.io_tracker).since(marker.socket);
+        let plaintext = counters(&self.plaintext_tracker).since(marker.plaintext);
+        let stream_io = std::mem::take(&mut self.stream_io);
+
+        if self.protocol.is_http1() {
+            return RequestIo { socket, plaintext };
+        }
+
+        let tls_overhead = socket.since(plaintext);
+        RequestIo {
+            socket: IoCounters {
+                read: stream_io.read + tls_overhead.read,
+                written: stream_io.written + tls_overhead.written,
+            },
+            plaintext: stream_io,
+        }
+    }
+
+    /// Waits until the connection is ready to send another request,
+    /// returning the time spent waiting.
+    ///
+    /// This is backpressure within the client rather than server latency,
+    /// the time a HTTP/2 request body is blocked by flow control is
+    /// measured separately, see [ResponsePhases::send_blocked].
+    pub(crate) async fn ready(&mut self) -> Result<Duration, hyper::Error> {
+        let start = Instant::now();
+        self.stream.ready().await?;
+        Ok(start.elapsed())
+    }
+
     #[inline]
     /// Executes a request.
     ///
     /// This will override the request host, scheme, port and host headers.
+    /// Any default headers which are not already set on the request are added.
     pub(crate) async fn execute_req(
         &mut self,
         mut request: Request<Body>,
     ) -> Result<(Parts, Bytes), hyper::Error> {
-        let request_uri = request.uri();
-        let mut builder = Uri::builder()
-            .scheme(self.uri.scheme().unwrap().clone())
-            .authority(self.uri.authority().unwrap().clone());
-        if let Some(path) = request_uri.path_and_query() {
-            builder = builder.path_and_query(path.clone());
+        self.template.prepare(&mut request);
+
+        let mut body_sent = None;
+        if self.protocol.is_http2() {
+            self.stream_io.written += estimate_request_frames(&request);
+            body_sent = track_body_sent(&mut request);
         }
-        (*request.uri_mut()) = builder.build().unwrap();
-        request
-            .headers_mut()
-            .insert(header::HOST, self.host_header.clone());
 
+        let start = Instant::now();
         let resp = self.stream.send(request).await?;
+        let send_blocked = body_sent
+            .and_then(|sent| sent.get().copied())
+            .map_or(Duration::ZERO, |at| at.saturating_duration_since(start));
+        let ttfb = start.elapsed().saturating_sub(send_blocked);
         let (head, body) = resp.into_parts();
-        let body = hyper::body::to_bytes(body).await?;
+        let (body, body_len) = match self.body_validator.as_ref() {
+            None => {
+                let body = read_body(body).await?;
+                let body_len = body.len() as u64;
+                (body, body_len)
+            },
+            Some(validator) => {
+                let (body_len, error) =
+                    stream_body(body, &head, validator.as_ref()).await?;
+                self.last_body_error = error;
+                (Bytes::new(), body_len)
+            },
+        };
+        self.last_response = ResponsePhases {
+            send_blocked,
+            ttfb,
+            body_read: start.elapsed() - send_blocked - ttfb,
+        };
+
+        if self.protocol.is_http2() {
+            self.stream_io.read += estimate_response_frames(&head, body_len);
+        }
+
         Ok((head, body))
     }
+
+    /// Sends a request without waiting for the response.
+    ///
+    /// The request is sent once the returned future is first polled,
+    /// or as soon as the connection processes it with HTTP/2.
+    pub(crate) fn send_detached(
+        &mut self,
+        mut request: Request<Body>,
+    ) -> ResponseFuture {
+        self.template.prepare(&mut request);
+        self.stream.send(request)
+    }
+}
+
+/// Wraps a HTTP/2 request body to record when it has been fully handed
+/// to the connection.
+///
+/// The body is handed over in frame sized chunks and HTTP/2 only takes the
+/// next chunk once the stream has send capacity, so this is delayed by the
+/// peer's flow control window and the connection's stream limit. Requests
+/// without a body are sent as a single frame and aren't tracked.
+fn track_body_sent(request: &mut Request<Body>) -> Option<Arc<OnceLock<Instant>>> {
+    if request.body().is_end_stream() {
+        return None;
+    }
+
+    // The wrapped body has no size hint for the content length to be set from.
+    if let Some(len) = HttpBody::size_hint(request.body()).exact() {
+        request
+            .headers_mut()
+            .entry(header::CONTENT_LENGTH)
+            .or_insert_with(|| HeaderValue::from(len));
+    }
+
+    let sent_at = Arc::new(OnceLock::new());
+    let body = SentBody {
+        body: std::mem::take(request.body_mut()),
+        chunk: Bytes::new(),
+        sent_at: sent_at.clone(),
+    };
+    *request.body_mut() = Body::wrap_stream(body);
+    Some(sent_at)
+}
+
+/// A request body which records when it has been fully taken, see [track_body_sent].
+struct SentBody {
+    body: Body,
+    chunk: Bytes,
+    sent_at: Arc<OnceLock<Instant>>,
+}
+
+impl Stream for SentBody {
+    type Item = Result<Bytes, hyper::Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.chunk.is_empty() {
+            match ready!(Pin::new(&mut self.body).poll_data(cx)) {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    let _ = self.sent_at.set(Instant::now());
+                    return Poll::Ready(None);
+                },
+            }
+        }
+
+        let len = self.chunk.len().min(H2_MAX_FRAME_SIZE as usize);
+        Poll::Ready(Some(Ok(self.chunk.split_to(len))))
+    }
+}
+
+/// Reads a body to completion.
+///
+/// Bodies which are known to be empty, i.e. of `HEAD` responses or
+/// requests without a body, skip the body machinery entirely.
+pub async fn read_body(body: Body) -> Result<Bytes, hyper::Error> {
+    if body.is_end_stream() {
+        return Ok(Bytes::new());
+    }
+
+    hyper::body::to_bytes(body).await
+}
+
+/// Reads a body to completion without buffering it, passing each
+/// chunk to the validator if it validates streamed bodies.
+///
+/// Returns the length of the body and the error of the first chunk
+/// the validator rejected, the rest of the body is still read so the
+/// connection can be reused.
+async fn stream_body(
+    mut body: Body,
+    head: &Parts,
+    validator: &dyn ResponseValidator,
+) -> Result<(u64, Option<ValidationError>), hyper::Error> {
+    let validate = validator.body_mode() == BodyMode::Streaming;
+    let mut body_len = 0;
+    let mut error = None;
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        body_len += chunk.len() as u64;
+        if validate && error.is_none() {
+            error = validator.validate_chunk(head, &chunk).err();
+        }
+    }
+
+    Ok((body_len, error))
+}
+
+/// Reads a body to completion, dropping each chunk as it's received.
+pub(crate) async fn drain_body(mut body: Body) -> Result<(), hyper::Error> {
+    while let Some(chunk) = body.data().await {
+        chunk?;
+    }
+    Ok(())
+}
+
+/// Estimates the bytes of the HTTP/2 frames sent for a request.
+fn estimate_request_frames(request: &Request<Body>) -> u64 {
+    let uri = request.uri();
+    let pseudo_headers = [
+        (":method", request.method().as_str().len()),
+        (
+            ":scheme",
+            uri.scheme_str().map(str::len).unwrap_or_default(),
+        ),
+        (
+            ":authority",
+            uri.authority()
+                .map(|a| a.as_str().len())
+                .unwrap_or_default(),
+        ),
+        (
+            ":path",
+            uri.path_and_query()
+                .map(|p| p.as_str().len())
+                .unwrap_or_default(),
+        ),
+    ]
+    .iter()
+    .map(|(name, len)| (name.len() + len) as u64)
+    .sum();
+
+    let body_len = HttpBody::size_hint(request.body()).lower();
+    estimate_stream_frames(pseudo_headers, request.headers(), body_len)
+}
+
+/// Estimates the bytes of the HTTP/2 frames received for a response.
+fn estimate_response_frames(head: &Parts, body_len: u64) -> u64 {
+    let pseudo_headers = (":status".len() + 3) as u64;
+    estimate_stream_frames(pseudo_headers, &head.headers, body_len)
+}
+
+/// Estimates the bytes of a HTTP/2 stream's HEADERS and DATA frames.
+///
+/// Header blocks are counted uncompressed, so this is an upper
+/// bound for HPACK encoded headers.
+fn estimate_stream_frames(
+    pseudo_headers: u64,
+    headers: &HeaderMap,
+    body_len: u64,
+) -> u64 {
+    let header_block = headers
+        .iter()
+        .map(|(name, value)| (name.as_str().len() + value.len()) as u64)
+        .sum::<u64>()
+        + pseudo_headers;
+    let data_frames = body_len.div_ceil(H2_MAX_FRAME_SIZE);
+
+    H2_FRAME_HEADER_SIZE + header_block + data_frames * H2_FRAME_HEADER_SIZE + body_len
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The IO counters of a connection at a point in time.
+pub(crate) struct IoMarker {
+    socket: IoCounters,
+    plaintext: IoCounters,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The bytes transferred by a request at both layers of the connection.
+pub(crate) struct RequestIo {
+    /// The bytes passed over the socket, including any TLS overhead.
+    pub socket: IoCounters,
+    /// The bytes of the HTTP messages before encryption.
+    pub plaintext: IoCounters,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// The time spent in each phase of receiving a response.
+pub(crate) struct ResponsePhases {
+    /// The time a HTTP/2 request body was blocked by flow control or stream
+    /// limits before it was fully handed to the connection.
+    pub send_blocked: Duration,
+    /// The time from sending the request until the response head is received,
+    /// excluding the time the body was blocked.
+    pub ttfb: Duration,
+    /// The time taken to read the full response body after the head.
+    pub body_read: Duration,
+}
+
+fn counters(tracker: &IoUsageTracker) -> IoCounters {
+    IoCounters {
+        read: tracker.get_received_count(),
+        written: tracker.get_written_count(),
+    }
+}
+
+/// The headers which are set on every request by default.
+fn default_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::USER_AGENT,
+        HeaderValue::from_static(DEFAULT_USER_AGENT),
+    );
+    headers
 }
 
 /// Performs the HTTP handshake
@@ -208,10 +880,12 @@ pub struct HttpStream {
 }
 
 impl HttpStream {
-    pub fn send(
-        &mut self,
-        request: Request<Body>,
-    ) -> impl Future<Output = Result<Response<Body>, hyper::Error>> {
+    /// Waits until the connection can accept another request.
+    pub async fn ready(&mut self) -> Result<(), hyper::Error> {
+        std::future::poll_fn(|cx| self.conn.poll_ready(cx)).await
+    }
+
+    pub fn send(&mut self, request: Request<Body>) -> ResponseFuture {
         self.conn.send_request(request)
     }
 }
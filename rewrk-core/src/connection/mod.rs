@@ -1,11 +1,38 @@
+use serde::{Deserialize, Serialize};
 use tokio_native_tls::TlsConnector;
 
+mod bench;
 mod conn;
+mod template;
+mod transport;
 
-pub use self::conn::{HttpStream, ReWrkConnection, ReWrkConnector};
+pub use self::bench::{
+    BenchConnection,
+    BenchConnectionError,
+    IoCounters,
+    TimedResponse,
+};
+pub(crate) use self::conn::drain_body;
+pub use self::conn::{
+    read_body,
+    ConnectError,
+    ReWrkConnection,
+    ReWrkConnector,
+    DEFAULT_CONNECT_BACKOFF,
+    DEFAULT_CONNECT_TIMEOUT,
+    DEFAULT_HANDSHAKE_TIMEOUT,
+};
+pub use self::template::RequestTemplate;
+pub use self::transport::{
+    BoxedTransportStream,
+    DuplexTransport,
+    Transport,
+    TransportStream,
+    DEFAULT_DUPLEX_BUFFER_SIZE,
+};
 
 /// The type of bench that is being ran.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HttpProtocol {
     /// Sets the http protocol to be used as h1
     HTTP1,
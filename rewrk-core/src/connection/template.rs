@@ -0,0 +1,99 @@
+use std::mem;
+
+use http::uri::{Authority, PathAndQuery, Scheme};
+use http::{header, HeaderMap, HeaderValue, Request, Uri};
+
+#[derive(Debug, Clone)]
+/// Prepares requests to be sent to a target.
+///
+/// Requests are pointed at the target and given the `Host` and default
+/// headers. The target's URI parts and a header map holding the `Host` and
+/// default headers are built once and reused for every request, rather than
+/// each request building its URI and growing its header map from scratch.
+///
+/// Requests without any headers take a copy of the pre-built header map, which
+/// is sized up front. Headers already set on a request take priority over the
+/// default headers, the `Host` header is always replaced.
+///
+/// ```
+/// use http::header::{HeaderValue, USER_AGENT};
+/// use http::{HeaderMap, Request, Uri};
+/// use rewrk_core::RequestTemplate;
+///
+/// let mut defaults = HeaderMap::new();
+/// defaults.insert(USER_AGENT, HeaderValue::from_static("rewrk"));
+/// let template = RequestTemplate::new(
+///     &Uri::from_static("http://127.0.0.1:8080"),
+///     HeaderValue::from_static("127.0.0.1:8080"),
+///     &defaults,
+/// );
+///
+/// let mut request = Request::new(());
+/// *request.uri_mut() = Uri::from_static("/hello?name=rewrk");
+/// template.prepare(&mut request);
+///
+/// assert_eq!(request.uri(), "http://127.0.0.1:8080/hello?name=rewrk");
+/// assert_eq!(request.headers()[USER_AGENT], "rewrk");
+/// ```
+pub struct RequestTemplate {
+    scheme: Scheme,
+    authority: Authority,
+    host_header: HeaderValue,
+    default_headers: HeaderMap,
+    /// The `Host` and default headers every request is given.
+    headers: HeaderMap,
+}
+
+impl RequestTemplate {
+    /// Creates a new template for the given target.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the URI is missing its scheme or authority.
+    pub fn new(
+        uri: &Uri,
+        host_header: HeaderValue,
+        default_headers: &HeaderMap,
+    ) -> Self {
+        let mut headers = HeaderMap::with_capacity(default_headers.len() + 1);
+        headers.insert(header::HOST, host_header.clone());
+        for (name, value) in default_headers.iter() {
+            if !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+
+        Self {
+            scheme: uri.scheme().expect("URI has a scheme").clone(),
+            authority: uri.authority().expect("URI has an authority").clone(),
+            host_header,
+            default_headers: default_headers.clone(),
+            headers,
+        }
+    }
+
+    /// Points the request at the target and adds the `Host` and default headers.
+    pub fn prepare<B>(&self, request: &mut Request<B>) {
+        let mut parts = mem::take(request.uri_mut()).into_parts();
+        parts.scheme = Some(self.scheme.clone());
+        parts.authority = Some(self.authority.clone());
+        if parts.path_and_query.is_none() {
+            parts.path_and_query = Some(PathAndQuery::from_static("/"));
+        }
+        *request.uri_mut() = Uri::from_parts(parts).expect("URI parts are complete");
+
+        let headers = request.headers_mut();
+        if headers.is_empty() {
+            *headers = self.headers.clone();
+            return;
+        }
+
+        headers.reserve(self.headers.len());
+        headers.insert(header::HOST, self.host_header.clone());
+        for (name, value) in self.default_headers.iter() {
+            if !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::error::Error as StdError;
+use std::io;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use http::{Request, Response};
+use hyper::body::HttpBody;
+use hyper::server::conn::Http;
+use hyper::service::Service;
+use hyper::Body;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// The default buffer size of each direction of a [DuplexTransport] stream.
+pub const DEFAULT_DUPLEX_BUFFER_SIZE: usize = 64 * 1024;
+
+/// A stream returned by a [Transport].
+pub trait TransportStream: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T> TransportStream for T where T: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+/// A boxed [TransportStream].
+pub type BoxedTransportStream = Box<dyn TransportStream>;
+
+#[async_trait]
+/// Opens the underlying streams benchmark connections are established over.
+///
+/// By default connections are made over TCP to the resolved address of the
+/// base URI, a custom transport replaces this while the base URI is still
+/// used for the request URIs and `Host` header.
+pub trait Transport: Send + Sync + 'static {
+    /// Open a new stream to the target.
+    async fn connect(&self) -> io::Result<BoxedTransportStream>;
+}
+
+/// A transport which serves each connection in-process with the given
+/// hyper or axum service over a [tokio::io::duplex] stream.
+///
+/// This removes the kernel networking stack from the measurement, which is
+/// useful for measuring the overhead of a framework and for environments
+/// without network access.
+///
+/// The service runs on the same worker runtime as the connection, so the
+/// measured latency includes the time spent handling the request.
+///
+/// ```no_run
+/// use axum::routing::get;
+/// use axum::Router;
+/// use rewrk_core::DuplexTransport;
+///
+/// let app: Router = Router::new().route("/", get(|| async { "Hello, World!" }));
+/// let transport = DuplexTransport::new(app);
+/// // benchmarker.set_transport(transport);
+/// ```
+pub struct DuplexTransport<S> {
+    // Services are often `Send` but not `Sync`, the mutex is only
+    // held while cloning the service for a new connection.
+    service: Mutex<S>,
+    buffer_size: usize,
+}
+
+impl<S> DuplexTransport<S> {
+    /// Create a new transport serving connections with the given service.
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Mutex::new(service),
+            buffer_size: DEFAULT_DUPLEX_BUFFER_SIZE,
+        }
+    }
+
+    /// Set the buffer size of each direction of the duplex stream.
+    pub fn set_buffer_size(&mut self, buffer_size: usize) {
+        self.buffer_size = buffer_size.max(1);
+    }
+}
+
+#[async_trait]
+impl<S, B> Transport for DuplexTransport<S>
+where
+    S: Service<Request<Body>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    async fn connect(&self) -> io::Result<BoxedTransportStream> {
+        let (client, server) = tokio::io::duplex(self.buffer_size);
+        let service = self.service.lock().unwrap().clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = Http::new().serve_connection(server, service).await {
+                debug!(error = ?e, "In-process connection closed with error.");
+            }
+        });
+
+        Ok(Box::new(client))
+    }
+}
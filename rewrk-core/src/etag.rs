@@ -0,0 +1,292 @@
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::header::{self, HeaderValue};
+use http::response::Parts;
+use http::{Method, Request, StatusCode, Uri};
+use hyper::body::Bytes;
+use hyper::Body;
+
+use crate::producer::{Batch, Producer, RequestBatch};
+use crate::validator::{BodyMode, Classification, ResponseValidator, ValidationError};
+
+/// The classification given to `412 Precondition Failed` responses
+/// by the [EtagValidator].
+pub const ETAG_CONFLICT_CLASSIFICATION: &str = "etag_write_conflict";
+
+/// The default number of read-then-write pairs in each batch.
+const DEFAULT_BATCH_SIZE: usize = 50;
+
+#[derive(Debug, Clone)]
+/// A workload exercising an endpoint with optimistic concurrency control.
+///
+/// The [EtagProducer] reads the resource with a `GET` and then writes it with
+/// a `PUT` guarded by an `If-Match` header, while the [EtagValidator] keeps
+/// track of the latest `ETag` returned by the server. Concurrent writers race
+/// each other, so stores with compare-and-set semantics reject some writes
+/// with `412 Precondition Failed`. These conflicts are classified as
+/// [ETAG_CONFLICT_CLASSIFICATION] so they are counted separately in each
+/// sample's classified latencies.
+///
+/// Batches are produced ahead of their responses, so writes use the latest
+/// `ETag` observed when their batch was produced. Writes are only produced
+/// once an `ETag` has been observed.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use http::Uri;
+/// use rewrk_core::{DefaultValidator, EtagWorkload};
+///
+/// let workload = EtagWorkload::new(Uri::from_static("/items/1"), "{\"count\": 1}");
+/// let producer = workload.producer(Duration::from_secs(10));
+/// let validator = workload.validator(DefaultValidator);
+/// ```
+pub struct EtagWorkload {
+    uri: Uri,
+    body: Bytes,
+    etag: Arc<Mutex<Option<HeaderValue>>>,
+}
+
+impl EtagWorkload {
+    /// Creates a new workload writing the body to the given resource.
+    pub fn new(uri: Uri, body: impl Into<Bytes>) -> Self {
+        Self {
+            uri,
+            body: body.into(),
+            etag: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Creates a producer which runs the workload for the given duration.
+    pub fn producer(&self, duration: Duration) -> EtagProducer {
+        EtagProducer {
+            workload: self.clone(),
+            duration,
+            deadline: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Wraps an existing validator to observe the `ETag` of each response.
+    pub fn validator<V>(&self, inner: V) -> EtagValidator<V> {
+        EtagValidator {
+            inner,
+            workload: self.clone(),
+            reject_conflicts: false,
+        }
+    }
+
+    /// The classification given to write conflicts.
+    pub fn classification() -> Classification {
+        Classification::from(ETAG_CONFLICT_CLASSIFICATION)
+    }
+
+    /// The latest `ETag` returned by the server, if any.
+    pub fn latest_etag(&self) -> Option<HeaderValue> {
+        self.etag.lock().expect("Lock etag").clone()
+    }
+
+    fn observe(&self, etag: &HeaderValue) {
+        *self.etag.lock().expect("Lock etag") = Some(etag.clone());
+    }
+}
+
+#[derive(Clone)]
+/// Produces `GET` and `If-Match` guarded `PUT` requests for an [EtagWorkload].
+pub struct EtagProducer {
+    workload: EtagWorkload,
+    duration: Duration,
+    deadline: Option<Instant>,
+    batch_size: usize,
+}
+
+impl EtagProducer {
+    /// Set the number of read-then-write pairs in each batch.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    fn read(&self) -> Result<Request<Body>, http::Error> {
+        Request::builder()
+            .method(Method::GET)
+            .uri(self.workload.uri.clone())
+            .body(Body::empty())
+    }
+
+    fn write(&self, etag: HeaderValue) -> Result<Request<Body>, http::Error> {
+        Request::builder()
+            .method(Method::PUT)
+            .uri(self.workload.uri.clone())
+            .header(header::IF_MATCH, etag)
+            .body(Body::from(self.workload.body.clone()))
+    }
+}
+
+#[async_trait::async_trait]
+impl Producer for EtagProducer {
+    fn ready(&mut self) {
+        self.deadline = Some(Instant::now() + self.duration);
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self
+            .deadline
+            .map_or(true, |deadline| Instant::now() >= deadline)
+        {
+            return Ok(RequestBatch::End);
+        }
+
+        let etag = self.workload.latest_etag();
+        let mut requests = Vec::with_capacity(self.batch_size * 2);
+        for _ in 0..self.batch_size {
+            requests.push(self.read()?);
+            if let Some(etag) = etag.clone() {
+                requests.push(self.write(etag)?);
+            }
+        }
+
+        Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+    }
+}
+
+#[derive(Debug)]
+/// A validator which observes the `ETag` of responses for an [EtagWorkload].
+///
+/// `412 Precondition Failed` responses are expected under contention, they
+/// are classified as [ETAG_CONFLICT_CLASSIFICATION] rather than treated as
+/// errors unless [EtagValidator::reject_conflicts] is set. All other responses
+/// are validated and classified by the inner validator.
+pub struct EtagValidator<V> {
+    inner: V,
+    workload: EtagWorkload,
+    reject_conflicts: bool,
+}
+
+impl<V> EtagValidator<V> {
+    /// Reject write conflicts with a validation error instead of only classifying them.
+    pub fn reject_conflicts(mut self) -> Self {
+        self.reject_conflicts = true;
+        self
+    }
+}
+
+impl<V> ResponseValidator for EtagValidator<V>
+where
+    V: ResponseValidator,
+{
+    fn validate(&self, head: Parts, body: Bytes) -> Result<(), ValidationError> {
+        if head.status == StatusCode::PRECONDITION_FAILED {
+            return if self.reject_conflicts {
+                Err(ValidationError::Other(Cow::Borrowed("etag-write-conflict")))
+            } else {
+                Ok(())
+            };
+        }
+
+        if head.status.is_success() {
+            if let Some(etag) = head.headers.get(header::ETAG) {
+                self.workload.observe(etag);
+            }
+        }
+
+        self.inner.validate(head, body)
+    }
+
+    fn classify(&self, head: &Parts, body: &Bytes) -> Option<Classification> {
+        if head.status == StatusCode::PRECONDITION_FAILED {
+            return Some(EtagWorkload::classification());
+        }
+
+        self.inner.classify(head, body)
+    }
+
+    fn body_mode(&self) -> BodyMode {
+        self.inner.body_mode()
+    }
+
+    fn validate_chunk(
+        &self,
+        head: &Parts,
+        chunk: &Bytes,
+    ) -> Result<(), ValidationError> {
+        self.inner.validate_chunk(head, chunk)
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Response;
+
+    use super::*;
+    use crate::DefaultValidator;
+
+    fn parts(status: StatusCode, etag: Option<&'static str>) -> Parts {
+        let mut response = Response::new(());
+        *response.status_mut() = status;
+        if let Some(etag) = etag {
+            response
+                .headers_mut()
+                .insert(header::ETAG, HeaderValue::from_static(etag));
+        }
+        response.into_parts().0
+    }
+
+    #[test]
+    fn test_etag_validator() {
+        let workload = EtagWorkload::new(Uri::from_static("/items/1"), "{}");
+        let validator = workload.validator(DefaultValidator);
+
+        assert!(validator
+            .validate(parts(StatusCode::OK, Some("\"v1\"")), Bytes::new())
+            .is_ok());
+        assert_eq!(workload.latest_etag().unwrap(), "\"v1\"");
+
+        let conflict = parts(StatusCode::PRECONDITION_FAILED, Some("\"v2\""));
+        assert_eq!(
+            validator.classify(&conflict, &Bytes::new()),
+            Some(EtagWorkload::classification()),
+        );
+        assert!(validator.validate(conflict, Bytes::new()).is_ok());
+        assert_eq!(workload.latest_etag().unwrap(), "\"v1\"");
+
+        let error = parts(StatusCode::INTERNAL_SERVER_ERROR, Some("\"v3\""));
+        assert!(validator.validate(error, Bytes::new()).is_err());
+        assert_eq!(workload.latest_etag().unwrap(), "\"v1\"");
+
+        let validator = validator.reject_conflicts();
+        let conflict = parts(StatusCode::PRECONDITION_FAILED, None);
+        assert!(validator.validate(conflict, Bytes::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_etag_producer_writes_after_read() {
+        let workload = EtagWorkload::new(Uri::from_static("/items/1"), "{}");
+        let mut producer = workload
+            .producer(Duration::from_secs(60))
+            .with_batch_size(2);
+        producer.ready();
+
+        let requests = match producer.create_batch().await.unwrap() {
+            RequestBatch::Batch(batch) => batch.requests,
+            _ => panic!("Expected a batch"),
+        };
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.method() == Method::GET));
+
+        workload.observe(&HeaderValue::from_static("\"v1\""));
+        let requests = match producer.create_batch().await.unwrap() {
+            RequestBatch::Batch(batch) => batch.requests,
+            _ => panic!("Expected a batch"),
+        };
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[1].method(), Method::PUT);
+        assert_eq!(requests[1].headers()[header::IF_MATCH], "\"v1\"");
+    }
+}
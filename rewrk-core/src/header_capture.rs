@@ -0,0 +1,72 @@
+use http::header::HeaderName;
+use http::HeaderMap;
+
+use crate::Sample;
+
+/// The default maximum number of distinct values counted for each captured header.
+pub const DEFAULT_MAX_HEADER_VALUES: usize = 32;
+
+/// The value responses are counted under once a captured
+/// header has reached its maximum number of distinct values.
+pub const OTHER_HEADER_VALUE: &str = "(other)";
+
+#[derive(Debug, Clone)]
+/// Configuration for counting the values of chosen response headers.
+///
+/// Each sample counts the responses received for every distinct value of
+/// the captured headers, i.e. capturing `X-Served-By` shows which backends
+/// or PoPs behind a load balancer served the requests during each sample window.
+/// The counts are available via [Sample::captured_header](crate::Sample::captured_header).
+///
+/// The number of distinct values counted for each header is bounded, once the
+/// limit is reached any new values are counted under [OTHER_HEADER_VALUE].
+/// Responses without a captured header are not counted for that header,
+/// and responses to mirrored requests are not counted at all.
+///
+/// ```
+/// use http::header::HeaderName;
+/// use rewrk_core::HeaderCapture;
+///
+/// let capture = HeaderCapture::new([HeaderName::from_static("x-served-by")])
+///     .with_max_values(16);
+/// ```
+pub struct HeaderCapture {
+    headers: Vec<HeaderName>,
+    max_values: usize,
+}
+
+impl HeaderCapture {
+    /// Create a new config capturing the given headers.
+    pub fn new(headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        Self {
+            headers: headers.into_iter().collect(),
+            max_values: DEFAULT_MAX_HEADER_VALUES,
+        }
+    }
+
+    /// Set the maximum number of distinct values counted for each header.
+    pub fn with_max_values(mut self, max_values: usize) -> Self {
+        self.max_values = max_values;
+        self
+    }
+
+    /// The headers which are captured.
+    pub fn headers(&self) -> &[HeaderName] {
+        &self.headers
+    }
+
+    /// The maximum number of distinct values counted for each header.
+    pub fn max_values(&self) -> usize {
+        self.max_values
+    }
+
+    /// Counts the values of the captured headers in the sample.
+    pub(crate) fn record(&self, headers: &HeaderMap, sample: &mut Sample) {
+        for name in self.headers.iter() {
+            for value in headers.get_all(name) {
+                let value = String::from_utf8_lossy(value.as_bytes());
+                sample.record_header_value(name.as_str(), value.trim());
+            }
+        }
+    }
+}
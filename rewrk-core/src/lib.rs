@@ -47,7 +47,7 @@
 //!         BasicCollector::default(),
 //!     )
 //!     .await?;
-//!     benchmarker.set_num_workers(1);
+//!     benchmarker.set_num_workers(1)?;
 //!     benchmarker.run().await;
 //!
 //!     let mut collector = benchmarker.consume_collector().await;
@@ -117,22 +117,167 @@
 extern crate tracing;
 
 mod connection;
+mod etag;
+mod header_capture;
+pub mod middleware;
+mod one_way_delay;
 mod producer;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
 mod recording;
+mod registry;
+mod response_tracking;
+mod retry;
 mod runtime;
+mod scheduler;
+mod server_timing;
+pub mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod utils;
 mod validator;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use async_trait::async_trait;
 pub use http;
 
-pub use self::connection::{HttpProtocol, Scheme};
-pub use self::producer::{Batch, Producer, ProducerBatches, RequestBatch};
-pub use self::recording::{Sample, SampleCollector};
+pub use self::connection::{
+    read_body,
+    BenchConnection,
+    BenchConnectionError,
+    BoxedTransportStream,
+    ConnectError,
+    DuplexTransport,
+    HttpProtocol,
+    IoCounters,
+    RequestTemplate,
+    Scheme,
+    TimedResponse,
+    Transport,
+    TransportStream,
+    DEFAULT_CONNECT_BACKOFF,
+    DEFAULT_CONNECT_TIMEOUT,
+    DEFAULT_DUPLEX_BUFFER_SIZE,
+    DEFAULT_HANDSHAKE_TIMEOUT,
+};
+pub use self::etag::{
+    EtagProducer,
+    EtagValidator,
+    EtagWorkload,
+    ETAG_CONFLICT_CLASSIFICATION,
+};
+pub use self::header_capture::{
+    HeaderCapture,
+    DEFAULT_MAX_HEADER_VALUES,
+    OTHER_HEADER_VALUE,
+};
+pub use self::one_way_delay::{
+    OneWayDelay,
+    DEFAULT_RECEIVED_AT_HEADER,
+    DEFAULT_SENT_AT_HEADER,
+};
+pub use self::producer::{
+    Batch,
+    NotBefore,
+    Producer,
+    ProducerBatches,
+    ProducerEnd,
+    RequestBatch,
+    StickyKey,
+};
+pub use self::recording::{
+    Annotation,
+    ArchiveCollector,
+    ArchiveError,
+    ArchiveHeader,
+    ArchiveReader,
+    ArchiveWriter,
+    ConnectErrorKind,
+    ConnectSample,
+    DrainedCollector,
+    FoldedStacks,
+    ImbalanceCause,
+    ImbalanceDetector,
+    ImbalanceMetric,
+    ImbalanceScope,
+    ImbalanceWarning,
+    LatencyHeatmap,
+    Metric,
+    MetricFilter,
+    MetricKind,
+    Outlier,
+    ProgressSnapshot,
+    RequestKey,
+    ResumedArchive,
+    Sample,
+    SampleCollector,
+    SampleMerger,
+    SampleMetadata,
+    Snapshot,
+    WindowStats,
+    WorkerReport,
+    ARCHIVE_EXTENSION,
+    DEFAULT_IMBALANCE_THRESHOLD,
+};
+pub use self::registry::{BoxedProducer, Registry, RegistryError};
+pub use self::response_tracking::{ResponseTracking, DEFAULT_REQUEST_KEY_HEADER};
+pub use self::retry::{
+    Backoff,
+    RetryPolicy,
+    DEFAULT_RETRY_BACKOFF_MAX,
+    DEFAULT_RETRY_BACKOFF_MIN,
+    DEFAULT_RETRY_IDEMPOTENT_ONLY,
+    DEFAULT_RETRY_MAX_ATTEMPTS,
+};
 pub use self::runtime::{
+    BenchmarkPlan,
+    ConfigError,
+    ConnectionGroup,
+    ConnectionGroupPlan,
     Error,
+    HeaderCapturePlan,
+    HealthCheck,
+    HealthCheckAction,
+    HealthCheckPlan,
+    MemoryLimitAction,
+    MemoryLimitPlan,
+    OneWayDelayPlan,
+    Phase,
+    PhasePlan,
+    PlanError,
+    PreflightError,
+    PreflightReport,
+    PrimingPlan,
     ReWrkBenchmark,
+    ResponseModel,
+    ResponseTrackingPlan,
+    RetryPolicyPlan,
+    RunError,
+    SendMode,
+    ServerTimingPlan,
+    SimulatedResponse,
+    Simulation,
+    SlowStartPlan,
+    DEFAULT_MAX_ERROR_EXEMPLARS,
+    DEFAULT_MAX_OUTLIERS,
     DEFAULT_WAIT_WARNING_THRESHOLD,
     DEFAULT_WINDOW_DURATION,
 };
-pub use self::validator::{DefaultValidator, ResponseValidator, ValidationError};
+pub use self::scheduler::TagScheduler;
+pub use self::server_timing::ServerTimingSource;
+pub use self::utils::{IoUsageTracker, RateLimiter, RecordStream};
+pub use self::validator::{
+    is_intermediary_cache_hit,
+    BodyMode,
+    CacheHitValidator,
+    Classification,
+    DefaultValidator,
+    ResponseLatency,
+    ResponseValidator,
+    ValidationError,
+    ValidationErrorKind,
+    WithLatencySlo,
+    CACHE_HIT_CLASSIFICATION,
+    DEFAULT_VALIDATOR_NAME,
+};
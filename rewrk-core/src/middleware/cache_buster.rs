@@ -0,0 +1,183 @@
+use std::borrow::Cow;
+
+use http::header::HeaderName;
+use http::uri::PathAndQuery;
+use http::{HeaderValue, Request, Uri};
+use hyper::Body;
+use rand::Rng;
+
+use crate::producer::{Producer, RequestBatch};
+
+#[derive(Debug, Clone)]
+/// Where the randomized cache-busting value is placed on the request.
+pub enum CacheBustMode {
+    /// Set a header with the given name to a random value.
+    Header(HeaderName),
+    /// Append a query parameter with the given name and a random value.
+    QueryParam(Cow<'static, str>),
+}
+
+#[derive(Clone)]
+/// A producer middleware which adds a random value to every request.
+///
+/// This is useful when benchmarking through a CDN or caching proxy
+/// which would otherwise serve every request for a static path from its cache.
+///
+/// # Example
+///
+/// ```
+/// use rewrk_core::middleware::{CacheBustMode, CacheBuster};
+/// # use rewrk_core::{Producer, RequestBatch};
+/// #
+/// # #[derive(Clone)]
+/// # struct BasicProducer;
+/// #
+/// # #[rewrk_core::async_trait]
+/// # impl Producer for BasicProducer {
+/// #     fn ready(&mut self) {}
+/// #
+/// #     async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+/// #         Ok(RequestBatch::End)
+/// #     }
+/// # }
+///
+/// let producer = CacheBuster::new(
+///     BasicProducer,
+///     CacheBustMode::QueryParam("cache-bust".into()),
+/// );
+/// ```
+pub struct CacheBuster<P> {
+    inner: P,
+    mode: CacheBustMode,
+}
+
+impl<P> CacheBuster<P> {
+    /// Wraps an existing producer with the given cache busting mode.
+    pub fn new(inner: P, mode: CacheBustMode) -> Self {
+        Self { inner, mode }
+    }
+
+    fn apply(&self, request: &mut Request<Body>) {
+        let value = format!("{:016x}", rand::thread_rng().gen::<u64>());
+
+        match &self.mode {
+            CacheBustMode::Header(name) => {
+                let value = HeaderValue::try_from(value)
+                    .expect("Hex string should always be a valid header value");
+                request.headers_mut().insert(name.clone(), value);
+            },
+            CacheBustMode::QueryParam(param) => {
+                let uri = request.uri().clone();
+                let path = uri.path();
+                let path_and_query = match uri.query() {
+                    Some(query) => format!("{path}?{query}&{param}={value}"),
+                    None => format!("{path}?{param}={value}"),
+                };
+
+                let mut parts = uri.into_parts();
+                match PathAndQuery::try_from(path_and_query) {
+                    Ok(path_and_query) => parts.path_and_query = Some(path_and_query),
+                    Err(e) => {
+                        warn!(error = ?e, "Failed to add cache busting query parameter.");
+                        return;
+                    },
+                }
+
+                if let Ok(uri) = Uri::from_parts(parts) {
+                    *request.uri_mut() = uri;
+                }
+            },
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<P> Producer for CacheBuster<P>
+where
+    P: Producer,
+{
+    fn ready(&mut self) {
+        self.inner.ready()
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        let mut batch = self.inner.create_batch().await?;
+
+        if let RequestBatch::Batch(batch) | RequestBatch::Priority(batch) = &mut batch {
+            // Every request is busted separately, so repeated requests are expanded.
+            batch.expand();
+            for request in batch.requests.iter_mut() {
+                self.apply(request);
+            }
+        }
+
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use hyper::body::Bytes;
+
+    use super::*;
+    use crate::Batch;
+
+    #[derive(Clone)]
+    struct RepeatProducer;
+
+    #[async_trait::async_trait]
+    impl Producer for RepeatProducer {
+        fn ready(&mut self) {}
+
+        async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+            let request = Request::get("/items?page=1").body(Bytes::new())?;
+            Ok(RequestBatch::Batch(Batch::repeat(request, 3)))
+        }
+    }
+
+    async fn bust(mode: CacheBustMode) -> Vec<Request<Body>> {
+        let mut producer = CacheBuster::new(RepeatProducer, mode);
+        producer.ready();
+        match producer.create_batch().await.expect("Create batch") {
+            RequestBatch::Batch(batch) => batch.requests,
+            _ => panic!("Expected a batch"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_bust_query_param() {
+        let requests = bust(CacheBustMode::QueryParam("cb".into())).await;
+        assert_eq!(requests.len(), 3);
+
+        let mut values = BTreeSet::new();
+        for request in requests.iter() {
+            assert_eq!(request.uri().path(), "/items");
+            let query = request.uri().query().expect("Get query");
+            let value = query
+                .strip_prefix("page=1&cb=")
+                .expect("Existing query kept");
+            assert_eq!(value.len(), 16);
+            values.insert(value.to_string());
+        }
+        // Every repeated request is busted separately.
+        assert_eq!(values.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cache_bust_header() {
+        let name = HeaderName::from_static("x-cache-bust");
+        let requests = bust(CacheBustMode::Header(name.clone())).await;
+        assert_eq!(requests.len(), 3);
+
+        let values = requests
+            .iter()
+            .map(|request| request.headers()[&name].clone())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(values.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|request| request.uri() == "/items?page=1"));
+    }
+}
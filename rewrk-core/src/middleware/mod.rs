@@ -0,0 +1,5 @@
+//! Middleware which wraps the core traits to adjust their behaviour.
+
+mod cache_buster;
+
+pub use self::cache_buster::{CacheBustMode, CacheBuster};
@@ -0,0 +1,117 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::HeaderName;
+use http::{HeaderMap, HeaderValue};
+
+/// The default header the client send timestamp is written to.
+pub static DEFAULT_SENT_AT_HEADER: HeaderName =
+    HeaderName::from_static("x-rewrk-sent-at");
+/// The default header the server receive timestamp is read from.
+pub static DEFAULT_RECEIVED_AT_HEADER: HeaderName =
+    HeaderName::from_static("x-rewrk-received-at");
+
+#[derive(Debug, Clone)]
+/// Configuration for estimating the one-way delay of requests and responses.
+///
+/// Each request has the client send time written to a header as microseconds
+/// since the unix epoch. A cooperating server is expected to respond with the
+/// time it received the request in the same format.
+///
+/// As the client and server clocks are not synchronised, the raw one-way delays
+/// include an unknown but constant clock offset. The offset is removed by
+/// recording each delay relative to the smallest delay observed on the
+/// connection, this gives the queuing delay added in each direction which
+/// shows if latency is caused by the request or response path.
+pub struct OneWayDelay {
+    sent_at_header: HeaderName,
+    received_at_header: HeaderName,
+}
+
+impl Default for OneWayDelay {
+    fn default() -> Self {
+        Self {
+            sent_at_header: DEFAULT_SENT_AT_HEADER.clone(),
+            received_at_header: DEFAULT_RECEIVED_AT_HEADER.clone(),
+        }
+    }
+}
+
+impl OneWayDelay {
+    /// Create a new config using the given request and response headers.
+    pub fn new(sent_at_header: HeaderName, received_at_header: HeaderName) -> Self {
+        Self {
+            sent_at_header,
+            received_at_header,
+        }
+    }
+
+    /// The request header the client send timestamp is written to.
+    pub fn sent_at_header(&self) -> &HeaderName {
+        &self.sent_at_header
+    }
+
+    /// The response header the server receive timestamp is read from.
+    pub fn received_at_header(&self) -> &HeaderName {
+        &self.received_at_header
+    }
+}
+
+#[derive(Debug, Default)]
+/// Tracks the one-way delay baselines for a single connection.
+pub(crate) struct OneWayDelayEstimator {
+    min_forward: Option<i64>,
+    min_backward: Option<i64>,
+}
+
+impl OneWayDelayEstimator {
+    /// Writes the current time to the request headers.
+    ///
+    /// Returns the timestamp written in microseconds.
+    pub fn stamp(&self, config: &OneWayDelay, headers: &mut HeaderMap) -> i64 {
+        let sent_at = unix_micros(SystemTime::now());
+        headers.insert(config.sent_at_header.clone(), HeaderValue::from(sent_at));
+        sent_at
+    }
+
+    /// Estimates the forward and backward delay of a request above
+    /// the connection's baseline delay.
+    ///
+    /// Returns `None` if the response did not contain a valid timestamp.
+    pub fn estimate(
+        &mut self,
+        config: &OneWayDelay,
+        sent_at: i64,
+        headers: &HeaderMap,
+    ) -> Option<(Duration, Duration)> {
+        let received_at = unix_micros(SystemTime::now());
+        let server_at = headers
+            .get(&config.received_at_header)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<i64>()
+            .ok()?;
+
+        let forward = server_at - sent_at;
+        let backward = received_at - server_at;
+        let min_forward = *self.min_forward.get_or_insert(forward);
+        let min_backward = *self.min_backward.get_or_insert(backward);
+        self.min_forward = Some(min_forward.min(forward));
+        self.min_backward = Some(min_backward.min(backward));
+
+        Some((
+            relative_delay(forward, min_forward),
+            relative_delay(backward, min_backward),
+        ))
+    }
+}
+
+fn relative_delay(delay: i64, baseline: i64) -> Duration {
+    Duration::from_micros(delay.saturating_sub(baseline).max(0) as u64)
+}
+
+fn unix_micros(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|dur| dur.as_micros() as i64)
+        .unwrap_or_default()
+}
@@ -1,8 +1,24 @@
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
-use flume::Receiver;
+use flume::{Receiver, RecvError};
+use futures_util::future::{select, Either};
 use http::Request;
+use hyper::body::Bytes;
 use hyper::Body;
-use tokio::sync::oneshot;
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Runtime;
+use tokio::sync::{oneshot, watch};
+
+use crate::recording::WindowStats;
+use crate::runtime::{RunError, ShutdownHandle};
+use crate::scheduler::{TagQueues, TagUsage};
 
 /// A batch of requests or single to the workers.
 pub enum RequestBatch {
@@ -12,6 +28,90 @@ pub enum RequestBatch {
     End,
     /// A new batch to process.
     Batch(Batch),
+    /// A new batch to process ahead of any batches still waiting
+    /// for a connection.
+    ///
+    /// This is useful for urgent control requests, i.e. refreshing a
+    /// session mid-run, which shouldn't wait behind the bulk load.
+    Priority(Batch),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// What a producer returning [RequestBatch::End] ends.
+///
+/// Each worker runs its own clone of the producer, so by default one
+/// producer ending only ends its own worker and the benchmark continues
+/// until every producer has ended.
+pub enum ProducerEnd {
+    #[default]
+    /// Only the worker running the producer finishes.
+    Worker,
+    /// All workers finish their current batch and the benchmark ends.
+    Benchmark,
+    /// All producers stop creating batches and the benchmark ends once every
+    /// batch already produced has been executed and its responses received.
+    ///
+    /// Unlike [ProducerEnd::Benchmark] no produced requests are dropped, which
+    /// guarantees the exact number of requests produced are sent. With
+    /// [SendMode::Mirror](crate::SendMode::Mirror) the connections also wait
+    /// for the responses of requests still in flight.
+    Drain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Schedules a request to be sent no earlier than the given point in time.
+///
+/// This is set as an extension on the request, workers wait until the time
+/// has passed before sending it. Requests within a batch are sent in order,
+/// so to delay a whole batch only its first request needs to be scheduled.
+///
+/// Latency is measured from when the request is sent, so waiting for the
+/// scheduled time is not included in the results. If a connection is still
+/// busy at the scheduled time the request is sent as soon as it is free.
+///
+/// ```
+/// use std::time::{Duration, Instant};
+///
+/// use http::Request;
+/// use hyper::Body;
+/// use rewrk_core::NotBefore;
+///
+/// let mut request = Request::new(Body::empty());
+/// let at = Instant::now() + Duration::from_millis(250);
+/// request.extensions_mut().insert(NotBefore(at));
+/// ```
+pub struct NotBefore(pub Instant);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Executes a batch on the same connection as every other batch with the same key.
+///
+/// This is set as an extension on the first request of a batch and is only
+/// used once sticky routing is enabled with
+/// [ReWrkBenchmark::set_sticky_routing](crate::ReWrkBenchmark::set_sticky_routing).
+/// A batch is always executed on a single connection, so the key applies to
+/// the whole batch. This lets servers relying on session affinity, i.e. with
+/// per connection state, be benchmarked realistically.
+///
+/// Each worker routes the batches of its own producer, so a key sticks to
+/// one of the connections of the worker which produced it.
+///
+/// ```
+/// use http::Request;
+/// use hyper::Body;
+/// use rewrk_core::StickyKey;
+///
+/// let mut request = Request::new(Body::empty());
+/// request.extensions_mut().insert(StickyKey::new("session-42"));
+/// ```
+pub struct StickyKey(pub u64);
+
+impl StickyKey {
+    /// Creates a key from the hash of the given value.
+    pub fn new(value: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        Self(hasher.finish())
+    }
 }
 
 pub struct Batch {
@@ -28,6 +128,97 @@ pub struct Batch {
     pub requests: Vec<Request<Body>>,
 }
 
+impl Batch {
+    /// Creates a batch of `n` identical requests.
+    ///
+    /// Only a single request is held by the batch, the copies are created
+    /// by the worker as each one is sent. This saves producers allocating
+    /// hundreds of identical requests per batch, i.e. for plain GET load.
+    ///
+    /// Extensions such as [NotBefore] and [StickyKey] only apply to the first
+    /// request, every copy has the same method, URI, version, headers and body.
+    /// The batch has the tag `0`, which can be changed via the `tag` field.
+    ///
+    /// ```
+    /// use http::Request;
+    /// use hyper::body::Bytes;
+    /// use rewrk_core::Batch;
+    ///
+    /// let request = Request::get("/").body(Bytes::new()).unwrap();
+    /// let batch = Batch {
+    ///     tag: 1,
+    ///     ..Batch::repeat(request, 500)
+    /// };
+    /// ```
+    pub fn repeat(request: Request<Bytes>, n: usize) -> Self {
+        if n == 0 {
+            return Self {
+                tag: 0,
+                requests: Vec::new(),
+            };
+        }
+
+        let (parts, body) = request.into_parts();
+        let mut request = Request::from_parts(parts, Body::from(body.clone()));
+        request.extensions_mut().insert(Repeat {
+            body,
+            copies: n - 1,
+        });
+
+        Self {
+            tag: 0,
+            requests: vec![request],
+        }
+    }
+
+    /// Consumes the batch, returning its requests with any
+    /// repeated requests expanded as they are iterated.
+    pub(crate) fn into_requests(self) -> impl Iterator<Item = Request<Body>> {
+        self.requests.into_iter().flat_map(expand_repeated)
+    }
+
+    /// Expands any repeated requests, so every request of the batch
+    /// is held in `requests`.
+    pub(crate) fn expand(&mut self) {
+        if self
+            .requests
+            .iter()
+            .any(|request| request.extensions().get::<Repeat>().is_some())
+        {
+            let batch = mem::take(&mut self.requests);
+            self.requests = batch.into_iter().flat_map(expand_repeated).collect();
+        }
+    }
+}
+
+/// Marks a request created by [Batch::repeat] which is sent several times.
+struct Repeat {
+    body: Bytes,
+    copies: usize,
+}
+
+/// Yields the request followed by its copies if it's repeated.
+fn expand_repeated(mut request: Request<Body>) -> impl Iterator<Item = Request<Body>> {
+    let copies = request.extensions_mut().remove::<Repeat>().map(|repeat| {
+        let method = request.method().clone();
+        let uri = request.uri().clone();
+        let version = request.version();
+        let headers = request.headers().clone();
+
+        std::iter::repeat_with(move || {
+            let mut copy = Request::new(Body::from(repeat.body.clone()));
+            *copy.method_mut() = method.clone();
+            *copy.uri_mut() = uri.clone();
+            *copy.version_mut() = version;
+            *copy.headers_mut() = headers.clone();
+            copy
+        })
+        .take(repeat.copies)
+    });
+
+    std::iter::once(request).chain(copies.into_iter().flatten())
+}
+
 #[async_trait]
 /// A producer creates requests used in benchmarking
 ///
@@ -35,7 +226,8 @@ pub struct Batch {
 /// concurrent connections at once.
 ///
 /// When a producer returns [RequestBatch::End] workers will finish
-/// the remaining requests then shutdown.
+/// the remaining requests then shutdown, see [ProducerEnd] for how this
+/// applies to the other workers.
 ///
 /// # Example
 ///
@@ -88,9 +280,185 @@ pub trait Producer: Send + 'static {
     /// the producer must be able to produce more requests than the target server
     /// can consume, otherwise the statistics may not be as accurate.
     async fn create_batch(&mut self) -> anyhow::Result<RequestBatch>;
+
+    /// Called with the stats of the latest window before the next batch is created.
+    ///
+    /// This is only called once enabled with
+    /// [ReWrkBenchmark::set_window_stats](crate::ReWrkBenchmark::set_window_stats),
+    /// at most once per window. Adaptive producers can use this to grow or
+    /// shrink their batches, i.e. backing off while the p99 latency is above
+    /// a target.
+    fn window_stats(&mut self, _stats: &WindowStats) {}
+}
+
+#[derive(Clone)]
+/// Fails the benchmark if a producer takes too long to create a batch.
+pub(crate) struct StallTimeout {
+    /// The maximum time a producer may take to create a batch.
+    pub timeout: Duration,
+    /// The handle the benchmark is failed with.
+    pub shutdown: ShutdownHandle,
 }
 
-pub type ProducerBatches = Receiver<Batch>;
+/// Creates the next batch, failing the benchmark with
+/// [RunError::ProducerStalled] if the producer stalls.
+async fn next_batch(
+    producer: &mut impl Producer,
+    worker_id: usize,
+    stall_timeout: &Option<StallTimeout>,
+) -> anyhow::Result<RequestBatch> {
+    let stall_timeout = match stall_timeout {
+        None => return producer.create_batch().await,
+        Some(stall_timeout) => stall_timeout,
+    };
+
+    let timeout = stall_timeout.timeout;
+    match tokio::time::timeout(timeout, producer.create_batch()).await {
+        Ok(result) => result,
+        Err(_) => {
+            let error = RunError::ProducerStalled { worker_id, timeout };
+            stall_timeout.shutdown.fail(error.clone());
+            Err(error.into())
+        },
+    }
+}
+
+/// Passes the stats of the latest window to the producer if they have changed.
+fn update_window_stats(
+    producer: &mut impl Producer,
+    stats: &mut Option<watch::Receiver<Option<WindowStats>>>,
+) {
+    let stats = match stats.as_mut() {
+        Some(stats) if stats.has_changed().unwrap_or(false) => stats,
+        _ => return,
+    };
+
+    let latest = *stats.borrow_and_update();
+    if let Some(latest) = latest {
+        producer.window_stats(&latest);
+    }
+}
+
+#[derive(Clone)]
+/// The batches created by a worker's producer.
+pub struct ProducerBatches {
+    batches: Receiver<Batch>,
+    priority: Receiver<Batch>,
+    /// The batches routed to a single connection by their [StickyKey].
+    sticky: Option<Receiver<Batch>>,
+}
+
+impl ProducerBatches {
+    /// Creates a new set of batch channels with the given capacity.
+    pub(crate) fn bounded(buffer_size: usize) -> (BatchSender, Self) {
+        let (tx, batches) = flume::bounded(buffer_size);
+        let (priority_tx, priority) = flume::bounded(buffer_size.max(1));
+
+        let sender = BatchSender {
+            batches: tx,
+            priority: priority_tx,
+        };
+        let batches = Self {
+            batches,
+            priority,
+            sticky: None,
+        };
+        (sender, batches)
+    }
+
+    /// Also receives the batches routed to this connection alone.
+    pub(crate) fn with_sticky(mut self, sticky: Receiver<Batch>) -> Self {
+        self.sticky = Some(sticky);
+        self
+    }
+
+    /// Receives the next batch, priority batches are always received first.
+    ///
+    /// Returns an error once the producer has finished and
+    /// all batches have been received.
+    pub async fn recv_async(&self) -> Result<Batch, RecvError> {
+        self.recv_with_priority().await.map(|(batch, _)| batch)
+    }
+
+    /// Receives the next batch along with if it is a priority batch.
+    ///
+    /// Priority batches are received first, then the batches routed to this
+    /// connection or the shared batches, whichever is available first.
+    pub(crate) async fn recv_with_priority(&self) -> Result<(Batch, bool), RecvError> {
+        let sticky = match self.sticky.as_ref() {
+            None => return self.recv_shared().await,
+            Some(sticky) => sticky,
+        };
+
+        if let Ok(batch) = self.priority.try_recv() {
+            return Ok((batch, true));
+        }
+        if let Ok(batch) = sticky.try_recv() {
+            return Ok((batch, false));
+        }
+
+        let shared = Box::pin(self.recv_shared());
+        match select(sticky.recv_async(), shared).await {
+            Either::Left((Ok(batch), _)) => Ok((batch, false)),
+            Either::Right((Ok(batch), _)) => Ok(batch),
+            Either::Left((Err(_), shared)) => shared.await,
+            Either::Right((Err(_), sticky)) => sticky.await.map(|batch| (batch, false)),
+        }
+    }
+
+    /// Receives the next batch shared by all of the connections.
+    async fn recv_shared(&self) -> Result<(Batch, bool), RecvError> {
+        if let Ok(batch) = self.priority.try_recv() {
+            return Ok((batch, true));
+        }
+
+        let priority = self.priority.recv_async();
+        let batches = self.batches.recv_async();
+        match select(priority, batches).await {
+            Either::Left((Ok(batch), _)) => Ok((batch, true)),
+            Either::Right((Ok(batch), _)) => Ok((batch, false)),
+            Either::Left((Err(_), batches)) => batches.await.map(|batch| (batch, false)),
+            Either::Right((Err(_), priority)) => {
+                priority.await.map(|batch| (batch, true))
+            },
+        }
+    }
+}
+
+#[derive(Clone)]
+/// The sending half of [ProducerBatches].
+pub(crate) struct BatchSender {
+    batches: flume::Sender<Batch>,
+    priority: flume::Sender<Batch>,
+}
+
+impl BatchSender {
+    /// Sends the batch, returning `false` if all receivers have been dropped.
+    pub async fn send(&self, batch: Batch, is_priority: bool) -> bool {
+        let tx = if is_priority {
+            &self.priority
+        } else {
+            &self.batches
+        };
+
+        tx.send_async(batch).await.is_ok()
+    }
+
+    /// Attempts to send the batch without waiting.
+    pub fn try_send(
+        &self,
+        batch: Batch,
+        is_priority: bool,
+    ) -> Result<(), flume::TrySendError<Batch>> {
+        let tx = if is_priority {
+            &self.priority
+        } else {
+            &self.batches
+        };
+
+        tx.try_send(batch)
+    }
+}
 
 /// A sample collector which waits for and calls the
 /// specific collector handler.
@@ -98,13 +466,66 @@ pub struct ProducerActor;
 
 impl ProducerActor {
     /// Spawn a new collector actor for processing incoming samples.
+    ///
+    /// If a tag scheduler is given batches are buffered per tag and
+    /// handed to the connections in the order chosen by the scheduler.
+    ///
+    /// Unless the producer end is [ProducerEnd::Worker] the end signal is set
+    /// once the producer ends, when draining the producer also stops once
+    /// another worker's producer has set the signal.
+    ///
+    /// If a producer pool is given the producer runs on the pool rather than
+    /// the worker's runtime, see [ProducerPool].
+    ///
+    /// If window stats are given the producer is passed the latest stats
+    /// before each batch is created, see [Producer::window_stats].
+    ///
+    /// If a stall timeout is given the benchmark fails once the producer
+    /// takes longer than the timeout to create a batch.
+    #[allow(clippy::too_many_arguments)]
     pub async fn spawn(
         buffer_size: usize,
         worker_id: usize,
-        mut producer: impl Producer,
+        producer: impl Producer,
         ready: oneshot::Receiver<()>,
+        tag_usage: Option<TagUsage>,
+        end: ProducerEnd,
+        end_signal: Arc<AtomicBool>,
+        pool: Option<ProducerPool>,
+        mut window_stats: Option<watch::Receiver<Option<WindowStats>>>,
+        stall_timeout: Option<StallTimeout>,
     ) -> ProducerBatches {
-        let (tx, rx) = flume::bounded(buffer_size);
+        if let Some(usage) = tag_usage {
+            return Self::spawn_scheduled(
+                buffer_size,
+                worker_id,
+                producer,
+                ready,
+                usage,
+                end,
+                end_signal,
+                pool,
+                window_stats,
+                stall_timeout,
+            );
+        }
+
+        if let Some(pool) = pool {
+            return Self::spawn_pooled(
+                buffer_size,
+                worker_id,
+                producer,
+                ready,
+                end,
+                end_signal,
+                pool,
+                window_stats,
+                stall_timeout,
+            );
+        }
+
+        let mut producer = producer;
+        let (tx, rx) = ProducerBatches::bounded(buffer_size);
 
         tokio::spawn(async move {
             info!(worker_id = worker_id, "Starting producer actor.");
@@ -113,9 +534,19 @@ impl ProducerActor {
             producer.ready();
 
             loop {
-                let batch = match producer.create_batch().await {
-                    Ok(RequestBatch::End) => break,
-                    Ok(RequestBatch::Batch(batch)) => batch,
+                if is_drained(worker_id, end, &end_signal) {
+                    break;
+                }
+
+                update_window_stats(&mut producer, &mut window_stats);
+                let result = next_batch(&mut producer, worker_id, &stall_timeout).await;
+                let (batch, is_priority) = match result {
+                    Ok(RequestBatch::End) => {
+                        signal_end(worker_id, end, &end_signal);
+                        break;
+                    },
+                    Ok(RequestBatch::Batch(batch)) => (batch, false),
+                    Ok(RequestBatch::Priority(batch)) => (batch, true),
                     Err(e) => {
                         error!(
                             worker_id = worker_id,
@@ -129,9 +560,217 @@ impl ProducerActor {
                 debug!(
                     worker_id = worker_id,
                     batch_tag = batch.tag,
+                    is_priority = is_priority,
                     "Submitting request batch."
                 );
-                if tx.send_async(batch).await.is_err() {
+                if !tx.send(batch, is_priority).await {
+                    break;
+                }
+            }
+
+            info!(worker_id = worker_id, "Producer actor has shutdown.");
+        });
+
+        rx
+    }
+
+    /// Spawns the producer on the pool, handing off its batches in groups.
+    ///
+    /// A task on the worker's runtime hands the batches on to the connections,
+    /// so the pool only wakes the worker once per group. Priority batches are
+    /// handed off straight away.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_pooled(
+        buffer_size: usize,
+        worker_id: usize,
+        mut producer: impl Producer,
+        ready: oneshot::Receiver<()>,
+        end: ProducerEnd,
+        end_signal: Arc<AtomicBool>,
+        pool: ProducerPool,
+        mut window_stats: Option<watch::Receiver<Option<WindowStats>>>,
+        stall_timeout: Option<StallTimeout>,
+    ) -> ProducerBatches {
+        let handoff_size = pool.handoff_size;
+        let (tx, rx) = ProducerBatches::bounded(buffer_size);
+        let (handoff_tx, handoff_rx) =
+            flume::bounded::<Vec<Batch>>((buffer_size / handoff_size).max(1));
+
+        let priority_tx = tx.clone();
+        pool.spawn(async move {
+            info!(worker_id = worker_id, "Starting pooled producer actor.");
+
+            let _ = ready.await;
+            producer.ready();
+
+            let mut pending = Vec::with_capacity(handoff_size);
+            let mut flush_at = Instant::now();
+            'produce: loop {
+                if is_drained(worker_id, end, &end_signal) {
+                    break;
+                }
+
+                update_window_stats(&mut producer, &mut window_stats);
+                let mut next =
+                    Box::pin(next_batch(&mut producer, worker_id, &stall_timeout));
+                // Partial handoffs are flushed while waiting on a slow producer,
+                // so produced batches aren't held back indefinitely.
+                let result = loop {
+                    if pending.is_empty() {
+                        break next.await;
+                    }
+
+                    let flush = Box::pin(tokio::time::sleep_until(flush_at.into()));
+                    match select(next.as_mut(), flush).await {
+                        Either::Left((result, _)) => break result,
+                        Either::Right(_) => {
+                            let batches = mem::replace(
+                                &mut pending,
+                                Vec::with_capacity(handoff_size),
+                            );
+                            if handoff_tx.send_async(batches).await.is_err() {
+                                break 'produce;
+                            }
+                        },
+                    }
+                };
+
+                match result {
+                    Ok(RequestBatch::End) => {
+                        signal_end(worker_id, end, &end_signal);
+                        break;
+                    },
+                    Ok(RequestBatch::Batch(batch)) => {
+                        if pending.is_empty() {
+                            flush_at = Instant::now() + MAX_HANDOFF_DELAY;
+                        }
+                        pending.push(batch);
+                    },
+                    Ok(RequestBatch::Priority(batch)) => {
+                        if !priority_tx.send(batch, true).await {
+                            break;
+                        }
+                    },
+                    Err(e) => {
+                        error!(
+                            worker_id = worker_id,
+                            error = ?e,
+                            "Failed to produce batch due to error, aborting...",
+                        );
+                        break;
+                    },
+                }
+
+                if pending.len() >= handoff_size {
+                    debug!(
+                        worker_id = worker_id,
+                        num_batches = pending.len(),
+                        "Handing off request batches."
+                    );
+                    let batches =
+                        mem::replace(&mut pending, Vec::with_capacity(handoff_size));
+                    if handoff_tx.send_async(batches).await.is_err() {
+                        break;
+                    }
+                }
+            }
+
+            // Batches which have already been produced are still sent.
+            if !pending.is_empty() {
+                let _ = handoff_tx.send_async(pending).await;
+            }
+
+            info!(worker_id = worker_id, "Producer actor has shutdown.");
+        });
+
+        tokio::spawn(async move {
+            while let Ok(batches) = handoff_rx.recv_async().await {
+                for batch in batches {
+                    debug!(
+                        worker_id = worker_id,
+                        batch_tag = batch.tag,
+                        "Submitting request batch."
+                    );
+                    if !tx.send(batch, false).await {
+                        return;
+                    }
+                }
+            }
+        });
+
+        rx
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_scheduled(
+        buffer_size: usize,
+        worker_id: usize,
+        mut producer: impl Producer,
+        ready: oneshot::Receiver<()>,
+        usage: TagUsage,
+        end: ProducerEnd,
+        end_signal: Arc<AtomicBool>,
+        pool: Option<ProducerPool>,
+        mut window_stats: Option<watch::Receiver<Option<WindowStats>>>,
+        stall_timeout: Option<StallTimeout>,
+    ) -> ProducerBatches {
+        // Batches are only handed over once a connection is ready for them
+        // so the scheduler decides with the latest usage.
+        let (tx, rx) = ProducerBatches::bounded(0);
+        let buffer_size = usage.scheduler().lookahead().unwrap_or(buffer_size);
+
+        spawn_on(pool.as_ref(), async move {
+            info!(worker_id = worker_id, "Starting scheduled producer actor.");
+
+            let _ = ready.await;
+            producer.ready();
+
+            let mut queues = TagQueues::new(usage);
+            let mut is_finished = false;
+            loop {
+                while !is_finished && queues.len() < buffer_size {
+                    // Batches which are already queued are still sent when draining.
+                    if is_drained(worker_id, end, &end_signal) {
+                        is_finished = true;
+                        break;
+                    }
+
+                    update_window_stats(&mut producer, &mut window_stats);
+                    match next_batch(&mut producer, worker_id, &stall_timeout).await {
+                        Ok(RequestBatch::End) => {
+                            signal_end(worker_id, end, &end_signal);
+                            is_finished = true;
+                        },
+                        Ok(RequestBatch::Batch(batch)) => queues.push(batch),
+                        // Priority batches skip the scheduler.
+                        Ok(RequestBatch::Priority(batch)) => {
+                            if !tx.send(batch, true).await {
+                                is_finished = true;
+                            }
+                        },
+                        Err(e) => {
+                            error!(
+                                worker_id = worker_id,
+                                error = ?e,
+                                "Failed to produce batch due to error, aborting...",
+                            );
+                            info!(worker_id = worker_id, "Producer actor has shutdown.");
+                            return;
+                        },
+                    }
+                }
+
+                let batch = match queues.pop() {
+                    None => break,
+                    Some(batch) => batch,
+                };
+
+                debug!(
+                    worker_id = worker_id,
+                    batch_tag = batch.tag,
+                    "Submitting scheduled request batch."
+                );
+                if !tx.send(batch, false).await {
                     break;
                 }
             }
@@ -142,3 +781,99 @@ impl ProducerActor {
         rx
     }
 }
+
+/// The longest a produced batch waits on the producer pool for the rest of
+/// its handoff before being handed off anyway.
+pub(crate) const MAX_HANDOFF_DELAY: Duration = Duration::from_millis(5);
+
+#[derive(Clone)]
+/// A dedicated multi-threaded runtime which producers are run on.
+///
+/// By default each producer runs on its worker's runtime alongside the
+/// connections, so producers doing CPU heavy work, i.e. templating request
+/// bodies, slow down the connections. Running them on a separate pool frees
+/// up the workers, and handing off several batches at a time cuts the number
+/// of wakeups at very high request rates.
+///
+/// Batches are handed off once `handoff_size` have been produced, or once the
+/// oldest has waited for [MAX_HANDOFF_DELAY], so slow producers still have
+/// their batches sent promptly.
+pub(crate) struct ProducerPool {
+    runtime: Arc<PoolRuntime>,
+    /// The number of batches handed to a worker at once.
+    handoff_size: usize,
+}
+
+impl ProducerPool {
+    /// Creates a new pool with the given number of threads.
+    pub fn new(threads: usize, handoff_size: usize) -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .worker_threads(threads)
+            .thread_name("rewrk-producer")
+            .build()?;
+
+        Ok(Self {
+            runtime: Arc::new(PoolRuntime(Some(runtime))),
+            handoff_size,
+        })
+    }
+
+    fn spawn<F>(&self, fut: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if let Some(runtime) = self.runtime.0.as_ref() {
+            runtime.spawn(fut);
+        }
+    }
+}
+
+/// Shuts down the pool without blocking, as it may
+/// be dropped from within an async context.
+struct PoolRuntime(Option<Runtime>);
+
+impl Drop for PoolRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Spawns the task on the pool if given, otherwise on the current runtime.
+fn spawn_on<F>(pool: Option<&ProducerPool>, fut: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    match pool {
+        Some(pool) => pool.spawn(fut),
+        None => {
+            tokio::spawn(fut);
+        },
+    }
+}
+
+/// Signals all workers to finish once a producer has ended.
+fn signal_end(worker_id: usize, end: ProducerEnd, end_signal: &AtomicBool) {
+    if end != ProducerEnd::Worker {
+        info!(
+            worker_id = worker_id,
+            "Producer has ended, ending benchmark."
+        );
+        end_signal.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Checks if the producer should stop creating batches because
+/// another worker's producer has ended a draining benchmark.
+fn is_drained(worker_id: usize, end: ProducerEnd, end_signal: &AtomicBool) -> bool {
+    let is_drained = end == ProducerEnd::Drain && end_signal.load(Ordering::Relaxed);
+    if is_drained {
+        info!(
+            worker_id = worker_id,
+            "Benchmark has ended, draining produced batches."
+        );
+    }
+    is_drained
+}
@@ -0,0 +1,279 @@
+//! A Prometheus metrics endpoint for observing benchmarks as they run.
+//!
+//! This is enabled with the `prometheus` feature. Once started via
+//! [ReWrkBenchmark::serve_metrics](crate::ReWrkBenchmark::serve_metrics) every
+//! request to the server is answered with the samples collected so far in the
+//! Prometheus text format, which lets dashboards follow long soak tests live.
+//!
+//! Every metric is labeled with the sample tag and the labels set via
+//! [ReWrkBenchmark::set_labels](crate::ReWrkBenchmark::set_labels). Samples are
+//! only collected at the end of each sample window, so recent requests may not
+//! be included yet.
+//!
+//! The following metrics are exposed:
+//!
+//! - `rewrk_requests_total` the number of requests sent.
+//! - `rewrk_failed_requests_total` the number of requests which failed.
+//! - `rewrk_errors_total` the number of errors of each kind.
+//! - `rewrk_requests_per_second` the request rate since the previous scrape.
+//! - `rewrk_latency_seconds` a summary of the request latency.
+//! - `rewrk_samples_processed` the number of samples processed by the collector.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use http::header::CONTENT_TYPE;
+use http::{Request, Response, StatusCode};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::recording::{CollectorMailbox, CollectorMessage};
+use crate::{Sample, Snapshot};
+
+/// The content type of the Prometheus text format.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4";
+/// The latency quantiles exported by the summary.
+const QUANTILES: [f64; 5] = [0.5, 0.75, 0.9, 0.99, 0.999];
+
+/// A running Prometheus metrics endpoint.
+///
+/// The server is shutdown when dropped.
+pub struct MetricsServer {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    pub(crate) async fn spawn(
+        addr: SocketAddr,
+        collector: CollectorMailbox,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let listener = listener.into_std()?;
+
+        let exporter = Arc::new(Exporter {
+            collector,
+            last_scrape: Mutex::default(),
+        });
+        let make_service = make_service_fn(move |_| {
+            let exporter = exporter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let exporter = exporter.clone();
+                    async move { Ok::<_, Infallible>(exporter.respond(request).await) }
+                }))
+            }
+        });
+
+        let server = Server::from_tcp(listener)
+            .map_err(|e| io::Error::other(e.to_string()))?
+            .serve(make_service);
+        let handle = tokio::spawn(async move {
+            if let Err(e) = server.await {
+                warn!(error = ?e, "Metrics server exited with error.");
+            }
+        });
+
+        info!(addr = %addr, "Serving Prometheus metrics.");
+        Ok(Self { addr, handle })
+    }
+
+    /// The address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+struct Exporter {
+    collector: CollectorMailbox,
+    /// The time and number of requests of each tag at the previous scrape.
+    last_scrape: Mutex<BTreeMap<usize, (Instant, u64)>>,
+}
+
+impl Exporter {
+    async fn respond(&self, request: Request<Body>) -> Response<Body> {
+        if request.uri().path() != "/metrics" {
+            return status(StatusCode::NOT_FOUND);
+        }
+
+        let snapshot = match self.snapshot().await {
+            None => return status(StatusCode::SERVICE_UNAVAILABLE),
+            Some(snapshot) => snapshot,
+        };
+
+        Response::builder()
+            .header(CONTENT_TYPE, TEXT_FORMAT)
+            .body(Body::from(self.render(&snapshot)))
+            .expect("Build response")
+    }
+
+    async fn snapshot(&self) -> Option<Snapshot> {
+        let (tx, rx) = oneshot::channel();
+        self.collector
+            .send_async(CollectorMessage::Snapshot(tx))
+            .await
+            .ok()?;
+        rx.await.ok()
+    }
+
+    fn render(&self, snapshot: &Snapshot) -> String {
+        let now = Instant::now();
+        let mut last_scrape = self.last_scrape.lock().unwrap();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "rewrk_samples_processed",
+            "gauge",
+            "The number of samples processed by the collector.",
+        );
+        let _ = writeln!(
+            out,
+            "rewrk_samples_processed {}",
+            snapshot.samples_processed()
+        );
+
+        header(
+            &mut out,
+            "rewrk_requests_total",
+            "counter",
+            "The number of requests sent.",
+        );
+        for sample in snapshot.samples() {
+            let labels = labels(sample, &[]);
+            let total = sample.total_requests();
+            let _ = writeln!(out, "rewrk_requests_total{labels} {total}");
+        }
+
+        header(
+            &mut out,
+            "rewrk_failed_requests_total",
+            "counter",
+            "The number of requests which failed.",
+        );
+        for sample in snapshot.samples() {
+            let labels = labels(sample, &[]);
+            let failed = sample.failed_requests();
+            let _ = writeln!(out, "rewrk_failed_requests_total{labels} {failed}");
+        }
+
+        header(
+            &mut out,
+            "rewrk_errors_total",
+            "counter",
+            "The number of errors of each kind.",
+        );
+        for sample in snapshot.samples() {
+            for (kind, count) in sample.error_counts() {
+                let kind = format!("{kind:?}");
+                let labels = labels(sample, &[("kind", &kind)]);
+                let _ = writeln!(out, "rewrk_errors_total{labels} {count}");
+            }
+        }
+
+        header(
+            &mut out,
+            "rewrk_requests_per_second",
+            "gauge",
+            "The request rate since the previous scrape.",
+        );
+        for sample in snapshot.samples() {
+            let total = sample.total_requests();
+            let rate = match last_scrape.insert(sample.tag(), (now, total)) {
+                Some((at, previous)) if now > at => {
+                    total.saturating_sub(previous) as f64
+                        / now.duration_since(at).as_secs_f64()
+                },
+                // The average rate is used until there is a previous scrape.
+                _ => sample.requests_per_sec(),
+            };
+            let labels = labels(sample, &[]);
+            let _ = writeln!(out, "rewrk_requests_per_second{labels} {rate}");
+        }
+
+        header(
+            &mut out,
+            "rewrk_latency_seconds",
+            "summary",
+            "The latency of requests.",
+        );
+        for sample in snapshot.samples() {
+            for quantile in QUANTILES {
+                let value = sample.latency_percentile(quantile * 100.0).as_secs_f64();
+                let quantile = quantile.to_string();
+                let labels = labels(sample, &[("quantile", &quantile)]);
+                let _ = writeln!(out, "rewrk_latency_seconds{labels} {value}");
+            }
+
+            let latency = sample.latency();
+            let count = latency.len();
+            let sum = latency.mean() * count as f64 / 1_000_000.0;
+            let labels = labels(sample, &[]);
+            let _ = writeln!(out, "rewrk_latency_seconds_sum{labels} {sum}");
+            let _ = writeln!(out, "rewrk_latency_seconds_count{labels} {count}");
+        }
+
+        out
+    }
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("Build response")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Formats the label set of the sample along with any extra labels.
+fn labels(sample: &Sample, extra: &[(&str, &str)]) -> String {
+    let tag = sample.tag().to_string();
+    let labels = sample
+        .labels()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain([("tag", tag.as_str())])
+        .chain(extra.iter().copied())
+        .map(|(name, value)| format!("{}=\"{}\"", label_name(name), escape(value)))
+        .collect::<Vec<_>>();
+
+    format!("{{{}}}", labels.join(","))
+}
+
+/// Replaces any characters which aren't valid in a label name.
+fn label_name(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' | 'A'..='Z' | '_' => c,
+            '0'..='9' if i > 0 => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use super::collector::SampleCollector;
+use super::sample::Sample;
+use crate::runtime::BenchmarkPlan;
+
+/// The file extension used for sample archives.
+pub const ARCHIVE_EXTENSION: &str = "rewrk";
+
+/// The magic bytes every archive starts with.
+const MAGIC: &[u8; 6] = b"REWRK\0";
+/// The version of the archive format written.
+const FORMAT_VERSION: u16 = 6;
+/// The largest frame an archive may contain.
+///
+/// Frames are read into memory whole, so this stops a corrupt length prefix
+/// from allocating an unbounded buffer.
+const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+/// An archive could not be written or read.
+pub enum ArchiveError {
+    #[error(transparent)]
+    /// The archive could not be read from or written to.
+    Io(#[from] io::Error),
+    #[error("The file is not a rewrk archive")]
+    /// The file doesn't start with the archive magic bytes.
+    InvalidMagic,
+    #[error("The archive format version {0} is not supported")]
+    /// The archive was written with an unknown version of the format.
+    UnsupportedVersion(u16),
+    #[error("The archive header is invalid: {0}")]
+    /// The archive header could not be (de)serialized.
+    Header(#[from] serde_json::Error),
+    #[error("An archived sample is invalid: {0}")]
+    /// A sample could not be (de)serialized.
+    Sample(#[from] bincode::Error),
+    #[error("The archive frame of {0} bytes exceeds the maximum frame size")]
+    /// A frame is larger than any archive should contain, i.e. it's corrupt.
+    FrameTooLarge(usize),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The header of a sample archive, describing the run that produced it.
+pub struct ArchiveHeader {
+    /// The wall clock time the archive was created.
+    pub created_at: SystemTime,
+    /// The version of rewrk-core which wrote the archive.
+    pub rewrk_version: String,
+    /// The plan of the benchmark which produced the samples, if known.
+    pub plan: Option<BenchmarkPlan>,
+    /// Arbitrary metadata describing the run, i.e. the commit being benchmarked.
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Default for ArchiveHeader {
+    fn default() -> Self {
+        Self {
+            created_at: SystemTime::now(),
+            rewrk_version: env!("CARGO_PKG_VERSION").to_string(),
+            plan: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+}
+
+/// Writes samples to a `.rewrk` archive.
+///
+/// An archive is the canonical unit for analysing a run offline, it holds
+/// the [ArchiveHeader] followed by every sample, gzip compressed.
+///
+/// The layout is the magic bytes `REWRK\0` and the format version as a
+/// little endian `u16`, followed by a gzip stream of length prefixed frames.
+/// The first frame is the JSON encoded header, every other frame is a
+/// bincode encoded [Sample] with its histograms in the HdrHistogram V2 format.
+pub struct ArchiveWriter<W: Write> {
+    encoder: GzEncoder<W>,
+    buffer: Vec<u8>,
+}
+
+impl ArchiveWriter<BufWriter<File>> {
+    /// Creates a new archive at the given path, replacing any existing file.
+    pub fn create(
+        path: impl AsRef<Path>,
+        header: &ArchiveHeader,
+    ) -> Result<Self, ArchiveError> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::new(file), header)
+    }
+
+    /// Resumes writing to an existing archive, i.e. one left behind by
+    /// a crashed benchmark.
+    ///
+    /// The archive's header and every sample up to its last checkpoint are
+    /// kept, new samples are appended after them. This returns the writer along
+    /// with what was kept, so the rounds and windows of the new samples can
+    /// continue after those of the kept samples rather than colliding with them.
+    pub fn resume(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, ResumedArchive), ArchiveError> {
+        let path = path.as_ref();
+        let reader = ArchiveReader::open(path)?;
+
+        // The existing samples are copied into a new archive, as the gzip
+        // stream of an unfinished archive can't be appended to.
+        let mut resume_path = PathBuf::from(path).into_os_string();
+        resume_path.push(".resume");
+        let mut writer = ArchiveWriter::create(&resume_path, reader.header())?;
+
+        let mut resumed = ResumedArchive::default();
+        for sample in reader {
+            let sample = sample?;
+            writer.write_sample(&sample)?;
+            resumed.samples += 1;
+            resumed.rounds = resumed.rounds.max(sample.metadata().round + 1);
+            resumed.windows = resumed.windows.max(sample.window_index() + 1);
+        }
+        writer.checkpoint()?;
+        std::fs::rename(&resume_path, path)?;
+
+        Ok((writer, resumed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The samples kept from an archive when it was resumed.
+pub struct ResumedArchive {
+    /// The number of samples kept.
+    pub samples: usize,
+    /// The number of rounds covered by the kept samples, i.e. the round
+    /// after the last.
+    pub rounds: usize,
+    /// The number of sample windows covered by the kept samples, i.e. the
+    /// window index after the last.
+    pub windows: usize,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Creates a new archive writing to the given writer.
+    pub fn new(mut writer: W, header: &ArchiveHeader) -> Result<Self, ArchiveError> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+
+        let mut archive = Self {
+            encoder: GzEncoder::new(writer, Compression::default()),
+            buffer: Vec::new(),
+        };
+
+        archive.buffer = serde_json::to_vec(header)?;
+        archive.write_frame()?;
+
+        Ok(archive)
+    }
+
+    /// Appends a sample to the archive.
+    pub fn write_sample(&mut self, sample: &Sample) -> Result<(), ArchiveError> {
+        self.buffer.clear();
+        bincode::serialize_into(&mut self.buffer, sample)?;
+        self.write_frame()
+    }
+
+    /// Flushes every sample written so far through to the inner writer.
+    ///
+    /// If the process is killed before the archive is finished, the
+    /// archive can still be read up to the last checkpoint, see
+    /// [ArchiveReader::is_truncated].
+    pub fn checkpoint(&mut self) -> Result<(), ArchiveError> {
+        self.encoder.flush()?;
+        self.encoder.get_mut().flush()?;
+        Ok(())
+    }
+
+    /// Completes the archive, returning the inner writer.
+    ///
+    /// Archives which aren't finished may be missing their last samples.
+    pub fn finish(self) -> Result<W, ArchiveError> {
+        let mut writer = self.encoder.finish()?;
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    fn write_frame(&mut self) -> Result<(), ArchiveError> {
+        if self.buffer.len() > MAX_FRAME_SIZE {
+            return Err(ArchiveError::FrameTooLarge(self.buffer.len()));
+        }
+
+        let len = self.buffer.len() as u32;
+        self.encoder.write_all(&len.to_le_bytes())?;
+        self.encoder.write_all(&self.buffer)?;
+        Ok(())
+    }
+}
+
+/// Reads the samples of a `.rewrk` archive written by an [ArchiveWriter].
+///
+/// Samples are read lazily by iterating over the reader.
+///
+/// Archives which were never finished, i.e. because the benchmark crashed,
+/// are read up to the last complete sample rather than failing.
+pub struct ArchiveReader<R: Read> {
+    header: ArchiveHeader,
+    decoder: GzDecoder<R>,
+    buffer: Vec<u8>,
+    truncated: bool,
+}
+
+impl ArchiveReader<BufReader<File>> {
+    /// Opens the archive at the given path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> ArchiveReader<R> {
+    /// Reads an archive from the given reader.
+    pub fn new(mut reader: R) -> Result<Self, ArchiveError> {
+        let mut magic = [0; MAGIC.len()];
+        match reader.read_exact(&mut magic) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(ArchiveError::InvalidMagic)
+            },
+            other => other?,
+        }
+        if &magic != MAGIC {
+            return Err(ArchiveError::InvalidMagic);
+        }
+
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(version));
+        }
+
+        let mut decoder = GzDecoder::new(reader);
+        let mut buffer = Vec::new();
+        if !read_frame(&mut decoder, &mut buffer)? {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let header = serde_json::from_slice(&buffer)?;
+
+        Ok(Self {
+            header,
+            decoder,
+            buffer,
+            truncated: false,
+        })
+    }
+
+    /// The header of the archive.
+    pub fn header(&self) -> &ArchiveHeader {
+        &self.header
+    }
+
+    /// If the archive ended part way through, without being finished.
+    ///
+    /// This is only known once every sample has been read.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Reads the next sample of the archive, if any remain.
+    pub fn read_sample(&mut self) -> Result<Option<Sample>, ArchiveError> {
+        if self.truncated {
+            return Ok(None);
+        }
+
+        match read_frame(&mut self.decoder, &mut self.buffer) {
+            Ok(true) => {},
+            Ok(false) => return Ok(None),
+            Err(ArchiveError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                self.truncated = true;
+                return Ok(None);
+            },
+            Err(e) => return Err(e),
+        }
+
+        let sample = bincode::deserialize(&self.buffer)?;
+        Ok(Some(sample))
+    }
+}
+
+impl<R: Read> Iterator for ArchiveReader<R> {
+    type Item = Result<Sample, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_sample().transpose()
+    }
+}
+
+/// Reads the next frame into the buffer, returning `false` at the end of the archive.
+fn read_frame(
+    reader: &mut impl Read,
+    buffer: &mut Vec<u8>,
+) -> Result<bool, ArchiveError> {
+    let mut len = [0; 4];
+    let mut filled = 0;
+    while filled < len.len() {
+        match reader.read(&mut len[filled..])? {
+            0 if filled == 0 => return Ok(false),
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            n => filled += n,
+        }
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(ArchiveError::FrameTooLarge(len));
+    }
+
+    buffer.resize(len, 0);
+    reader.read_exact(buffer)?;
+    Ok(true)
+}
+
+/// A collector which writes every sample to a `.rewrk` archive.
+///
+/// Call [ArchiveCollector::finish] once the benchmark has completed to
+/// ensure the archive is completely written.
+///
+/// ```no_run
+/// use rewrk_core::{ArchiveCollector, ArchiveHeader};
+///
+/// # fn create() -> Result<(), rewrk_core::ArchiveError> {
+/// let mut header = ArchiveHeader::default();
+/// header.metadata.insert("commit".into(), "a1b2c3d".into());
+///
+/// let collector = ArchiveCollector::create("results.rewrk", &header)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// For long running benchmarks a checkpoint interval can be set, so the
+/// archive can be analysed or resumed with [ArchiveCollector::resume]
+/// if the benchmark crashes part way through.
+///
+/// Samples are written on Tokio's blocking thread pool, so the file IO
+/// doesn't stall the collector's runtime.
+pub struct ArchiveCollector {
+    /// The writer, only taken while it's in use on the blocking thread pool.
+    writer: Option<ArchiveWriter<BufWriter<File>>>,
+    checkpoint_interval: Option<Duration>,
+    last_checkpoint: Instant,
+    resumed: ResumedArchive,
+}
+
+impl ArchiveCollector {
+    /// Creates a new archive collector writing to the given path.
+    pub fn create(
+        path: impl AsRef<Path>,
+        header: &ArchiveHeader,
+    ) -> Result<Self, ArchiveError> {
+        let writer = ArchiveWriter::create(path, header)?;
+        Ok(Self::from_writer(writer, ResumedArchive::default()))
+    }
+
+    /// Resumes writing to an existing archive, i.e. one left behind by
+    /// a crashed benchmark.
+    ///
+    /// The archive's header and every sample up to its last checkpoint are
+    /// kept, new samples are appended after them. The window indexes of new
+    /// samples continue after those of the kept samples, so the windows of
+    /// the two runs are reported separately.
+    pub fn resume(path: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+        let (writer, resumed) = ArchiveWriter::resume(path)?;
+        Ok(Self::from_writer(writer, resumed))
+    }
+
+    fn from_writer(
+        writer: ArchiveWriter<BufWriter<File>>,
+        resumed: ResumedArchive,
+    ) -> Self {
+        Self {
+            writer: Some(writer),
+            checkpoint_interval: None,
+            last_checkpoint: Instant::now(),
+            resumed,
+        }
+    }
+
+    /// Checkpoints the archive at most once per interval, see
+    /// [ArchiveWriter::checkpoint].
+    pub fn set_checkpoint_interval(&mut self, interval: Duration) {
+        self.checkpoint_interval = Some(interval);
+    }
+
+    /// The number of samples kept from the archive when it was resumed.
+    pub fn resumed_samples(&self) -> usize {
+        self.resumed.samples
+    }
+
+    /// Flushes every sample collected so far to the archive.
+    pub async fn checkpoint(&mut self) -> Result<(), ArchiveError> {
+        self.last_checkpoint = Instant::now();
+        self.with_writer(|writer| writer.checkpoint()).await
+    }
+
+    /// Completes the archive.
+    pub async fn finish(mut self) -> Result<(), ArchiveError> {
+        let writer = self.writer.take().expect("Archive writer is in use");
+        tokio::task::spawn_blocking(move || writer.finish())
+            .await
+            .expect("Archive writer panicked")?;
+        Ok(())
+    }
+
+    /// Runs the given function with the writer on the blocking thread pool.
+    async fn with_writer<F>(&mut self, func: F) -> Result<(), ArchiveError>
+    where
+        F: FnOnce(&mut ArchiveWriter<BufWriter<File>>) -> Result<(), ArchiveError>
+            + Send
+            + 'static,
+    {
+        let mut writer = self.writer.take().expect("Archive writer is in use");
+        let (writer, result) = tokio::task::spawn_blocking(move || {
+            let result = func(&mut writer);
+            (writer, result)
+        })
+        .await
+        .expect("Archive writer panicked");
+        self.writer = Some(writer);
+        result
+    }
+}
+
+#[async_trait::async_trait]
+impl SampleCollector for ArchiveCollector {
+    async fn process_sample(&mut self, mut sample: Sample) -> anyhow::Result<()> {
+        sample.offset_window_index(self.resumed.windows);
+        let checkpoint = self
+            .checkpoint_interval
+            .is_some_and(|interval| self.last_checkpoint.elapsed() >= interval);
+        if checkpoint {
+            self.last_checkpoint = Instant::now();
+        }
+
+        self.with_writer(move |writer| {
+            writer.write_sample(&sample)?;
+            if checkpoint {
+                writer.checkpoint()?;
+            }
+            Ok(())
+        })
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,16 +1,120 @@
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
 use flume::Sender;
+use futures_util::future::{select, Either};
+use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 
+use super::connect::ConnectSample;
+use super::filter::MetricFilter;
+use super::imbalance::ImbalanceDetector;
+use super::merger::SampleMerger;
+use super::metric::{Annotation, Metric};
 use super::sample::Sample;
+use super::snapshot::{ProgressSnapshot, Snapshot};
+use super::window::StatsWindow;
+use crate::Phase;
 
 #[async_trait]
 /// A collector for processing submitted samples.
 pub trait SampleCollector: Send + 'static {
     async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()>;
+
+    /// The records the collector is interested in.
+    ///
+    /// This is called once when the benchmark is created, records which
+    /// don't match the filter are never passed to the collector.
+    /// By default every record is passed.
+    fn filter(&self) -> MetricFilter {
+        MetricFilter::all()
+    }
+
+    /// Called with every record sent by the benchmark.
+    ///
+    /// By default samples, connect samples and annotations are passed to
+    /// their own handlers and any other records are ignored. Overriding this allows
+    /// a collector to receive every record type, including ones added in
+    /// the future.
+    async fn process_metric(&mut self, metric: Metric) -> anyhow::Result<()> {
+        match metric {
+            Metric::Sample(sample) => self.process_sample(*sample).await,
+            Metric::ConnectSample(sample) => self.process_connect_sample(*sample).await,
+            Metric::Annotation(annotation) => self.process_annotation(annotation).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Called each time a worker attempts to establish a new connection.
+    async fn process_connect_sample(
+        &mut self,
+        _sample: ConnectSample,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called with each annotation added via
+    /// [ReWrkBenchmark::annotate](crate::ReWrkBenchmark::annotate).
+    async fn process_annotation(
+        &mut self,
+        _annotation: Annotation,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called before any samples of the given benchmark round are processed.
+    ///
+    /// Samples from a previous round are always processed before this is called.
+    async fn start_round(&mut self, _round: usize) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called once all samples of the given benchmark round have been processed.
+    async fn end_round(&mut self, _round: usize) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called before any samples of the given benchmark phase are processed
+    /// when running phases via
+    /// [ReWrkBenchmark::run_phases](crate::ReWrkBenchmark::run_phases).
+    ///
+    /// Samples only carry the [index](crate::SampleMetadata::phase) of
+    /// their phase, this maps the index to the phase and its label.
+    async fn start_phase(
+        &mut self,
+        _index: usize,
+        _phase: &Phase,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
-pub type CollectorMailbox = Sender<Sample>;
+pub type CollectorMailbox = Sender<CollectorMessage>;
+
+/// A message sent to the [CollectorActor].
+pub enum CollectorMessage {
+    /// A record to be processed by the collector.
+    Metric(Metric),
+    /// Starts merging a copy of each sample for snapshots from now on.
+    EnableSnapshots,
+    /// Starts tracking the load of each worker and connection from now on,
+    /// with the given imbalance threshold.
+    EnableImbalanceDetection(f64),
+    /// A request for a snapshot of the samples processed so far.
+    ///
+    /// The sender is dropped if snapshots have not been enabled.
+    Snapshot(oneshot::Sender<Snapshot>),
+    /// A request for the progress of the benchmark started at the given time.
+    Progress(Instant, oneshot::Sender<ProgressSnapshot>),
+    /// Marks the start of a benchmark round, sent before any of its samples.
+    StartRound(usize),
+    /// Marks the end of a benchmark round, sent after all of its samples.
+    EndRound(usize),
+    /// Marks the start of a benchmark phase, sent before any of its samples.
+    StartPhase(usize, Box<Phase>),
+    /// Publishes the stats of each window of samples from now on.
+    StatsWindow(Box<StatsWindow>),
+}
 
 /// A sample collector which waits for and calls the
 /// specific collector handler.
@@ -36,7 +140,10 @@ pub type CollectorMailbox = Sender<Sample>;
 ///     }
 /// }
 /// ```
-pub struct CollectorActor<C>(pub(crate) JoinHandle<C>);
+pub struct CollectorActor<C> {
+    handle: JoinHandle<DrainedCollector<C>>,
+    abort: oneshot::Sender<()>,
+}
 
 impl<C> CollectorActor<C>
 where
@@ -45,21 +152,214 @@ where
     /// Spawn a new collector actor for processing incoming samples.
     pub async fn spawn(mut collector: C) -> (Self, CollectorMailbox) {
         let (tx, rx) = flume::unbounded();
+        let (abort, mut aborted) = oneshot::channel();
 
         let handle = tokio::spawn(async move {
             info!("Starting collector actor");
 
-            while let Ok(sample) = rx.recv_async().await {
-                trace!(sample = ?sample, "Collector actor received processing sample.");
-                if let Err(e) = collector.process_sample(sample).await {
-                    warn!(error = ?e, "Collector failed to process sample due to error.");
+            let filter = collector.filter();
+            if !filter.is_all() {
+                debug!(filter = ?filter, "Collector only receives filtered records.");
+            }
+
+            let mut merger: Option<SampleMerger> = None;
+            let mut stats_window: Option<StatsWindow> = None;
+            let mut imbalance: Option<ImbalanceDetector> = None;
+            let mut samples_processed = 0;
+            let mut requests_sent = 0;
+            let mut errors = 0;
+            let mut dropped_samples = 0;
+            let mut current_phase = None;
+            loop {
+                let message = match select(rx.recv_async(), &mut aborted).await {
+                    Either::Left((Ok(message), _)) => message,
+                    Either::Left((Err(_), _)) => break,
+                    Either::Right(_) => {
+                        dropped_samples += count_samples(&rx);
+                        break;
+                    },
+                };
+
+                let is_sample = is_sample(&message);
+                let process = match message {
+                    CollectorMessage::Metric(metric) => {
+                        trace!(metric = ?metric, "Collector actor received processing metric.");
+                        match metric {
+                            Metric::Sample(ref sample) => {
+                                samples_processed += 1;
+                                requests_sent += sample.total_requests();
+                                errors += sample.failed_requests();
+                                if let Some(imbalance) = imbalance.as_mut() {
+                                    imbalance.add_sample(sample);
+                                }
+                                if let Some(window) = stats_window.as_mut() {
+                                    window.add_sample(sample);
+                                }
+                                if let Some(merger) = merger.as_mut() {
+                                    merger.add_sample(sample.as_ref().clone());
+                                }
+                            },
+                            Metric::WorkerReport(ref report) => {
+                                if let Some(imbalance) = imbalance.as_mut() {
+                                    imbalance.add_worker_report(report);
+                                }
+                            },
+                            _ => {},
+                        }
+                        if !filter.matches(&metric) {
+                            continue;
+                        }
+                        collector.process_metric(metric)
+                    },
+                    CollectorMessage::EnableSnapshots => {
+                        merger.get_or_insert_with(SampleMerger::default);
+                        continue;
+                    },
+                    CollectorMessage::EnableImbalanceDetection(threshold) => {
+                        imbalance
+                            .get_or_insert_with(|| ImbalanceDetector::new(threshold));
+                        continue;
+                    },
+                    CollectorMessage::Snapshot(tx) => {
+                        if let Some(merger) = merger.as_ref() {
+                            let _ = tx.send(Snapshot::new(merger, samples_processed));
+                        }
+                        continue;
+                    },
+                    CollectorMessage::Progress(started_at, tx) => {
+                        let progress = ProgressSnapshot::new(
+                            started_at.elapsed(),
+                            requests_sent,
+                            errors,
+                            samples_processed,
+                        );
+                        let _ = tx.send(progress);
+                        continue;
+                    },
+                    CollectorMessage::StatsWindow(window) => {
+                        stats_window = Some(*window);
+                        continue;
+                    },
+                    CollectorMessage::StartRound(round) => {
+                        debug!(round = round, "Collector actor starting round.");
+                        if let Some(window) = stats_window.as_mut() {
+                            window.restart();
+                        }
+                        collector.start_round(round)
+                    },
+                    CollectorMessage::EndRound(round) => {
+                        debug!(round = round, "Collector actor ending round.");
+                        collector.end_round(round)
+                    },
+                    CollectorMessage::StartPhase(index, phase) => {
+                        debug!(
+                            phase = index,
+                            label = phase.label(),
+                            "Collector actor starting phase."
+                        );
+                        collector.start_phase(index, current_phase.insert(*phase))
+                    },
+                };
+
+                match select(process, &mut aborted).await {
+                    Either::Left((Ok(()), _)) => {},
+                    Either::Left((Err(e), _)) if is_sample => {
+                        warn!(error = ?e, "Collector failed to process sample due to error.");
+                    },
+                    Either::Left((Err(e), _)) => {
+                        warn!(error = ?e, "Collector failed to handle message due to error.");
+                    },
+                    Either::Right(_) => {
+                        dropped_samples += usize::from(is_sample) + count_samples(&rx);
+                        break;
+                    },
                 }
             }
 
+            if dropped_samples > 0 {
+                warn!(
+                    dropped_samples = dropped_samples,
+                    "Collector actor was aborted before processing all samples."
+                );
+            }
+
+            for warning in imbalance.iter().flat_map(ImbalanceDetector::warnings) {
+                warn!("Benchmark load was imbalanced, {warning}.");
+            }
+
             info!("Collector actor has shutdown.");
-            collector
+            DrainedCollector {
+                collector,
+                dropped_samples,
+                imbalance,
+            }
         });
 
-        (Self(handle), tx)
+        (Self { handle, abort }, tx)
+    }
+
+    /// Waits for the collector to process the remaining samples.
+    ///
+    /// If a timeout is given and the collector has not finished within it,
+    /// the sample being processed and any remaining samples are dropped.
+    pub async fn drain(self, timeout: Option<Duration>) -> DrainedCollector<C> {
+        let Self { mut handle, abort } = self;
+
+        if let Some(timeout) = timeout {
+            match tokio::time::timeout(timeout, &mut handle).await {
+                Ok(result) => return result.expect("Join task"),
+                Err(_) => {
+                    warn!(timeout = ?timeout, "Collector did not finish within the drain timeout, aborting.");
+                    let _ = abort.send(());
+                },
+            }
+        }
+
+        handle.await.expect("Join task")
+    }
+}
+
+/// Counts the samples left in the mailbox.
+fn count_samples(rx: &flume::Receiver<CollectorMessage>) -> usize {
+    rx.drain().filter(is_sample).count()
+}
+
+fn is_sample(message: &CollectorMessage) -> bool {
+    matches!(message, CollectorMessage::Metric(Metric::Sample(_)))
+}
+
+/// A collector which has finished processing the samples of a benchmark.
+///
+/// Returned by [ReWrkBenchmark::drain_collector](crate::ReWrkBenchmark::drain_collector).
+pub struct DrainedCollector<C> {
+    collector: C,
+    dropped_samples: usize,
+    imbalance: Option<ImbalanceDetector>,
+}
+
+impl<C> DrainedCollector<C> {
+    /// The number of samples which were dropped because the
+    /// collector did not finish within the drain timeout.
+    pub fn dropped_samples(&self) -> usize {
+        self.dropped_samples
+    }
+
+    /// Returns if the collector was aborted before processing all samples.
+    pub fn is_partial(&self) -> bool {
+        self.dropped_samples > 0
+    }
+
+    /// The per-worker and per-connection load of the benchmark, used
+    /// to detect workers or connections which lagged behind their peers.
+    ///
+    /// This is `None` unless enabled via
+    /// [ReWrkBenchmark::enable_imbalance_detection](crate::ReWrkBenchmark::enable_imbalance_detection).
+    pub fn imbalance(&self) -> Option<&ImbalanceDetector> {
+        self.imbalance.as_ref()
+    }
+
+    /// Consumes the result returning the collector.
+    pub fn into_inner(self) -> C {
+        self.collector
     }
 }
@@ -0,0 +1,173 @@
+use std::io;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+use crate::connection::ConnectError;
+use crate::recording::SampleMetadata;
+
+#[derive(Debug, Clone, Copy, Default)]
+/// The time spent in each phase of establishing a connection.
+pub(crate) struct ConnectPhases {
+    /// The number of connection attempts made.
+    pub attempts: usize,
+    /// The time taken to open the TCP stream or custom transport.
+    pub tcp: Option<Duration>,
+    /// The time taken to complete the TLS handshake.
+    pub tls: Option<Duration>,
+    /// The time taken to complete the HTTP handshake.
+    pub handshake: Option<Duration>,
+    /// The address of the peer connected to, or attempted.
+    pub peer_addr: Option<SocketAddr>,
+}
+
+impl ConnectPhases {
+    /// Resets the phase timings before a new attempt.
+    pub(crate) fn start_attempt(&mut self) {
+        *self = Self {
+            attempts: self.attempts + 1,
+            ..Self::default()
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Why a connection could not be established.
+pub enum ConnectErrorKind {
+    /// The TCP stream or custom transport was not opened within the connect timeout.
+    ConnectTimeout,
+    /// The TLS or HTTP handshake did not complete within the handshake timeout.
+    HandshakeTimeout,
+    /// The target refused the connection, i.e. nothing is listening on the port.
+    Refused,
+    /// Any other error, i.e. the TLS handshake failed.
+    Other,
+}
+
+impl ConnectErrorKind {
+    /// The kind of the error returned when connecting.
+    pub(crate) fn of(error: &anyhow::Error) -> Self {
+        if let Some(error) = error.downcast_ref::<ConnectError>() {
+            return match error {
+                ConnectError::ConnectTimeout(_) => Self::ConnectTimeout,
+                ConnectError::HandshakeTimeout(_) => Self::HandshakeTimeout,
+            };
+        }
+
+        match error.downcast_ref::<io::Error>() {
+            Some(error) if error.kind() == io::ErrorKind::ConnectionRefused => {
+                Self::Refused
+            },
+            _ => Self::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A record of a worker attempting to establish a new connection.
+///
+/// Connect samples are sent to the collector as they happen via
+/// [SampleCollector::process_connect_sample](crate::SampleCollector::process_connect_sample),
+/// separately from the request samples, so the connection health can be
+/// tracked over the course of the benchmark.
+///
+/// A sample is sent for every attempt, so when connecting is retried each
+/// failed attempt is recorded along with the final one.
+/// The target's address is resolved once when the benchmark is created, so
+/// there is no DNS lookup to time when connecting.
+pub struct ConnectSample {
+    metadata: SampleMetadata,
+    timestamp: SystemTime,
+    duration: Duration,
+    phases: ConnectPhases,
+    error: Option<(ConnectErrorKind, String)>,
+}
+
+impl ConnectSample {
+    pub(crate) fn new(
+        metadata: SampleMetadata,
+        timestamp: SystemTime,
+        duration: Duration,
+        phases: ConnectPhases,
+        error: Option<(ConnectErrorKind, String)>,
+    ) -> Self {
+        Self {
+            metadata,
+            timestamp,
+            duration,
+            phases,
+            error,
+        }
+    }
+
+    /// The metadata of the connection which was established.
+    pub fn metadata(&self) -> SampleMetadata {
+        self.metadata
+    }
+
+    /// The wall clock time the attempt started.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// The time spent on the attempt.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The number of attempts made to connect, including this one.
+    pub fn attempts(&self) -> usize {
+        self.phases.attempts
+    }
+
+    /// The time taken to open the TCP stream, or the custom
+    /// [Transport](crate::Transport) if one is set.
+    ///
+    /// This is `None` if the attempt did not get this far.
+    pub fn tcp(&self) -> Option<Duration> {
+        self.phases.tcp
+    }
+
+    /// The time taken to complete the TLS handshake.
+    ///
+    /// This is `None` for plain HTTP targets or if the attempt did not get this far.
+    pub fn tls(&self) -> Option<Duration> {
+        self.phases.tls
+    }
+
+    /// The time taken to complete the HTTP handshake.
+    ///
+    /// This is `None` if the attempt did not get this far.
+    pub fn handshake(&self) -> Option<Duration> {
+        self.phases.handshake
+    }
+
+    /// The address of the peer the connection was made to.
+    ///
+    /// If connecting failed this is the address which was attempted.
+    /// This is `None` when a custom [Transport](crate::Transport) is set.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.phases.peer_addr
+    }
+
+    /// Returns if the connection was made over IPv6.
+    pub fn is_ipv6(&self) -> bool {
+        self.phases.peer_addr.is_some_and(|addr| addr.is_ipv6())
+    }
+
+    /// Returns if the connection was established.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The reason the connection could not be established.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_ref().map(|(_, error)| error.as_str())
+    }
+
+    /// The kind of error which stopped the connection being established.
+    ///
+    /// This tells timeouts apart from the target refusing the connection.
+    pub fn error_kind(&self) -> Option<ConnectErrorKind> {
+        self.error.as_ref().map(|(kind, _)| *kind)
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use crate::recording::Metric;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+/// The type of a [Metric] record.
+pub enum MetricKind {
+    /// A [Metric::Sample] record.
+    Sample,
+    /// A [Metric::ConnectSample] record.
+    ConnectSample,
+    /// A [Metric::WorkerReport] record.
+    WorkerReport,
+    /// A [Metric::Annotation] record.
+    Annotation,
+}
+
+#[derive(Debug, Clone, Default)]
+/// The records a collector is interested in, declared via
+/// [SampleCollector::filter](crate::SampleCollector::filter).
+///
+/// Records which don't match the filter are never passed to the collector,
+/// so exporters which only care about some tags or workers don't pay for
+/// processing the rest. By default every record matches.
+///
+/// ```
+/// use rewrk_core::{MetricFilter, MetricKind};
+///
+/// // Only the samples of tags 1 and 2.
+/// let filter = MetricFilter::all()
+///     .with_kinds([MetricKind::Sample])
+///     .with_tags([1, 2]);
+/// ```
+pub struct MetricFilter {
+    tags: Option<HashSet<usize>>,
+    worker_ids: Option<HashSet<usize>>,
+    kinds: Option<HashSet<MetricKind>>,
+}
+
+impl MetricFilter {
+    /// A filter matching every record.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Only match samples with one of the given tags.
+    ///
+    /// Records without a tag, i.e. anything other than samples,
+    /// are not affected by this filter.
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = usize>) -> Self {
+        self.tags = Some(tags.into_iter().collect());
+        self
+    }
+
+    /// Only match records produced by one of the given workers.
+    ///
+    /// Records which aren't produced by a worker, i.e. annotations,
+    /// are not affected by this filter.
+    pub fn with_worker_ids(
+        mut self,
+        worker_ids: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        self.worker_ids = Some(worker_ids.into_iter().collect());
+        self
+    }
+
+    /// Only match records of the given kinds.
+    pub fn with_kinds(mut self, kinds: impl IntoIterator<Item = MetricKind>) -> Self {
+        self.kinds = Some(kinds.into_iter().collect());
+        self
+    }
+
+    /// Returns if the filter matches every record.
+    pub fn is_all(&self) -> bool {
+        self.tags.is_none() && self.worker_ids.is_none() && self.kinds.is_none()
+    }
+
+    /// Returns if the record passes the filter.
+    pub fn matches(&self, metric: &Metric) -> bool {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&metric.kind()) {
+                return false;
+            }
+        }
+
+        if let (Some(tags), Metric::Sample(sample)) = (&self.tags, metric) {
+            if !tags.contains(&sample.tag()) {
+                return false;
+            }
+        }
+
+        if let (Some(worker_ids), Some(metadata)) = (&self.worker_ids, metric.metadata())
+        {
+            if !worker_ids.contains(&metadata.worker_id) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,115 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+use super::sample::Sample;
+use crate::runtime::Phase;
+
+/// Exports the latency of samples in the folded stack format used by
+/// flamegraph tools, i.e. `inferno-flamegraph` or `flamegraph.pl`.
+///
+/// Each line is a `tag;phase` stack followed by the total time in microseconds
+/// spent on requests with that tag in that phase, making it easy to see
+/// where time goes across the tags and phases of a complex scenario.
+///
+/// Totals are derived from the latency histograms so have the same precision.
+///
+/// ```
+/// use rewrk_core::{FoldedStacks, Sample, SampleCollector};
+///
+/// #[derive(Default)]
+/// pub struct FlamegraphCollector {
+///     stacks: FoldedStacks,
+/// }
+///
+/// #[rewrk_core::async_trait]
+/// impl SampleCollector for FlamegraphCollector {
+///     async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+///         self.stacks.add_sample(&sample);
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct FoldedStacks {
+    stacks: BTreeMap<(usize, usize), u64>,
+    tag_labels: BTreeMap<usize, Cow<'static, str>>,
+    phase_labels: BTreeMap<usize, Cow<'static, str>>,
+}
+
+impl FoldedStacks {
+    /// Sets the frame label used for the given tag.
+    ///
+    /// Tags without a label are written as `tag_<tag>`.
+    pub fn set_tag_label(&mut self, tag: usize, label: impl Into<Cow<'static, str>>) {
+        self.tag_labels.insert(tag, label.into());
+    }
+
+    /// Labels the phase frames with the labels of the given phases.
+    ///
+    /// Phases without a label are written as `phase_<index>`.
+    pub fn set_phases(&mut self, phases: &[Phase]) {
+        for (index, phase) in phases.iter().enumerate() {
+            self.phase_labels
+                .insert(index, Cow::Owned(phase.label().to_string()));
+        }
+    }
+
+    /// Adds the latency of the sample to its `tag;phase` stack.
+    pub fn add_sample(&mut self, sample: &Sample) {
+        let latency = sample.latency();
+        let total = (latency.mean() * latency.len() as f64).round() as u64;
+
+        if total == 0 {
+            return;
+        }
+
+        let key = (sample.tag(), sample.metadata().phase);
+        *self.stacks.entry(key).or_default() += total;
+    }
+
+    /// Writes the folded stacks, one stack per line.
+    pub fn write_to(&self, mut writer: impl io::Write) -> io::Result<()> {
+        write!(writer, "{self}")
+    }
+
+    fn tag_label(&self, tag: usize) -> Cow<'_, str> {
+        match self.tag_labels.get(&tag) {
+            Some(label) => sanitize_frame(label),
+            None => Cow::Owned(format!("tag_{tag}")),
+        }
+    }
+
+    fn phase_label(&self, phase: usize) -> Cow<'_, str> {
+        match self.phase_labels.get(&phase) {
+            Some(label) => sanitize_frame(label),
+            None => Cow::Owned(format!("phase_{phase}")),
+        }
+    }
+}
+
+impl Display for FoldedStacks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (&(tag, phase), total) in self.stacks.iter() {
+            writeln!(
+                f,
+                "{};{} {}",
+                self.tag_label(tag),
+                self.phase_label(phase),
+                total
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces the characters with a special meaning in the folded format.
+fn sanitize_frame(label: &str) -> Cow<'_, str> {
+    if label.contains([';', ' ', '\n', '\r']) {
+        Cow::Owned(label.replace([';', ' ', '\n', '\r'], "_"))
+    } else {
+        Cow::Borrowed(label)
+    }
+}
@@ -0,0 +1,212 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io;
+use std::time::Duration;
+
+use serde_json::json;
+
+use super::sample::Sample;
+
+/// The default upper bound of the first latency bucket.
+const DEFAULT_FIRST_BUCKET: Duration = Duration::from_micros(100);
+/// The default number of exponentially growing latency buckets.
+const DEFAULT_NUM_BUCKETS: usize = 16;
+
+/// Builds a latency heatmap, the number of requests in each latency bucket
+/// of each sample window, for plotting latency over time.
+///
+/// Samples are aligned by their window, so the samples of every connection
+/// and worker covering the same window are combined into the same column.
+/// The start of a window is its index multiplied by its
+/// [sample window](crate::SampleMetadata::sample_window) duration.
+///
+/// ```
+/// use rewrk_core::{LatencyHeatmap, Sample, SampleCollector};
+///
+/// #[derive(Default)]
+/// pub struct HeatmapCollector {
+///     heatmap: LatencyHeatmap,
+/// }
+///
+/// #[rewrk_core::async_trait]
+/// impl SampleCollector for HeatmapCollector {
+///     async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+///         self.heatmap.add_sample(&sample);
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LatencyHeatmap {
+    /// The inclusive upper bound of each bucket, in microseconds.
+    ///
+    /// Latencies above the last bound are counted in an overflow bucket.
+    bounds: Vec<u64>,
+    windows: BTreeMap<HeatmapWindow, Vec<u64>>,
+    /// The labels of the benchmark the samples were produced by.
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// The position of a column in the heatmap.
+struct HeatmapWindow {
+    round: usize,
+    phase: usize,
+    window_index: usize,
+    start: Duration,
+}
+
+impl Default for LatencyHeatmap {
+    /// A heatmap with 16 buckets doubling in size from 100µs,
+    /// covering latencies up to ~3.3s.
+    fn default() -> Self {
+        Self::exponential(DEFAULT_FIRST_BUCKET, 2.0, DEFAULT_NUM_BUCKETS)
+    }
+}
+
+impl LatencyHeatmap {
+    /// Creates a heatmap with the given inclusive bucket upper bounds.
+    ///
+    /// The bounds are sorted and deduplicated, latencies above the
+    /// last bound are counted in an overflow bucket.
+    pub fn with_bounds(bounds: impl IntoIterator<Item = Duration>) -> Self {
+        let mut bounds = bounds
+            .into_iter()
+            .map(|bound| bound.as_micros() as u64)
+            .collect::<Vec<_>>();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        Self {
+            bounds,
+            windows: BTreeMap::new(),
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a heatmap with `count` buckets, the first ending at `first`
+    /// and each following bucket `factor` times larger than the last.
+    pub fn exponential(first: Duration, factor: f64, count: usize) -> Self {
+        let bounds = (0..count as i32).map(|i| first.mul_f64(factor.powi(i)));
+        Self::with_bounds(bounds)
+    }
+
+    /// The inclusive upper bounds of the buckets.
+    pub fn bounds(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.bounds
+            .iter()
+            .map(|bound| Duration::from_micros(*bound))
+    }
+
+    /// Adds the latencies of the sample to the column of its window.
+    ///
+    /// The labels of the first sample with any are kept for the heatmap.
+    pub fn add_sample(&mut self, sample: &Sample) {
+        if self.labels.is_empty() && !sample.labels().is_empty() {
+            self.labels = sample.labels().clone();
+        }
+
+        let metadata = sample.metadata();
+        let window = HeatmapWindow {
+            round: metadata.round,
+            phase: metadata.phase,
+            window_index: sample.window_index(),
+            start: metadata.sample_window * sample.window_index() as u32,
+        };
+
+        let counts = self
+            .windows
+            .entry(window)
+            .or_insert_with(|| vec![0; self.bounds.len() + 1]);
+        let latency = sample.latency();
+        for value in latency.iter_recorded() {
+            // The lowest equivalent value keeps latencies recorded
+            // exactly on a bound in that bound's bucket.
+            let micros = latency.lowest_equivalent(value.value_iterated_to());
+            let bucket = self.bounds.partition_point(|bound| *bound < micros);
+            counts[bucket] += u64::from(value.count_at_value());
+        }
+    }
+
+    /// Writes the heatmap as CSV, one row per window.
+    ///
+    /// The columns are `round,phase,window,start_us` followed by a column
+    /// per bucket named after its upper bound in microseconds, and `+Inf`
+    /// for the overflow bucket. Each of the benchmark's
+    /// [labels](crate::ReWrkBenchmark::set_labels) is added as a leading column.
+    pub fn write_csv(&self, mut writer: impl io::Write) -> io::Result<()> {
+        for name in self.labels.keys() {
+            write!(writer, "{},", csv_field(name))?;
+        }
+        write!(writer, "round,phase,window,start_us")?;
+        for bound in &self.bounds {
+            write!(writer, ",{bound}")?;
+        }
+        writeln!(writer, ",+Inf")?;
+
+        for (window, counts) in &self.windows {
+            for value in self.labels.values() {
+                write!(writer, "{},", csv_field(value))?;
+            }
+            write!(
+                writer,
+                "{},{},{},{}",
+                window.round,
+                window.phase,
+                window.window_index,
+                window.start.as_micros(),
+            )?;
+            for count in counts {
+                write!(writer, ",{count}")?;
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the heatmap as JSON.
+    ///
+    /// The `buckets_us` field holds the upper bound of each bucket in
+    /// microseconds, followed by `null` for the overflow bucket.
+    /// Each entry of `windows` holds the counts of each bucket, and
+    /// `labels` holds the benchmark's labels.
+    pub fn write_json(&self, writer: impl io::Write) -> io::Result<()> {
+        let buckets = self
+            .bounds
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain([None])
+            .collect::<Vec<_>>();
+        let windows = self
+            .windows
+            .iter()
+            .map(|(window, counts)| {
+                json!({
+                    "round": window.round,
+                    "phase": window.phase,
+                    "window": window.window_index,
+                    "start_us": window.start.as_micros() as u64,
+                    "counts": counts,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let heatmap = json!({
+            "labels": self.labels,
+            "buckets_us": buckets,
+            "windows": windows,
+        });
+        serde_json::to_writer(writer, &heatmap)?;
+        Ok(())
+    }
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or newline.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use hdrhistogram::Histogram;
+
+use super::metric::WorkerReport;
+use super::sample::Sample;
+
+/// The default relative distance from the median at which
+/// a worker or connection is considered imbalanced.
+pub const DEFAULT_IMBALANCE_THRESHOLD: f64 = 0.5;
+
+/// The minimum number of successful requests before the
+/// latency of a worker or connection is compared.
+const MIN_LATENCY_REQUESTS: u64 = 100;
+
+/// The share of the runtime spent waiting on the producer
+/// which is considered producer starvation.
+const STARVATION_WAIT_RATIO: f64 = 0.05;
+
+/// Detects workers and connections which performed noticeably worse than
+/// their peers during a benchmark.
+///
+/// The requests per second and p99 latency of each worker are compared to
+/// the median of every worker, and each connection is compared to the other
+/// connections of its worker. When the skew exceeds the threshold a warning
+/// is produced suggesting the likely causes.
+///
+/// Once enabled via
+/// [ReWrkBenchmark::enable_imbalance_detection](crate::ReWrkBenchmark::enable_imbalance_detection)
+/// the collector actor runs a detector over the benchmark and logs its
+/// warnings once the collector has drained, the detector is available via
+/// [DrainedCollector::imbalance](crate::DrainedCollector::imbalance).
+#[derive(Debug, Clone)]
+pub struct ImbalanceDetector {
+    threshold: f64,
+    workers: BTreeMap<usize, Usage>,
+    connections: BTreeMap<(usize, usize), Usage>,
+    producer_wait: BTreeMap<usize, f64>,
+}
+
+impl Default for ImbalanceDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_IMBALANCE_THRESHOLD)
+    }
+}
+
+impl ImbalanceDetector {
+    /// Creates a new detector with the given threshold.
+    ///
+    /// The threshold is the relative distance from the median, i.e. `0.5`
+    /// warns when a worker handles 50% fewer requests per second than the
+    /// median or has a p99 latency 50% higher than the median.
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            workers: BTreeMap::new(),
+            connections: BTreeMap::new(),
+            producer_wait: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the requests and latencies of the sample to its worker and connection.
+    pub fn add_sample(&mut self, sample: &Sample) {
+        let metadata = sample.metadata();
+        self.workers
+            .entry(metadata.worker_id)
+            .or_default()
+            .add_sample(sample);
+        self.connections
+            .entry((metadata.worker_id, metadata.connection_id))
+            .or_default()
+            .add_sample(sample);
+    }
+
+    /// Adds the time the worker spent waiting on the producer.
+    pub fn add_worker_report(&mut self, report: &WorkerReport) {
+        self.producer_wait
+            .insert(report.metadata().worker_id, report.producer_wait_ratio());
+    }
+
+    /// Compares the workers and connections, returning a warning
+    /// for the worst outlier of each comparison exceeding the threshold.
+    pub fn warnings(&self) -> Vec<ImbalanceWarning> {
+        let mut warnings = Vec::new();
+
+        let workers = self
+            .workers
+            .iter()
+            .map(|(worker_id, usage)| (ImbalanceScope::Worker(*worker_id), usage))
+            .collect::<Vec<_>>();
+        for mut warning in self.compare(&workers) {
+            if let ImbalanceScope::Worker(worker_id) = warning.scope {
+                warning.causes = self.worker_causes(worker_id);
+            }
+            warnings.push(warning);
+        }
+
+        let mut by_worker = BTreeMap::<usize, Vec<_>>::new();
+        for ((worker_id, connection_id), usage) in &self.connections {
+            let scope = ImbalanceScope::Connection {
+                worker_id: *worker_id,
+                connection_id: *connection_id,
+            };
+            by_worker
+                .entry(*worker_id)
+                .or_default()
+                .push((scope, usage));
+        }
+        for connections in by_worker.values() {
+            for mut warning in self.compare(connections) {
+                warning.causes = vec![ImbalanceCause::TargetRouting];
+                warnings.push(warning);
+            }
+        }
+
+        warnings
+    }
+
+    /// Finds the lowest throughput and highest latency outliers of the group.
+    fn compare(&self, group: &[(ImbalanceScope, &Usage)]) -> Vec<ImbalanceWarning> {
+        let mut warnings = Vec::new();
+        if group.len() < 2 {
+            return warnings;
+        }
+
+        let throughput = group
+            .iter()
+            .map(|(scope, usage)| (*scope, usage.requests_per_sec()))
+            .collect::<Vec<_>>();
+        let median = lower_median(throughput.iter().map(|(_, value)| *value));
+        let slowest = throughput
+            .iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .copied();
+        if let Some((scope, requests_per_sec)) = slowest {
+            let skew = 1.0 - (requests_per_sec / median);
+            if median > 0.0 && skew > self.threshold {
+                warnings.push(ImbalanceWarning {
+                    scope,
+                    metric: ImbalanceMetric::Throughput {
+                        requests_per_sec,
+                        median,
+                    },
+                    skew,
+                    causes: Vec::new(),
+                });
+            }
+        }
+
+        let latency = group
+            .iter()
+            .filter(|(_, usage)| usage.latency.len() >= MIN_LATENCY_REQUESTS)
+            .map(|(scope, usage)| (*scope, usage.latency.value_at_quantile(0.99)))
+            .collect::<Vec<_>>();
+        if latency.len() < 2 {
+            return warnings;
+        }
+        let median = lower_median(latency.iter().map(|(_, value)| *value as f64));
+        let worst = latency.iter().max_by_key(|(_, value)| *value).copied();
+        if let Some((scope, p99)) = worst {
+            let skew = (p99 as f64 / median) - 1.0;
+            if median > 0.0 && skew > self.threshold {
+                warnings.push(ImbalanceWarning {
+                    scope,
+                    metric: ImbalanceMetric::P99Latency {
+                        p99: Duration::from_micros(p99),
+                        median: Duration::from_micros(median.round() as u64),
+                    },
+                    skew,
+                    causes: Vec::new(),
+                });
+            }
+        }
+
+        warnings
+    }
+
+    /// The likely causes of the worker performing worse than its peers.
+    fn worker_causes(&self, worker_id: usize) -> Vec<ImbalanceCause> {
+        let mut causes = Vec::new();
+
+        let connections = self.workers[&worker_id].connections.len();
+        let median_connections = lower_median(
+            self.workers
+                .values()
+                .map(|usage| usage.connections.len() as f64),
+        )
+        .round() as usize;
+        if connections != median_connections {
+            causes.push(ImbalanceCause::UnevenConcurrency {
+                connections,
+                median: median_connections,
+            });
+        }
+
+        if let Some(wait_ratio) = self.producer_wait.get(&worker_id) {
+            if *wait_ratio >= STARVATION_WAIT_RATIO {
+                causes.push(ImbalanceCause::ProducerStarvation {
+                    wait_ratio: *wait_ratio,
+                });
+            }
+        }
+
+        if causes.is_empty() {
+            causes.push(ImbalanceCause::CorePinning);
+        }
+
+        causes
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The requests of a single worker or connection.
+struct Usage {
+    requests: u64,
+    latency: Histogram<u32>,
+    /// The connections which produced samples.
+    connections: BTreeSet<usize>,
+    /// The summed sample durations of each connection.
+    durations: BTreeMap<usize, Duration>,
+}
+
+impl Default for Usage {
+    fn default() -> Self {
+        Self {
+            requests: 0,
+            latency: Histogram::new(2).unwrap(),
+            connections: BTreeSet::new(),
+            durations: BTreeMap::new(),
+        }
+    }
+}
+
+impl Usage {
+    fn add_sample(&mut self, sample: &Sample) {
+        let connection_id = sample.metadata().connection_id;
+        self.requests += sample.total_requests();
+        let _ = self.latency.add(sample.latency());
+        self.connections.insert(connection_id);
+        *self.durations.entry(connection_id).or_default() += sample.duration();
+    }
+
+    /// The requests per second, connections run concurrently so
+    /// the longest running connection is used as the duration.
+    fn requests_per_sec(&self) -> f64 {
+        let duration = self.durations.values().max().copied().unwrap_or_default();
+        if duration.is_zero() {
+            return 0.0;
+        }
+        self.requests as f64 / duration.as_secs_f64()
+    }
+}
+
+/// The lower median of the values.
+fn lower_median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values = values.collect::<Vec<_>>();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    values[(values.len() - 1) / 2]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A worker or connection which performed noticeably worse than its peers.
+pub struct ImbalanceWarning {
+    /// The worker or connection the warning is about.
+    pub scope: ImbalanceScope,
+    /// The metric which was skewed.
+    pub metric: ImbalanceMetric,
+    /// The relative distance of the metric from the median.
+    pub skew: f64,
+    /// The likely causes of the skew.
+    pub causes: Vec<ImbalanceCause>,
+}
+
+impl Display for ImbalanceWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.scope)?;
+        match self.metric {
+            ImbalanceMetric::Throughput {
+                requests_per_sec,
+                median,
+            } => write!(
+                f,
+                "handled {requests_per_sec:.2} req/sec, {:.0}% below the median of {median:.2} req/sec",
+                self.skew * 100.0,
+            )?,
+            ImbalanceMetric::P99Latency { p99, median } => write!(
+                f,
+                "had a p99 latency of {:.2}ms, {:.0}% above the median of {:.2}ms",
+                p99.as_secs_f64() * 1000.0,
+                self.skew * 100.0,
+                median.as_secs_f64() * 1000.0,
+            )?,
+        }
+
+        for (i, cause) in self.causes.iter().enumerate() {
+            if i == 0 {
+                write!(f, ", possibly due to {cause}")?;
+            } else {
+                write!(f, " or {cause}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What an [ImbalanceWarning] is about.
+pub enum ImbalanceScope {
+    /// A worker compared to every other worker.
+    Worker(usize),
+    /// A connection compared to the other connections of its worker.
+    Connection {
+        worker_id: usize,
+        connection_id: usize,
+    },
+}
+
+impl Display for ImbalanceScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Worker(worker_id) => write!(f, "worker {worker_id}"),
+            Self::Connection {
+                worker_id,
+                connection_id,
+            } => write!(f, "connection {connection_id} of worker {worker_id}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The skewed metric of an [ImbalanceWarning].
+pub enum ImbalanceMetric {
+    /// Fewer requests per second than the median.
+    Throughput { requests_per_sec: f64, median: f64 },
+    /// A higher p99 latency than the median.
+    P99Latency { p99: Duration, median: Duration },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A likely cause of an [ImbalanceWarning].
+pub enum ImbalanceCause {
+    /// The worker thread competing for a CPU core with other busy threads.
+    CorePinning,
+    /// The worker spent a large share of its runtime waiting on the producer.
+    ProducerStarvation {
+        /// The share of the runtime spent waiting between `0.0` and `1.0`.
+        wait_ratio: f64,
+    },
+    /// The worker ran a different number of connections to its peers.
+    UnevenConcurrency { connections: usize, median: usize },
+    /// The target serving the connection differently, e.g. a load
+    /// balancer routing it to a slower backend.
+    TargetRouting,
+}
+
+impl Display for ImbalanceCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CorePinning => write!(
+                f,
+                "CPU contention, try pinning workers to cores or running fewer workers"
+            ),
+            Self::ProducerStarvation { wait_ratio } => write!(
+                f,
+                "producer starvation, {:.2}% of the runtime was spent waiting on the producer",
+                wait_ratio * 100.0,
+            ),
+            Self::UnevenConcurrency {
+                connections,
+                median,
+            } => write!(
+                f,
+                "an uneven concurrency split, {connections} connections ran against a median of {median}"
+            ),
+            Self::TargetRouting => write!(
+                f,
+                "the target, e.g. a load balancer routing the connection to a slower backend"
+            ),
+        }
+    }
+}
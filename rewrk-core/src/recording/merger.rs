@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use super::sample::Sample;
+
+/// A utility for merging samples together into a single sample per tag.
+///
+/// Samples produced by different connections cover overlapping periods of
+/// time while samples produced by the same connection cover sequential
+/// windows, the merger accounts for this by summing the durations of each
+/// connection's samples and using the longest of these as the duration of
+/// the merged sample.
+///
+/// This means rates derived from the merged sample are correctly weighted
+/// even when samples were truncated early due to a tag change.
+///
+/// # Example
+///
+/// ```
+/// use rewrk_core::{Sample, SampleCollector, SampleMerger};
+///
+/// #[derive(Default)]
+/// pub struct MergingCollector {
+///     merger: SampleMerger,
+/// }
+///
+/// #[rewrk_core::async_trait]
+/// impl SampleCollector for MergingCollector {
+///     async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+///         self.merger.add_sample(sample);
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct SampleMerger {
+    tags: BTreeMap<usize, MergedSample>,
+}
+
+impl SampleMerger {
+    /// Add a new sample to the merger.
+    pub fn add_sample(&mut self, sample: Sample) {
+        let metadata = sample.metadata();
+        let connection = (metadata.worker_id, metadata.connection_id);
+
+        match self.tags.get_mut(&sample.tag()) {
+            Some(merged) => merged.add_sample(connection, sample),
+            None => {
+                let mut connection_durations = HashMap::new();
+                connection_durations.insert(connection, sample.duration());
+
+                self.tags.insert(
+                    sample.tag(),
+                    MergedSample {
+                        sample,
+                        connection_durations,
+                    },
+                );
+            },
+        }
+    }
+
+    /// Get the merged sample for the given tag.
+    pub fn get(&self, tag: usize) -> Option<&Sample> {
+        self.tags.get(&tag).map(|merged| &merged.sample)
+    }
+
+    /// The merged samples ordered by tag.
+    pub fn samples(&self) -> impl Iterator<Item = &Sample> {
+        self.tags.values().map(|merged| &merged.sample)
+    }
+
+    /// Consumes the merger returning the merged samples ordered by tag.
+    pub fn into_samples(self) -> Vec<Sample> {
+        self.tags
+            .into_values()
+            .map(|merged| merged.sample)
+            .collect()
+    }
+}
+
+struct MergedSample {
+    sample: Sample,
+    /// The total duration of the samples produced by each connection.
+    connection_durations: HashMap<(usize, usize), Duration>,
+}
+
+impl MergedSample {
+    fn add_sample(&mut self, connection: (usize, usize), sample: Sample) {
+        *self.connection_durations.entry(connection).or_default() += sample.duration();
+
+        self.sample += &sample;
+
+        let duration = self
+            .connection_durations
+            .values()
+            .copied()
+            .max()
+            .unwrap_or_default();
+        self.sample.set_duration(duration);
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::{Duration, SystemTime};
+
+use crate::recording::{ConnectSample, MetricKind, Sample, SampleMetadata};
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+/// A record sent from the benchmark to the collector.
+///
+/// Collectors receive every record via
+/// [SampleCollector::process_metric](crate::SampleCollector::process_metric),
+/// which by default passes each record type to its own handler and ignores
+/// the records without one. New record types may be added in the future
+/// without breaking existing collectors.
+pub enum Metric {
+    /// The request metrics of a connection over a sample window.
+    Sample(Box<Sample>),
+    /// A record of a worker establishing a new connection.
+    ConnectSample(Box<ConnectSample>),
+    /// The runtime breakdown of a worker once it has finished.
+    WorkerReport(WorkerReport),
+    /// A user provided note marking an external event.
+    Annotation(Annotation),
+}
+
+impl Metric {
+    /// The type of the record.
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            Self::Sample(_) => MetricKind::Sample,
+            Self::ConnectSample(_) => MetricKind::ConnectSample,
+            Self::WorkerReport(_) => MetricKind::WorkerReport,
+            Self::Annotation(_) => MetricKind::Annotation,
+        }
+    }
+
+    /// The metadata of the worker which produced the record, if any.
+    pub fn metadata(&self) -> Option<SampleMetadata> {
+        match self {
+            Self::Sample(sample) => Some(sample.metadata()),
+            Self::ConnectSample(sample) => Some(sample.metadata()),
+            Self::WorkerReport(report) => Some(report.metadata()),
+            Self::Annotation(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The runtime breakdown of a worker, sent once it has finished a run.
+pub struct WorkerReport {
+    metadata: SampleMetadata,
+    connections: usize,
+    producer_wait: Duration,
+    execute_wait: Duration,
+}
+
+impl WorkerReport {
+    pub(crate) fn new(
+        metadata: SampleMetadata,
+        connections: usize,
+        producer_wait: Duration,
+        execute_wait: Duration,
+    ) -> Self {
+        Self {
+            metadata,
+            connections,
+            producer_wait,
+            execute_wait,
+        }
+    }
+
+    /// The metadata of the worker, the connection ID is always `0`.
+    pub fn metadata(&self) -> SampleMetadata {
+        self.metadata
+    }
+
+    /// The number of connections the worker opened.
+    pub fn connections(&self) -> usize {
+        self.connections
+    }
+
+    /// The total time the worker's connections spent waiting on the producer.
+    pub fn producer_wait(&self) -> Duration {
+        self.producer_wait
+    }
+
+    /// The total time the worker's connections spent executing requests.
+    pub fn execute_wait(&self) -> Duration {
+        self.execute_wait
+    }
+
+    /// The share of the worker's runtime spent waiting on
+    /// the producer between `0.0` and `1.0`.
+    pub fn producer_wait_ratio(&self) -> f64 {
+        let total = self.producer_wait + self.execute_wait;
+        if total.is_zero() {
+            return 0.0;
+        }
+        self.producer_wait.as_secs_f64() / total.as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A timestamped note marking an external event during the benchmark.
+pub struct Annotation {
+    timestamp: SystemTime,
+    message: String,
+}
+
+impl Annotation {
+    /// Creates a new annotation timestamped with the current time.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            message: message.into(),
+        }
+    }
+
+    /// The wall clock time the annotation was created.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// The message of the annotation.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
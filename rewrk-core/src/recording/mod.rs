@@ -1,6 +1,46 @@
+mod archive;
 mod collector;
+mod connect;
+mod filter;
+mod folded;
+mod heatmap;
+mod imbalance;
+mod merger;
+mod metric;
 mod sample;
+mod snapshot;
+mod summary;
+mod window;
 
-pub use collector::SampleCollector;
-pub(crate) use collector::{CollectorActor, CollectorMailbox};
-pub use sample::{Sample, SampleFactory, SampleMetadata};
+pub use archive::{
+    ArchiveCollector,
+    ArchiveError,
+    ArchiveHeader,
+    ArchiveReader,
+    ArchiveWriter,
+    ResumedArchive,
+    ARCHIVE_EXTENSION,
+};
+pub(crate) use collector::{CollectorActor, CollectorMailbox, CollectorMessage};
+pub use collector::{DrainedCollector, SampleCollector};
+pub(crate) use connect::ConnectPhases;
+pub use connect::{ConnectErrorKind, ConnectSample};
+pub use filter::{MetricFilter, MetricKind};
+pub use folded::FoldedStacks;
+pub use heatmap::LatencyHeatmap;
+pub use imbalance::{
+    ImbalanceCause,
+    ImbalanceDetector,
+    ImbalanceMetric,
+    ImbalanceScope,
+    ImbalanceWarning,
+    DEFAULT_IMBALANCE_THRESHOLD,
+};
+pub use merger::SampleMerger;
+pub use metric::{Annotation, Metric, WorkerReport};
+pub(crate) use sample::SampleWindowOverrides;
+pub use sample::{Outlier, RequestKey, Sample, SampleFactory, SampleMetadata};
+pub use snapshot::{ProgressSnapshot, Snapshot};
+pub use summary::LatencySummary;
+pub(crate) use window::StatsWindow;
+pub use window::WindowStats;
@@ -1,16 +1,82 @@
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
-use std::time::{Duration, Instant};
+use std::net::SocketAddr;
+use std::ops::{Add, AddAssign};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use flume::TrySendError;
 use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
 
-use crate::recording::collector::CollectorMailbox;
-use crate::validator::ValidationError;
+use crate::connection::IoCounters;
+use crate::header_capture::{DEFAULT_MAX_HEADER_VALUES, OTHER_HEADER_VALUE};
+use crate::recording::collector::{CollectorMailbox, CollectorMessage};
+use crate::recording::{ConnectPhases, LatencySummary, Metric};
+use crate::response_tracking::ResponseCounts;
+use crate::utils::histogram;
+use crate::validator::{Classification, ValidationError, ValidationErrorKind};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct SampleMetadata {
     /// The unique ID of the worker thread.
     pub worker_id: usize,
+    /// The ID of the connection within the worker which produced the sample.
+    pub connection_id: usize,
+    /// The benchmark round which produced the sample.
+    pub round: usize,
+    /// The index of the benchmark [Phase](crate::Phase) which produced the sample.
+    pub phase: usize,
+    /// The sample window duration the sample was recorded with.
+    ///
+    /// Windows can differ between tags and workers, exporters should use
+    /// this rather than assuming a global window when normalizing rates.
+    pub sample_window: Duration,
+    /// The address of the peer the connection which produced the sample is
+    /// made to, so errors and slow requests can be attributed to a backend.
+    ///
+    /// This is `None` for samples not produced by a single connection or
+    /// when a custom [Transport](crate::Transport) is set.
+    pub peer_addr: Option<SocketAddr>,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Sample window durations which replace the benchmark's
+/// sample window for certain tags or workers.
+pub(crate) struct SampleWindowOverrides {
+    /// The windows of samples with a given tag.
+    ///
+    /// These take priority over the worker windows.
+    pub tags: BTreeMap<usize, Duration>,
+    /// The windows of samples produced by a given worker.
+    pub workers: BTreeMap<usize, Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A unique identifier for a single request issued by the benchmarker.
+pub struct RequestKey {
+    /// The unique ID of the worker thread.
+    pub worker_id: usize,
+    /// The ID of the connection within the worker which sent the request.
+    pub connection_id: usize,
+    /// The sequence number of the request on the connection.
+    pub request_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A request which exceeded the configured outlier latency threshold.
+///
+/// Outliers capture enough information to correlate tail latency spikes
+/// with the server logs after the benchmark has completed.
+pub struct Outlier {
+    /// The key of the request.
+    pub key: RequestKey,
+    /// The wall clock time the request was sent.
+    pub timestamp: SystemTime,
+    /// The status code returned by the server.
+    pub status: u16,
+    /// The latency of the request.
+    pub latency: Duration,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -25,8 +91,26 @@ pub struct SampleFactory {
     /// is submitted to be processed.
     window_timeout: Duration,
 
+    /// The sample windows of specific tags.
+    tag_windows: BTreeMap<usize, Duration>,
+
+    /// The maximum number of outliers a single sample will hold.
+    max_outliers: usize,
+
+    /// The maximum number of error exemplars a single sample will hold.
+    max_error_exemplars: usize,
+
+    /// The maximum number of distinct values a single sample
+    /// will count for each captured header.
+    max_header_values: usize,
+
+    /// The index of the next sample window.
+    next_window_index: usize,
+
     /// Metadata associated with the specific sample factory thread.
     metadata: SampleMetadata,
+    /// The labels attached to every sample.
+    labels: Arc<BTreeMap<String, String>>,
     submitter: CollectorMailbox,
 }
 
@@ -34,43 +118,109 @@ impl SampleFactory {
     /// Create a new sample factory.
     pub fn new(
         window_timeout: Duration,
+        max_outliers: usize,
+        max_error_exemplars: usize,
         metadata: SampleMetadata,
         submitter: CollectorMailbox,
     ) -> Self {
         Self {
             window_timeout,
+            tag_windows: BTreeMap::new(),
+            max_outliers,
+            max_error_exemplars,
+            max_header_values: DEFAULT_MAX_HEADER_VALUES,
+            next_window_index: 0,
             metadata,
+            labels: Arc::default(),
             submitter,
         }
     }
 
+    /// Set the labels attached to every sample.
+    pub(crate) fn with_labels(mut self, labels: Arc<BTreeMap<String, String>>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Apply the sample window overrides relevant to the factory's worker.
+    pub(crate) fn with_window_overrides(
+        mut self,
+        overrides: &SampleWindowOverrides,
+    ) -> Self {
+        if let Some(window) = overrides.workers.get(&self.metadata.worker_id) {
+            self.window_timeout = *window;
+            self.metadata.sample_window = *window;
+        }
+        self.tag_windows = overrides.tags.clone();
+        self
+    }
+
+    /// Set the maximum number of distinct values counted for each captured header.
+    pub(crate) fn with_max_header_values(mut self, max_header_values: usize) -> Self {
+        self.max_header_values = max_header_values;
+        self
+    }
+
+    /// The sample window duration of samples with the given tag.
+    pub fn window_for(&self, tag: usize) -> Duration {
+        self.tag_windows
+            .get(&tag)
+            .copied()
+            .unwrap_or(self.window_timeout)
+    }
+
+    /// Create a new sample factory for the given connection.
+    pub fn for_connection(&self, connection_id: usize) -> Self {
+        let mut factory = self.clone();
+        factory.metadata.connection_id = connection_id;
+        factory
+    }
+
+    /// Set the peer address of the connection the samples are produced by.
+    pub(crate) fn set_peer_addr(&mut self, peer_addr: Option<SocketAddr>) {
+        self.metadata.peer_addr = peer_addr;
+    }
+
     #[inline]
-    /// Check if the handler should submit the current sample.
-    pub fn should_submit(&self, instant: Instant) -> bool {
-        self.window_timeout <= instant.elapsed()
+    /// Check if the handler should submit the current sample with
+    /// the given tag.
+    pub fn should_submit(&self, tag: usize, instant: Instant) -> bool {
+        self.window_for(tag) <= instant.elapsed()
     }
 
     #[inline]
     /// Create a new sample to record metrics.
-    pub fn new_sample(&self, tag: usize) -> Sample {
+    pub fn new_sample(&mut self, tag: usize) -> Sample {
+        let window_index = self.next_window_index;
+        self.next_window_index += 1;
+
+        let mut metadata = self.metadata;
+        metadata.sample_window = self.window_for(tag);
+
         Sample {
-            tag,
-            latency_hist: Histogram::new(2).unwrap(),
-            write_transfer_hist: Histogram::new(2).unwrap(),
-            read_transfer_hist: Histogram::new(2).unwrap(),
-            errors: Vec::with_capacity(4),
-            metadata: self.metadata,
+            window_index,
+            max_error_exemplars: self.max_error_exemplars,
+            max_header_values: self.max_header_values,
+            max_outliers: self.max_outliers,
+            labels: self.labels.clone(),
+            ..Sample::empty(tag, metadata)
         }
     }
 
     #[inline]
     /// Attempts to submit a sample to the processor.
+    ///
+    /// This marks the end of the sample window, any sample submitted
+    /// before the window duration has elapsed is marked as truncated.
     pub fn submit_sample(&self, sample: Sample) -> Result<(), Shutdown> {
-        debug!(sample = ?sample, "Submitting sample to processor");
-        // This should never block as it's an unbounded channel.
-        let result = self.submitter.try_send(sample);
+        let duration = sample.started.elapsed();
+        self.submit_sample_with_duration(sample, duration)
+    }
 
-        match result {
+    /// Attempts to submit a record to the processor.
+    pub fn submit_metric(&self, metric: Metric) -> Result<(), Shutdown> {
+        // This should never block as it's an unbounded channel.
+        match self.submitter.try_send(CollectorMessage::Metric(metric)) {
             Ok(()) => Ok(()),
             Err(TrySendError::Full(_)) => {
                 panic!("Sample submitter should never be full.")
@@ -78,9 +228,32 @@ impl SampleFactory {
             Err(TrySendError::Disconnected(_)) => Err(Shutdown),
         }
     }
+
+    /// The metadata attached to the samples of the factory.
+    pub fn metadata(&self) -> SampleMetadata {
+        self.metadata
+    }
+
+    #[inline]
+    /// Attempts to submit a sample which covers the given duration
+    /// to the processor.
+    ///
+    /// This is used when the sample window is not measured with the
+    /// wall clock, i.e. in a [Simulation](crate::Simulation).
+    pub fn submit_sample_with_duration(
+        &self,
+        mut sample: Sample,
+        duration: Duration,
+    ) -> Result<(), Shutdown> {
+        sample.duration = duration;
+        sample.truncated = sample.duration < sample.metadata.sample_window;
+
+        debug!(sample = ?sample, "Submitting sample to processor");
+        self.submit_metric(Metric::Sample(Box::new(sample)))
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// A collection of metrics taken from the benchmark for a given time window.
 ///
 /// The sample contains the standard metrics (latency, IO, etc...) along with
@@ -88,13 +261,68 @@ impl SampleFactory {
 ///
 /// Internally this uses HDR Histograms which can generate the min, max, stdev and
 /// varying percentile statistics of the benchmark.
+///
+/// Samples can be serialized, i.e. to be archived with an
+/// [ArchiveWriter](crate::ArchiveWriter) for offline analysis.
 pub struct Sample {
     tag: usize,
+    window_index: usize,
+    #[serde(skip, default = "Instant::now")]
+    started: Instant,
+    duration: Duration,
+    truncated: bool,
+    target_unhealthy: bool,
+    total_requests: u64,
+    successful_requests: u64,
+    read_bytes: u64,
+    written_bytes: u64,
+    plaintext_read_bytes: u64,
+    plaintext_written_bytes: u64,
+    retries: u64,
+    mirrored_requests: u64,
+    rate_limited: u64,
+    duplicate_responses: u64,
+    missing_responses: u64,
+    out_of_order_responses: u64,
+    backoff_duration: Duration,
+    client_backpressure: Duration,
+    #[serde(with = "histogram")]
     latency_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    attempt_latency_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    connect_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    tls_handshake_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    ttfb_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    body_read_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    client_backpressure_hist: Histogram<u32>,
+    #[serde(with = "histogram::map")]
+    classified_latency_hists: BTreeMap<Classification, Histogram<u32>>,
+    #[serde(with = "histogram")]
+    server_time_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    network_overhead_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    forward_delay_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
+    backward_delay_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
     write_transfer_hist: Histogram<u32>,
+    #[serde(with = "histogram")]
     read_transfer_hist: Histogram<u32>,
 
-    errors: Vec<ValidationError>,
+    error_counts: BTreeMap<ValidationErrorKind, u64>,
+    error_exemplars: Vec<ValidationError>,
+    max_error_exemplars: usize,
+    header_values: BTreeMap<String, BTreeMap<String, u64>>,
+    max_header_values: usize,
+    outliers: Vec<Outlier>,
+    max_outliers: usize,
+    labels: Arc<BTreeMap<String, String>>,
     metadata: SampleMetadata,
 }
 
@@ -102,22 +330,236 @@ impl Debug for Sample {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Sample")
             .field("num_records", &self.latency().len())
+            .field("num_outliers", &self.outliers.len())
+            .field("window_index", &self.window_index)
+            .field("duration", &self.duration)
+            .field("truncated", &self.truncated)
             .field("metadata", &self.metadata)
             .finish()
     }
 }
 
 impl Sample {
+    /// Creates a sample from requests recorded outside of a rewrk-core
+    /// benchmark, i.e. by the rewrk CLI, so they can be archived and
+    /// analysed alongside the samples of other benchmarks.
+    ///
+    /// Each latency is counted as a successful request and each error as
+    /// a failed one, one exemplar is kept for each distinct error.
+    pub fn from_requests(
+        metadata: SampleMetadata,
+        latencies: &[Duration],
+        read_bytes: u64,
+        errors: impl IntoIterator<Item = (ValidationError, u64)>,
+    ) -> Self {
+        let mut sample = Sample {
+            duration: metadata.sample_window,
+            max_error_exemplars: usize::MAX,
+            ..Sample::empty(0, metadata)
+        };
+
+        for latency in latencies {
+            sample.record_total_request();
+            sample.record_successful_request();
+            sample.record_latency(*latency);
+        }
+        for (error, count) in errors {
+            sample.total_requests += count;
+            *sample.error_counts.entry(error.kind()).or_default() += count;
+            sample.error_exemplars.push(error);
+        }
+        sample.read_bytes = read_bytes;
+
+        sample
+    }
+
+    /// An empty sample which keeps no outliers, exemplars or header values.
+    fn empty(tag: usize, metadata: SampleMetadata) -> Self {
+        Sample {
+            tag,
+            window_index: 0,
+            started: Instant::now(),
+            duration: Duration::ZERO,
+            truncated: false,
+            target_unhealthy: false,
+            total_requests: 0,
+            successful_requests: 0,
+            read_bytes: 0,
+            written_bytes: 0,
+            plaintext_read_bytes: 0,
+            plaintext_written_bytes: 0,
+            retries: 0,
+            mirrored_requests: 0,
+            rate_limited: 0,
+            duplicate_responses: 0,
+            missing_responses: 0,
+            out_of_order_responses: 0,
+            backoff_duration: Duration::ZERO,
+            client_backpressure: Duration::ZERO,
+            latency_hist: Histogram::new(2).unwrap(),
+            attempt_latency_hist: Histogram::new(2).unwrap(),
+            connect_hist: Histogram::new(2).unwrap(),
+            tls_handshake_hist: Histogram::new(2).unwrap(),
+            ttfb_hist: Histogram::new(2).unwrap(),
+            body_read_hist: Histogram::new(2).unwrap(),
+            client_backpressure_hist: Histogram::new(2).unwrap(),
+            classified_latency_hists: BTreeMap::new(),
+            server_time_hist: Histogram::new(2).unwrap(),
+            network_overhead_hist: Histogram::new(2).unwrap(),
+            forward_delay_hist: Histogram::new(2).unwrap(),
+            backward_delay_hist: Histogram::new(2).unwrap(),
+            write_transfer_hist: Histogram::new(2).unwrap(),
+            read_transfer_hist: Histogram::new(2).unwrap(),
+            error_counts: BTreeMap::new(),
+            error_exemplars: Vec::new(),
+            max_error_exemplars: 0,
+            header_values: BTreeMap::new(),
+            max_header_values: 0,
+            outliers: Vec::new(),
+            max_outliers: 0,
+            labels: Arc::default(),
+            metadata,
+        }
+    }
+
     /// The sample metadata.
     pub fn metadata(&self) -> SampleMetadata {
         self.metadata
     }
 
+    /// The labels of the benchmark which produced the sample.
+    ///
+    /// See [ReWrkBenchmark::set_labels](crate::ReWrkBenchmark::set_labels).
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        &self.labels
+    }
+
     /// The sample latency histogram
+    ///
+    /// Requests which were retried are recorded once with their
+    /// end-to-end latency, including any backoff between attempts.
     pub fn latency(&self) -> &Histogram<u32> {
         &self.latency_hist
     }
 
+    /// The latency histogram of successful requests with the given classification.
+    ///
+    /// Responses are classified by [ResponseValidator::classify](crate::ResponseValidator::classify).
+    pub fn classified_latency(
+        &self,
+        classification: &Classification,
+    ) -> Option<&Histogram<u32>> {
+        self.classified_latency_hists.get(classification)
+    }
+
+    /// The latency histograms of each response classification.
+    pub fn classified_latencies(
+        &self,
+    ) -> impl Iterator<Item = (&Classification, &Histogram<u32>)> {
+        self.classified_latency_hists.iter()
+    }
+
+    /// The number of responses received for each distinct value of a captured header.
+    ///
+    /// This is only populated when a [HeaderCapture](crate::HeaderCapture) is set.
+    /// Header names are lowercase, values beyond the maximum number of distinct
+    /// values are counted under [OTHER_HEADER_VALUE](crate::OTHER_HEADER_VALUE).
+    pub fn captured_header(&self, name: &str) -> Option<&BTreeMap<String, u64>> {
+        self.header_values.get(name)
+    }
+
+    /// The value counts of every captured header.
+    pub fn captured_headers(
+        &self,
+    ) -> impl Iterator<Item = (&str, &BTreeMap<String, u64>)> {
+        self.header_values
+            .iter()
+            .map(|(name, values)| (name.as_str(), values))
+    }
+
+    /// The histogram of server reported processing times.
+    ///
+    /// This is only populated when a [ServerTimingSource](crate::ServerTimingSource)
+    /// is set and successful responses contain the timing information.
+    pub fn server_time(&self) -> &Histogram<u32> {
+        &self.server_time_hist
+    }
+
+    /// The histogram of the client latency minus the server reported
+    /// processing time for each request.
+    ///
+    /// This is the time spent on the network and within the client and
+    /// server IO layers.
+    pub fn network_overhead(&self) -> &Histogram<u32> {
+        &self.network_overhead_hist
+    }
+
+    /// The histogram of the request path delay above the connection's baseline.
+    ///
+    /// This is only populated when [OneWayDelay](crate::OneWayDelay) estimation
+    /// is enabled and the server responds with its receive timestamp.
+    pub fn forward_delay(&self) -> &Histogram<u32> {
+        &self.forward_delay_hist
+    }
+
+    /// The histogram of the response path delay above the connection's baseline.
+    ///
+    /// This is only populated when [OneWayDelay](crate::OneWayDelay) estimation
+    /// is enabled and the server responds with its receive timestamp.
+    pub fn backward_delay(&self) -> &Histogram<u32> {
+        &self.backward_delay_hist
+    }
+
+    /// The latency histogram of every individual request attempt.
+    ///
+    /// Without a [RetryPolicy](crate::RetryPolicy) every request is a single
+    /// attempt and this matches [Sample::latency] aside from failed requests.
+    pub fn attempt_latency(&self) -> &Histogram<u32> {
+        &self.attempt_latency_hist
+    }
+
+    /// The histogram of the time taken to open the TCP stream, or custom
+    /// transport, of each connection established in the sample window.
+    ///
+    /// The target's address is resolved once when the benchmark is created,
+    /// so no DNS lookup is included.
+    pub fn connect_latency(&self) -> &Histogram<u32> {
+        &self.connect_hist
+    }
+
+    /// The histogram of the time taken to complete the TLS handshake of
+    /// each connection established in the sample window.
+    ///
+    /// This is empty when benchmarking plain HTTP.
+    pub fn tls_handshake(&self) -> &Histogram<u32> {
+        &self.tls_handshake_hist
+    }
+
+    /// The histogram of the time from sending each request attempt
+    /// until the response head was received.
+    pub fn time_to_first_byte(&self) -> &Histogram<u32> {
+        &self.ttfb_hist
+    }
+
+    /// The histogram of the time taken to read each response body
+    /// once the response head was received.
+    ///
+    /// Together with [Sample::time_to_first_byte] this makes
+    /// up [Sample::attempt_latency].
+    pub fn body_read(&self) -> &Histogram<u32> {
+        &self.body_read_hist
+    }
+
+    /// The histogram of the time each request attempt waited for its
+    /// connection to be ready to send and the time its HTTP/2 body was
+    /// blocked by the peer's flow control window or stream limits.
+    ///
+    /// This time is within the client, so it is excluded from
+    /// [Sample::latency] and [Sample::attempt_latency].
+    pub fn client_backpressure(&self) -> &Histogram<u32> {
+        &self.client_backpressure_hist
+    }
+
     /// The sample write transfer rate histogram
     pub fn write_transfer(&self) -> &Histogram<u32> {
         &self.write_transfer_hist
@@ -128,16 +570,294 @@ impl Sample {
         &self.read_transfer_hist
     }
 
+    /// The requests which exceeded the outlier latency threshold.
+    ///
+    /// This list is bounded, once the limit has been reached
+    /// any additional outliers in the sample window are dropped.
+    pub fn outliers(&self) -> &[Outlier] {
+        &self.outliers
+    }
+
     #[inline]
     /// The current sample batch tag.
     pub fn tag(&self) -> usize {
         self.tag
     }
 
+    #[inline]
+    /// The index of the sample window on the connection which produced it.
+    ///
+    /// Windows are numbered sequentially starting from `0`, a new window
+    /// is started whenever a sample is submitted, including when the batch
+    /// tag changes part way through a window.
+    pub fn window_index(&self) -> usize {
+        self.window_index
+    }
+
+    /// Moves the sample `offset` windows later, i.e. after the windows of
+    /// a resumed archive.
+    pub(crate) fn offset_window_index(&mut self, offset: usize) {
+        self.window_index += offset;
+    }
+
+    #[inline]
+    /// The duration of time the sample covers.
+    ///
+    /// This is the elapsed time between the sample being created and
+    /// it being submitted to the collector.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    #[inline]
+    /// If the sample was submitted before the full window duration elapsed.
+    ///
+    /// This happens when the batch tag changes mid-window or when the
+    /// benchmark completes, rates derived from a truncated sample should
+    /// be weighted by the sample's [Sample::duration].
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    #[inline]
+    /// If the benchmark target failed its health check at any point
+    /// during the sample window.
+    ///
+    /// Metrics from these samples are likely to be skewed by the target
+    /// failing, see [HealthCheck](crate::HealthCheck).
+    pub fn is_target_unhealthy(&self) -> bool {
+        self.target_unhealthy
+    }
+
+    #[inline]
+    /// The total number of requests sent during the sample window.
+    ///
+    /// This includes both successful and failed requests.
+    pub fn total_requests(&self) -> u64 {
+        self.total_requests
+    }
+
+    #[inline]
+    /// The number of requests which passed validation.
+    pub fn successful_requests(&self) -> u64 {
+        self.successful_requests
+    }
+
+    #[inline]
+    /// The number of requests which failed, either due to a connection
+    /// error or by failing validation.
+    ///
+    /// Mirrored requests are not validated, so are never counted as failed.
+    pub fn failed_requests(&self) -> u64 {
+        self.total_requests - self.successful_requests - self.mirrored_requests
+    }
+
+    /// A bounded set of example errors recorded during the sample window.
+    ///
+    /// Only the first errors recorded are kept as exemplars, the number of
+    /// errors of each kind is tracked by [Sample::error_counts].
+    pub fn error_exemplars(&self) -> &[ValidationError] {
+        &self.error_exemplars
+    }
+
+    /// The number of errors recorded for each kind of error.
+    pub fn error_counts(&self) -> &BTreeMap<ValidationErrorKind, u64> {
+        &self.error_counts
+    }
+
+    /// The total number of errors recorded during the sample window.
+    pub fn total_errors(&self) -> u64 {
+        self.error_counts.values().sum()
+    }
+
+    #[inline]
+    /// The number of additional attempts made by retrying requests.
+    pub fn retries(&self) -> u64 {
+        self.retries
+    }
+
+    #[inline]
+    /// The number of requests sent without waiting for a response
+    /// with [SendMode::Mirror](crate::SendMode::Mirror).
+    ///
+    /// Mirrored requests are included in [Sample::total_requests] but their
+    /// responses aren't validated, so they are neither successful nor failed
+    /// and have no latency recorded.
+    pub fn mirrored_requests(&self) -> u64 {
+        self.mirrored_requests
+    }
+
+    #[inline]
+    /// The number of `429 Too Many Requests` responses received.
+    ///
+    /// This counts every attempt, including attempts which were retried.
+    pub fn rate_limited_responses(&self) -> u64 {
+        self.rate_limited
+    }
+
+    #[inline]
+    /// The number of responses for a request which wasn't awaiting a response,
+    /// i.e. it already received one or it was sent on another connection.
+    ///
+    /// This is only recorded with [ResponseTracking](crate::ResponseTracking).
+    pub fn duplicate_responses(&self) -> u64 {
+        self.duplicate_responses
+    }
+
+    #[inline]
+    /// The number of requests which never received a response.
+    ///
+    /// Requests can receive their response in a later sample window, so these
+    /// are only counted by the last sample of each connection. Requests which
+    /// failed with an error are not counted.
+    ///
+    /// This is only recorded with [ResponseTracking](crate::ResponseTracking).
+    pub fn missing_responses(&self) -> u64 {
+        self.missing_responses
+    }
+
+    #[inline]
+    /// The number of responses received before the response
+    /// to an older request on the same connection.
+    ///
+    /// This is only recorded with [ResponseTracking](crate::ResponseTracking).
+    pub fn out_of_order_responses(&self) -> u64 {
+        self.out_of_order_responses
+    }
+
+    #[inline]
+    /// The total time spent waiting between retry attempts.
+    ///
+    /// This time is part of the end-to-end latency of retried requests.
+    pub fn backoff_duration(&self) -> Duration {
+        self.backoff_duration
+    }
+
+    #[inline]
+    /// The total time requests spent waiting for their connection
+    /// to be ready to send, see [Sample::client_backpressure].
+    pub fn client_backpressure_duration(&self) -> Duration {
+        self.client_backpressure
+    }
+
+    #[inline]
+    /// The total number of bytes read by successful requests.
+    ///
+    /// With HTTP/2 the bytes of each request are estimated from the size of
+    /// its stream's frames, excluding frames shared by the whole connection.
+    pub fn read_bytes(&self) -> u64 {
+        self.read_bytes
+    }
+
+    #[inline]
+    /// The total number of bytes written by successful requests.
+    ///
+    /// With HTTP/2 the bytes of each request are estimated from the size of
+    /// its stream's frames, excluding frames shared by the whole connection.
+    pub fn written_bytes(&self) -> u64 {
+        self.written_bytes
+    }
+
+    #[inline]
+    /// The total number of HTTP bytes read by successful requests,
+    /// before being decrypted by TLS.
+    ///
+    /// For plain HTTP connections this is the same as [Self::read_bytes].
+    pub fn plaintext_read_bytes(&self) -> u64 {
+        self.plaintext_read_bytes
+    }
+
+    #[inline]
+    /// The total number of HTTP bytes written by successful requests,
+    /// before being encrypted by TLS.
+    ///
+    /// For plain HTTP connections this is the same as [Self::written_bytes].
+    pub fn plaintext_written_bytes(&self) -> u64 {
+        self.plaintext_written_bytes
+    }
+
+    /// The number of bytes added by TLS to the reads and writes of
+    /// successful requests, including handshake records sent during them.
+    pub fn tls_overhead_bytes(&self) -> u64 {
+        (self.read_bytes + self.written_bytes)
+            .saturating_sub(self.plaintext_read_bytes + self.plaintext_written_bytes)
+    }
+
+    /// The average number of requests sent per second over the sample duration.
+    pub fn requests_per_sec(&self) -> f64 {
+        per_sec(self.total_requests, self.duration)
+    }
+
+    /// The average number of bytes read per second over the sample duration.
+    pub fn read_bytes_per_sec(&self) -> f64 {
+        per_sec(self.read_bytes, self.duration)
+    }
+
+    /// The average number of bytes written per second over the sample duration.
+    pub fn written_bytes_per_sec(&self) -> f64 {
+        per_sec(self.written_bytes, self.duration)
+    }
+
+    /// The fraction of requests which failed between `0.0` and `1.0`.
+    pub fn error_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            return 0.0;
+        }
+
+        self.failed_requests() as f64 / self.total_requests as f64
+    }
+
+    /// The latency at the given percentile between `0.0` and `100.0`.
+    pub fn latency_percentile(&self, percentile: f64) -> Duration {
+        Duration::from_micros(self.latency_hist.value_at_percentile(percentile))
+    }
+
+    /// A summary of the latency distribution of the sample.
+    pub fn latency_summary(&self) -> LatencySummary {
+        LatencySummary::from(self)
+    }
+
+    /// The mean latency of the sample.
+    pub fn latency_mean(&self) -> Duration {
+        Duration::from_secs_f64(self.latency_hist.mean() / 1_000_000.0)
+    }
+
+    /// The standard deviation of the sample latency.
+    pub fn latency_stdev(&self) -> Duration {
+        Duration::from_secs_f64(self.latency_hist.stdev() / 1_000_000.0)
+    }
+
+    #[inline]
+    /// Record a request being sent.
+    pub(crate) fn record_total_request(&mut self) {
+        self.total_requests += 1;
+    }
+
+    #[inline]
+    /// Record a request which passed validation.
+    pub(crate) fn record_successful_request(&mut self) {
+        self.successful_requests += 1;
+    }
+
     #[inline]
     /// Record a request validation error.
+    ///
+    /// The error is kept as an exemplar if the sample is not yet at capacity.
     pub(crate) fn record_error(&mut self, e: ValidationError) {
-        self.errors.push(e);
+        *self.error_counts.entry(e.kind()).or_default() += 1;
+        if self.error_exemplars.len() < self.max_error_exemplars {
+            self.error_exemplars.push(e);
+        }
+    }
+
+    #[inline]
+    /// Record a request which exceeded the outlier threshold.
+    ///
+    /// The outlier is dropped if the sample is already at capacity.
+    pub(crate) fn record_outlier(&mut self, outlier: Outlier) {
+        if self.outliers.len() < self.max_outliers {
+            self.outliers.push(outlier);
+        }
     }
 
     #[inline]
@@ -149,6 +869,155 @@ impl Sample {
         self.latency_hist.record(micros).expect("Record value");
     }
 
+    #[inline]
+    /// Record a latency duration for a classified response.
+    ///
+    /// This value is converted to micro seconds.
+    pub(crate) fn record_classified_latency(
+        &mut self,
+        classification: Classification,
+        dur: Duration,
+    ) {
+        let micros = dur.as_micros() as u64;
+        self.classified_latency_hists
+            .entry(classification)
+            .or_insert_with(|| Histogram::new(2).unwrap())
+            .record(micros)
+            .expect("Record value");
+    }
+
+    #[inline]
+    /// Record the server reported processing time of a request
+    /// along with the request's client latency.
+    ///
+    /// These values are converted to micro seconds.
+    pub(crate) fn record_server_time(
+        &mut self,
+        server_time: Duration,
+        latency: Duration,
+    ) {
+        let micros = server_time.as_micros() as u64;
+        self.server_time_hist.record(micros).expect("Record value");
+
+        let overhead = latency.saturating_sub(server_time).as_micros() as u64;
+        self.network_overhead_hist
+            .record(overhead)
+            .expect("Record value");
+    }
+
+    #[inline]
+    /// Record the estimated one-way delays of a request.
+    ///
+    /// These values are converted to micro seconds.
+    pub(crate) fn record_one_way_delay(
+        &mut self,
+        forward: Duration,
+        backward: Duration,
+    ) {
+        self.forward_delay_hist
+            .record(forward.as_micros() as u64)
+            .expect("Record value");
+        self.backward_delay_hist
+            .record(backward.as_micros() as u64)
+            .expect("Record value");
+    }
+
+    #[inline]
+    /// Record the latency of a single request attempt.
+    ///
+    /// This value is converted to micro seconds.
+    pub(crate) fn record_attempt_latency(&mut self, dur: Duration) {
+        let micros = dur.as_micros() as u64;
+        self.attempt_latency_hist
+            .record(micros)
+            .expect("Record value");
+    }
+
+    #[inline]
+    /// Record the time taken to establish a connection, split into
+    /// the time to open the stream and to complete the TLS handshake.
+    ///
+    /// These values are converted to micro seconds.
+    pub(crate) fn record_connect_phases(&mut self, phases: &ConnectPhases) {
+        if let Some(tcp) = phases.tcp {
+            self.connect_hist
+                .record(tcp.as_micros() as u64)
+                .expect("Record value");
+        }
+        if let Some(tls) = phases.tls {
+            self.tls_handshake_hist
+                .record(tls.as_micros() as u64)
+                .expect("Record value");
+        }
+    }
+
+    #[inline]
+    /// Record the time to first byte and the body read time of a response.
+    ///
+    /// These values are converted to micro seconds.
+    pub(crate) fn record_response_phases(
+        &mut self,
+        ttfb: Duration,
+        body_read: Duration,
+    ) {
+        self.ttfb_hist
+            .record(ttfb.as_micros() as u64)
+            .expect("Record value");
+        self.body_read_hist
+            .record(body_read.as_micros() as u64)
+            .expect("Record value");
+    }
+
+    #[inline]
+    /// Record the time a request attempt waited for its connection to be ready.
+    ///
+    /// This value is converted to micro seconds.
+    pub(crate) fn record_client_backpressure(&mut self, dur: Duration) {
+        self.client_backpressure += dur;
+        self.client_backpressure_hist
+            .record(dur.as_micros() as u64)
+            .expect("Record value");
+    }
+
+    #[inline]
+    /// Record a request being retried after waiting for the given backoff.
+    pub(crate) fn record_retry(&mut self, backoff: Duration) {
+        self.retries += 1;
+        self.backoff_duration += backoff;
+    }
+
+    #[inline]
+    /// Record a request sent without waiting for its response.
+    pub(crate) fn record_mirrored_request(&mut self) {
+        self.mirrored_requests += 1;
+    }
+
+    #[inline]
+    /// Record a rate limited response.
+    pub(crate) fn record_rate_limited(&mut self) {
+        self.rate_limited += 1;
+    }
+
+    #[inline]
+    /// Record the response mismatches found by response tracking.
+    pub(crate) fn record_response_counts(&mut self, counts: ResponseCounts) {
+        self.duplicate_responses += counts.duplicate;
+        self.missing_responses += counts.missing;
+        self.out_of_order_responses += counts.out_of_order;
+    }
+
+    /// Record a response containing the value of a captured header.
+    pub(crate) fn record_header_value(&mut self, name: &str, value: &str) {
+        if !self.header_values.contains_key(name) {
+            self.header_values.insert(name.to_string(), BTreeMap::new());
+        }
+        let values = self
+            .header_values
+            .get_mut(name)
+            .expect("Header values exist");
+        count_header_value(values, value, 1, self.max_header_values);
+    }
+
     #[inline]
     /// Record a write transfer rate.
     pub(crate) fn record_write_transfer(
@@ -157,6 +1026,7 @@ impl Sample {
         end_count: u64,
         dur: Duration,
     ) {
+        self.written_bytes += end_count - start_count;
         self.write_transfer_hist
             .record(calculate_rate(start_count, end_count, dur))
             .expect("Record value");
@@ -170,13 +1040,331 @@ impl Sample {
         end_count: u64,
         dur: Duration,
     ) {
+        self.read_bytes += end_count - start_count;
         self.read_transfer_hist
             .record(calculate_rate(start_count, end_count, dur))
             .expect("Record value");
     }
+
+    #[inline]
+    /// Record the bytes of a request before TLS encryption.
+    pub(crate) fn record_plaintext_transfer(&mut self, io: IoCounters) {
+        self.plaintext_read_bytes += io.read;
+        self.plaintext_written_bytes += io.written;
+    }
+
+    /// Marks the sample as being recorded while the target was unhealthy.
+    pub(crate) fn mark_target_unhealthy(&mut self) {
+        self.target_unhealthy = true;
+    }
+
+    /// Sets the duration of the sample.
+    pub(crate) fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+}
+
+impl AddAssign<&Sample> for Sample {
+    /// Merges another sample's metrics into this sample.
+    ///
+    /// The tag, labels and metadata of `self` are kept as is, the sample durations
+    /// are summed as the samples are treated as sequential windows.
+    /// If the samples cover overlapping periods of time, i.e. they were produced
+    /// by different connections, the [SampleMerger](crate::SampleMerger) should
+    /// be used instead which weights durations correctly.
+    ///
+    /// Error counts and captured header values are summed, error exemplars,
+    /// outliers and distinct header values are merged up to the limits of `self`.
+    fn add_assign(&mut self, rhs: &Sample) {
+        self.duration += rhs.duration;
+        self.truncated |= rhs.truncated;
+        self.target_unhealthy |= rhs.target_unhealthy;
+        self.window_index = self.window_index.max(rhs.window_index);
+        self.total_requests += rhs.total_requests;
+        self.successful_requests += rhs.successful_requests;
+        self.read_bytes += rhs.read_bytes;
+        self.written_bytes += rhs.written_bytes;
+        self.plaintext_read_bytes += rhs.plaintext_read_bytes;
+        self.plaintext_written_bytes += rhs.plaintext_written_bytes;
+        self.retries += rhs.retries;
+        self.mirrored_requests += rhs.mirrored_requests;
+        self.rate_limited += rhs.rate_limited;
+        self.duplicate_responses += rhs.duplicate_responses;
+        self.missing_responses += rhs.missing_responses;
+        self.out_of_order_responses += rhs.out_of_order_responses;
+        self.backoff_duration += rhs.backoff_duration;
+        self.client_backpressure += rhs.client_backpressure;
+
+        merge_histogram(&mut self.latency_hist, &rhs.latency_hist);
+        merge_histogram(&mut self.attempt_latency_hist, &rhs.attempt_latency_hist);
+        merge_histogram(&mut self.connect_hist, &rhs.connect_hist);
+        merge_histogram(&mut self.tls_handshake_hist, &rhs.tls_handshake_hist);
+        merge_histogram(&mut self.ttfb_hist, &rhs.ttfb_hist);
+        merge_histogram(&mut self.body_read_hist, &rhs.body_read_hist);
+        merge_histogram(
+            &mut self.client_backpressure_hist,
+            &rhs.client_backpressure_hist,
+        );
+        merge_histogram(&mut self.server_time_hist, &rhs.server_time_hist);
+        merge_histogram(&mut self.forward_delay_hist, &rhs.forward_delay_hist);
+        merge_histogram(&mut self.backward_delay_hist, &rhs.backward_delay_hist);
+        merge_histogram(&mut self.network_overhead_hist, &rhs.network_overhead_hist);
+        for (classification, hist) in rhs.classified_latency_hists.iter() {
+            match self.classified_latency_hists.get_mut(classification) {
+                Some(target) => merge_histogram(target, hist),
+                None => {
+                    self.classified_latency_hists
+                        .insert(classification.clone(), hist.clone());
+                },
+            }
+        }
+        merge_histogram(&mut self.write_transfer_hist, &rhs.write_transfer_hist);
+        merge_histogram(&mut self.read_transfer_hist, &rhs.read_transfer_hist);
+
+        for (kind, count) in rhs.error_counts.iter() {
+            *self.error_counts.entry(*kind).or_default() += count;
+        }
+        let remaining = self
+            .max_error_exemplars
+            .saturating_sub(self.error_exemplars.len());
+        self.error_exemplars
+            .extend(rhs.error_exemplars.iter().take(remaining).cloned());
+
+        for (name, values) in rhs.header_values.iter() {
+            let target = self.header_values.entry(name.clone()).or_default();
+            for (value, count) in values.iter() {
+                count_header_value(target, value, *count, self.max_header_values);
+            }
+        }
+
+        let remaining = self.max_outliers.saturating_sub(self.outliers.len());
+        self.outliers
+            .extend(rhs.outliers.iter().take(remaining).cloned());
+    }
+}
+
+impl AddAssign for Sample {
+    fn add_assign(&mut self, rhs: Sample) {
+        *self += &rhs;
+    }
+}
+
+impl Add for Sample {
+    type Output = Sample;
+
+    /// Merges two samples together, see the [AddAssign] implementation
+    /// for the exact semantics.
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += &rhs;
+        self
+    }
+}
+
+/// Adds the values of the `other` histogram to the `target` histogram.
+///
+/// If the histograms cannot be added directly, i.e. the values of `other`
+/// are out of the range of `target`, the values are re-recorded saturating
+/// at the bounds of `target` rather than being lost.
+fn merge_histogram(target: &mut Histogram<u32>, other: &Histogram<u32>) {
+    if let Err(e) = target.add(other) {
+        warn!(error = ?e, "Unable to add histograms directly, saturating values.");
+        for value in other.iter_recorded() {
+            target
+                .saturating_record_n(value.value_iterated_to(), value.count_at_value());
+        }
+    }
+}
+
+/// Counts a header value, values beyond the maximum
+/// number of distinct values are counted as other.
+fn count_header_value(
+    values: &mut BTreeMap<String, u64>,
+    value: &str,
+    count: u64,
+    max_values: usize,
+) {
+    if let Some(existing) = values.get_mut(value) {
+        *existing += count;
+        return;
+    }
+
+    let distinct = values.len() - values.contains_key(OTHER_HEADER_VALUE) as usize;
+    let value = if distinct < max_values {
+        value
+    } else {
+        OTHER_HEADER_VALUE
+    };
+    *values.entry(value.to_string()).or_default() += count;
+}
+
+#[inline]
+fn per_sec(count: u64, dur: Duration) -> f64 {
+    if dur.is_zero() {
+        return 0.0;
+    }
+
+    count as f64 / dur.as_secs_f64()
 }
 
 #[inline]
 fn calculate_rate(start: u64, stop: u64, dur: Duration) -> u64 {
     ((stop - start) as f64 / dur.as_secs_f64()).round() as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn create_sample(latencies: &[u64], errors: usize, duration: Duration) -> Sample {
+        let (tx, _rx) = flume::unbounded();
+        let metadata = SampleMetadata {
+            worker_id: 0,
+            connection_id: 0,
+            round: 0,
+            phase: 0,
+            sample_window: Duration::from_secs(1),
+            peer_addr: None,
+        };
+        let mut factory =
+            SampleFactory::new(Duration::from_secs(1), 4, 64, metadata, tx);
+
+        let mut sample = factory.new_sample(0);
+        for latency in latencies {
+            sample.record_total_request();
+            sample.record_successful_request();
+            sample.record_latency(Duration::from_micros(*latency));
+        }
+        for _ in 0..errors {
+            sample.record_total_request();
+            sample.record_error(ValidationError::Timeout);
+        }
+        sample.set_duration(duration);
+        sample
+    }
+
+    proptest! {
+        #[test]
+        fn test_add_preserves_counts(
+            a in prop::collection::vec(1..10_000_000u64, 0..200),
+            b in prop::collection::vec(1..10_000_000u64, 0..200),
+            a_errors in 0..20usize,
+            b_errors in 0..20usize,
+            a_secs in 0..100u64,
+            b_secs in 0..100u64,
+        ) {
+            let left = create_sample(&a, a_errors, Duration::from_secs(a_secs));
+            let right = create_sample(&b, b_errors, Duration::from_secs(b_secs));
+            let merged = left.clone() + right.clone();
+
+            prop_assert_eq!(merged.latency().len(), (a.len() + b.len()) as u64);
+            prop_assert_eq!(
+                merged.total_requests(),
+                left.total_requests() + right.total_requests(),
+            );
+            prop_assert_eq!(
+                merged.successful_requests(),
+                left.successful_requests() + right.successful_requests(),
+            );
+            prop_assert_eq!(merged.total_errors(), (a_errors + b_errors) as u64);
+            prop_assert_eq!(merged.duration(), Duration::from_secs(a_secs + b_secs));
+        }
+
+        #[test]
+        fn test_add_is_commutative(
+            a in prop::collection::vec(1..10_000_000u64, 1..200),
+            b in prop::collection::vec(1..10_000_000u64, 1..200),
+        ) {
+            let left = create_sample(&a, 0, Duration::from_secs(1));
+            let right = create_sample(&b, 0, Duration::from_secs(1));
+
+            let forward = left.clone() + right.clone();
+            let backward = right + left;
+
+            prop_assert_eq!(forward.latency().min(), backward.latency().min());
+            prop_assert_eq!(forward.latency().max(), backward.latency().max());
+            prop_assert_eq!(
+                forward.latency_percentile(99.0),
+                backward.latency_percentile(99.0),
+            );
+        }
+    }
+
+    #[test]
+    fn test_add_saturates_out_of_range_histograms() {
+        let mut target = Histogram::<u32>::new_with_max(1_000, 2).unwrap();
+        let mut other = Histogram::<u32>::new(2).unwrap();
+        other.record(5).unwrap();
+        other.record(1_000_000).unwrap();
+
+        merge_histogram(&mut target, &other);
+
+        assert_eq!(target.len(), 2);
+        assert!(target.equivalent(target.max(), target.high()));
+    }
+
+    #[test]
+    fn test_error_exemplars_are_bounded() {
+        let (tx, _rx) = flume::unbounded();
+        let metadata = SampleMetadata {
+            worker_id: 0,
+            connection_id: 0,
+            round: 0,
+            phase: 0,
+            sample_window: Duration::from_secs(1),
+            peer_addr: None,
+        };
+        let mut factory = SampleFactory::new(Duration::from_secs(1), 4, 2, metadata, tx);
+
+        let mut left = factory.new_sample(0);
+        let mut right = factory.new_sample(0);
+        for _ in 0..3 {
+            left.record_error(ValidationError::Timeout);
+            right.record_error(ValidationError::Timeout);
+        }
+        assert_eq!(left.error_exemplars().len(), 2);
+        assert_eq!(left.total_errors(), 3);
+
+        left += right;
+        assert_eq!(left.error_exemplars().len(), 2);
+        assert_eq!(left.total_errors(), 6);
+        assert_eq!(left.error_counts()[&ValidationErrorKind::Timeout], 6);
+    }
+
+    #[test]
+    fn test_header_values_are_bounded() {
+        let (tx, _rx) = flume::unbounded();
+        let metadata = SampleMetadata {
+            worker_id: 0,
+            connection_id: 0,
+            round: 0,
+            phase: 0,
+            sample_window: Duration::from_secs(1),
+            peer_addr: None,
+        };
+        let mut factory = SampleFactory::new(Duration::from_secs(1), 4, 2, metadata, tx)
+            .with_max_header_values(2);
+
+        let mut left = factory.new_sample(0);
+        let mut right = factory.new_sample(0);
+        for value in ["a", "b", "a", "c"] {
+            left.record_header_value("x-served-by", value);
+        }
+        for value in ["b", "d"] {
+            right.record_header_value("x-served-by", value);
+        }
+
+        let values = left.captured_header("x-served-by").unwrap();
+        assert_eq!(values["a"], 2);
+        assert_eq!(values["b"], 1);
+        assert_eq!(values[OTHER_HEADER_VALUE], 1);
+
+        left += right;
+        let values = left.captured_header("x-served-by").unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values["b"], 2);
+        assert_eq!(values[OTHER_HEADER_VALUE], 2);
+        assert!(left.captured_header("x-cache").is_none());
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::{Duration, SystemTime};
+
+use super::merger::SampleMerger;
+use super::sample::Sample;
+
+#[derive(Debug, Clone)]
+/// An immutable aggregate of the samples collected so far by a running benchmark.
+///
+/// Snapshots are taken with [ReWrkBenchmark::snapshot](crate::ReWrkBenchmark::snapshot)
+/// without interrupting the benchmark, this allows dashboards and controllers
+/// to poll the progress of a run.
+pub struct Snapshot {
+    taken_at: SystemTime,
+    samples_processed: usize,
+    samples: Vec<Sample>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(merger: &SampleMerger, samples_processed: usize) -> Self {
+        Self {
+            taken_at: SystemTime::now(),
+            samples_processed,
+            samples: merger.samples().cloned().collect(),
+        }
+    }
+
+    /// The time the snapshot was taken.
+    pub fn taken_at(&self) -> SystemTime {
+        self.taken_at
+    }
+
+    /// The number of samples the collector had processed when
+    /// the snapshot was taken.
+    pub fn samples_processed(&self) -> usize {
+        self.samples_processed
+    }
+
+    /// The samples collected so far merged into a single sample per tag,
+    /// ordered by tag.
+    ///
+    /// See [SampleMerger] for how samples are merged.
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// The merged sample for the given tag.
+    pub fn get(&self, tag: usize) -> Option<&Sample> {
+        self.samples.iter().find(|sample| sample.tag() == tag)
+    }
+
+    /// The samples for every tag combined into a single sample.
+    ///
+    /// Returns `None` if no samples have been collected.
+    pub fn total(&self) -> Option<Sample> {
+        let mut samples = self.samples.iter();
+        let mut total = samples.next()?.clone();
+        for sample in samples {
+            total += sample;
+        }
+        Some(total)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The aggregate progress of a running benchmark.
+///
+/// This is passed to the handler set with
+/// [ReWrkBenchmark::set_progress_handler](crate::ReWrkBenchmark::set_progress_handler)
+/// every sample window. Unlike a [Snapshot] no samples are copied, so it is
+/// cheap enough to drive a progress bar or TUI.
+pub struct ProgressSnapshot {
+    elapsed: Duration,
+    requests_sent: u64,
+    errors: u64,
+    samples_processed: usize,
+}
+
+impl ProgressSnapshot {
+    pub(crate) fn new(
+        elapsed: Duration,
+        requests_sent: u64,
+        errors: u64,
+        samples_processed: usize,
+    ) -> Self {
+        Self {
+            elapsed,
+            requests_sent,
+            errors,
+            samples_processed,
+        }
+    }
+
+    /// The time since the benchmark was started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The number of requests sent so far.
+    pub fn requests_sent(&self) -> u64 {
+        self.requests_sent
+    }
+
+    /// The number of requests which failed so far.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// The number of samples the collector has processed so far.
+    pub fn samples_processed(&self) -> usize {
+        self.samples_processed
+    }
+
+    /// The average number of requests sent per second since the benchmark started.
+    pub fn requests_per_sec(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+
+        self.requests_sent as f64 / self.elapsed.as_secs_f64()
+    }
+}
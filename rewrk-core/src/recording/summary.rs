@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::sample::Sample;
+use crate::utils::micros;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A summary of the latency distribution of a sample.
+///
+/// This is the canonical format for exporting latency statistics,
+/// all durations are rounded to and serialized as a whole number of microseconds.
+///
+/// A summary can be created from any sample including samples
+/// which have been merged together, i.e. by the [SampleMerger](crate::SampleMerger).
+pub struct LatencySummary {
+    /// The number of latency values recorded.
+    pub count: u64,
+    #[serde(with = "micros")]
+    /// The minimum recorded latency.
+    pub min: Duration,
+    #[serde(with = "micros")]
+    /// The maximum recorded latency.
+    pub max: Duration,
+    #[serde(with = "micros")]
+    /// The mean recorded latency.
+    pub mean: Duration,
+    #[serde(with = "micros")]
+    /// The standard deviation of the recorded latency.
+    pub stdev: Duration,
+    #[serde(with = "micros")]
+    /// The 50th percentile latency.
+    pub p50: Duration,
+    #[serde(with = "micros")]
+    /// The 75th percentile latency.
+    pub p75: Duration,
+    #[serde(with = "micros")]
+    /// The 90th percentile latency.
+    pub p90: Duration,
+    #[serde(with = "micros")]
+    /// The 99th percentile latency.
+    pub p99: Duration,
+    #[serde(with = "micros")]
+    /// The 99.9th percentile latency.
+    pub p999: Duration,
+    #[serde(with = "micros")]
+    /// The 99.99th percentile latency.
+    pub p9999: Duration,
+}
+
+impl From<&Sample> for LatencySummary {
+    fn from(sample: &Sample) -> Self {
+        let hist = sample.latency();
+
+        Self {
+            count: hist.len(),
+            min: Duration::from_micros(hist.min()),
+            max: Duration::from_micros(hist.max()),
+            mean: Duration::from_micros(hist.mean().round() as u64),
+            stdev: Duration::from_micros(hist.stdev().round() as u64),
+            p50: sample.latency_percentile(50.0),
+            p75: sample.latency_percentile(75.0),
+            p90: sample.latency_percentile(90.0),
+            p99: sample.latency_percentile(99.0),
+            p999: sample.latency_percentile(99.9),
+            p9999: sample.latency_percentile(99.99),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use tokio::sync::watch;
+
+use super::sample::Sample;
+use super::summary::LatencySummary;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The stats of the most recent window of samples across all workers.
+///
+/// These are passed to [Producer::window_stats](crate::Producer::window_stats)
+/// once enabled with [ReWrkBenchmark::set_window_stats](crate::ReWrkBenchmark::set_window_stats),
+/// which allows adaptive producers to grow or shrink their batches depending
+/// on how the target is coping, i.e. to keep the p99 latency under a target.
+pub struct WindowStats {
+    /// The index of the window, starting from `0` for the first window.
+    pub window_index: usize,
+    /// The wall time the window covers.
+    pub duration: Duration,
+    /// The number of requests sent within the window.
+    pub total_requests: u64,
+    /// The number of requests which failed within the window.
+    pub failed_requests: u64,
+    /// The latency distribution of the requests within the window.
+    pub latency: LatencySummary,
+}
+
+impl WindowStats {
+    /// The number of requests sent per second over the window.
+    pub fn requests_per_sec(&self) -> f64 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+
+        self.total_requests as f64 / self.duration.as_secs_f64()
+    }
+
+    /// The fraction of requests which failed between `0.0` and `1.0`.
+    pub fn error_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            return 0.0;
+        }
+
+        self.failed_requests as f64 / self.total_requests as f64
+    }
+}
+
+/// Merges the samples received by the collector into tumbling windows
+/// and publishes the stats of each window once it has elapsed.
+pub(crate) struct StatsWindow {
+    interval: Duration,
+    started_at: Instant,
+    window_index: usize,
+    merged: Option<Sample>,
+    tx: watch::Sender<Option<WindowStats>>,
+}
+
+impl StatsWindow {
+    pub fn new(interval: Duration, tx: watch::Sender<Option<WindowStats>>) -> Self {
+        Self {
+            interval,
+            started_at: Instant::now(),
+            window_index: 0,
+            merged: None,
+            tx,
+        }
+    }
+
+    /// Starts a new window, discarding any samples in the current one.
+    ///
+    /// This is called at the start of each round so idle time between
+    /// rounds isn't counted.
+    pub fn restart(&mut self) {
+        self.started_at = Instant::now();
+        self.merged = None;
+    }
+
+    /// Adds the sample to the current window, publishing the window's
+    /// stats if it has elapsed.
+    ///
+    /// Samples of every tag are merged together.
+    pub fn add_sample(&mut self, sample: &Sample) {
+        match self.merged.as_mut() {
+            None => self.merged = Some(sample.clone()),
+            Some(merged) => *merged += sample,
+        }
+
+        let elapsed = self.started_at.elapsed();
+        if elapsed < self.interval {
+            return;
+        }
+
+        if let Some(merged) = self.merged.take() {
+            let stats = WindowStats {
+                window_index: self.window_index,
+                duration: elapsed,
+                total_requests: merged.total_requests(),
+                failed_requests: merged.failed_requests(),
+                latency: merged.latency_summary(),
+            };
+            let _ = self.tx.send(Some(stats));
+        }
+
+        self.started_at = Instant::now();
+        self.window_index += 1;
+    }
+}
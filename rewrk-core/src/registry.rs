@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::producer::{Producer, RequestBatch};
+use crate::validator::{DefaultValidator, ResponseValidator, DEFAULT_VALIDATOR_NAME};
+
+type ValidatorFactory =
+    Arc<dyn Fn(&Value) -> anyhow::Result<Arc<dyn ResponseValidator>> + Send + Sync>;
+type ProducerFactory =
+    Arc<dyn Fn(&Value) -> anyhow::Result<BoxedProducer> + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+/// A component could not be created from the [Registry].
+pub enum RegistryError {
+    #[error("No validator is registered with the name {0:?}")]
+    /// No validator factory is registered with the given name.
+    UnknownValidator(String),
+    #[error("No producer is registered with the name {0:?}")]
+    /// No producer factory is registered with the given name.
+    UnknownProducer(String),
+    #[error("Failed to create {name:?} from the given options: {error}")]
+    /// The factory rejected the provided options.
+    InvalidOptions {
+        /// The name of the component.
+        name: String,
+        /// The error returned by the factory.
+        error: anyhow::Error,
+    },
+}
+
+#[derive(Clone)]
+/// A set of named factories for producers and validators.
+///
+/// Downstream binaries register their custom components under a name,
+/// allowing them to be selected from a config file along with a set of
+/// JSON options which are passed to the factory.
+///
+/// The [DefaultValidator] is always registered as
+/// [DEFAULT_VALIDATOR_NAME].
+///
+/// ```
+/// use http::response::Parts;
+/// use hyper::body::Bytes;
+/// use rewrk_core::{Registry, ResponseValidator, ValidationError};
+///
+/// pub struct StatusValidator {
+///     status: u16,
+/// }
+///
+/// impl ResponseValidator for StatusValidator {
+///     fn validate(&self, head: Parts, _body: Bytes) -> Result<(), ValidationError> {
+///         if head.status.as_u16() == self.status {
+///             Ok(())
+///         } else {
+///             Err(ValidationError::InvalidStatus(head.status.as_u16()))
+///         }
+///     }
+/// }
+///
+/// let mut registry = Registry::default();
+/// registry.register_validator("status", |options| {
+///     let status = options["status"].as_u64().unwrap_or(200) as u16;
+///     Ok(StatusValidator { status })
+/// });
+///
+/// let options = serde_json::json!({ "status": 204 });
+/// let validator = registry.create_validator("status", &options).unwrap();
+/// ```
+pub struct Registry {
+    validators: BTreeMap<String, ValidatorFactory>,
+    producers: BTreeMap<String, ProducerFactory>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self {
+            validators: BTreeMap::new(),
+            producers: BTreeMap::new(),
+        };
+        registry.register_validator(DEFAULT_VALIDATOR_NAME, |_| Ok(DefaultValidator));
+        registry
+    }
+}
+
+impl Registry {
+    /// Registers a validator factory under the given name.
+    ///
+    /// Any existing factory with the same name is replaced.
+    pub fn register_validator<V, F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        V: ResponseValidator,
+        F: Fn(&Value) -> anyhow::Result<V> + Send + Sync + 'static,
+    {
+        let factory: ValidatorFactory = Arc::new(move |options| {
+            let validator = factory(options)?;
+            Ok(Arc::new(validator) as Arc<dyn ResponseValidator>)
+        });
+        self.validators.insert(name.into(), factory);
+    }
+
+    /// Registers a producer factory under the given name.
+    ///
+    /// Any existing factory with the same name is replaced.
+    pub fn register_producer<P, F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        P: Producer + Clone,
+        F: Fn(&Value) -> anyhow::Result<P> + Send + Sync + 'static,
+    {
+        let factory: ProducerFactory =
+            Arc::new(move |options| Ok(BoxedProducer::new(factory(options)?)));
+        self.producers.insert(name.into(), factory);
+    }
+
+    /// The names of all registered validators.
+    pub fn validators(&self) -> impl Iterator<Item = &str> {
+        self.validators.keys().map(String::as_str)
+    }
+
+    /// The names of all registered producers.
+    pub fn producers(&self) -> impl Iterator<Item = &str> {
+        self.producers.keys().map(String::as_str)
+    }
+
+    /// Creates a new validator with the factory registered under the given name.
+    pub fn create_validator(
+        &self,
+        name: &str,
+        options: &Value,
+    ) -> Result<Arc<dyn ResponseValidator>, RegistryError> {
+        let factory = self
+            .validators
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownValidator(name.to_string()))?;
+
+        factory(options).map_err(|error| RegistryError::InvalidOptions {
+            name: name.to_string(),
+            error,
+        })
+    }
+
+    /// Creates a new producer with the factory registered under the given name.
+    pub fn create_producer(
+        &self,
+        name: &str,
+        options: &Value,
+    ) -> Result<BoxedProducer, RegistryError> {
+        let factory = self
+            .producers
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownProducer(name.to_string()))?;
+
+        factory(options).map_err(|error| RegistryError::InvalidOptions {
+            name: name.to_string(),
+            error,
+        })
+    }
+}
+
+/// A type erased [Producer] created by the [Registry].
+pub struct BoxedProducer(Box<dyn CloneableProducer>);
+
+impl BoxedProducer {
+    /// Wraps the given producer.
+    pub fn new(producer: impl Producer + Clone) -> Self {
+        Self(Box::new(producer))
+    }
+}
+
+impl Clone for BoxedProducer {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_boxed())
+    }
+}
+
+#[async_trait]
+impl Producer for BoxedProducer {
+    fn ready(&mut self) {
+        self.0.ready()
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        self.0.create_batch().await
+    }
+}
+
+trait CloneableProducer: Producer {
+    fn clone_boxed(&self) -> Box<dyn CloneableProducer>;
+}
+
+impl<P> CloneableProducer for P
+where
+    P: Producer + Clone,
+{
+    fn clone_boxed(&self) -> Box<dyn CloneableProducer> {
+        Box::new(self.clone())
+    }
+}
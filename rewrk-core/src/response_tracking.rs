@@ -0,0 +1,219 @@
+use std::collections::BTreeSet;
+use std::mem;
+
+use http::header::HeaderName;
+use http::{HeaderMap, HeaderValue};
+
+use crate::recording::RequestKey;
+
+/// The default header the request key is written to and read back from.
+pub static DEFAULT_REQUEST_KEY_HEADER: HeaderName =
+    HeaderName::from_static("x-rewrk-request-key");
+
+#[derive(Debug, Clone)]
+/// Configuration for verifying every request receives exactly one response.
+///
+/// Each request has its [RequestKey] written to a header in the format
+/// `{worker_id}-{connection_id}-{request_id}`. A cooperating server is expected
+/// to echo the header back in its response, which lets the connection match
+/// each response to the request it was sent for.
+///
+/// This catches duplicated, missing and reordered responses, which become
+/// possible once pipelining, HTTP/2 multiplexing and retries are in play.
+/// The results are recorded by [Sample::duplicate_responses](crate::Sample::duplicate_responses),
+/// [Sample::missing_responses](crate::Sample::missing_responses) and
+/// [Sample::out_of_order_responses](crate::Sample::out_of_order_responses).
+///
+/// When requests are retried only the response to the final attempt is matched.
+pub struct ResponseTracking {
+    header: HeaderName,
+}
+
+impl Default for ResponseTracking {
+    fn default() -> Self {
+        Self {
+            header: DEFAULT_REQUEST_KEY_HEADER.clone(),
+        }
+    }
+}
+
+impl ResponseTracking {
+    /// Create a new config using the given header.
+    pub fn new(header: HeaderName) -> Self {
+        Self { header }
+    }
+
+    /// The header the request key is written to and read back from.
+    pub fn header(&self) -> &HeaderName {
+        &self.header
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// The response mismatches found by a [ResponseLedger].
+pub(crate) struct ResponseCounts {
+    /// Responses for a request which wasn't awaiting a response.
+    pub duplicate: u64,
+    /// Requests which never received a response.
+    pub missing: u64,
+    /// Responses received before the response to an older request.
+    pub out_of_order: u64,
+}
+
+#[derive(Debug)]
+/// Tracks the requests awaiting a response on a single connection.
+pub(crate) struct ResponseLedger {
+    worker_id: usize,
+    connection_id: usize,
+    outstanding: BTreeSet<u64>,
+    counts: ResponseCounts,
+}
+
+impl ResponseLedger {
+    /// Creates a new ledger for the connection of the given key.
+    pub fn new(key: RequestKey) -> Self {
+        Self {
+            worker_id: key.worker_id,
+            connection_id: key.connection_id,
+            outstanding: BTreeSet::new(),
+            counts: ResponseCounts::default(),
+        }
+    }
+
+    /// Writes the request key to the request headers and
+    /// marks the request as awaiting a response.
+    pub fn stamp(
+        &mut self,
+        config: &ResponseTracking,
+        key: RequestKey,
+        headers: &mut HeaderMap,
+    ) {
+        let value =
+            format!("{}-{}-{}", key.worker_id, key.connection_id, key.request_id);
+        headers.insert(
+            config.header.clone(),
+            HeaderValue::from_str(&value).expect("Valid header value"),
+        );
+        self.outstanding.insert(key.request_id);
+    }
+
+    /// Stops waiting for the response to a request, i.e. because
+    /// the request failed and was already recorded as an error.
+    pub fn forget(&mut self, key: RequestKey) {
+        self.outstanding.remove(&key.request_id);
+    }
+
+    /// Matches a response to the request it was sent for.
+    ///
+    /// Responses without the request key header are ignored, leaving
+    /// the request awaiting a response.
+    pub fn record_response(&mut self, config: &ResponseTracking, headers: &HeaderMap) {
+        let request_id = match headers
+            .get(&config.header)
+            .and_then(|value| value.to_str().ok())
+        {
+            None => return,
+            Some(value) => self.parse_request_id(value.trim()),
+        };
+
+        let oldest = self.outstanding.first().copied();
+        match request_id {
+            Some(request_id) if self.outstanding.remove(&request_id) => {
+                if oldest != Some(request_id) {
+                    self.counts.out_of_order += 1;
+                }
+            },
+            _ => self.counts.duplicate += 1,
+        }
+    }
+
+    /// Counts every request still awaiting a response as missing.
+    pub fn finish(&mut self) {
+        self.counts.missing += self.outstanding.len() as u64;
+        self.outstanding.clear();
+    }
+
+    /// Takes the mismatches found since the counts were last taken.
+    pub fn take_counts(&mut self) -> ResponseCounts {
+        mem::take(&mut self.counts)
+    }
+
+    /// Parses the request ID of a key sent on this connection.
+    fn parse_request_id(&self, value: &str) -> Option<u64> {
+        let mut parts = value.splitn(3, '-');
+        let worker_id = parts.next()?.parse::<usize>().ok()?;
+        let connection_id = parts.next()?.parse::<usize>().ok()?;
+        let request_id = parts.next()?.parse::<u64>().ok()?;
+
+        (worker_id == self.worker_id && connection_id == self.connection_id)
+            .then_some(request_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(request_id: u64) -> RequestKey {
+        RequestKey {
+            worker_id: 1,
+            connection_id: 2,
+            request_id,
+        }
+    }
+
+    fn response(config: &ResponseTracking, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            config.header().clone(),
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_ledger_counts_mismatches() {
+        let config = ResponseTracking::default();
+        let mut ledger = ResponseLedger::new(key(0));
+        for request_id in 0..4 {
+            let mut headers = HeaderMap::new();
+            ledger.stamp(&config, key(request_id), &mut headers);
+            assert_eq!(
+                headers[config.header()],
+                format!("1-2-{request_id}").as_str()
+            );
+        }
+
+        ledger.record_response(&config, &response(&config, "1-2-0"));
+        ledger.record_response(&config, &response(&config, "1-2-2"));
+        ledger.record_response(&config, &response(&config, "1-2-2"));
+        ledger.record_response(&config, &response(&config, "1-3-1"));
+        ledger.record_response(&config, &HeaderMap::new());
+        ledger.finish();
+
+        let counts = ledger.take_counts();
+        assert_eq!(
+            counts,
+            ResponseCounts {
+                duplicate: 2,
+                missing: 2,
+                out_of_order: 1,
+            }
+        );
+        assert_eq!(ledger.take_counts(), ResponseCounts::default());
+    }
+
+    #[test]
+    fn test_ledger_forgets_failed_requests() {
+        let config = ResponseTracking::default();
+        let mut ledger = ResponseLedger::new(key(0));
+        let mut headers = HeaderMap::new();
+        ledger.stamp(&config, key(0), &mut headers);
+        ledger.stamp(&config, key(1), &mut headers);
+        ledger.forget(key(0));
+
+        ledger.record_response(&config, &response(&config, "1-2-1"));
+        ledger.finish();
+        assert_eq!(ledger.take_counts(), ResponseCounts::default());
+    }
+}
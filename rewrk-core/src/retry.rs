@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use http::{Method, StatusCode};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::micros;
+
+/// The default maximum number of attempts, including the initial request.
+pub const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 3;
+/// The default minimum backoff delay between retry attempts.
+pub const DEFAULT_RETRY_BACKOFF_MIN: Duration = Duration::from_millis(50);
+/// The default maximum backoff delay between retry attempts.
+pub const DEFAULT_RETRY_BACKOFF_MAX: Duration = Duration::from_secs(2);
+/// If only requests with idempotent methods are retried by default.
+///
+/// Requests with non-idempotent methods, e.g. `POST` and `PATCH`,
+/// are never retried unless this is disabled.
+pub const DEFAULT_RETRY_IDEMPOTENT_ONLY: bool = true;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The strategy used to determine the delay before retrying a request.
+pub enum Backoff {
+    /// Retry immediately.
+    None,
+    /// Wait a fixed amount of time between every attempt.
+    Fixed(#[serde(with = "micros")] Duration),
+    /// Double the delay after every attempt starting at `min`,
+    /// never waiting longer than `max`.
+    Exponential {
+        #[serde(with = "micros")]
+        /// The delay before the first retry.
+        min: Duration,
+        #[serde(with = "micros")]
+        /// The upper limit of the delay.
+        max: Duration,
+    },
+    /// Wait a random delay between zero and the [Backoff::Exponential] delay.
+    ///
+    /// The randomness spreads out the attempts of many clients failing at
+    /// once, i.e. every connection being refused while the target restarts.
+    ExponentialJitter {
+        #[serde(with = "micros")]
+        /// The delay before the first retry, without jitter.
+        min: Duration,
+        #[serde(with = "micros")]
+        /// The upper limit of the delay.
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// The delay before the next attempt after `attempts` attempts
+    /// have already been made.
+    pub fn delay(&self, attempts: u32) -> Duration {
+        match *self {
+            Self::None => Duration::ZERO,
+            Self::Fixed(delay) => delay,
+            Self::Exponential { min, max } => {
+                let exponent = attempts.saturating_sub(1).min(31);
+                min.saturating_mul(1 << exponent).min(max)
+            },
+            Self::ExponentialJitter { min, max } => {
+                let delay = Self::Exponential { min, max }.delay(attempts);
+                delay.mul_f64(rand::thread_rng().gen::<f64>())
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A policy describing when and how requests should be retried.
+///
+/// By default requests are retried when the server responds with
+/// `429 Too Many Requests` or `503 Service Unavailable`, up to
+/// [DEFAULT_RETRY_MAX_ATTEMPTS] attempts in total with an exponential backoff.
+/// Only requests with idempotent methods are retried by default,
+/// see [DEFAULT_RETRY_IDEMPOTENT_ONLY].
+///
+/// Retried requests are recorded once in the sample latency with the
+/// end-to-end latency, the latency of each individual attempt is recorded
+/// separately in [Sample::attempt_latency](crate::Sample::attempt_latency).
+pub struct RetryPolicy {
+    statuses: Vec<StatusCode>,
+    max_attempts: u32,
+    backoff: Backoff,
+    idempotent_only: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::SERVICE_UNAVAILABLE,
+            ],
+            max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            backoff: Backoff::Exponential {
+                min: DEFAULT_RETRY_BACKOFF_MIN,
+                max: DEFAULT_RETRY_BACKOFF_MAX,
+            },
+            idempotent_only: DEFAULT_RETRY_IDEMPOTENT_ONLY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Set the status codes which cause a request to be retried.
+    pub fn set_statuses(&mut self, statuses: impl IntoIterator<Item = StatusCode>) {
+        self.statuses = statuses.into_iter().collect();
+    }
+
+    /// Add a status code which causes a request to be retried.
+    pub fn add_status(&mut self, status: StatusCode) {
+        if !self.statuses.contains(&status) {
+            self.statuses.push(status);
+        }
+    }
+
+    /// Set the maximum number of attempts, including the initial request.
+    ///
+    /// A value of `1` disables retries.
+    pub fn set_max_attempts(&mut self, max_attempts: u32) {
+        self.max_attempts = max_attempts.max(1);
+    }
+
+    /// Set the backoff strategy used between attempts.
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = backoff;
+    }
+
+    /// Set if only requests with idempotent methods should be retried.
+    ///
+    /// This defaults to [DEFAULT_RETRY_IDEMPOTENT_ONLY], so requests with
+    /// non-idempotent methods, e.g. `POST`, are never retried unless
+    /// this is set to `false`.
+    pub fn set_idempotent_only(&mut self, idempotent_only: bool) {
+        self.idempotent_only = idempotent_only;
+    }
+
+    /// The status codes which cause a request to be retried.
+    pub fn statuses(&self) -> &[StatusCode] {
+        &self.statuses
+    }
+
+    /// The maximum number of attempts, including the initial request.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// The backoff strategy used between attempts.
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// If only requests with idempotent methods are retried.
+    pub fn idempotent_only(&self) -> bool {
+        self.idempotent_only
+    }
+
+    /// Checks if a request with the given method can be retried.
+    pub fn allows_method(&self, method: &Method) -> bool {
+        !self.idempotent_only || is_idempotent(method)
+    }
+
+    /// Checks if a request should be retried after `attempts` attempts
+    /// were made and the last attempt returned the given status.
+    pub fn should_retry(&self, status: StatusCode, attempts: u32) -> bool {
+        attempts < self.max_attempts && self.statuses.contains(&status)
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET
+            | Method::HEAD
+            | Method::PUT
+            | Method::DELETE
+            | Method::OPTIONS
+            | Method::TRACE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idempotent_only_default() {
+        let mut policy = RetryPolicy::default();
+        assert!(policy.idempotent_only());
+        assert!(policy.allows_method(&Method::GET));
+        assert!(policy.allows_method(&Method::PUT));
+        assert!(!policy.allows_method(&Method::POST));
+        assert!(!policy.allows_method(&Method::PATCH));
+
+        policy.set_idempotent_only(false);
+        assert!(policy.allows_method(&Method::POST));
+    }
+}
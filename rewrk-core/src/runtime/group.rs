@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+
+use flume::TrySendError;
+use futures_util::future::{select, Either};
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt;
+
+use crate::producer::{BatchSender, ProducerBatches};
+use crate::Batch;
+
+#[derive(Debug, Clone)]
+/// A set of connections dedicated to executing the batches of certain tags.
+///
+/// Batches with one of the group's tags are only executed on the group's
+/// connections and no other batches are executed on them, so slow endpoints
+/// can't cause head-of-line blocking for the requests of fast endpoints.
+///
+/// Group connections are opened in addition to the benchmark's concurrency
+/// and are split across the workers in the same way. Workers which would
+/// have no connections for the group execute its batches on their shared
+/// connections instead.
+///
+/// Each worker has a single producer, so it should create batches roughly in
+/// proportion to the number of connections in each group. Batches for a busy
+/// group are buffered so the other groups keep running, but once its buffer is
+/// full the other groups wait for its batches to be taken.
+pub struct ConnectionGroup {
+    tags: Vec<usize>,
+    connections: usize,
+}
+
+impl ConnectionGroup {
+    /// Creates a new group of `connections` connections
+    /// dedicated to the given tags.
+    pub fn new(tags: impl IntoIterator<Item = usize>, connections: usize) -> Self {
+        Self {
+            tags: tags.into_iter().collect(),
+            connections,
+        }
+    }
+
+    /// The tags executed by the group.
+    pub fn tags(&self) -> &[usize] {
+        &self.tags
+    }
+
+    /// The total number of connections in the group across all workers.
+    pub fn connections(&self) -> usize {
+        self.connections
+    }
+}
+
+/// Routes the batches of a worker's producer to the connections of their tag's group.
+///
+/// Batches are handed to each group without waiting, a group whose connections
+/// are busy buffers its batches so it doesn't block the other groups. The router
+/// only waits on a group once its buffer is full, this bounds the number of
+/// batches buffered when the producer outpaces a group.
+pub(crate) struct BatchRouter {
+    /// The shared connections followed by each of the groups.
+    routes: Vec<Route>,
+    tags: BTreeMap<usize, usize>,
+}
+
+impl BatchRouter {
+    /// Creates a router for the given groups.
+    ///
+    /// Groups with no connections on this worker are routed to the shared
+    /// connections. This returns the router along with the batches for the
+    /// shared connections and each of the groups.
+    pub fn new(
+        buffer_size: usize,
+        groups: &[(&ConnectionGroup, usize)],
+    ) -> (Self, ProducerBatches, Vec<ProducerBatches>) {
+        let (shared, shared_rx) = ProducerBatches::bounded(buffer_size);
+
+        let mut routes = vec![Route::new(shared, buffer_size)];
+        let mut receivers = Vec::with_capacity(groups.len());
+        let mut tags = BTreeMap::new();
+        for (group, connections) in groups {
+            let (tx, rx) = ProducerBatches::bounded(connections * 4);
+
+            if *connections > 0 {
+                for tag in group.tags() {
+                    tags.insert(*tag, routes.len());
+                }
+            }
+
+            routes.push(Route::new(tx, connections * 4));
+            receivers.push(rx);
+        }
+
+        let router = Self { routes, tags };
+
+        (router, shared_rx, receivers)
+    }
+
+    /// Forwards batches from the producer until it has finished.
+    pub async fn run(mut self, producer: ProducerBatches) {
+        let mut pending = FuturesUnordered::new();
+
+        loop {
+            let is_full = self.routes.iter().any(Route::is_full);
+            let event = if is_full {
+                // A route is only full while it is waiting on its connections.
+                pending.next().await.map(Event::Sent)
+            } else if pending.is_empty() {
+                producer.recv_with_priority().await.ok().map(Event::Batch)
+            } else {
+                let batch = Box::pin(producer.recv_with_priority());
+                match select(batch, pending.next()).await {
+                    Either::Left((batch, _)) => batch.ok().map(Event::Batch),
+                    Either::Right((sent, _)) => sent.map(Event::Sent),
+                }
+            };
+
+            match event {
+                None => break,
+                Some(Event::Batch((batch, is_priority))) => {
+                    let index = self.tags.get(&batch.tag).copied().unwrap_or(0);
+                    let route = &mut self.routes[index];
+                    match route.push(batch, is_priority) {
+                        Err(()) => return,
+                        Ok(None) => {},
+                        Ok(Some((batch, is_priority))) => {
+                            pending.push(forward(index, route, batch, is_priority));
+                        },
+                    }
+                },
+                Some(Event::Sent((index, sent))) => {
+                    if !sent {
+                        return;
+                    }
+                    self.next_pending(index, &mut pending);
+                },
+            }
+        }
+
+        // Hand over the batches still buffered before finishing.
+        while let Some((index, sent)) = pending.next().await {
+            if !sent {
+                return;
+            }
+            self.next_pending(index, &mut pending);
+        }
+    }
+
+    /// Starts sending the next buffered batch of the route, if any.
+    fn next_pending(&mut self, index: usize, pending: &mut FuturesUnordered<Forward>) {
+        let route = &mut self.routes[index];
+        match route.buffer.pop_front() {
+            None => route.is_sending = false,
+            Some((batch, is_priority)) => {
+                pending.push(forward(index, route, batch, is_priority));
+            },
+        }
+    }
+}
+
+enum Event {
+    Batch((Batch, bool)),
+    Sent((usize, bool)),
+}
+
+type Forward = Pin<Box<dyn Future<Output = (usize, bool)> + Send>>;
+
+/// Sends the batch to the route, resolving once the batch has been sent.
+fn forward(index: usize, route: &Route, batch: Batch, is_priority: bool) -> Forward {
+    let tx = route.tx.clone();
+    Box::pin(async move { (index, tx.send(batch, is_priority).await) })
+}
+
+/// The batches of the shared connections or a group.
+struct Route {
+    tx: BatchSender,
+    /// The batches waiting for the route's connections, in order.
+    buffer: VecDeque<(Batch, bool)>,
+    buffer_size: usize,
+    /// If a batch is currently waiting to be sent.
+    is_sending: bool,
+}
+
+impl Route {
+    fn new(tx: BatchSender, buffer_size: usize) -> Self {
+        Self {
+            tx,
+            buffer: VecDeque::new(),
+            buffer_size: buffer_size.max(1),
+            is_sending: false,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.buffer.len() >= self.buffer_size
+    }
+
+    /// Sends the batch without waiting or buffers it if the connections are busy.
+    ///
+    /// Returns the batch if the route must start waiting to send it, or an
+    /// error if the connections have shutdown.
+    fn push(
+        &mut self,
+        batch: Batch,
+        is_priority: bool,
+    ) -> Result<Option<(Batch, bool)>, ()> {
+        if self.is_sending {
+            self.buffer.push_back((batch, is_priority));
+            return Ok(None);
+        }
+
+        match self.tx.try_send(batch, is_priority) {
+            Ok(()) => Ok(None),
+            Err(TrySendError::Disconnected(_)) => Err(()),
+            Err(TrySendError::Full(batch)) => {
+                self.is_sending = true;
+                Ok(Some((batch, is_priority)))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::Request;
+    use hyper::Body;
+
+    use super::*;
+
+    fn batch(tag: usize, id: usize) -> Batch {
+        let requests = (0..id).map(|_| Request::new(Body::empty())).collect();
+        Batch { tag, requests }
+    }
+
+    #[tokio::test]
+    async fn test_router_busy_group() {
+        let slow = ConnectionGroup::new([1], 1);
+        let fast = ConnectionGroup::new([2], 1);
+        let (router, _shared, groups) = BatchRouter::new(4, &[(&slow, 1), (&fast, 1)]);
+        let (tx, producer) = ProducerBatches::bounded(16);
+        let handle = tokio::spawn(router.run(producer));
+
+        // Fill the slow group's channel, the rest of its batches are buffered.
+        for id in 1..=6 {
+            assert!(tx.send(batch(1, id), false).await);
+        }
+        assert!(tx.send(batch(2, 1), false).await);
+
+        let fast_batch =
+            tokio::time::timeout(Duration::from_secs(1), groups[1].recv_async())
+                .await
+                .expect("Fast group is not blocked by the slow group")
+                .expect("Receive batch");
+        assert_eq!(fast_batch.tag, 2);
+
+        drop(tx);
+        let mut ids = Vec::new();
+        while let Ok(batch) = groups[0].recv_async().await {
+            ids.push(batch.requests.len());
+        }
+        assert_eq!(ids, [1, 2, 3, 4, 5, 6]);
+        handle.await.expect("Run router");
+    }
+}
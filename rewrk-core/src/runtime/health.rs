@@ -0,0 +1,154 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+use super::ShutdownHandle;
+use crate::connection::ReWrkConnector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The action taken when the target's health check fails.
+pub enum HealthCheckAction {
+    /// Pause sending requests until the health check passes again.
+    Pause,
+    /// Log an error and shutdown the benchmark.
+    Abort,
+}
+
+#[derive(Debug, Clone)]
+/// A health check which is polled while the benchmark is running.
+///
+/// Samples recorded while the target is unhealthy are marked, see
+/// [Sample::is_target_unhealthy](crate::Sample::is_target_unhealthy).
+pub struct HealthCheck {
+    uri: Uri,
+    interval: Duration,
+    action: HealthCheckAction,
+}
+
+impl HealthCheck {
+    /// Create a new health check polling the given URI every `interval`.
+    ///
+    /// The target is considered healthy if the endpoint responds with
+    /// a successful status within the interval.
+    pub fn new(uri: Uri, interval: Duration, action: HealthCheckAction) -> Self {
+        Self {
+            uri,
+            interval,
+            action,
+        }
+    }
+
+    /// The URI of the health check endpoint.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// The interval the health check is polled at.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The action taken when the health check fails.
+    pub fn action(&self) -> HealthCheckAction {
+        self.action
+    }
+}
+
+#[derive(Default, Clone)]
+/// The health of the benchmark target shared between the
+/// health checker and workers.
+pub struct TargetHealth {
+    is_unhealthy: Arc<AtomicBool>,
+    should_pause: Arc<AtomicBool>,
+}
+
+impl TargetHealth {
+    /// Checks if the target is currently failing its health check.
+    pub fn is_unhealthy(&self) -> bool {
+        self.is_unhealthy.load(Ordering::Relaxed)
+    }
+
+    /// Checks if workers should pause sending requests.
+    pub fn should_pause(&self) -> bool {
+        self.should_pause.load(Ordering::Relaxed)
+    }
+
+    fn set_unhealthy(&self, is_unhealthy: bool, pause: bool) {
+        self.is_unhealthy.store(is_unhealthy, Ordering::Relaxed);
+        self.should_pause
+            .store(is_unhealthy && pause, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone)]
+/// Polls the health check endpoint and updates the target health.
+pub(crate) struct HealthChecker {
+    pub check: HealthCheck,
+    pub connector: ReWrkConnector,
+    pub health: TargetHealth,
+}
+
+impl HealthChecker {
+    /// Spawns the health checker task.
+    ///
+    /// The task runs until it is aborted or the benchmark is shutdown.
+    pub fn spawn(self, shutdown: ShutdownHandle) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.check.interval);
+            self.health.set_unhealthy(false, false);
+
+            while !shutdown.should_abort() {
+                interval.tick().await;
+
+                let is_healthy = self.probe().await;
+                let was_unhealthy = self.health.is_unhealthy();
+                let pause = self.check.action == HealthCheckAction::Pause;
+                self.health.set_unhealthy(!is_healthy, pause);
+
+                if is_healthy {
+                    if was_unhealthy {
+                        info!(uri = %self.check.uri, "The target health check is passing again.");
+                    }
+                    continue;
+                }
+
+                match self.check.action {
+                    HealthCheckAction::Pause => {
+                        if !was_unhealthy {
+                            warn!(uri = %self.check.uri, "The target health check failed, pausing benchmark...");
+                        }
+                    },
+                    HealthCheckAction::Abort => {
+                        error!(uri = %self.check.uri, "The target health check failed, aborting...");
+                        shutdown.set_abort();
+                    },
+                }
+            }
+
+            // Make sure workers never wait on a stopped health checker.
+            self.health.set_unhealthy(false, false);
+        })
+    }
+
+    /// Sends a single request to the health check endpoint.
+    async fn probe(&self) -> bool {
+        let fut = async {
+            let mut conn = self.connector.connect().await.ok()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(self.check.uri.clone())
+                .body(Body::empty())
+                .ok()?;
+            let (head, _) = conn.execute_req(request).await.ok()?;
+            Some(head.status.is_success())
+        };
+
+        matches!(timeout(self.check.interval, fut).await, Ok(Some(true)))
+    }
+}
@@ -1,25 +1,86 @@
+mod group;
+mod health;
+mod phase;
+mod plan;
+mod preflight;
+mod priming;
+mod progress;
+mod send_mode;
+mod simulation;
+mod sticky;
+mod watchdog;
 mod worker;
 
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::future::Future;
-use std::io::ErrorKind;
-use std::net::ToSocketAddrs;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::Duration;
 use std::{cmp, io};
 
-use http::{HeaderValue, Uri};
+use http::header::{HeaderName, USER_AGENT};
+use http::{HeaderValue, Method, StatusCode, Uri};
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
 use tokio_native_tls::TlsConnector;
 
-pub(crate) use self::worker::{spawn_workers, ShutdownHandle, WorkerConfig};
+pub use self::group::ConnectionGroup;
+pub use self::health::{HealthCheck, HealthCheckAction};
+use self::health::{HealthChecker, TargetHealth};
+pub use self::phase::Phase;
+pub use self::plan::{
+    BenchmarkPlan,
+    ConnectionGroupPlan,
+    HeaderCapturePlan,
+    HealthCheckPlan,
+    MemoryLimitPlan,
+    OneWayDelayPlan,
+    PhasePlan,
+    PlanError,
+    PrimingPlan,
+    ResponseTrackingPlan,
+    RetryPolicyPlan,
+    ServerTimingPlan,
+    SlowStartPlan,
+};
+pub use self::preflight::{PreflightError, PreflightReport};
+use self::priming::Priming;
+use self::progress::ProgressReporter;
+pub use self::send_mode::SendMode;
+pub use self::simulation::{ResponseModel, SimulatedResponse, Simulation};
+pub use self::watchdog::MemoryLimitAction;
+use self::watchdog::MemoryWatchdog;
+pub(crate) use self::worker::{spawn_workers, ShutdownHandle, SlowStart, WorkerConfig};
 use crate::connection::ReWrkConnector;
-use crate::producer::Producer;
-use crate::recording::CollectorActor;
+use crate::producer::{Producer, ProducerEnd, ProducerPool};
+use crate::recording::{
+    Annotation,
+    CollectorActor,
+    CollectorMessage,
+    DrainedCollector,
+    Metric,
+    ProgressSnapshot,
+    Snapshot,
+    StatsWindow,
+};
+use crate::registry::{Registry, RegistryError};
+use crate::utils::RateLimiter;
 use crate::{
+    Backoff,
     DefaultValidator,
+    HeaderCapture,
     HttpProtocol,
+    OneWayDelay,
+    ResponseTracking,
     ResponseValidator,
+    RetryPolicy,
     SampleCollector,
     Scheme,
+    ServerTimingSource,
+    TagScheduler,
+    Transport,
 };
 
 /// The default percentage workers must be waiting on
@@ -31,6 +92,14 @@ pub const DEFAULT_WAIT_WARNING_THRESHOLD: f32 = 5.0;
 /// The default period of time that should elapse before
 /// a [Sample](crate::Sample) is sent to a collector.
 pub const DEFAULT_WINDOW_DURATION: Duration = Duration::from_secs(10);
+/// The default maximum number of outliers a single [Sample](crate::Sample)
+/// will capture before dropping additional outliers.
+pub const DEFAULT_MAX_OUTLIERS: usize = 64;
+/// The maximum time the pre-flight check waits to connect to the target.
+const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The default maximum number of example errors a single [Sample](crate::Sample)
+/// will hold, additional errors are only counted.
+pub const DEFAULT_MAX_ERROR_EXEMPLARS: usize = 64;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -51,6 +120,105 @@ pub enum Error {
     AddressLookup(io::Error),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+/// An error which failed a running benchmark.
+pub enum RunError {
+    #[error("The producer of worker {worker_id} took longer than {timeout:?} to create a batch")]
+    /// A producer took longer than the producer timeout to create a batch.
+    ProducerStalled {
+        /// The ID of the worker the producer was running on.
+        worker_id: usize,
+        /// The producer timeout.
+        timeout: Duration,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+/// An invalid value was passed to one of the [ReWrkBenchmark] setters.
+pub enum ConfigError {
+    #[error("The number of workers must be greater than zero")]
+    /// The number of workers is zero.
+    ZeroWorkers,
+    #[error("The sample window must be greater than zero")]
+    /// The sample window is zero.
+    ZeroSampleWindow,
+    #[error("The producer wait warning threshold must be between 0 and 100 got {0}")]
+    /// The producer wait warning threshold is not a valid percentage.
+    InvalidWaitWarningThreshold(f32),
+    #[error("The maximum connect rate must be greater than zero")]
+    /// The maximum connect rate is zero.
+    ZeroConnectRate,
+    #[error("The memory limit must be greater than zero")]
+    /// The memory limit is zero.
+    ZeroMemoryLimit,
+    #[error("The number of rounds must be greater than zero")]
+    /// The number of rounds is zero.
+    ZeroRounds,
+    #[error("The retry backoff minimum ({min:?}) must not be greater than the maximum ({max:?})")]
+    /// The minimum retry backoff is greater than the maximum.
+    InvalidRetryBackoff {
+        /// The minimum backoff delay.
+        min: Duration,
+        /// The maximum backoff delay.
+        max: Duration,
+    },
+    #[error("The maximum number of retry attempts must be greater than zero")]
+    /// The maximum number of retry attempts is zero.
+    ZeroRetryAttempts,
+    #[error("The connection group must have at least one connection")]
+    /// The connection group has no connections.
+    ZeroGroupConnections,
+    #[error("The tag {0} is already assigned to a connection group")]
+    /// The tag is assigned to more than one connection group.
+    DuplicateGroupTag(usize),
+    #[error("The concurrency ({concurrency}) must be at least the number of workers ({num_workers})")]
+    /// The concurrency would leave some workers without a connection.
+    ConcurrencyBelowWorkers {
+        /// The requested concurrency.
+        concurrency: usize,
+        /// The number of worker threads.
+        num_workers: usize,
+    },
+    #[error("The number of slow start requests must be greater than zero")]
+    /// The slow start ramp has no requests.
+    ZeroSlowStartRequests,
+    #[error("The maximum start jitter must be greater than zero")]
+    /// The maximum start jitter is zero.
+    ZeroStartJitter,
+    #[error("The producer timeout must be greater than zero")]
+    /// The producer timeout is zero.
+    ZeroProducerTimeout,
+    #[error(
+        "The maximum number of in-flight mirrored requests must be greater than zero"
+    )]
+    /// The mirror mode allows no requests in-flight.
+    ZeroMirrorInFlight,
+    #[error("The number of producer threads must be greater than zero")]
+    /// The producer pool has no threads.
+    ZeroProducerThreads,
+    #[error("The producer handoff size must be greater than zero")]
+    /// The producer pool hands off no batches at a time.
+    ZeroProducerHandoff,
+    #[error("The target request rate must be greater than zero")]
+    /// The target request rate is zero.
+    ZeroTargetRps,
+    #[error("The connect timeout must be greater than zero")]
+    /// The connect timeout is zero.
+    ZeroConnectTimeout,
+    #[error("The handshake timeout must be greater than zero")]
+    /// The handshake timeout is zero.
+    ZeroHandshakeTimeout,
+    #[error("The window stats interval must be greater than zero")]
+    /// The window stats interval is zero.
+    ZeroWindowStatsInterval,
+    #[error("Failed to create the producer pool: {0}")]
+    /// The producer pool's runtime could not be created.
+    ProducerPool(String),
+    #[error("At least one target address must be given")]
+    /// No target addresses were given.
+    NoTargetAddrs,
+}
+
 /// The core benchmarker runtime.
 ///
 /// Once a benchmarker is created you can run the benchmark
@@ -67,8 +235,21 @@ where
 {
     shutdown: ShutdownHandle,
     collector_handle: CollectorActor<C>,
+    collector_drain_timeout: Option<Duration>,
     num_workers: usize,
-    concurrency: usize,
+    live_concurrency: watch::Sender<usize>,
+    rounds: usize,
+    round_cooldown: Duration,
+    memory_watchdog: Option<MemoryWatchdog>,
+    health_checker: Option<HealthChecker>,
+    priming: Option<Priming<P>>,
+    priming_plan: Option<PrimingPlan>,
+    validator_options: serde_json::Value,
+    progress: Option<ProgressReporter>,
+    preflight_method: Method,
+    host_header: Option<HeaderValue>,
+    window_stats_interval: Option<Duration>,
+    phases: Vec<Phase>,
     worker_config: WorkerConfig<P>,
 }
 
@@ -92,13 +273,42 @@ where
         let connector = create_connector(base_uri, protocol)?;
         let (collector_handle, collector) = CollectorActor::spawn(collector).await;
         let shutdown = ShutdownHandle::default();
+        let (live_concurrency, live_concurrency_rx) = watch::channel(concurrency);
         let worker_config = WorkerConfig {
             connector,
             validator: Arc::new(DefaultValidator),
             collector,
             producer,
             sample_window: DEFAULT_WINDOW_DURATION,
+            sample_window_overrides: Arc::default(),
             producer_wait_warning_threshold: DEFAULT_WAIT_WARNING_THRESHOLD,
+            outlier_threshold: None,
+            max_outliers: DEFAULT_MAX_OUTLIERS,
+            max_error_exemplars: DEFAULT_MAX_ERROR_EXEMPLARS,
+            retry_policy: None,
+            server_timing: None,
+            one_way_delay: None,
+            response_tracking: None,
+            header_capture: None,
+            target_health: TargetHealth::default(),
+            round: 0,
+            phase: 0,
+            run_duration: None,
+            warmup: None,
+            live_concurrency: live_concurrency_rx,
+            tag_scheduler: None,
+            connection_groups: Arc::default(),
+            producer_end: ProducerEnd::default(),
+            send_mode: SendMode::default(),
+            slow_start: None,
+            start_jitter: None,
+            producer_timeout: None,
+            producer_pool: None,
+            request_limiter: None,
+            sticky_routing: false,
+            labels: Arc::default(),
+            window_stats: None,
+            benchmark_ended: Arc::default(),
         };
 
         let num_workers = cmp::max(num_cpus::get() - 1, 1);
@@ -106,8 +316,21 @@ where
         Ok(Self {
             shutdown,
             collector_handle,
+            collector_drain_timeout: None,
             num_workers,
-            concurrency,
+            live_concurrency,
+            rounds: 1,
+            round_cooldown: Duration::ZERO,
+            memory_watchdog: None,
+            health_checker: None,
+            priming: None,
+            priming_plan: None,
+            validator_options: serde_json::Value::Null,
+            progress: None,
+            preflight_method: Method::GET,
+            host_header: None,
+            window_stats_interval: None,
+            phases: Vec::new(),
             worker_config,
         })
     }
@@ -116,33 +339,317 @@ where
     ///
     /// This returns a future which will complete once all
     /// workers for the benchmark have completed.
+    ///
+    /// If multiple rounds are configured via [ReWrkBenchmark::set_rounds]
+    /// the benchmark is repeated with a fresh set of connections and
+    /// producers each round, waiting for the round cooldown in between.
+    /// Samples are labeled with the round which produced them.
+    ///
+    /// Each round is delimited by calls to [SampleCollector::start_round] and
+    /// [SampleCollector::end_round] so collectors can tell when a round's
+    /// samples begin and end.
+    ///
+    /// If a priming producer is set via [ReWrkBenchmark::set_priming_producer]
+    /// it is run before the first round starts.
     pub fn run(&self) -> impl Future<Output = ()> {
+        self.run_with(self.worker_config.clone())
+    }
+
+    /// Run a benchmark for at most the given duration.
+    ///
+    /// This behaves like [ReWrkBenchmark::run], except each round ends once
+    /// the duration has elapsed even if the producer has more batches. The
+    /// duration starts when the first batch is received, so producer setup
+    /// is not counted.
+    ///
+    /// The producer can run indefinitely, every connection is stopped
+    /// cleanly once the deadline is reached.
+    pub fn run_for(&self, duration: Duration) -> impl Future<Output = ()> {
+        let mut config = self.worker_config.clone();
+        config.run_duration = Some(duration);
+        self.run_with(config)
+    }
+
+    fn run_with(&self, config: WorkerConfig<P>) -> impl Future<Output = ()> {
         info!(
             num_workers = self.num_workers,
-            concurrency = self.concurrency,
+            concurrency = self.live_concurrency(),
+            rounds = self.rounds,
+            run_duration = ?config.run_duration,
             "Starting benchmark."
         );
 
-        let waiter = spawn_workers(
-            self.shutdown.clone(),
-            self.num_workers,
-            self.concurrency,
-            self.worker_config.clone(),
-        );
+        let shutdown = self.shutdown.clone();
+        let num_workers = self.num_workers;
+        let rounds = self.rounds;
+        let round_cooldown = self.round_cooldown;
+        let memory_watchdog = self.memory_watchdog;
+        let health_checker = self.health_checker.clone();
+        let priming = self.priming.clone();
+        let progress = self.progress.clone();
+
+        // Without a priming phase the first round starts straight away.
+        let mut first_round = None;
+        if priming.is_none() {
+            let _ = config.collector.send(CollectorMessage::StartRound(0));
+            first_round =
+                Some(spawn_workers(shutdown.clone(), num_workers, config.clone()));
+        }
 
         async move {
+            let monitors =
+                spawn_monitors(memory_watchdog, health_checker, shutdown.clone());
+            let progress = progress
+                .map(|progress| progress.start(&config.collector, config.sample_window));
+            let waiter = match first_round {
+                Some(waiter) => waiter,
+                None => {
+                    if let Some(priming) = priming {
+                        priming
+                            .run(shutdown.clone(), num_workers, config.clone())
+                            .await;
+                    }
+
+                    let _ = config.collector.send(CollectorMessage::StartRound(0));
+                    spawn_workers(shutdown.clone(), num_workers, config.clone())
+                },
+            };
             let _ = waiter.recv_async().await;
+            let _ = config.collector.send(CollectorMessage::EndRound(0));
+
+            for round in 1..rounds {
+                if shutdown.should_abort() {
+                    break;
+                }
+
+                debug!(round = round, cooldown = ?round_cooldown, "Waiting for round cooldown.");
+                tokio::time::sleep(round_cooldown).await;
+
+                info!(round = round, "Starting benchmark round.");
+                let mut config = config.clone();
+                config.round = round;
+                let _ = config.collector.send(CollectorMessage::StartRound(round));
+                let waiter =
+                    spawn_workers(shutdown.clone(), num_workers, config.clone());
+                let _ = waiter.recv_async().await;
+                let _ = config.collector.send(CollectorMessage::EndRound(round));
+            }
+
+            for monitor in monitors {
+                monitor.abort();
+            }
+            if let Some(progress) = progress {
+                progress.finish().await;
+            }
+        }
+    }
+
+    /// Set the method of the request sent by [ReWrkBenchmark::preflight].
+    ///
+    /// This should match the method of the benchmark's requests, so the probe
+    /// exercises the same route as the benchmark. By default this is `GET`.
+    pub fn set_preflight_method(&mut self, method: Method) {
+        self.preflight_method = method;
+    }
+
+    /// Checks the benchmark target is reachable and responding correctly.
+    ///
+    /// This sends a single request with the
+    /// [preflight method](ReWrkBenchmark::set_preflight_method) to the base URI
+    /// using the benchmark's protocol, then checks the protocol negotiated with
+    /// the target matches and the response passes the benchmark's validator. Running this before [ReWrkBenchmark::run]
+    /// catches DNS, TLS and protocol issues with a clear diagnostic rather than
+    /// all workers aborting with connect errors once the benchmark has started.
+    pub async fn preflight(&self) -> Result<PreflightReport, PreflightError> {
+        let report = preflight::preflight(
+            &self.worker_config.connector,
+            self.preflight_method.clone(),
+            self.worker_config.validator.as_ref(),
+            PREFLIGHT_TIMEOUT,
+        )
+        .await?;
+
+        info!(
+            addr = %report.addr,
+            version = ?report.version,
+            status = %report.status,
+            connect_latency = ?report.connect_latency,
+            request_latency = ?report.request_latency,
+            "Pre-flight check passed."
+        );
+
+        Ok(report)
+    }
+
+    /// Run each of the given phases back to back.
+    ///
+    /// Each phase runs for its set duration with its own sample window,
+    /// the round settings are ignored. Samples are labeled with the index
+    /// of the phase which produced them, the collector is given the phase of
+    /// each index via [SampleCollector::start_phase] before its samples.
+    /// All phases are run as a single round.
+    ///
+    /// If a priming producer is set via [ReWrkBenchmark::set_priming_producer]
+    /// it is run before the first phase starts.
+    ///
+    /// This returns a future which will complete once all
+    /// phases have completed.
+    pub fn run_phases(&self, phases: Vec<Phase>) -> impl Future<Output = ()> {
+        let shutdown = self.shutdown.clone();
+        let num_workers = self.num_workers;
+        let memory_watchdog = self.memory_watchdog;
+        let health_checker = self.health_checker.clone();
+        let priming = self.priming.clone();
+        let progress = self.progress.clone();
+        let config = self.worker_config.clone();
+
+        async move {
+            let monitors =
+                spawn_monitors(memory_watchdog, health_checker, shutdown.clone());
+            let progress = progress
+                .map(|progress| progress.start(&config.collector, config.sample_window));
+            if let Some(priming) = priming {
+                priming
+                    .run(shutdown.clone(), num_workers, config.clone())
+                    .await;
+            }
+
+            let _ = config.collector.send(CollectorMessage::StartRound(0));
+            for (index, phase) in phases.into_iter().enumerate() {
+                if shutdown.should_abort() {
+                    break;
+                }
+
+                info!(
+                    phase = index,
+                    label = phase.label(),
+                    duration = ?phase.duration(),
+                    sample_window = ?phase.sample_window(),
+                    "Starting benchmark phase."
+                );
+
+                let _ = config
+                    .collector
+                    .send(CollectorMessage::StartPhase(index, Box::new(phase.clone())));
+
+                let mut config = config.clone();
+                config.phase = index;
+                config.sample_window = phase.sample_window();
+                config.run_duration = Some(phase.duration());
+                let waiter = spawn_workers(shutdown.clone(), num_workers, config);
+                let _ = waiter.recv_async().await;
+            }
+
+            let _ = config.collector.send(CollectorMessage::EndRound(0));
+
+            for monitor in monitors {
+                monitor.abort();
+            }
+            if let Some(progress) = progress {
+                progress.finish().await;
+            }
         }
     }
 
     /// Shuts the benchmarker down and returns the
     /// collector once complete.
+    ///
+    /// If a collector drain timeout is set via
+    /// [ReWrkBenchmark::set_collector_drain_timeout] any samples not processed
+    /// in time are dropped, use [ReWrkBenchmark::drain_collector] to find out
+    /// if the results are partial.
     pub async fn consume_collector(self) -> C {
+        self.drain_collector().await.into_inner()
+    }
+
+    /// Shuts the benchmarker down and returns the collector
+    /// once it has processed the remaining samples.
+    ///
+    /// If the collector does not finish within the collector drain timeout
+    /// it is aborted and the number of dropped samples is returned with it.
+    pub async fn drain_collector(self) -> DrainedCollector<C> {
         self.shutdown();
         drop(self.worker_config);
 
-        let handle = self.collector_handle;
-        handle.0.await.expect("Join task")
+        self.collector_handle
+            .drain(self.collector_drain_timeout)
+            .await
+    }
+
+    /// Enables taking snapshots via [ReWrkBenchmark::snapshot].
+    ///
+    /// Snapshots require the collector to keep a merged copy of every sample,
+    /// so this is disabled by default. Only samples collected after this is
+    /// called are included in snapshots.
+    pub fn enable_snapshots(&mut self) {
+        let _ = self
+            .worker_config
+            .collector
+            .send(CollectorMessage::EnableSnapshots);
+    }
+
+    /// Enables detecting workers and connections which lagged behind their
+    /// peers, see [ImbalanceDetector](crate::ImbalanceDetector).
+    ///
+    /// The detector keeps a latency histogram per connection, so this is
+    /// disabled by default. The threshold is the relative distance from the
+    /// median which is considered imbalanced, i.e.
+    /// [DEFAULT_IMBALANCE_THRESHOLD](crate::DEFAULT_IMBALANCE_THRESHOLD).
+    pub fn enable_imbalance_detection(&mut self, threshold: f64) {
+        let _ = self
+            .worker_config
+            .collector
+            .send(CollectorMessage::EnableImbalanceDetection(threshold));
+    }
+
+    /// Takes a snapshot of the samples collected so far.
+    ///
+    /// This does not interrupt the benchmark, although samples are only
+    /// collected at the end of each sample window so recent requests may
+    /// not be included yet.
+    ///
+    /// Returns `None` if the collector has shutdown or snapshots have not been
+    /// enabled via [ReWrkBenchmark::enable_snapshots].
+    pub async fn snapshot(&self) -> Option<Snapshot> {
+        let (tx, rx) = oneshot::channel();
+        self.worker_config
+            .collector
+            .send_async(CollectorMessage::Snapshot(tx))
+            .await
+            .ok()?;
+        rx.await.ok()
+    }
+
+    #[cfg(feature = "prometheus")]
+    /// Serves the samples collected so far as Prometheus metrics on `/metrics`.
+    ///
+    /// The server runs until the returned handle is dropped, see
+    /// [prometheus](crate::prometheus) for the metrics exposed. The metrics are
+    /// built from snapshots, which are enabled by this, see
+    /// [ReWrkBenchmark::enable_snapshots].
+    pub async fn serve_metrics(
+        &self,
+        addr: std::net::SocketAddr,
+    ) -> io::Result<crate::prometheus::MetricsServer> {
+        let collector = self.worker_config.collector.clone();
+        let _ = collector.send(CollectorMessage::EnableSnapshots);
+        crate::prometheus::MetricsServer::spawn(addr, collector).await
+    }
+
+    /// Sends a timestamped annotation to the collector, marking an external
+    /// event such as a deployment alongside the samples.
+    ///
+    /// The annotation is processed in order with the samples which have been
+    /// submitted so far, see [Metric::Annotation](crate::Metric::Annotation).
+    ///
+    /// Returns `false` if the collector has shutdown.
+    pub fn annotate(&self, message: impl Into<String>) -> bool {
+        let annotation = Annotation::new(message);
+        debug!(annotation = ?annotation, "Annotating benchmark.");
+        self.worker_config
+            .collector
+            .send(CollectorMessage::Metric(Metric::Annotation(annotation)))
+            .is_ok()
     }
 
     /// Sets the shutdown flag for the running benchmark.
@@ -150,26 +657,453 @@ where
         self.shutdown.set_abort();
     }
 
+    /// The error which failed the benchmark, if any.
+    ///
+    /// The benchmark is shutdown once it fails, so this should be checked
+    /// after [ReWrkBenchmark::run] completes.
+    pub fn run_error(&self) -> Option<RunError> {
+        self.shutdown.error()
+    }
+
     /// Sets the maximum number of times the connector will attempt
     /// to connect to the server before error.
     pub fn set_connection_retry_max(&mut self, max: usize) {
         self.worker_config.connector.set_retry_max(max)
     }
 
+    /// Set the backoff between failed attempts to establish a connection.
+    ///
+    /// Connections are retried up to the connection retry maximum, set via
+    /// [ReWrkBenchmark::set_connection_retry_max]. A [Backoff::ExponentialJitter]
+    /// avoids every connection retrying in lockstep when the target is briefly
+    /// unavailable.
+    ///
+    /// By default this is [DEFAULT_CONNECT_BACKOFF](crate::DEFAULT_CONNECT_BACKOFF).
+    pub fn set_connect_backoff(&mut self, backoff: Backoff) -> Result<(), ConfigError> {
+        if let Backoff::Exponential { min, max }
+        | Backoff::ExponentialJitter { min, max } = backoff
+        {
+            if min > max {
+                return Err(ConfigError::InvalidRetryBackoff { min, max });
+            }
+        }
+
+        self.worker_config.connector.set_connect_backoff(backoff);
+        Ok(())
+    }
+
+    /// Set the time allowed to open each connection's TCP stream, or the
+    /// custom transport if one is set.
+    ///
+    /// Each connect attempt which takes longer fails with a
+    /// [ConnectErrorKind::ConnectTimeout](crate::ConnectErrorKind::ConnectTimeout)
+    /// and is retried up to the connection retry maximum.
+    ///
+    /// By default this is [DEFAULT_CONNECT_TIMEOUT](crate::DEFAULT_CONNECT_TIMEOUT).
+    pub fn set_connect_timeout(&mut self, dur: Duration) -> Result<(), ConfigError> {
+        if dur.is_zero() {
+            return Err(ConfigError::ZeroConnectTimeout);
+        }
+
+        self.worker_config.connector.set_connect_timeout(dur);
+        Ok(())
+    }
+
+    /// Set the time allowed to complete each connection's TLS and HTTP handshakes.
+    ///
+    /// Each connect attempt whose handshakes take longer fails with a
+    /// [ConnectErrorKind::HandshakeTimeout](crate::ConnectErrorKind::HandshakeTimeout)
+    /// and is retried up to the connection retry maximum.
+    ///
+    /// By default this is [DEFAULT_HANDSHAKE_TIMEOUT](crate::DEFAULT_HANDSHAKE_TIMEOUT).
+    pub fn set_handshake_timeout(&mut self, dur: Duration) -> Result<(), ConfigError> {
+        if dur.is_zero() {
+            return Err(ConfigError::ZeroHandshakeTimeout);
+        }
+
+        self.worker_config.connector.set_handshake_timeout(dur);
+        Ok(())
+    }
+
+    /// Set the addresses of the target new connections are made to.
+    ///
+    /// Each new connection is made to the next address in turn. By default
+    /// these are every address the host of the target URI resolves to, in
+    /// the order they were resolved, so connections to a DNS balanced or
+    /// dual-stack target are spread across its backends.
+    pub fn set_target_addrs(
+        &mut self,
+        addrs: Vec<SocketAddr>,
+    ) -> Result<(), ConfigError> {
+        if addrs.is_empty() {
+            return Err(ConfigError::NoTargetAddrs);
+        }
+
+        self.worker_config.connector.set_addrs(addrs);
+        Ok(())
+    }
+
+    /// Set the `Host` header sent with every request.
+    ///
+    /// By default this is the host of the target URI, including the port
+    /// when it isn't the scheme's default. The `Host` header always takes
+    /// priority over any set by the producer or the default headers.
+    pub fn set_host_header(&mut self, host: HeaderValue) {
+        self.worker_config.connector.set_host_header(host.clone());
+        self.host_header = Some(host);
+    }
+
+    /// Set a header which is added to every request sent by the benchmark.
+    ///
+    /// Headers already set on a request by the producer take priority
+    /// over the default headers.
+    pub fn set_default_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.worker_config.connector.set_default_header(name, value);
+    }
+
+    /// Removes a header from the set of default headers.
+    ///
+    /// This can be used to remove the default `User-Agent` header.
+    pub fn remove_default_header(&mut self, name: &HeaderName) {
+        self.worker_config.connector.remove_default_header(name);
+    }
+
+    /// Set the `User-Agent` header sent with every request.
+    ///
+    /// By default this is `rewrk-core/<version>`.
+    pub fn set_user_agent(&mut self, user_agent: HeaderValue) {
+        self.set_default_header(USER_AGENT, user_agent);
+    }
+
+    /// Set the maximum number of new connections established per second.
+    ///
+    /// This ramps up connections at a controlled pace rather than every worker
+    /// connecting at once, which can trip SYN flood protection on the target
+    /// and pollute the first sample window.
+    ///
+    /// By default connections are established as fast as possible.
+    pub fn set_max_connect_rate(
+        &mut self,
+        conns_per_sec: u32,
+    ) -> Result<(), ConfigError> {
+        let conns_per_sec =
+            NonZeroU32::new(conns_per_sec).ok_or(ConfigError::ZeroConnectRate)?;
+        self.worker_config
+            .connector
+            .set_max_connect_rate(conns_per_sec);
+        Ok(())
+    }
+
+    /// Pace the requests sent across all workers to `rps` requests per second.
+    ///
+    /// Rather than sending requests as fast as the target responds, requests
+    /// are spaced out evenly so the latency can be measured under a fixed load.
+    /// Connections only send a request once the previous has completed, so
+    /// the rate is only reached with enough connections to sustain it.
+    ///
+    /// Requests follow a fixed schedule and their latency is measured from the
+    /// point in time they were scheduled to be sent, rather than when they
+    /// were actually sent. A slow response which holds up the requests
+    /// behind it is therefore included in their latencies instead of being
+    /// hidden by the requests which were never sent, avoiding coordinated
+    /// omission. If there aren't enough connections to sustain the rate the
+    /// latencies keep growing. By default requests are sent as fast as possible.
+    pub fn set_target_rps(&mut self, rps: u32) -> Result<(), ConfigError> {
+        let rps = NonZeroU32::new(rps).ok_or(ConfigError::ZeroTargetRps)?;
+        self.worker_config.request_limiter = Some(RateLimiter::per_second(rps));
+        Ok(())
+    }
+
+    /// Spread the first `requests` of each connection evenly over `interval`.
+    ///
+    /// This gently ramps up new connections so HTTP/2 servers with small
+    /// initial flow control windows aren't saturated the instant the
+    /// benchmark starts, which would otherwise show up as artificially bad
+    /// latencies in the first sample window. The time spent waiting is not
+    /// included in the request latencies.
+    ///
+    /// By default connections send requests as fast as possible.
+    pub fn set_slow_start(
+        &mut self,
+        requests: usize,
+        interval: Duration,
+    ) -> Result<(), ConfigError> {
+        if requests == 0 {
+            return Err(ConfigError::ZeroSlowStartRequests);
+        }
+
+        self.worker_config.slow_start = Some(SlowStart { requests, interval });
+        Ok(())
+    }
+
+    /// Delay the first request of each connection by a random offset
+    /// of up to `max`.
+    ///
+    /// Without this every connection starts at the same instant, so their
+    /// sample windows and any periodic effects on the server, i.e. timers
+    /// or garbage collection, line up across hundreds of connections.
+    /// Staggering the start decorrelates them. The time spent waiting is
+    /// not included in the request latencies.
+    ///
+    /// By default connections start sending requests immediately.
+    pub fn set_start_jitter(&mut self, max: Duration) -> Result<(), ConfigError> {
+        if max.is_zero() {
+            return Err(ConfigError::ZeroStartJitter);
+        }
+
+        self.worker_config.start_jitter = Some(max);
+        Ok(())
+    }
+
+    /// Set the maximum time a producer may take to create a batch.
+    ///
+    /// A producer whose [Producer::create_batch] hangs would otherwise leave
+    /// its worker waiting on the producer for the rest of the benchmark, with
+    /// only a warning once it completes. Instead the benchmark is shutdown and
+    /// fails with [RunError::ProducerStalled], see [ReWrkBenchmark::run_error].
+    ///
+    /// The time spent waiting for connections to take the produced batches
+    /// is not counted. By default producers have no timeout.
+    pub fn set_producer_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<(), ConfigError> {
+        if timeout.is_zero() {
+            return Err(ConfigError::ZeroProducerTimeout);
+        }
+
+        self.worker_config.producer_timeout = Some(timeout);
+        Ok(())
+    }
+
+    /// Set the transport connections are established over.
+    ///
+    /// The base URI is still used for the request URIs and `Host` header,
+    /// but no connections are made to its address. See
+    /// [DuplexTransport](crate::DuplexTransport) for benchmarking a service in-process.
+    pub fn set_transport(&mut self, transport: impl Transport) {
+        self.worker_config
+            .connector
+            .set_transport(Arc::new(transport));
+    }
+
     /// Sets the benchmark validator.
     pub fn set_validator(&mut self, validator: impl ResponseValidator) {
         self.worker_config.validator = Arc::new(validator);
+        self.validator_options = serde_json::Value::Null;
     }
 
     /// Set the number of workers to spawn.
-    pub fn set_num_workers(&mut self, n: usize) {
+    pub fn set_num_workers(&mut self, n: usize) -> Result<(), ConfigError> {
+        if n == 0 {
+            return Err(ConfigError::ZeroWorkers);
+        }
+
         self.num_workers = n;
+        Ok(())
+    }
+
+    /// Set the number of concurrent connections while the benchmark is running.
+    ///
+    /// Workers open new connections or drain their most recently opened ones
+    /// until they reach their share of the new concurrency. Draining connections
+    /// finish their current batch before closing.
+    ///
+    /// This takes `&self` so it can be called alongside [ReWrkBenchmark::run],
+    /// combined with [ReWrkBenchmark::snapshot] it can be used to build a
+    /// controller targeting a given latency. The value also applies to
+    /// any later runs of the benchmark.
+    pub fn set_live_concurrency(&self, n: usize) -> Result<(), ConfigError> {
+        if n < self.num_workers {
+            return Err(ConfigError::ConcurrencyBelowWorkers {
+                concurrency: n,
+                num_workers: self.num_workers,
+            });
+        }
+
+        info!(concurrency = n, "Adjusting benchmark concurrency.");
+        self.live_concurrency.send_replace(n);
+        Ok(())
+    }
+
+    /// The current target number of concurrent connections.
+    pub fn live_concurrency(&self) -> usize {
+        *self.live_concurrency.borrow()
+    }
+
+    /// Set the scheduler used to share connection time across tags.
+    ///
+    /// See [TagScheduler] for more details.
+    pub fn set_tag_scheduler(&mut self, scheduler: TagScheduler) {
+        self.worker_config.tag_scheduler = Some(scheduler);
+    }
+
+    /// Set what a producer returning [RequestBatch::End](crate::RequestBatch::End) ends.
+    ///
+    /// By default only the producer's worker ends, see [ProducerEnd].
+    /// Use [ProducerEnd::Drain] when every produced request must be sent.
+    pub fn set_producer_end(&mut self, end: ProducerEnd) {
+        self.worker_config.producer_end = end;
+    }
+
+    /// Run the producers on a dedicated pool of `threads` rather than on
+    /// the workers, handing off `handoff_size` batches at a time.
+    ///
+    /// This is useful for producers doing CPU heavy work, i.e. templating
+    /// request bodies, which would otherwise slow down the connections
+    /// sharing their worker. Batches are handed off once `handoff_size` have
+    /// been produced, the oldest has waited for a few milliseconds or the
+    /// producer has ended, so this is best suited to producers creating
+    /// batches at very high rates.
+    ///
+    /// Producers using a [TagScheduler] hand off their batches one at a time.
+    /// By default producers run on their worker's runtime.
+    pub fn set_producer_pool(
+        &mut self,
+        threads: usize,
+        handoff_size: usize,
+    ) -> Result<(), ConfigError> {
+        if threads == 0 {
+            return Err(ConfigError::ZeroProducerThreads);
+        }
+        if handoff_size == 0 {
+            return Err(ConfigError::ZeroProducerHandoff);
+        }
+
+        let pool = ProducerPool::new(threads, handoff_size)
+            .map_err(|e| ConfigError::ProducerPool(e.to_string()))?;
+        self.worker_config.producer_pool = Some(pool);
+        Ok(())
+    }
+
+    /// Set the labels attached to every sample produced by the benchmark.
+    ///
+    /// Labels describe the benchmark as a whole, i.e. the build, region or
+    /// scenario, so results from many benchmarks can be stored together and
+    /// sliced by them. They are exported with the samples, i.e. in archives,
+    /// and with the [LatencyHeatmap](crate::LatencyHeatmap).
+    pub fn set_labels(&mut self, labels: HashMap<String, String>) {
+        self.worker_config.labels = Arc::new(labels.into_iter().collect());
+    }
+
+    /// The labels attached to every sample produced by the benchmark.
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        &self.worker_config.labels
+    }
+
+    /// Route batches to connections by their [StickyKey](crate::StickyKey).
+    ///
+    /// When enabled, every batch with the same key is executed on the same
+    /// connection of its worker, so servers relying on session affinity are
+    /// benchmarked realistically. Batches without a key are executed on
+    /// whichever connection is free.
+    ///
+    /// Keys are only routed to the connections each worker opens when the
+    /// benchmark starts, connections added later via
+    /// [ReWrkBenchmark::set_live_concurrency] only execute batches without a
+    /// key. A connection which is busy holds up the batches of later keys
+    /// until it has room for the next batch of its own keys.
+    ///
+    /// By default batches are executed on whichever connection is free.
+    pub fn set_sticky_routing(&mut self, enabled: bool) {
+        self.worker_config.sticky_routing = enabled;
+    }
+
+    /// Set how workers send requests and handle their responses.
+    ///
+    /// By default every response is waited for and validated, see [SendMode].
+    pub fn set_send_mode(&mut self, mode: SendMode) -> Result<(), ConfigError> {
+        if let SendMode::Mirror { max_in_flight: 0 } = mode {
+            return Err(ConfigError::ZeroMirrorInFlight);
+        }
+
+        self.worker_config.send_mode = mode;
+        Ok(())
+    }
+
+    /// Set the maximum time to wait for the collector to process the
+    /// remaining samples once the benchmark has shutdown.
+    ///
+    /// Once elapsed the collector is aborted, dropping the remaining samples.
+    /// By default there is no timeout.
+    pub fn set_collector_drain_timeout(&mut self, timeout: Duration) {
+        self.collector_drain_timeout = Some(timeout);
+    }
+
+    /// Add a group of connections dedicated to the batches of certain tags.
+    ///
+    /// See [ConnectionGroup] for more details.
+    pub fn add_connection_group(
+        &mut self,
+        group: ConnectionGroup,
+    ) -> Result<(), ConfigError> {
+        if group.connections() == 0 {
+            return Err(ConfigError::ZeroGroupConnections);
+        }
+
+        let existing = self.worker_config.connection_groups.iter();
+        for other in existing {
+            if let Some(tag) = group.tags().iter().find(|tag| other.tags().contains(tag))
+            {
+                return Err(ConfigError::DuplicateGroupTag(*tag));
+            }
+        }
+
+        Arc::make_mut(&mut self.worker_config.connection_groups).push(group);
+        Ok(())
     }
 
     /// Set the duration which should elapse before a sample
     /// is submitted to be processed in the collector.
-    pub fn set_sample_window(&mut self, dur: Duration) {
+    pub fn set_sample_window(&mut self, dur: Duration) -> Result<(), ConfigError> {
+        if dur.is_zero() {
+            return Err(ConfigError::ZeroSampleWindow);
+        }
+
         self.worker_config.sample_window = dur;
+        Ok(())
+    }
+
+    /// Set the sample window of samples with the given tag, replacing
+    /// the benchmark's and any worker's sample window.
+    ///
+    /// This allows fine-grained windows for critical tags while
+    /// background load is sampled more coarsely. The window a sample
+    /// was recorded with is available via
+    /// [SampleMetadata::sample_window](crate::SampleMetadata::sample_window).
+    pub fn set_tag_sample_window(
+        &mut self,
+        tag: usize,
+        dur: Duration,
+    ) -> Result<(), ConfigError> {
+        if dur.is_zero() {
+            return Err(ConfigError::ZeroSampleWindow);
+        }
+
+        Arc::make_mut(&mut self.worker_config.sample_window_overrides)
+            .tags
+            .insert(tag, dur);
+        Ok(())
+    }
+
+    /// Set the sample window of the samples produced by the given worker,
+    /// replacing the benchmark's sample window.
+    ///
+    /// Tag specific windows set via [Self::set_tag_sample_window]
+    /// take priority over this.
+    pub fn set_worker_sample_window(
+        &mut self,
+        worker_id: usize,
+        dur: Duration,
+    ) -> Result<(), ConfigError> {
+        if dur.is_zero() {
+            return Err(ConfigError::ZeroSampleWindow);
+        }
+
+        Arc::make_mut(&mut self.worker_config.sample_window_overrides)
+            .workers
+            .insert(worker_id, dur);
+        Ok(())
     }
 
     /// Set the percentage threshold that the system must be
@@ -177,13 +1111,651 @@ where
     ///
     /// This is useful in situations where you know the producer will
     /// take more time than normal and want to silence the warning.
-    pub fn set_producer_wait_warning_threshold(&mut self, pct: f32) {
+    pub fn set_producer_wait_warning_threshold(
+        &mut self,
+        pct: f32,
+    ) -> Result<(), ConfigError> {
+        if !(0.0..=100.0).contains(&pct) {
+            return Err(ConfigError::InvalidWaitWarningThreshold(pct));
+        }
+
         self.worker_config.producer_wait_warning_threshold = pct;
+        Ok(())
+    }
+
+    /// Set a warm-up period at the start of the benchmark whose samples are discarded.
+    ///
+    /// Requests are sent as normal while warming up, so servers with lazy
+    /// initialisation, i.e. JIT compilation or connection pools, reach a steady
+    /// state on the benchmark's own connections before any metrics are kept.
+    /// The first sample window starts once the warm-up has elapsed.
+    ///
+    /// The warm-up starts when the first batch is received and is repeated at
+    /// the start of each round and phase, as each opens new connections. It is
+    /// not included in the duration given to [ReWrkBenchmark::run_for]. The
+    /// connect phases of each connection are recorded in its first sample
+    /// after the warm-up.
+    ///
+    /// Unlike [priming](ReWrkBenchmark::set_priming_producer), which brings the
+    /// target's data into a defined state with its own producer and connections,
+    /// the warm-up runs the benchmark's own producer on the measured connections.
+    /// If both are set, priming runs to completion first and the warm-up starts
+    /// once the benchmark's connections receive their first batch.
+    pub fn set_warmup(&mut self, duration: Duration) {
+        self.worker_config.warmup = Some(duration);
+    }
+
+    /// Pass the stats of each window of the given interval to the producers.
+    ///
+    /// The samples of all workers are merged into windows of the interval and
+    /// once a window has elapsed its stats are passed to
+    /// [Producer::window_stats] before the next batch is created. This allows
+    /// feedback controlled workloads, i.e. producers which shrink their batches
+    /// while the p99 latency is above a target and grow them again once the
+    /// target recovers.
+    ///
+    /// Windows are only complete once a sample arrives after the interval has
+    /// elapsed, so the interval should be at least the sample window.
+    pub fn set_window_stats(&mut self, interval: Duration) -> Result<(), ConfigError> {
+        if interval.is_zero() {
+            return Err(ConfigError::ZeroWindowStatsInterval);
+        }
+
+        let (tx, rx) = watch::channel(None);
+        let window = StatsWindow::new(interval, tx);
+        let _ = self
+            .worker_config
+            .collector
+            .send(CollectorMessage::StatsWindow(Box::new(window)));
+        self.worker_config.window_stats = Some(rx);
+        self.window_stats_interval = Some(interval);
+        Ok(())
+    }
+
+    /// Set the latency threshold which marks a request as an outlier.
+    ///
+    /// Requests which take longer than this threshold have their
+    /// [RequestKey](crate::RequestKey), timestamp, status and connection
+    /// captured in the sample's outlier list. By default no outliers are captured.
+    pub fn set_outlier_threshold(&mut self, threshold: Duration) {
+        self.worker_config.outlier_threshold = Some(threshold);
+    }
+
+    /// Set the maximum number of outliers captured per sample.
+    pub fn set_max_outliers(&mut self, n: usize) {
+        self.worker_config.max_outliers = n;
+    }
+
+    /// Set the maximum number of example errors held by a single sample.
+    ///
+    /// Every error is counted by kind, but only the first errors of a sample
+    /// are kept in full, this bounds the memory used by long running
+    /// benchmarks with a high error rate.
+    pub fn set_max_error_exemplars(&mut self, n: usize) {
+        self.worker_config.max_error_exemplars = n;
+    }
+
+    /// Set a limit on the resident memory of the process in bytes.
+    ///
+    /// While the benchmark is running the memory usage of the process
+    /// is checked every second, if the usage exceeds the limit the given
+    /// action is taken. This is currently only supported on Linux.
+    pub fn set_memory_limit(
+        &mut self,
+        limit: u64,
+        action: MemoryLimitAction,
+    ) -> Result<(), ConfigError> {
+        if limit == 0 {
+            return Err(ConfigError::ZeroMemoryLimit);
+        }
+
+        self.memory_watchdog = Some(MemoryWatchdog { limit, action });
+        Ok(())
+    }
+
+    /// Set a health check which is polled while the benchmark is running.
+    ///
+    /// If the target fails its health check the benchmark is either paused
+    /// until the target recovers or aborted. Samples recorded while the target
+    /// is unhealthy are marked so they can be excluded from reports.
+    pub fn set_health_check(&mut self, check: HealthCheck) -> Result<(), Error> {
+        let connector = create_connector(check.uri().clone(), HttpProtocol::HTTP1)?;
+        self.health_checker = Some(HealthChecker {
+            check,
+            connector,
+            health: self.worker_config.target_health.clone(),
+        });
+        Ok(())
+    }
+
+    /// Set the number of rounds the benchmark is run for
+    /// each call to [ReWrkBenchmark::run].
+    ///
+    /// By default the benchmark runs a single round.
+    pub fn set_rounds(&mut self, rounds: usize) -> Result<(), ConfigError> {
+        if rounds == 0 {
+            return Err(ConfigError::ZeroRounds);
+        }
+
+        self.rounds = rounds;
+        Ok(())
+    }
+
+    /// Set the period of time to wait between benchmark rounds.
+    ///
+    /// This gives the server and OS time to clean up connections,
+    /// i.e. sockets in `TIME_WAIT`, before the next round begins.
+    pub fn set_round_cooldown(&mut self, cooldown: Duration) {
+        self.round_cooldown = cooldown;
+    }
+
+    /// Set the phases of the benchmark.
+    ///
+    /// The phases are exported with the [BenchmarkPlan] and can be run
+    /// with [ReWrkBenchmark::run_phases], i.e. after creating the benchmark
+    /// via [ReWrkBenchmark::from_plan].
+    pub fn set_phases(&mut self, phases: Vec<Phase>) {
+        self.phases = phases;
+    }
+
+    /// The phases of the benchmark set via [ReWrkBenchmark::set_phases].
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
+    }
+
+    /// Set a producer which primes the target before the measured
+    /// benchmark starts, e.g. a sequential scan over all keys to warm caches.
+    ///
+    /// The priming producer runs with the same connections and settings as
+    /// the benchmark for at most `duration`, or until it returns
+    /// [RequestBatch::End](crate::RequestBatch::End). Its samples are not sent
+    /// to the collector, so cache dependent benchmarks start from a defined state.
+    ///
+    /// Priming is never warmed up, a [warm-up](ReWrkBenchmark::set_warmup) only
+    /// starts after priming has finished.
+    pub fn set_priming_producer<Q>(&mut self, producer: Q, duration: Duration)
+    where
+        Q: Producer + Clone,
+    {
+        self.priming = Some(Priming::new(producer, duration));
+        self.priming_plan = None;
+    }
+
+    /// Set a handler which is called with the progress of the benchmark
+    /// every sample window while it runs.
+    ///
+    /// The handler is passed the total requests sent, errors and time elapsed
+    /// so far, allowing embedding applications to show progress without a
+    /// custom collector. It is called once more after the benchmark has
+    /// completed. The handler is called from the runtime, so it should return
+    /// quickly, i.e. by sending the progress to a channel.
+    pub fn set_progress_handler(
+        &mut self,
+        handler: impl Fn(ProgressSnapshot) + Send + Sync + 'static,
+    ) {
+        self.progress = Some(ProgressReporter::new(handler));
+    }
+
+    /// Set the source of server reported processing times.
+    ///
+    /// Server times are recorded in [Sample::server_time](crate::Sample::server_time)
+    /// along with the network overhead of each request. By default server
+    /// times are not extracted.
+    pub fn set_server_timing(&mut self, source: ServerTimingSource) {
+        self.worker_config.server_timing = Some(source);
+    }
+
+    /// Enable one-way delay estimation using the given config.
+    ///
+    /// This requires a cooperating server which responds with the time
+    /// it received each request, see [OneWayDelay] for more details.
+    pub fn set_one_way_delay(&mut self, config: OneWayDelay) {
+        self.worker_config.one_way_delay = Some(config);
+    }
+
+    /// Enable verifying every request receives exactly one response.
+    ///
+    /// This requires a cooperating server which echoes the request key
+    /// header back, see [ResponseTracking] for more details.
+    pub fn set_response_tracking(&mut self, config: ResponseTracking) {
+        self.worker_config.response_tracking = Some(config);
+    }
+
+    /// Enable counting the values of chosen response headers in each sample.
+    ///
+    /// See [HeaderCapture] for more details.
+    pub fn set_header_capture(&mut self, config: HeaderCapture) {
+        self.worker_config.header_capture = Some(config);
+    }
+
+    /// Set the policy for retrying requests.
+    ///
+    /// By default requests are never retried.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.worker_config.retry_policy = Some(Arc::new(policy));
+    }
+
+    /// Retry requests which are rate limited by the server
+    /// with a `429 Too Many Requests` response.
+    ///
+    /// Only requests with idempotent methods are retried unless
+    /// [ReWrkBenchmark::set_retry_idempotent_only] is set to `false`.
+    pub fn enable_ratelimit_retry(&mut self) {
+        self.retry_policy_mut()
+            .add_status(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    /// Set the exponential backoff used between retry attempts.
+    ///
+    /// This has no effect unless retries are enabled via
+    /// [ReWrkBenchmark::enable_ratelimit_retry] or a custom [RetryPolicy].
+    pub fn set_retry_backoff(
+        &mut self,
+        min: Duration,
+        max: Duration,
+    ) -> Result<(), ConfigError> {
+        if min > max {
+            return Err(ConfigError::InvalidRetryBackoff { min, max });
+        }
+
+        self.retry_policy_mut()
+            .set_backoff(Backoff::Exponential { min, max });
+        Ok(())
+    }
+
+    /// Set the maximum number of attempts for a retried request,
+    /// including the initial request.
+    ///
+    /// This has no effect unless retries are enabled via
+    /// [ReWrkBenchmark::enable_ratelimit_retry] or a custom [RetryPolicy].
+    pub fn set_retry_max_attempts(
+        &mut self,
+        max_attempts: u32,
+    ) -> Result<(), ConfigError> {
+        if max_attempts == 0 {
+            return Err(ConfigError::ZeroRetryAttempts);
+        }
+
+        self.retry_policy_mut().set_max_attempts(max_attempts);
+        Ok(())
+    }
+
+    /// Set if only requests with idempotent methods are retried.
+    ///
+    /// This defaults to [DEFAULT_RETRY_IDEMPOTENT_ONLY](crate::DEFAULT_RETRY_IDEMPOTENT_ONLY),
+    /// so `POST` and `PATCH` requests are never retried unless this is set
+    /// to `false`.
+    ///
+    /// This has no effect unless retries are enabled via
+    /// [ReWrkBenchmark::enable_ratelimit_retry] or a custom [RetryPolicy].
+    pub fn set_retry_idempotent_only(&mut self, idempotent_only: bool) {
+        self.retry_policy_mut().set_idempotent_only(idempotent_only);
+    }
+
+    /// Creates a new [ReWrkBenchmark] from a [BenchmarkPlan].
+    ///
+    /// The validator is selected by its [name](ResponseValidator::name),
+    /// only the [DefaultValidator] can be selected, use
+    /// [ReWrkBenchmark::from_plan_with_registry] to select custom validators.
+    pub async fn from_plan(
+        plan: &BenchmarkPlan,
+        producer: P,
+        collector: C,
+    ) -> Result<Self, PlanError> {
+        Self::from_plan_with_registry(plan, &Registry::default(), producer, collector)
+            .await
+    }
+
+    /// Creates a new [ReWrkBenchmark] from a [BenchmarkPlan], creating the
+    /// validator from the factory registered under its name in the [Registry].
+    pub async fn from_plan_with_registry(
+        plan: &BenchmarkPlan,
+        registry: &Registry,
+        producer: P,
+        collector: C,
+    ) -> Result<Self, PlanError> {
+        let validator = registry
+            .create_validator(&plan.validator, &plan.validator_options)
+            .map_err(|e| match e {
+                RegistryError::UnknownValidator(name) => {
+                    PlanError::UnknownValidator(name)
+                },
+                other => PlanError::Registry(other),
+            })?;
+
+        let uri = parse_plan_uri(&plan.target)?;
+        let mut benchmark =
+            Self::create(uri, plan.concurrency, plan.protocol, producer, collector)
+                .await?;
+        benchmark.worker_config.validator = validator;
+        benchmark.validator_options = plan.validator_options.clone();
+        benchmark.apply_plan(plan, registry)?;
+        Ok(benchmark)
+    }
+
+    /// Exports the configuration of the benchmark as a [BenchmarkPlan].
+    pub fn export_plan(&self) -> BenchmarkPlan {
+        let config = &self.worker_config;
+        let connector = &config.connector;
+
+        BenchmarkPlan {
+            target: connector.uri().to_string(),
+            concurrency: self.live_concurrency(),
+            protocol: connector.protocol(),
+            num_workers: self.num_workers,
+            sample_window: config.sample_window,
+            producer_wait_warning_threshold: config.producer_wait_warning_threshold,
+            connection_retry_max: connector.retry_max(),
+            max_connect_rate: connector.max_connect_rate(),
+            connect_timeout: Some(connector.connect_timeout_duration()),
+            handshake_timeout: Some(connector.handshake_timeout()),
+            connect_backoff: Some(connector.connect_backoff()),
+            target_rps: config
+                .request_limiter
+                .as_ref()
+                .map(|limiter| limiter.rate() as u32),
+            host_header: self
+                .host_header
+                .as_ref()
+                .map(|host| String::from_utf8_lossy(host.as_bytes()).into_owned()),
+            default_headers: connector
+                .default_headers()
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes());
+                    (name.to_string(), value.into_owned())
+                })
+                .collect(),
+            labels: BTreeMap::clone(&config.labels),
+            warmup: config.warmup,
+            slow_start: config.slow_start.map(|slow_start| SlowStartPlan {
+                requests: slow_start.requests,
+                interval: slow_start.interval,
+            }),
+            start_jitter: config.start_jitter,
+            producer_timeout: config.producer_timeout,
+            producer_end: config.producer_end,
+            send_mode: config.send_mode,
+            sticky_routing: config.sticky_routing,
+            connection_groups: config
+                .connection_groups
+                .iter()
+                .map(|group| ConnectionGroupPlan {
+                    tags: group.tags().to_vec(),
+                    connections: group.connections(),
+                })
+                .collect(),
+            tag_sample_windows: config.sample_window_overrides.tags.clone(),
+            worker_sample_windows: config.sample_window_overrides.workers.clone(),
+            window_stats: self.window_stats_interval,
+            collector_drain_timeout: self.collector_drain_timeout,
+            outlier_threshold: config.outlier_threshold,
+            max_outliers: config.max_outliers,
+            max_error_exemplars: config.max_error_exemplars,
+            rounds: self.rounds,
+            round_cooldown: self.round_cooldown,
+            memory_limit: self.memory_watchdog.map(|watchdog| MemoryLimitPlan {
+                limit: watchdog.limit,
+                action: watchdog.action,
+            }),
+            health_check: self.health_checker.as_ref().map(|checker| HealthCheckPlan {
+                uri: checker.check.uri().to_string(),
+                interval: checker.check.interval(),
+                action: checker.check.action(),
+            }),
+            retry_policy: config.retry_policy.as_ref().map(|policy| RetryPolicyPlan {
+                statuses: policy.statuses().iter().map(|s| s.as_u16()).collect(),
+                max_attempts: policy.max_attempts(),
+                backoff: policy.backoff(),
+                idempotent_only: policy.idempotent_only(),
+            }),
+            server_timing: config.server_timing.as_ref().map(|source| match source {
+                ServerTimingSource::ServerTiming(metric) => {
+                    ServerTimingPlan::ServerTiming {
+                        metric: metric.as_ref().map(|m| m.to_string()),
+                    }
+                },
+                ServerTimingSource::Header(name) => ServerTimingPlan::Header {
+                    name: name.to_string(),
+                },
+            }),
+            one_way_delay: config.one_way_delay.as_ref().map(|delay| OneWayDelayPlan {
+                sent_at_header: delay.sent_at_header().to_string(),
+                received_at_header: delay.received_at_header().to_string(),
+            }),
+            response_tracking: config.response_tracking.as_ref().map(|tracking| {
+                ResponseTrackingPlan {
+                    header: tracking.header().to_string(),
+                }
+            }),
+            header_capture: config.header_capture.as_ref().map(|capture| {
+                HeaderCapturePlan {
+                    headers: capture.headers().iter().map(|h| h.to_string()).collect(),
+                    max_values: capture.max_values(),
+                }
+            }),
+            priming: self.priming_plan.clone(),
+            validator: config.validator.name().into_owned(),
+            validator_options: self.validator_options.clone(),
+            phases: self.phases.iter().map(PhasePlan::from).collect(),
+        }
+    }
+
+    /// Applies the settings of a plan to the benchmark.
+    fn apply_plan(
+        &mut self,
+        plan: &BenchmarkPlan,
+        registry: &Registry,
+    ) -> Result<(), PlanError> {
+        self.set_num_workers(plan.num_workers)?;
+        self.set_sample_window(plan.sample_window)?;
+        self.set_producer_wait_warning_threshold(plan.producer_wait_warning_threshold)?;
+        self.set_connection_retry_max(plan.connection_retry_max);
+        if let Some(rate) = plan.max_connect_rate {
+            self.set_max_connect_rate(rate)?;
+        }
+        if let Some(timeout) = plan.connect_timeout {
+            self.set_connect_timeout(timeout)?;
+        }
+        if let Some(timeout) = plan.handshake_timeout {
+            self.set_handshake_timeout(timeout)?;
+        }
+        if let Some(backoff) = plan.connect_backoff {
+            self.set_connect_backoff(backoff)?;
+        }
+        if let Some(timeout) = plan.producer_timeout {
+            self.set_producer_timeout(timeout)?;
+        }
+        if let Some(rps) = plan.target_rps {
+            self.set_target_rps(rps)?;
+        }
+        if let Some(slow_start) = plan.slow_start {
+            self.set_slow_start(slow_start.requests, slow_start.interval)?;
+        }
+        if let Some(jitter) = plan.start_jitter {
+            self.set_start_jitter(jitter)?;
+        }
+        self.set_producer_end(plan.producer_end);
+        self.set_send_mode(plan.send_mode)?;
+        self.set_sticky_routing(plan.sticky_routing);
+        for group in plan.connection_groups.iter() {
+            self.add_connection_group(ConnectionGroup::new(
+                group.tags.iter().copied(),
+                group.connections,
+            ))?;
+        }
+        for (&tag, &window) in plan.tag_sample_windows.iter() {
+            self.set_tag_sample_window(tag, window)?;
+        }
+        for (&worker_id, &window) in plan.worker_sample_windows.iter() {
+            self.set_worker_sample_window(worker_id, window)?;
+        }
+        if let Some(interval) = plan.window_stats {
+            self.set_window_stats(interval)?;
+        }
+        if let Some(timeout) = plan.collector_drain_timeout {
+            self.set_collector_drain_timeout(timeout);
+        }
+
+        if let Some(host) = plan.host_header.as_ref() {
+            let host = HeaderValue::from_str(host)
+                .map_err(|_| PlanError::InvalidHeader(host.clone()))?;
+            self.set_host_header(host);
+        }
+
+        let existing_headers = self
+            .worker_config
+            .connector
+            .default_headers()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in existing_headers {
+            self.remove_default_header(&name);
+        }
+        for (name, value) in plan.default_headers.iter() {
+            let invalid = || PlanError::InvalidHeader(name.clone());
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+            let value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+            self.set_default_header(name, value);
+        }
+        self.worker_config.labels = Arc::new(plan.labels.clone());
+
+        if let Some(warmup) = plan.warmup {
+            self.set_warmup(warmup);
+        }
+        if let Some(threshold) = plan.outlier_threshold {
+            self.set_outlier_threshold(threshold);
+        }
+        self.set_max_outliers(plan.max_outliers);
+        self.set_max_error_exemplars(plan.max_error_exemplars);
+        self.set_rounds(plan.rounds)?;
+        self.set_round_cooldown(plan.round_cooldown);
+
+        if let Some(memory_limit) = plan.memory_limit {
+            self.set_memory_limit(memory_limit.limit, memory_limit.action)?;
+        }
+
+        if let Some(health_check) = plan.health_check.as_ref() {
+            let uri = parse_plan_uri(&health_check.uri)?;
+            self.set_health_check(HealthCheck::new(
+                uri,
+                health_check.interval,
+                health_check.action,
+            ))?;
+        }
+
+        if let Some(retry) = plan.retry_policy.as_ref() {
+            let statuses = retry
+                .statuses
+                .iter()
+                .map(|&status| {
+                    StatusCode::from_u16(status)
+                        .map_err(|_| PlanError::InvalidStatus(status))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut policy = RetryPolicy::default();
+            policy.set_statuses(statuses);
+            policy.set_max_attempts(retry.max_attempts);
+            policy.set_backoff(retry.backoff);
+            policy.set_idempotent_only(retry.idempotent_only);
+            self.set_retry_policy(policy);
+        }
+
+        if let Some(server_timing) = plan.server_timing.as_ref() {
+            let source = match server_timing {
+                ServerTimingPlan::ServerTiming { metric } => {
+                    ServerTimingSource::ServerTiming(metric.clone().map(Cow::Owned))
+                },
+                ServerTimingPlan::Header { name } => {
+                    ServerTimingSource::Header(parse_plan_header(name)?)
+                },
+            };
+            self.set_server_timing(source);
+        }
+
+        if let Some(delay) = plan.one_way_delay.as_ref() {
+            self.set_one_way_delay(OneWayDelay::new(
+                parse_plan_header(&delay.sent_at_header)?,
+                parse_plan_header(&delay.received_at_header)?,
+            ));
+        }
+
+        if let Some(tracking) = plan.response_tracking.as_ref() {
+            self.set_response_tracking(ResponseTracking::new(parse_plan_header(
+                &tracking.header,
+            )?));
+        }
+
+        if let Some(capture) = plan.header_capture.as_ref() {
+            let headers = capture
+                .headers
+                .iter()
+                .map(|name| parse_plan_header(name))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.set_header_capture(
+                HeaderCapture::new(headers).with_max_values(capture.max_values),
+            );
+        }
+
+        if let Some(priming) = plan.priming.as_ref() {
+            let producer =
+                registry.create_producer(&priming.producer, &priming.options)?;
+            self.set_priming_producer(producer, priming.duration);
+            self.priming_plan = Some(priming.clone());
+        }
+
+        self.set_phases(plan.phases());
+
+        Ok(())
+    }
+
+    /// Gets a mutable reference to the retry policy.
+    ///
+    /// If no policy is set, a policy which does not retry any
+    /// responses is created.
+    fn retry_policy_mut(&mut self) -> &mut RetryPolicy {
+        let policy = self.worker_config.retry_policy.get_or_insert_with(|| {
+            let mut policy = RetryPolicy::default();
+            policy.set_statuses([]);
+            Arc::new(policy)
+        });
+        Arc::make_mut(policy)
     }
 }
 
+fn parse_plan_uri(uri: &str) -> Result<Uri, PlanError> {
+    uri.parse()
+        .map_err(|_| PlanError::InvalidUri(uri.to_string()))
+}
+
+fn parse_plan_header(name: &str) -> Result<HeaderName, PlanError> {
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| PlanError::InvalidHeader(name.to_string()))
+}
+
+/// Spawns the tasks monitoring the benchmark while it runs.
+fn spawn_monitors(
+    memory_watchdog: Option<MemoryWatchdog>,
+    health_checker: Option<HealthChecker>,
+    shutdown: ShutdownHandle,
+) -> Vec<JoinHandle<()>> {
+    let mut monitors = Vec::new();
+    if let Some(watchdog) = memory_watchdog {
+        monitors.push(watchdog.spawn(shutdown.clone()));
+    }
+    if let Some(checker) = health_checker {
+        monitors.push(checker.spawn(shutdown));
+    }
+    monitors
+}
+
 /// Creates a new [ReWrkConnector] using a provided protocol and URI.
-fn create_connector(uri: Uri, protocol: HttpProtocol) -> Result<ReWrkConnector, Error> {
+pub(crate) fn create_connector(
+    uri: Uri,
+    protocol: HttpProtocol,
+) -> Result<ReWrkConnector, Error> {
     let scheme = uri.scheme_str().ok_or(Error::MissingScheme)?;
     let scheme = match scheme {
         "http" => Scheme::Http,
@@ -211,27 +1783,28 @@ fn create_connector(uri: Uri, protocol: HttpProtocol) -> Result<ReWrkConnector,
         .port_u16()
         .unwrap_or_else(|| scheme.default_port());
 
-    // Prefer ipv4.
-    let addr_iter = (host, port)
+    // Connections are spread across every resolved address.
+    let addrs: Vec<_> = (host, port)
         .to_socket_addrs()
-        .map_err(Error::AddressLookup)?;
-    let mut last_addr = None;
-    for addr in addr_iter {
-        last_addr = Some(addr);
-        if addr.is_ipv4() {
-            break;
-        }
-    }
-    let addr = last_addr.ok_or_else(|| {
-        Error::AddressLookup(io::Error::new(
-            ErrorKind::Other,
-            "Failed to lookup hostname",
-        ))
+        .map_err(Error::AddressLookup)?
+        .collect();
+    let addr = *addrs.first().ok_or_else(|| {
+        Error::AddressLookup(io::Error::other("Failed to lookup hostname"))
     })?;
-    let host_header = HeaderValue::from_str(host).map_err(|_| Error::MissingHost)?;
+    // The port is only included when it isn't the scheme's default,
+    // some virtual host routers won't match the host without it.
+    let host_header = match authority.port_u16() {
+        Some(port) if port != scheme.default_port() => {
+            HeaderValue::from_str(&format!("{host}:{port}"))
+        },
+        _ => HeaderValue::from_str(host),
+    }
+    .map_err(|_| Error::MissingHost)?;
     let host = host.to_string();
 
-    let connector = ReWrkConnector::new(uri, host_header, addr, protocol, scheme, host);
+    let mut connector =
+        ReWrkConnector::new(uri, host_header, addr, protocol, scheme, host);
+    connector.set_addrs(addrs);
 
     Ok(connector)
 }
@@ -0,0 +1,46 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+/// A single timed phase of a benchmark run via
+/// [ReWrkBenchmark::run_phases](crate::ReWrkBenchmark::run_phases).
+///
+/// Phases allow short, medium and long running (soak) benchmarks to be
+/// run back to back with their own sample windows, making degradations
+/// over time visible within a single set of results.
+pub struct Phase {
+    label: Cow<'static, str>,
+    duration: Duration,
+    sample_window: Duration,
+}
+
+impl Phase {
+    /// Create a new phase which runs for the given duration,
+    /// submitting a sample every `sample_window`.
+    pub fn new(
+        label: impl Into<Cow<'static, str>>,
+        duration: Duration,
+        sample_window: Duration,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            duration,
+            sample_window,
+        }
+    }
+
+    /// The label of the phase.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The duration the phase runs for.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The sample window used during the phase.
+    pub fn sample_window(&self) -> Duration {
+        self.sample_window
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{ConfigError, Error};
+use crate::registry::RegistryError;
+use crate::utils::micros;
+use crate::{
+    Backoff,
+    HealthCheckAction,
+    HttpProtocol,
+    MemoryLimitAction,
+    Phase,
+    ProducerEnd,
+    SendMode,
+};
+
+#[derive(Debug, thiserror::Error)]
+/// A [BenchmarkPlan] could not be applied.
+pub enum PlanError {
+    #[error("The plan URI {0:?} is invalid")]
+    /// A URI in the plan could not be parsed.
+    InvalidUri(String),
+    #[error("The plan header {0:?} is invalid")]
+    /// A header name or value in the plan is invalid.
+    InvalidHeader(String),
+    #[error("The plan status code {0} is invalid")]
+    /// A status code in the plan is invalid.
+    InvalidStatus(u16),
+    #[error("The plan validator {0:?} is unknown")]
+    /// The plan uses a validator which could not be found by name.
+    UnknownValidator(String),
+    #[error("{0}")]
+    /// A component could not be created from the registry.
+    Registry(#[from] RegistryError),
+    #[error("{0}")]
+    /// The benchmark could not be created.
+    Create(#[from] Error),
+    #[error("{0}")]
+    /// A value in the plan is invalid.
+    Config(#[from] ConfigError),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A complete, serializable description of a benchmark's configuration.
+///
+/// A plan can be exported from a benchmark with
+/// [ReWrkBenchmark::export_plan](crate::ReWrkBenchmark::export_plan) and
+/// imported with [ReWrkBenchmark::from_plan](crate::ReWrkBenchmark::from_plan)
+/// in order to reproduce a run or share a benchmark definition.
+///
+/// All durations are serialized as a whole number of microseconds.
+/// The producer, collector and any custom transport are not part of the plan.
+/// TLS is used when the target has the `https` scheme, certificates are never
+/// verified, so the target is the only TLS setting.
+pub struct BenchmarkPlan {
+    /// The base URI of the benchmark target.
+    pub target: String,
+    /// The number of concurrent connections.
+    pub concurrency: usize,
+    /// The HTTP protocol used by the benchmark.
+    pub protocol: HttpProtocol,
+    /// The number of worker threads.
+    pub num_workers: usize,
+    #[serde(with = "micros")]
+    /// The duration of each sample window.
+    pub sample_window: Duration,
+    /// The producer wait warning threshold as a percentage.
+    pub producer_wait_warning_threshold: f32,
+    /// The maximum number of connect retry attempts.
+    pub connection_retry_max: usize,
+    #[serde(default)]
+    /// The maximum number of new connections established per second.
+    pub max_connect_rate: Option<u32>,
+    #[serde(default, with = "micros::option")]
+    /// The time allowed to open each connection.
+    pub connect_timeout: Option<Duration>,
+    #[serde(default, with = "micros::option")]
+    /// The time allowed to complete each connection's TLS and HTTP handshakes.
+    pub handshake_timeout: Option<Duration>,
+    #[serde(default)]
+    /// The backoff between failed connect attempts.
+    pub connect_backoff: Option<Backoff>,
+    #[serde(default)]
+    /// The number of requests sent per second across all workers.
+    pub target_rps: Option<u32>,
+    #[serde(default)]
+    /// The `Host` header sent with every request, if it isn't derived from the target.
+    pub host_header: Option<String>,
+    #[serde(default)]
+    /// The headers added to every request.
+    pub default_headers: Vec<(String, String)>,
+    #[serde(default)]
+    /// The labels attached to every sample.
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, with = "micros::option")]
+    /// The period at the start of the benchmark whose samples are discarded.
+    pub warmup: Option<Duration>,
+    #[serde(default)]
+    /// The ramp applied to the first requests of each connection.
+    pub slow_start: Option<SlowStartPlan>,
+    #[serde(default, with = "micros::option")]
+    /// The maximum random delay before each connection sends its first request.
+    pub start_jitter: Option<Duration>,
+    #[serde(default, with = "micros::option")]
+    /// The maximum time a producer may take to create a batch.
+    pub producer_timeout: Option<Duration>,
+    #[serde(default)]
+    /// What a producer returning [RequestBatch::End](crate::RequestBatch::End) ends.
+    pub producer_end: ProducerEnd,
+    #[serde(default)]
+    /// How requests are sent and their responses handled.
+    pub send_mode: SendMode,
+    #[serde(default)]
+    /// Whether batches are routed to connections by their sticky key.
+    pub sticky_routing: bool,
+    #[serde(default)]
+    /// The groups of connections dedicated to certain tags.
+    pub connection_groups: Vec<ConnectionGroupPlan>,
+    #[serde(default, with = "micros::map")]
+    /// The sample windows of samples with a given tag.
+    pub tag_sample_windows: BTreeMap<usize, Duration>,
+    #[serde(default, with = "micros::map")]
+    /// The sample windows of samples produced by a given worker.
+    pub worker_sample_windows: BTreeMap<usize, Duration>,
+    #[serde(default, with = "micros::option")]
+    /// The interval of the window stats passed to the producers.
+    pub window_stats: Option<Duration>,
+    #[serde(default, with = "micros::option")]
+    /// The maximum time to wait for the collector once the benchmark has shutdown.
+    pub collector_drain_timeout: Option<Duration>,
+    #[serde(default, with = "micros::option")]
+    /// The latency threshold which marks a request as an outlier.
+    pub outlier_threshold: Option<Duration>,
+    /// The maximum number of outliers captured per sample.
+    pub max_outliers: usize,
+    /// The maximum number of error exemplars held per sample.
+    pub max_error_exemplars: usize,
+    /// The number of rounds run by the benchmark.
+    pub rounds: usize,
+    #[serde(with = "micros")]
+    /// The period of time to wait between rounds.
+    pub round_cooldown: Duration,
+    #[serde(default)]
+    /// The process memory limit.
+    pub memory_limit: Option<MemoryLimitPlan>,
+    #[serde(default)]
+    /// The target health check.
+    pub health_check: Option<HealthCheckPlan>,
+    #[serde(default)]
+    /// The request retry policy.
+    pub retry_policy: Option<RetryPolicyPlan>,
+    #[serde(default)]
+    /// The source of server reported processing times.
+    pub server_timing: Option<ServerTimingPlan>,
+    #[serde(default)]
+    /// The one-way delay estimation headers.
+    pub one_way_delay: Option<OneWayDelayPlan>,
+    #[serde(default)]
+    /// The response tracking header.
+    pub response_tracking: Option<ResponseTrackingPlan>,
+    #[serde(default)]
+    /// The response headers whose values are counted.
+    pub header_capture: Option<HeaderCapturePlan>,
+    #[serde(default)]
+    /// The producer priming the target before the benchmark starts.
+    pub priming: Option<PrimingPlan>,
+    /// The name of the response validator, see
+    /// [ResponseValidator::name](crate::ResponseValidator::name).
+    pub validator: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    /// The options passed to the validator factory of the [Registry](crate::Registry).
+    ///
+    /// The options are kept when the benchmark is created from a plan and
+    /// cleared when the validator is replaced.
+    pub validator_options: Value,
+    #[serde(default)]
+    /// The phases of the benchmark, see
+    /// [ReWrkBenchmark::set_phases](crate::ReWrkBenchmark::set_phases).
+    pub phases: Vec<PhasePlan>,
+}
+
+impl BenchmarkPlan {
+    /// The phases of the plan.
+    pub fn phases(&self) -> Vec<Phase> {
+        self.phases.iter().cloned().map(Phase::from).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The process memory limit of a [BenchmarkPlan].
+pub struct MemoryLimitPlan {
+    /// The memory limit in bytes.
+    pub limit: u64,
+    /// The action taken when the limit is exceeded.
+    pub action: MemoryLimitAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The target health check of a [BenchmarkPlan].
+pub struct HealthCheckPlan {
+    /// The URI of the health check endpoint.
+    pub uri: String,
+    #[serde(with = "micros")]
+    /// The interval the health check is polled at.
+    pub interval: Duration,
+    /// The action taken when the health check fails.
+    pub action: HealthCheckAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The retry policy of a [BenchmarkPlan].
+pub struct RetryPolicyPlan {
+    /// The status codes which cause a request to be retried.
+    pub statuses: Vec<u16>,
+    /// The maximum number of attempts, including the initial request.
+    pub max_attempts: u32,
+    /// The backoff strategy used between attempts.
+    pub backoff: Backoff,
+    /// If only requests with idempotent methods are retried.
+    pub idempotent_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// The server timing source of a [BenchmarkPlan].
+pub enum ServerTimingPlan {
+    /// The standard `Server-Timing` header, optionally for a single metric.
+    ServerTiming {
+        #[serde(default)]
+        /// The metric to use, all metrics are summed if not set.
+        metric: Option<String>,
+    },
+    /// A custom header containing a single duration.
+    Header {
+        /// The name of the header.
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The one-way delay estimation headers of a [BenchmarkPlan].
+pub struct OneWayDelayPlan {
+    /// The request header the client send timestamp is written to.
+    pub sent_at_header: String,
+    /// The response header the server receive timestamp is read from.
+    pub received_at_header: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The connection slow start of a [BenchmarkPlan].
+pub struct SlowStartPlan {
+    /// The number of requests of each connection the ramp covers.
+    pub requests: usize,
+    #[serde(with = "micros")]
+    /// The interval the requests are spread over.
+    pub interval: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A [ConnectionGroup](crate::ConnectionGroup) of a [BenchmarkPlan].
+pub struct ConnectionGroupPlan {
+    /// The tags whose batches are executed by the group.
+    pub tags: Vec<usize>,
+    /// The number of connections in the group.
+    pub connections: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The response tracking of a [BenchmarkPlan].
+pub struct ResponseTrackingPlan {
+    /// The header the request key is sent and echoed back in.
+    pub header: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The response header capture of a [BenchmarkPlan].
+pub struct HeaderCapturePlan {
+    /// The names of the captured headers.
+    pub headers: Vec<String>,
+    /// The maximum number of distinct values counted per header.
+    pub max_values: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The priming producer of a [BenchmarkPlan].
+///
+/// The producer is created from the factory registered under its
+/// name in the [Registry](crate::Registry). Priming producers set via
+/// [ReWrkBenchmark::set_priming_producer](crate::ReWrkBenchmark::set_priming_producer)
+/// have no name, so they are not exported.
+pub struct PrimingPlan {
+    /// The name of the producer in the registry.
+    pub producer: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    /// The options passed to the producer factory.
+    pub options: Value,
+    #[serde(with = "micros")]
+    /// The maximum duration the priming producer runs for.
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A benchmark [Phase] of a [BenchmarkPlan].
+pub struct PhasePlan {
+    /// The label of the phase.
+    pub label: String,
+    #[serde(with = "micros")]
+    /// The duration the phase runs for.
+    pub duration: Duration,
+    #[serde(with = "micros")]
+    /// The sample window used during the phase.
+    pub sample_window: Duration,
+}
+
+impl From<PhasePlan> for Phase {
+    fn from(plan: PhasePlan) -> Self {
+        Phase::new(plan.label, plan.duration, plan.sample_window)
+    }
+}
+
+impl From<&Phase> for PhasePlan {
+    fn from(phase: &Phase) -> Self {
+        Self {
+            label: phase.label().to_string(),
+            duration: phase.duration(),
+            sample_window: phase.sample_window(),
+        }
+    }
+}
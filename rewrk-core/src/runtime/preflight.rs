@@ -0,0 +1,155 @@
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, StatusCode, Version};
+use hyper::Body;
+
+use crate::connection::ReWrkConnector;
+use crate::{HttpProtocol, ResponseValidator, ValidationError};
+
+#[derive(Debug, thiserror::Error)]
+/// The pre-flight check of the benchmark target failed.
+pub enum PreflightError {
+    #[error("Failed to connect to {addr}: {error}")]
+    /// The connection, TLS or HTTP handshake failed.
+    Connect {
+        /// The resolved address of the target.
+        addr: SocketAddr,
+        /// The error which caused the failure.
+        error: anyhow::Error,
+    },
+    #[error("Failed to connect to {addr} within {timeout:?}")]
+    /// The connection could not be established within the timeout.
+    ConnectTimeout {
+        /// The resolved address of the target.
+        addr: SocketAddr,
+        /// The connect timeout.
+        timeout: Duration,
+    },
+    #[error("The probe request to {addr} failed: {error}")]
+    /// The probe request failed to complete.
+    Request {
+        /// The resolved address of the target.
+        addr: SocketAddr,
+        /// The error which caused the failure.
+        error: hyper::Error,
+    },
+    #[error("The target at {addr} responded with {version:?} rather than {expected:?}")]
+    /// The protocol negotiated with the target is not the benchmark's protocol.
+    Protocol {
+        /// The resolved address of the target.
+        addr: SocketAddr,
+        /// The protocol of the benchmark.
+        expected: HttpProtocol,
+        /// The HTTP version of the response.
+        version: Version,
+    },
+    #[error(
+        "The probe response ({version:?} {status}) did not pass validation: {error}"
+    )]
+    /// The probe response was rejected by the validator.
+    Validation {
+        /// The HTTP version of the response.
+        version: Version,
+        /// The status of the response.
+        status: StatusCode,
+        /// The validation error.
+        error: ValidationError,
+    },
+}
+
+#[derive(Debug, Clone)]
+/// The details of a successful pre-flight check.
+pub struct PreflightReport {
+    /// The resolved address of the target.
+    pub addr: SocketAddr,
+    /// The HTTP version negotiated with the target.
+    pub version: Version,
+    /// The status of the probe response.
+    pub status: StatusCode,
+    /// The time taken to establish the connection.
+    pub connect_latency: Duration,
+    /// The latency of the probe request.
+    pub request_latency: Duration,
+}
+
+/// Sends a single request with the given method to the base URI of
+/// the connector and validates the protocol and response.
+pub(crate) async fn preflight(
+    connector: &ReWrkConnector,
+    method: Method,
+    validator: &dyn ResponseValidator,
+    timeout: Duration,
+) -> Result<PreflightReport, PreflightError> {
+    let addr = connector.addr();
+
+    let connect_start = Instant::now();
+    let mut conn = match connector.connect_timeout(timeout).await {
+        Ok(Some(conn)) => conn,
+        Ok(None) => return Err(PreflightError::ConnectTimeout { addr, timeout }),
+        Err(error) => return Err(PreflightError::Connect { addr, error }),
+    };
+    let connect_latency = connect_start.elapsed();
+
+    let request = Request::builder()
+        .method(method)
+        .uri(connector.uri().clone())
+        .body(Body::empty())
+        .expect("Build request");
+
+    let request_start = Instant::now();
+    let (head, body) = conn
+        .execute_req(request)
+        .await
+        .map_err(|error| PreflightError::Request { addr, error })?;
+    let request_latency = request_start.elapsed();
+
+    let version = head.version;
+    let status = head.status;
+    let expected = connector.protocol();
+    if !matches_protocol(expected, version) {
+        return Err(PreflightError::Protocol {
+            addr,
+            expected,
+            version,
+        });
+    }
+
+    validator
+        .validate(head, body)
+        .map_err(|error| PreflightError::Validation {
+            version,
+            status,
+            error,
+        })?;
+
+    Ok(PreflightReport {
+        addr,
+        version,
+        status,
+        connect_latency,
+        request_latency,
+    })
+}
+
+/// Checks if a response version was negotiated for the given protocol.
+fn matches_protocol(protocol: HttpProtocol, version: Version) -> bool {
+    match protocol {
+        HttpProtocol::HTTP1 => matches!(version, Version::HTTP_10 | Version::HTTP_11),
+        HttpProtocol::HTTP2 => version == Version::HTTP_2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_protocol() {
+        assert!(matches_protocol(HttpProtocol::HTTP1, Version::HTTP_11));
+        assert!(matches_protocol(HttpProtocol::HTTP1, Version::HTTP_10));
+        assert!(!matches_protocol(HttpProtocol::HTTP1, Version::HTTP_2));
+        assert!(matches_protocol(HttpProtocol::HTTP2, Version::HTTP_2));
+        assert!(!matches_protocol(HttpProtocol::HTTP2, Version::HTTP_11));
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::producer::Producer;
+use crate::runtime::worker::{spawn_workers, ShutdownHandle, WorkerConfig};
+
+type SpawnPriming<P> =
+    dyn Fn(ShutdownHandle, usize, WorkerConfig<P>) -> flume::Receiver<()> + Send + Sync;
+
+/// A phase run with its own producer before the measured benchmark
+/// starts, bringing the target into a defined state e.g. warming its caches.
+///
+/// The samples of the priming phase are discarded.
+pub(crate) struct Priming<P>
+where
+    P: Producer + Clone,
+{
+    duration: Duration,
+    spawn: Arc<SpawnPriming<P>>,
+}
+
+impl<P> Clone for Priming<P>
+where
+    P: Producer + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            duration: self.duration,
+            spawn: self.spawn.clone(),
+        }
+    }
+}
+
+impl<P> Priming<P>
+where
+    P: Producer + Clone,
+{
+    /// Creates a new priming phase running the given producer for
+    /// at most `duration`.
+    pub(crate) fn new<Q>(producer: Q, duration: Duration) -> Self
+    where
+        Q: Producer + Clone,
+    {
+        // Producers are only required to be `Send`, the lock makes
+        // the producer shareable between clones of the benchmark future.
+        let producer = Mutex::new(producer);
+        let spawn = move |shutdown, num_workers, config: WorkerConfig<P>| {
+            let producer = producer.lock().unwrap().clone();
+            let mut config = config.with_producer(producer);
+            config.run_duration = Some(duration);
+            config.warmup = None;
+            config.window_stats = None;
+            spawn_workers(shutdown, num_workers, config)
+        };
+
+        Self {
+            duration,
+            spawn: Arc::new(spawn),
+        }
+    }
+
+    /// Runs the priming phase, completing once all workers have finished.
+    pub(crate) async fn run(
+        &self,
+        shutdown: ShutdownHandle,
+        num_workers: usize,
+        mut config: WorkerConfig<P>,
+    ) {
+        info!(duration = ?self.duration, "Priming benchmark target.");
+
+        let (collector, discarded) = flume::unbounded();
+        tokio::spawn(async move { while discarded.recv_async().await.is_ok() {} });
+        config.collector = collector;
+
+        let waiter = (self.spawn)(shutdown, num_workers, config);
+        let _ = waiter.recv_async().await;
+
+        debug!("Priming complete.");
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::recording::{CollectorMailbox, CollectorMessage, ProgressSnapshot};
+
+type ProgressHandler = dyn Fn(ProgressSnapshot) + Send + Sync + 'static;
+
+#[derive(Clone)]
+/// Passes the progress of a running benchmark to a handler.
+pub(crate) struct ProgressReporter {
+    handler: Arc<ProgressHandler>,
+}
+
+impl ProgressReporter {
+    pub fn new(handler: impl Fn(ProgressSnapshot) + Send + Sync + 'static) -> Self {
+        Self {
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Starts reporting the progress every `interval`.
+    pub fn start(
+        &self,
+        collector: &CollectorMailbox,
+        interval: Duration,
+    ) -> ProgressTask {
+        let started_at = Instant::now();
+
+        let reporter = self.clone();
+        let mailbox = collector.clone();
+        let handle = tokio::spawn(async move {
+            let start = tokio::time::Instant::from_std(started_at) + interval;
+            let mut interval = tokio::time::interval_at(start, interval);
+            loop {
+                interval.tick().await;
+                if !reporter.report(&mailbox, started_at).await {
+                    break;
+                }
+            }
+        });
+
+        ProgressTask {
+            reporter: self.clone(),
+            collector: collector.clone(),
+            started_at,
+            handle,
+        }
+    }
+
+    /// Fetches the progress from the collector and passes it to the handler.
+    ///
+    /// Returns `false` if the collector has shutdown.
+    async fn report(&self, collector: &CollectorMailbox, started_at: Instant) -> bool {
+        let (tx, rx) = oneshot::channel();
+        let message = CollectorMessage::Progress(started_at, tx);
+        if collector.send_async(message).await.is_err() {
+            return false;
+        }
+
+        match rx.await {
+            Ok(progress) => {
+                (self.handler)(progress);
+                true
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+/// A running task reporting the progress of a benchmark.
+pub(crate) struct ProgressTask {
+    reporter: ProgressReporter,
+    collector: CollectorMailbox,
+    started_at: Instant,
+    handle: JoinHandle<()>,
+}
+
+impl ProgressTask {
+    /// Stops reporting and reports the final progress of the benchmark.
+    ///
+    /// The collector processes messages in order, so the final progress
+    /// includes every sample submitted before this is called.
+    pub async fn finish(self) {
+        self.handle.abort();
+        self.reporter.report(&self.collector, self.started_at).await;
+    }
+}
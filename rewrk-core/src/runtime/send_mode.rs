@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// How workers send requests and handle their responses.
+pub enum SendMode {
+    #[default]
+    /// Wait for each full response, validate it and record its latency.
+    Full,
+    /// Send requests without waiting for their responses, maximizing the
+    /// offered load for stress testing or mirroring traffic.
+    ///
+    /// Responses are read and discarded in the background without being
+    /// validated, so latency and transfer metrics are not recorded. Requests
+    /// are counted by [Sample::mirrored_requests](crate::Sample::mirrored_requests)
+    /// instead of as successful requests.
+    ///
+    /// Each connection has at most `max_in_flight` requests awaiting a
+    /// response at once. With HTTP/1 a connection can only send its next
+    /// request once the previous response has been read, so this is mostly
+    /// useful with HTTP/2.
+    Mirror {
+        /// The maximum number of requests awaiting a response per connection.
+        max_in_flight: usize,
+    },
+}
@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use http::header::HeaderName;
+use http::{HeaderMap, HeaderValue, Request, Response, StatusCode};
+use hyper::body::Bytes;
+use hyper::Body;
+
+use super::{ConfigError, DEFAULT_MAX_ERROR_EXEMPLARS, DEFAULT_MAX_OUTLIERS};
+use crate::producer::{Batch, Producer, RequestBatch};
+use crate::recording::{
+    CollectorMessage,
+    Outlier,
+    RequestKey,
+    SampleFactory,
+    SampleMetadata,
+};
+use crate::{
+    DefaultValidator,
+    ResponseLatency,
+    ResponseValidator,
+    Sample,
+    SampleCollector,
+    DEFAULT_WINDOW_DURATION,
+};
+
+#[derive(Debug, Clone)]
+/// A response produced by a [ResponseModel].
+pub struct SimulatedResponse {
+    /// The simulated latency of the request.
+    pub latency: Duration,
+    /// The status of the response.
+    pub status: StatusCode,
+    /// The headers of the response.
+    pub headers: HeaderMap,
+    /// The body of the response.
+    pub body: Bytes,
+}
+
+impl SimulatedResponse {
+    /// Create a new response with the given status and latency.
+    pub fn new(status: StatusCode, latency: Duration) -> Self {
+        Self {
+            latency,
+            status,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+        }
+    }
+
+    /// Set a header on the response.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Set the body of the response.
+    pub fn with_body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// A model producing the response to each request in a [Simulation].
+///
+/// This is implemented for any `FnMut(RequestKey, &Request<Body>) -> SimulatedResponse`.
+pub trait ResponseModel: Send + 'static {
+    /// Produce the response to the given request.
+    fn respond(&mut self, key: RequestKey, request: &Request<Body>)
+        -> SimulatedResponse;
+}
+
+impl<F> ResponseModel for F
+where
+    F: FnMut(RequestKey, &Request<Body>) -> SimulatedResponse + Send + 'static,
+{
+    fn respond(
+        &mut self,
+        key: RequestKey,
+        request: &Request<Body>,
+    ) -> SimulatedResponse {
+        (self)(key, request)
+    }
+}
+
+/// A deterministic benchmark runtime where responses are produced by a
+/// [ResponseModel] rather than a real server.
+///
+/// Time is simulated, each connection has its own clock which advances by
+/// the latency of each response, and batches are handed to the connection
+/// with the earliest clock. Sample windows, tags, validation and outliers
+/// behave the same as a real benchmark, which makes the simulation useful for
+/// quickly testing producers, validators, mergers and collectors.
+///
+/// The simulation runs on the calling task and does not perform any IO,
+/// transfer metrics are not recorded.
+pub struct Simulation<P, M>
+where
+    P: Producer,
+    M: ResponseModel,
+{
+    concurrency: usize,
+    producer: P,
+    model: M,
+    validator: Arc<dyn ResponseValidator>,
+    sample_window: Duration,
+    outlier_threshold: Option<Duration>,
+    max_outliers: usize,
+    max_error_exemplars: usize,
+    labels: Arc<BTreeMap<String, String>>,
+}
+
+impl<P, M> Simulation<P, M>
+where
+    P: Producer,
+    M: ResponseModel,
+{
+    /// Create a new simulation with the given number of connections.
+    pub fn new(concurrency: usize, producer: P, model: M) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            producer,
+            model,
+            validator: Arc::new(DefaultValidator),
+            sample_window: DEFAULT_WINDOW_DURATION,
+            outlier_threshold: None,
+            max_outliers: DEFAULT_MAX_OUTLIERS,
+            max_error_exemplars: DEFAULT_MAX_ERROR_EXEMPLARS,
+            labels: Arc::default(),
+        }
+    }
+
+    /// Sets the simulation validator.
+    pub fn set_validator(&mut self, validator: impl ResponseValidator) {
+        self.validator = Arc::new(validator);
+    }
+
+    /// Set the simulated duration which should elapse before a sample
+    /// is submitted to the collector.
+    pub fn set_sample_window(&mut self, dur: Duration) -> Result<(), ConfigError> {
+        if dur.is_zero() {
+            return Err(ConfigError::ZeroSampleWindow);
+        }
+
+        self.sample_window = dur;
+        Ok(())
+    }
+
+    /// Set the latency threshold which marks a request as an outlier.
+    ///
+    /// Outlier timestamps are the simulated time since the unix epoch.
+    pub fn set_outlier_threshold(&mut self, threshold: Duration) {
+        self.outlier_threshold = Some(threshold);
+    }
+
+    /// Set the maximum number of outliers captured per sample.
+    pub fn set_max_outliers(&mut self, n: usize) {
+        self.max_outliers = n;
+    }
+
+    /// Set the maximum number of example errors held by a single sample.
+    pub fn set_max_error_exemplars(&mut self, n: usize) {
+        self.max_error_exemplars = n;
+    }
+
+    /// Set the labels attached to every sample produced by the simulation.
+    ///
+    /// See [ReWrkBenchmark::set_labels](crate::ReWrkBenchmark::set_labels).
+    pub fn set_labels(&mut self, labels: HashMap<String, String>) {
+        self.labels = Arc::new(labels.into_iter().collect());
+    }
+
+    /// Run the simulation until the producer ends, returning the collector.
+    ///
+    /// Samples are passed to the collector in the order they are submitted.
+    pub async fn run<C>(mut self, mut collector: C) -> anyhow::Result<C>
+    where
+        C: SampleCollector,
+    {
+        let (submitter, samples) = flume::unbounded();
+        let metadata = SampleMetadata {
+            worker_id: 0,
+            connection_id: 0,
+            round: 0,
+            phase: 0,
+            sample_window: self.sample_window,
+            peer_addr: None,
+        };
+        let sample_factory = SampleFactory::new(
+            self.sample_window,
+            self.max_outliers,
+            self.max_error_exemplars,
+            metadata,
+            submitter,
+        )
+        .with_labels(self.labels.clone());
+        let mut connections = (0..self.concurrency)
+            .map(|connection_id| {
+                SimulatedConnection::new(
+                    connection_id,
+                    sample_factory.for_connection(connection_id),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let filter = collector.filter();
+        collector.start_round(0).await?;
+        self.producer.ready();
+        loop {
+            // Batches are executed as soon as they are produced,
+            // so priority batches have no queue to skip.
+            let batch = match self.producer.create_batch().await? {
+                RequestBatch::End => break,
+                RequestBatch::Batch(batch) | RequestBatch::Priority(batch) => batch,
+            };
+
+            // The connection which finished its last request first takes the batch.
+            let conn = connections
+                .iter_mut()
+                .min_by_key(|conn| conn.clock)
+                .expect("At least one connection");
+            conn.execute_batch(&mut self, batch)?;
+
+            for message in samples.try_iter() {
+                match message {
+                    CollectorMessage::Metric(metric) if filter.matches(&metric) => {
+                        collector.process_metric(metric).await?;
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        for mut conn in connections {
+            conn.submit_sample(0)?;
+        }
+        for message in samples.try_iter() {
+            match message {
+                CollectorMessage::Metric(metric) if filter.matches(&metric) => {
+                    collector.process_metric(metric).await?;
+                },
+                _ => {},
+            }
+        }
+        collector.end_round(0).await?;
+
+        Ok(collector)
+    }
+}
+
+/// A connection with its own simulated clock.
+struct SimulatedConnection {
+    next_key: RequestKey,
+    sample_factory: SampleFactory,
+    sample: Sample,
+    /// The simulated time elapsed on the connection.
+    clock: Duration,
+    /// The simulated time the current sample was started.
+    sample_started: Duration,
+}
+
+impl SimulatedConnection {
+    fn new(connection_id: usize, mut sample_factory: SampleFactory) -> Self {
+        let sample = sample_factory.new_sample(0);
+        Self {
+            next_key: RequestKey {
+                worker_id: 0,
+                connection_id,
+                request_id: 0,
+            },
+            sample_factory,
+            sample,
+            clock: Duration::ZERO,
+            sample_started: Duration::ZERO,
+        }
+    }
+
+    fn execute_batch<P, M>(
+        &mut self,
+        simulation: &mut Simulation<P, M>,
+        batch: Batch,
+    ) -> anyhow::Result<()>
+    where
+        P: Producer,
+        M: ResponseModel,
+    {
+        if self.sample.tag() != batch.tag {
+            self.submit_sample(batch.tag)?;
+        }
+
+        for request in batch.into_requests() {
+            self.send(simulation, request);
+
+            if self.clock - self.sample_started >= simulation.sample_window {
+                let tag = self.sample.tag();
+                self.submit_sample(tag)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send<P, M>(&mut self, simulation: &mut Simulation<P, M>, request: Request<Body>)
+    where
+        P: Producer,
+        M: ResponseModel,
+    {
+        let key = self.next_key;
+        self.next_key.request_id += 1;
+        let timestamp = SystemTime::UNIX_EPOCH + self.clock;
+
+        let response = simulation.model.respond(key, &request);
+        let latency = response.latency;
+        self.clock += latency;
+        self.sample.record_total_request();
+        self.sample.record_attempt_latency(latency);
+
+        let mut builder = Response::builder().status(response.status);
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers;
+        }
+        let (mut head, _) = builder.body(()).expect("Build response").into_parts();
+        head.extensions.insert(ResponseLatency(latency));
+
+        if let Some(threshold) = simulation.outlier_threshold {
+            if latency >= threshold {
+                self.sample.record_outlier(Outlier {
+                    key,
+                    timestamp,
+                    status: head.status.as_u16(),
+                    latency,
+                });
+            }
+        }
+
+        let classification = simulation.validator.classify(&head, &response.body);
+        if let Err(e) = simulation.validator.validate(head, response.body) {
+            self.sample.record_error(e);
+        } else {
+            self.sample.record_successful_request();
+            self.sample.record_latency(latency);
+            if let Some(classification) = classification {
+                self.sample
+                    .record_classified_latency(classification, latency);
+            }
+        }
+    }
+
+    fn submit_sample(&mut self, next_sample_tag: usize) -> anyhow::Result<()> {
+        let new_sample = self.sample_factory.new_sample(next_sample_tag);
+        let old_sample = std::mem::replace(&mut self.sample, new_sample);
+        self.sample_factory
+            .submit_sample_with_duration(old_sample, self.clock - self.sample_started)?;
+        self.sample_started = self.clock;
+        Ok(())
+    }
+}
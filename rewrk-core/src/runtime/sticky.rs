@@ -0,0 +1,327 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::{iter, mem};
+
+use flume::{Receiver, SendError, Sender, TrySendError};
+use futures_util::future::{select, Either};
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt;
+
+use crate::producer::{Batch, BatchSender, ProducerBatches, StickyKey};
+
+/// The size of each connection's channel and of its buffer once full.
+const CONNECTION_BUFFER_SIZE: usize = 4;
+
+/// Routes the batches of a worker's producer to the connection of their [StickyKey].
+///
+/// Batches without a key are shared by all of the connections. Priority
+/// batches are always shared, so they are executed as soon as possible.
+/// Once a connection has closed the batches of its keys are shared instead.
+///
+/// Batches are handed to each connection without waiting, a busy connection
+/// buffers its batches so it doesn't block the batches of other keys. The
+/// router only waits once a buffer is full, this bounds the number of batches
+/// buffered when the producer outpaces a connection.
+pub(crate) struct StickyRouter {
+    /// The shared connections followed by each connection.
+    routes: Vec<Route>,
+}
+
+impl StickyRouter {
+    /// Creates a router for the given number of connections.
+    ///
+    /// This returns the router along with the shared batches, which every
+    /// connection receives, and the batches routed to each connection.
+    pub fn new(
+        buffer_size: usize,
+        connections: usize,
+    ) -> (Self, ProducerBatches, Vec<Receiver<Batch>>) {
+        let (shared, shared_rx) = ProducerBatches::bounded(buffer_size);
+
+        let mut routes = Vec::with_capacity(connections + 1);
+        routes.push(Route::new(RouteTx::Shared(shared), buffer_size));
+        let mut receivers = Vec::with_capacity(connections);
+        for _ in 0..connections {
+            let (tx, rx) = flume::bounded(CONNECTION_BUFFER_SIZE);
+            routes.push(Route::new(RouteTx::Connection(tx), CONNECTION_BUFFER_SIZE));
+            receivers.push(rx);
+        }
+
+        (Self { routes }, shared_rx, receivers)
+    }
+
+    /// Forwards batches from the producer until it has finished.
+    pub async fn run(mut self, producer: ProducerBatches) {
+        let mut pending = FuturesUnordered::new();
+
+        loop {
+            let is_full = self.routes.iter().any(Route::is_full);
+            let event = if is_full {
+                // A route is only full while it is waiting on its connections.
+                pending.next().await.map(Event::Sent)
+            } else if pending.is_empty() {
+                producer.recv_with_priority().await.ok().map(Event::Batch)
+            } else {
+                let batch = Box::pin(producer.recv_with_priority());
+                match select(batch, pending.next()).await {
+                    Either::Left((batch, _)) => batch.ok().map(Event::Batch),
+                    Either::Right((sent, _)) => sent.map(Event::Sent),
+                }
+            };
+
+            let is_open = match event {
+                None => break,
+                Some(Event::Batch((batch, is_priority))) => {
+                    let index = self.route_of(&batch, is_priority);
+                    self.push(index, batch, is_priority, &mut pending)
+                },
+                Some(Event::Sent(sent)) => self.sent(sent, &mut pending),
+            };
+            if !is_open {
+                return;
+            }
+        }
+
+        // Hand over the batches still buffered before finishing.
+        while let Some(sent) = pending.next().await {
+            if !self.sent(sent, &mut pending) {
+                return;
+            }
+        }
+    }
+
+    /// The index of the route the batch is sent to.
+    fn route_of(&self, batch: &Batch, is_priority: bool) -> usize {
+        let key = batch
+            .requests
+            .first()
+            .and_then(|request| request.extensions().get::<StickyKey>());
+        let connections = self.routes.len() as u64 - 1;
+
+        match key {
+            Some(StickyKey(key)) if !is_priority && connections > 0 => {
+                1 + (key % connections) as usize
+            },
+            _ => 0,
+        }
+    }
+
+    /// Sends the batch to the route, falling back to the shared connections
+    /// if the route's connection has closed.
+    ///
+    /// Returns `false` if the shared connections have shutdown.
+    fn push(
+        &mut self,
+        index: usize,
+        batch: Batch,
+        is_priority: bool,
+        pending: &mut FuturesUnordered<Forward>,
+    ) -> bool {
+        let route = &mut self.routes[index];
+        match route.push(batch, is_priority) {
+            Push::Done => true,
+            Push::Wait(batch) => {
+                pending.push(forward(index, route, batch, is_priority));
+                true
+            },
+            Push::Closed(None) => false,
+            Push::Closed(Some(batch)) => self.push(0, batch, is_priority, pending),
+        }
+    }
+
+    /// Handles a batch which a route was waiting to send.
+    ///
+    /// Returns `false` if the shared connections have shutdown.
+    fn sent(
+        &mut self,
+        (index, result): (usize, Result<(), Option<Batch>>),
+        pending: &mut FuturesUnordered<Forward>,
+    ) -> bool {
+        let route = &mut self.routes[index];
+        match result {
+            Ok(()) => {
+                match route.buffer.pop_front() {
+                    None => route.is_sending = false,
+                    Some((batch, is_priority)) => {
+                        pending.push(forward(index, route, batch, is_priority));
+                    },
+                }
+                true
+            },
+            Err(None) => false,
+            Err(Some(batch)) => {
+                // The connection has closed, so its batches are shared instead.
+                route.tx = None;
+                route.is_sending = false;
+                let buffered = mem::take(&mut route.buffer);
+                iter::once((batch, false))
+                    .chain(buffered)
+                    .all(|(batch, is_priority)| {
+                        self.push(0, batch, is_priority, pending)
+                    })
+            },
+        }
+    }
+}
+
+enum Event {
+    Batch((Batch, bool)),
+    Sent((usize, Result<(), Option<Batch>>)),
+}
+
+/// Sends a batch to a route, resolving once the batch has been sent.
+///
+/// The batch is returned if the route's connection has closed, or nothing
+/// if the shared connections have shutdown.
+type Forward = Pin<Box<dyn Future<Output = (usize, Result<(), Option<Batch>>)> + Send>>;
+
+fn forward(index: usize, route: &Route, batch: Batch, is_priority: bool) -> Forward {
+    match route.tx.clone().expect("Only open routes are sent batches") {
+        RouteTx::Shared(tx) => Box::pin(async move {
+            let sent = tx.send(batch, is_priority).await;
+            (index, if sent { Ok(()) } else { Err(None) })
+        }),
+        RouteTx::Connection(tx) => Box::pin(async move {
+            let result = tx.send_async(batch).await;
+            (index, result.map_err(|SendError(batch)| Some(batch)))
+        }),
+    }
+}
+
+#[derive(Clone)]
+enum RouteTx {
+    Shared(BatchSender),
+    Connection(Sender<Batch>),
+}
+
+enum Push {
+    /// The batch was sent or buffered.
+    Done,
+    /// The route must start waiting to send the batch.
+    Wait(Batch),
+    /// The route has closed, returning the batch if it can be shared instead.
+    Closed(Option<Batch>),
+}
+
+/// The batches of the shared connections or a single connection.
+struct Route {
+    /// The sender of the route, or `None` once its connection has closed.
+    tx: Option<RouteTx>,
+    /// The batches waiting for the route's connections, in order.
+    buffer: VecDeque<(Batch, bool)>,
+    buffer_size: usize,
+    /// If a batch is currently waiting to be sent.
+    is_sending: bool,
+}
+
+impl Route {
+    fn new(tx: RouteTx, buffer_size: usize) -> Self {
+        Self {
+            tx: Some(tx),
+            buffer: VecDeque::new(),
+            buffer_size: buffer_size.max(1),
+            is_sending: false,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.buffer.len() >= self.buffer_size
+    }
+
+    /// Sends the batch without waiting or buffers it if the connections are busy.
+    fn push(&mut self, batch: Batch, is_priority: bool) -> Push {
+        if self.is_sending {
+            self.buffer.push_back((batch, is_priority));
+            return Push::Done;
+        }
+
+        let result = match self.tx.as_ref() {
+            None => return Push::Closed(Some(batch)),
+            Some(RouteTx::Shared(tx)) => tx.try_send(batch, is_priority),
+            Some(RouteTx::Connection(tx)) => tx.try_send(batch),
+        };
+
+        match result {
+            Ok(()) => Push::Done,
+            Err(TrySendError::Full(batch)) => {
+                self.is_sending = true;
+                Push::Wait(batch)
+            },
+            Err(TrySendError::Disconnected(batch)) => match self.tx.take() {
+                Some(RouteTx::Connection(_)) => Push::Closed(Some(batch)),
+                _ => Push::Closed(None),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::Request;
+    use hyper::Body;
+
+    use super::*;
+
+    fn batch(key: u64, id: usize) -> Batch {
+        let requests = (0..id)
+            .map(|_| {
+                let mut request = Request::new(Body::empty());
+                request.extensions_mut().insert(StickyKey(key));
+                request
+            })
+            .collect();
+        Batch { tag: 0, requests }
+    }
+
+    #[tokio::test]
+    async fn test_router_busy_connection() {
+        let (router, _shared, connections) = StickyRouter::new(4, 2);
+        let (tx, producer) = ProducerBatches::bounded(16);
+        let handle = tokio::spawn(router.run(producer));
+
+        // Fill the first connection's channel, the rest of its batches are buffered.
+        for id in 1..=6 {
+            assert!(tx.send(batch(0, id), false).await);
+        }
+        assert!(tx.send(batch(1, 1), false).await);
+
+        tokio::time::timeout(Duration::from_secs(1), connections[1].recv_async())
+            .await
+            .expect("Connection is not blocked by the busy connection")
+            .expect("Receive batch");
+
+        drop(tx);
+        let mut ids = Vec::new();
+        while let Ok(batch) = connections[0].recv_async().await {
+            ids.push(batch.requests.len());
+        }
+        assert_eq!(ids, [1, 2, 3, 4, 5, 6]);
+        handle.await.expect("Run router");
+    }
+
+    #[tokio::test]
+    async fn test_router_closed_connection() {
+        let (router, shared, mut connections) = StickyRouter::new(16, 2);
+        let (tx, producer) = ProducerBatches::bounded(16);
+        let handle = tokio::spawn(router.run(producer));
+
+        // Batches buffered for a connection which closes are shared instead.
+        for id in 1..=6 {
+            assert!(tx.send(batch(0, id), false).await);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(connections.remove(0));
+        assert!(tx.send(batch(0, 7), false).await);
+
+        drop(tx);
+        handle.await.expect("Run router");
+        let mut ids = Vec::new();
+        while let Ok((batch, _)) = shared.recv_with_priority().await {
+            ids.push(batch.requests.len());
+        }
+        assert_eq!(ids, [5, 6, 7]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use super::ShutdownHandle;
+
+/// The interval at which the process memory usage is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The action taken when the process exceeds the configured memory limit.
+pub enum MemoryLimitAction {
+    /// Log a warning and continue benchmarking.
+    Warn,
+    /// Log an error and shutdown the benchmark.
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A watchdog which monitors the resident memory of the process.
+pub(crate) struct MemoryWatchdog {
+    /// The memory limit in bytes.
+    pub limit: u64,
+    /// The action taken when the limit is exceeded.
+    pub action: MemoryLimitAction,
+}
+
+impl MemoryWatchdog {
+    /// Spawns the watchdog task.
+    ///
+    /// The task runs until it is aborted or the benchmark is shutdown.
+    pub fn spawn(self, shutdown: ShutdownHandle) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut has_warned = false;
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+            while !shutdown.should_abort() {
+                interval.tick().await;
+
+                let usage = match resident_memory() {
+                    Some(usage) => usage,
+                    None => {
+                        warn!("Memory usage is not supported on this platform, the memory watchdog is disabled.");
+                        return;
+                    },
+                };
+
+                if usage < self.limit {
+                    continue;
+                }
+
+                match self.action {
+                    MemoryLimitAction::Warn => {
+                        if !has_warned {
+                            has_warned = true;
+                            warn!(
+                                usage = usage,
+                                limit = self.limit,
+                                "The process has exceeded the memory limit."
+                            );
+                        }
+                    },
+                    MemoryLimitAction::Abort => {
+                        error!(
+                            usage = usage,
+                            limit = self.limit,
+                            "The process has exceeded the memory limit, aborting..."
+                        );
+                        shutdown.set_abort();
+                    },
+                }
+            }
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+/// Gets the resident memory of the process in bytes.
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+/// Gets the resident memory of the process in bytes.
+fn resident_memory() -> Option<u64> {
+    None
+}
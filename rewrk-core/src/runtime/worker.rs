@@ -1,23 +1,69 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::mem;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
-use futures_util::future::join_all;
-use http::Request;
+use futures_util::future::{select, Either};
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt;
+use http::response::Parts;
+use http::{request, Request, StatusCode};
+use hyper::body::Bytes;
 use hyper::Body;
-use tokio::sync::oneshot;
+use rand::Rng;
+use tokio::sync::{oneshot, watch, Notify, Semaphore};
 use tokio::task::JoinHandle;
 
-use crate::connection::{ReWrkConnection, ReWrkConnector};
-use crate::producer::{Batch, Producer, ProducerActor, ProducerBatches};
-use crate::recording::{CollectorMailbox, SampleFactory, SampleMetadata};
-use crate::utils::RuntimeTimings;
-use crate::validator::ValidationError;
-use crate::{ResponseValidator, Sample};
+use crate::connection::{drain_body, read_body, ReWrkConnection, ReWrkConnector};
+use crate::one_way_delay::OneWayDelayEstimator;
+use crate::producer::{
+    Batch,
+    NotBefore,
+    Producer,
+    ProducerActor,
+    ProducerBatches,
+    ProducerEnd,
+    ProducerPool,
+    StallTimeout,
+    StickyKey,
+};
+use crate::recording::{
+    CollectorMailbox,
+    ConnectErrorKind,
+    ConnectPhases,
+    ConnectSample,
+    Metric,
+    Outlier,
+    RequestKey,
+    SampleFactory,
+    SampleMetadata,
+    SampleWindowOverrides,
+    WindowStats,
+    WorkerReport,
+};
+use crate::response_tracking::ResponseLedger;
+use crate::runtime::group::{BatchRouter, ConnectionGroup};
+use crate::runtime::health::TargetHealth;
+use crate::runtime::sticky::StickyRouter;
+use crate::runtime::RunError;
+use crate::scheduler::{TagScheduler, TagUsage};
+use crate::utils::{RateLimiter, RuntimeTimings, WallClock};
+use crate::validator::{ResponseLatency, ValidationError};
+use crate::{
+    HeaderCapture,
+    OneWayDelay,
+    ResponseTracking,
+    ResponseValidator,
+    RetryPolicy,
+    Sample,
+    SendMode,
+    ServerTimingSource,
+};
 
-const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The interval at which paused workers check if the target has recovered.
+const HEALTH_PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
 type ConnectionTask = JoinHandle<RuntimeTimings>;
 type WorkerGuard = flume::Receiver<()>;
 
@@ -37,19 +83,153 @@ where
     /// The duration which should elapse before a sample
     /// is submitted to be processed.
     pub sample_window: Duration,
+    /// The sample windows replacing `sample_window` for certain tags or workers.
+    pub sample_window_overrides: Arc<SampleWindowOverrides>,
     /// The percentage threshold that the system must be
     /// waiting on the producer in order for a warning to be raised.
     ///
     /// This is useful in situations where you know the producer will
     /// take more time than normal and want to silence the warning.
     pub producer_wait_warning_threshold: f32,
+    /// The latency threshold which marks a request as an outlier.
+    pub outlier_threshold: Option<Duration>,
+    /// The maximum number of outliers captured per sample.
+    pub max_outliers: usize,
+    /// The maximum number of error exemplars held per sample.
+    pub max_error_exemplars: usize,
+    /// The policy for retrying requests, if any.
+    pub retry_policy: Option<Arc<RetryPolicy>>,
+    /// The source of server reported processing times, if any.
+    pub server_timing: Option<ServerTimingSource>,
+    /// The one-way delay estimation config, if enabled.
+    pub one_way_delay: Option<OneWayDelay>,
+    /// The response tracking config, if enabled.
+    pub response_tracking: Option<ResponseTracking>,
+    /// The response header capture config, if enabled.
+    pub header_capture: Option<HeaderCapture>,
+    /// The health of the benchmark target.
+    pub target_health: TargetHealth,
+    /// The benchmark round the workers are running.
+    pub round: usize,
+    /// The benchmark phase the workers are running.
+    pub phase: usize,
+    /// The maximum duration to run the benchmark for once started.
+    pub run_duration: Option<Duration>,
+    /// The period at the start of the benchmark whose samples are discarded.
+    pub warmup: Option<Duration>,
+    /// The target number of concurrent connections across all workers.
+    pub live_concurrency: watch::Receiver<usize>,
+    /// The scheduler sharing connection time across tags, if any.
+    pub tag_scheduler: Option<TagScheduler>,
+    /// The groups of connections dedicated to certain tags.
+    pub connection_groups: Arc<Vec<ConnectionGroup>>,
+    /// What a producer returning [RequestBatch::End](crate::RequestBatch::End) ends.
+    pub producer_end: ProducerEnd,
+    /// How requests are sent and their responses handled.
+    pub send_mode: SendMode,
+    /// The ramp applied to the first requests of each connection, if any.
+    pub slow_start: Option<SlowStart>,
+    /// The maximum time a producer may take to create a batch, if any.
+    pub producer_timeout: Option<Duration>,
+    /// The maximum random delay before each connection sends its first request.
+    pub start_jitter: Option<Duration>,
+    /// The dedicated runtime producers are run on, if any.
+    pub producer_pool: Option<ProducerPool>,
+    /// Paces requests across all workers to a target rate, if any.
+    pub request_limiter: Option<RateLimiter>,
+    /// Whether batches are routed to connections by their sticky key.
+    pub sticky_routing: bool,
+    /// The labels attached to every sample.
+    pub labels: Arc<BTreeMap<String, String>>,
+    /// The stats of the latest window passed to producers, if enabled.
+    pub window_stats: Option<watch::Receiver<Option<WindowStats>>>,
+    /// A signal flag telling all workers a producer has ended the benchmark.
+    ///
+    /// This is reset each time the workers are spawned.
+    pub benchmark_ended: Arc<AtomicBool>,
+}
+
+impl<P> WorkerConfig<P>
+where
+    P: Producer + Clone,
+{
+    /// Creates a copy of the config using a different producer.
+    pub fn with_producer<Q>(self, producer: Q) -> WorkerConfig<Q>
+    where
+        Q: Producer + Clone,
+    {
+        WorkerConfig {
+            connector: self.connector,
+            validator: self.validator,
+            collector: self.collector,
+            producer,
+            sample_window: self.sample_window,
+            sample_window_overrides: self.sample_window_overrides,
+            producer_wait_warning_threshold: self.producer_wait_warning_threshold,
+            outlier_threshold: self.outlier_threshold,
+            max_outliers: self.max_outliers,
+            max_error_exemplars: self.max_error_exemplars,
+            retry_policy: self.retry_policy,
+            server_timing: self.server_timing,
+            one_way_delay: self.one_way_delay,
+            response_tracking: self.response_tracking,
+            header_capture: self.header_capture,
+            target_health: self.target_health,
+            round: self.round,
+            phase: self.phase,
+            run_duration: self.run_duration,
+            warmup: self.warmup,
+            live_concurrency: self.live_concurrency,
+            tag_scheduler: self.tag_scheduler,
+            connection_groups: self.connection_groups,
+            producer_end: self.producer_end,
+            send_mode: self.send_mode,
+            slow_start: self.slow_start,
+            producer_timeout: self.producer_timeout,
+            start_jitter: self.start_jitter,
+            producer_pool: self.producer_pool,
+            request_limiter: self.request_limiter,
+            sticky_routing: self.sticky_routing,
+            labels: self.labels,
+            window_stats: self.window_stats,
+            benchmark_ended: self.benchmark_ended,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Spreads the first requests of a connection evenly over an interval.
+pub(crate) struct SlowStart {
+    /// The number of requests the ramp covers.
+    pub requests: usize,
+    /// The interval the requests are spread over.
+    pub interval: Duration,
+}
+
+impl SlowStart {
+    /// The delay from the start of the connection's ramp
+    /// before the given request may be sent.
+    ///
+    /// Returns `None` once the request is past the ramp.
+    fn delay(&self, request_index: usize) -> Option<Duration> {
+        if request_index >= self.requests {
+            return None;
+        }
+
+        Some(
+            self.interval
+                .mul_f64(request_index as f64 / self.requests as f64),
+        )
+    }
 }
 
 /// Spawns N worker runtimes for executing search requests.
+///
+/// The connections are split between the workers according
+/// to the current live concurrency.
 pub(crate) fn spawn_workers<P>(
     shutdown: ShutdownHandle,
     num_workers: usize,
-    concurrency: usize,
     config: WorkerConfig<P>,
 ) -> WorkerGuard
 where
@@ -57,22 +237,16 @@ where
 {
     // We use a channel here as a guard in order to wait for all workers to shutdown.
     let (guard, waiter) = flume::bounded(1);
-
-    let per_worker_concurrency = concurrency / num_workers;
-    let mut remaining_concurrency = concurrency - (per_worker_concurrency * num_workers);
+    let mut config = config;
+    config.benchmark_ended = Arc::default();
+    if let Some(limiter) = config.request_limiter.as_ref() {
+        limiter.restart();
+    }
 
     for worker_id in 0..num_workers {
-        let concurrency_modifier = if remaining_concurrency != 0 {
-            remaining_concurrency -= 1;
-            1
-        } else {
-            0
-        };
-        let concurrency = per_worker_concurrency + concurrency_modifier;
-
         spawn_worker(
             worker_id,
-            concurrency,
+            num_workers,
             guard.clone(),
             shutdown.clone(),
             config.clone(),
@@ -82,10 +256,24 @@ where
     waiter
 }
 
+/// The number of connections the given worker is responsible for.
+///
+/// Any remainder is spread across the first workers.
+fn worker_concurrency(
+    concurrency: usize,
+    num_workers: usize,
+    worker_id: usize,
+) -> usize {
+    let per_worker_concurrency = concurrency / num_workers;
+    let remaining_concurrency = concurrency % num_workers;
+
+    per_worker_concurrency + usize::from(worker_id < remaining_concurrency)
+}
+
 /// Spawns a new runtime worker thread.
 fn spawn_worker<P>(
     worker_id: usize,
-    concurrency: usize,
+    num_workers: usize,
     guard: flume::Sender<()>,
     handle: ShutdownHandle,
     config: WorkerConfig<P>,
@@ -101,7 +289,7 @@ fn spawn_worker<P>(
         .name(format!("rewrk-worker-{worker_id}"))
         .spawn(move || {
             debug!(worker_id = worker_id, "Spawning worker");
-            rt.block_on(run_worker(worker_id, concurrency, handle, config));
+            rt.block_on(run_worker(worker_id, num_workers, handle, config));
 
             // Drop the guard explicitly to make sure it's not dropped
             // until after the runtime has completed.
@@ -117,58 +305,226 @@ fn spawn_worker<P>(
 /// This acts as the main runtime entrypoint.
 async fn run_worker<P>(
     worker_id: usize,
-    concurrency: usize,
+    num_workers: usize,
     shutdown: ShutdownHandle,
     config: WorkerConfig<P>,
 ) where
     P: Producer + Clone,
 {
+    let mut live_concurrency = config.live_concurrency.clone();
+    let concurrency = worker_concurrency(
+        *live_concurrency.borrow_and_update(),
+        num_workers,
+        worker_id,
+    );
+
+    let groups = config
+        .connection_groups
+        .iter()
+        .map(|group| {
+            let connections =
+                worker_concurrency(group.connections(), num_workers, worker_id);
+            (group, connections)
+        })
+        .collect::<Vec<_>>();
+    let grouped_concurrency = groups.iter().map(|(_, n)| n).sum::<usize>();
+
     let (ready_tx, ready_rx) = oneshot::channel();
-    let producer =
-        ProducerActor::spawn(concurrency * 4, worker_id, config.producer, ready_rx)
-            .await;
-    let metadata = SampleMetadata { worker_id };
-    let sample_factory =
-        SampleFactory::new(config.sample_window, metadata, config.collector);
+    let tag_usage = config.tag_scheduler.as_ref().map(TagScheduler::usage);
+    let stall_timeout = config.producer_timeout.map(|timeout| StallTimeout {
+        timeout,
+        shutdown: shutdown.clone(),
+    });
+    let mut producer = ProducerActor::spawn(
+        (concurrency + grouped_concurrency) * 4,
+        worker_id,
+        config.producer.clone(),
+        ready_rx,
+        tag_usage.clone(),
+        config.producer_end,
+        config.benchmark_ended.clone(),
+        config.producer_pool.clone(),
+        config.window_stats.clone(),
+        stall_timeout,
+    )
+    .await;
+
+    let mut group_batches = Vec::new();
+    if !groups.is_empty() {
+        let (router, shared, batches) = BatchRouter::new(concurrency * 4, &groups);
+        tokio::spawn(router.run(producer));
+        producer = shared;
+        group_batches = batches;
+    }
+
+    let mut sticky_batches = Vec::new();
+    if config.sticky_routing {
+        let (router, shared, batches) = StickyRouter::new(concurrency * 4, concurrency);
+        tokio::spawn(router.run(producer));
+        producer = shared;
+        sticky_batches = batches;
+    }
+
+    let metadata = SampleMetadata {
+        worker_id,
+        connection_id: 0,
+        round: config.round,
+        phase: config.phase,
+        sample_window: config.sample_window,
+        peer_addr: None,
+    };
+    let mut sample_factory = SampleFactory::new(
+        config.sample_window,
+        config.max_outliers,
+        config.max_error_exemplars,
+        metadata,
+        config.collector.clone(),
+    )
+    .with_window_overrides(&config.sample_window_overrides)
+    .with_labels(config.labels.clone());
+    if let Some(capture) = config.header_capture.as_ref() {
+        sample_factory = sample_factory.with_max_header_values(capture.max_values());
+    }
 
-    let mut pending_futures = Vec::<ConnectionTask>::with_capacity(concurrency);
+    let deadline = Arc::new(OnceLock::new());
+    let mut connections = WorkerConnections::default();
+    let mut sticky_batches = sticky_batches.into_iter();
     for _ in 0..concurrency {
+        let connection_id = connections.next_id();
+        let drain = Arc::new(AtomicBool::new(false));
+        let mut batches = producer.clone();
+        if let Some(sticky) = sticky_batches.next() {
+            batches = batches.with_sticky(sticky);
+        }
         let task_opt = create_worker_connection(
             worker_id,
-            &config.connector,
+            connection_id,
+            &config,
             shutdown.clone(),
-            sample_factory.clone(),
-            config.validator.clone(),
-            producer.clone(),
+            sample_factory.for_connection(connection_id),
+            batches,
+            tag_usage.clone(),
+            deadline.clone(),
+            drain.clone(),
         )
         .await;
 
         match task_opt {
             None => {
                 info!(worker_id = ?worker_id, "Cleaning up futures and shutting down...");
-                for pending in pending_futures {
-                    pending.abort();
-                }
+                connections.abort();
                 return;
             },
-            Some(task) => {
-                pending_futures.push(task);
-            },
+            Some(task) => connections.push(connection_id, task, drain),
+        }
+    }
+
+    for ((_, group_concurrency), batches) in groups.iter().zip(group_batches) {
+        for _ in 0..*group_concurrency {
+            let connection_id = connections.next_id();
+            let task_opt = create_worker_connection(
+                worker_id,
+                connection_id,
+                &config,
+                shutdown.clone(),
+                sample_factory.for_connection(connection_id),
+                batches.clone(),
+                tag_usage.clone(),
+                deadline.clone(),
+                Arc::new(AtomicBool::new(false)),
+            )
+            .await;
+
+            match task_opt {
+                None => {
+                    info!(worker_id = ?worker_id, "Cleaning up futures and shutting down...");
+                    connections.abort();
+                    return;
+                },
+                Some(task) => connections.push_pinned(connection_id, task),
+            }
         }
     }
 
     // Begin benchmarking.
     let _ = ready_tx.send(());
 
-    // Wait for all tasks to complete.
-    let timings = join_all(pending_futures)
-        .await
-        .into_iter()
-        .collect::<Result<RuntimeTimings, _>>()
-        .expect("Join tasks");
+    // Wait for all tasks to complete, adding or draining connections
+    // whenever the live concurrency changes.
+    let mut timings = RuntimeTimings::default();
+    loop {
+        let event = {
+            let changed = live_concurrency.changed();
+            futures_util::pin_mut!(changed);
+
+            match select(connections.tasks.next(), changed).await {
+                Either::Left((completed, _)) => Either::Left(completed),
+                Either::Right((changed, _)) => Either::Right(changed),
+            }
+        };
+
+        match event {
+            Either::Left(None) => break,
+            Either::Left(Some((connection_id, result))) => {
+                connections.remove(connection_id);
+                timings += result.expect("Join task");
+            },
+            Either::Right(Ok(())) => {
+                let target = worker_concurrency(
+                    *live_concurrency.borrow_and_update(),
+                    num_workers,
+                    worker_id,
+                );
+
+                connections.drain_to(target);
+                while connections.len() < target && !shutdown.should_abort() {
+                    let connection_id = connections.next_id();
+                    let drain = Arc::new(AtomicBool::new(false));
+                    let task_opt = create_worker_connection(
+                        worker_id,
+                        connection_id,
+                        &config,
+                        shutdown.clone(),
+                        sample_factory.for_connection(connection_id),
+                        producer.clone(),
+                        tag_usage.clone(),
+                        deadline.clone(),
+                        drain.clone(),
+                    )
+                    .await;
+
+                    match task_opt {
+                        None => break,
+                        Some(task) => connections.push(connection_id, task, drain),
+                    }
+                }
+
+                debug!(
+                    worker_id = worker_id,
+                    concurrency = connections.len(),
+                    "Adjusted worker concurrency."
+                );
+            },
+            Either::Right(Err(_)) => {
+                // The benchmark has been dropped, wait for the remaining connections.
+                while let Some((_, result)) = connections.tasks.next().await {
+                    timings += result.expect("Join task");
+                }
+                break;
+            },
+        }
+    }
 
     info!(worker_id = worker_id, "Benchmark completed for worker.");
 
+    let report = WorkerReport::new(
+        metadata,
+        connections.next_connection_id,
+        timings.producer_wait_runtime,
+        timings.execute_wait_runtime,
+    );
+    let _ = sample_factory.submit_metric(Metric::WorkerReport(report));
+
     let total_duration = timings.execute_wait_runtime + timings.producer_wait_runtime;
     let producer_wait_pct = (timings.producer_wait_runtime.as_secs_f32()
         / total_duration.as_secs_f32())
@@ -187,15 +543,112 @@ async fn run_worker<P>(
     }
 }
 
-async fn create_worker_connection(
+/// The connection tasks of a worker.
+#[derive(Default)]
+struct WorkerConnections {
+    tasks: FuturesUnordered<LabeledConnectionTask>,
+    /// The drain flags of the running shared connections which are not
+    /// draining, in the order they were created.
+    active: Vec<(usize, Arc<AtomicBool>)>,
+    handles: Vec<(usize, tokio::task::AbortHandle)>,
+    next_connection_id: usize,
+}
+
+type LabeledConnectionTask = futures_util::future::BoxFuture<
+    'static,
+    (usize, Result<RuntimeTimings, tokio::task::JoinError>),
+>;
+
+impl WorkerConnections {
+    /// The number of running shared connections which are not draining.
+    fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Allocates the ID of the next connection.
+    fn next_id(&mut self) -> usize {
+        let connection_id = self.next_connection_id;
+        self.next_connection_id += 1;
+        connection_id
+    }
+
+    fn push(
+        &mut self,
+        connection_id: usize,
+        task: ConnectionTask,
+        drain: Arc<AtomicBool>,
+    ) {
+        self.handles.push((connection_id, task.abort_handle()));
+        self.active.push((connection_id, drain));
+        self.tasks
+            .push(Box::pin(async move { (connection_id, task.await) }));
+    }
+
+    /// Adds a connection dedicated to a connection group.
+    ///
+    /// These connections are never drained when the live concurrency changes.
+    fn push_pinned(&mut self, connection_id: usize, task: ConnectionTask) {
+        self.handles.push((connection_id, task.abort_handle()));
+        self.tasks
+            .push(Box::pin(async move { (connection_id, task.await) }));
+    }
+
+    fn remove(&mut self, connection_id: usize) {
+        self.active.retain(|(id, _)| *id != connection_id);
+        self.handles.retain(|(id, _)| *id != connection_id);
+    }
+
+    /// Signals the most recently created connections to finish their
+    /// current batch and shutdown until only `target` remain.
+    fn drain_to(&mut self, target: usize) {
+        while self.active.len() > target {
+            if let Some((_, drain)) = self.active.pop() {
+                drain.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn abort(&self) {
+        for (_, handle) in self.handles.iter() {
+            handle.abort();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn create_worker_connection<P>(
     worker_id: usize,
-    connector: &ReWrkConnector,
+    connection_id: usize,
+    config: &WorkerConfig<P>,
     shutdown: ShutdownHandle,
-    sample_factory: SampleFactory,
-    validator: Arc<dyn ResponseValidator>,
+    mut sample_factory: SampleFactory,
     producer: ProducerBatches,
-) -> Option<ConnectionTask> {
-    let connect_result = connector.connect_timeout(CONNECT_TIMEOUT).await;
+    tag_usage: Option<TagUsage>,
+    deadline: Arc<OnceLock<Instant>>,
+    drain: Arc<AtomicBool>,
+) -> Option<ConnectionTask>
+where
+    P: Producer + Clone,
+{
+    let mut phases = ConnectPhases::default();
+    let connect_result = config
+        .connector
+        .connect_with_retries(&mut phases, |attempt| {
+            let error = attempt
+                .error
+                .map(|e| (ConnectErrorKind::of(e), e.to_string()));
+            let connect_sample = ConnectSample::new(
+                sample_factory.metadata(),
+                attempt.timestamp,
+                attempt.duration,
+                attempt.phases,
+                error,
+            );
+            let metric = Metric::ConnectSample(Box::new(connect_sample));
+            let _ = sample_factory.submit_metric(metric);
+        })
+        .await;
+
     let conn = match connect_result {
         Err(e) => {
             // We check this to prevent spam of the logs.
@@ -205,27 +658,46 @@ async fn create_worker_connection(
             }
             return None;
         },
-        Ok(None) => {
-            // We check this to prevent spam of the logs.
-            if !shutdown.should_abort() {
-                error!(worker_id = worker_id, "Worker failed to connect to server within {CONNECT_TIMEOUT:?}, aborting.");
-                shutdown.set_abort();
-            }
-            return None;
-        },
-        Ok(Some(conn)) => conn,
+        Ok(conn) => conn,
     };
+    sample_factory.set_peer_addr(conn.peer_addr());
 
+    let key = RequestKey {
+        worker_id,
+        connection_id,
+        request_id: 0,
+    };
     let mut connection = WorkerConnection::new(
+        key,
         conn,
         sample_factory,
-        validator,
         producer,
+        tag_usage,
         shutdown.clone(),
+        deadline,
+        config,
     );
+    connection.record_connect_phases(phases);
 
+    // When draining, connections stop once the producer's channel is empty.
+    let benchmark_ended = (config.producer_end == ProducerEnd::Benchmark)
+        .then(|| config.benchmark_ended.clone());
+    let drain_on_end = config.producer_end == ProducerEnd::Drain;
+    let pending_responses = match config.send_mode {
+        SendMode::Mirror { max_in_flight } if drain_on_end => connection
+            .in_flight
+            .clone()
+            .map(|in_flight| (in_flight, max_in_flight)),
+        _ => None,
+    };
     let fut = async move {
-        while !shutdown.should_abort() {
+        while !shutdown.should_abort()
+            && !connection.deadline_elapsed()
+            && !drain.load(Ordering::Relaxed)
+            && !benchmark_ended
+                .as_ref()
+                .is_some_and(|ended| ended.load(Ordering::Relaxed))
+        {
             let can_continue = connection.execute_next_batch().await;
 
             if !can_continue {
@@ -233,7 +705,15 @@ async fn create_worker_connection(
             }
         }
 
+        // Wait for the responses of any mirrored requests still in flight.
+        if let Some((in_flight, max_in_flight)) = pending_responses {
+            if !shutdown.should_abort() {
+                let _ = in_flight.acquire_many(max_in_flight as u32).await;
+            }
+        }
+
         // Submit the remaining sample.
+        connection.finish_response_tracking();
         connection.submit_sample(0);
 
         connection.timings
@@ -246,6 +726,10 @@ async fn create_worker_connection(
 pub struct ShutdownHandle {
     /// A signal flag telling all workers to shutdown.
     should_stop: Arc<AtomicBool>,
+    /// Wakes the workers waiting on the abort flag.
+    aborted: Arc<Notify>,
+    /// The error which failed the benchmark, if any.
+    error: Arc<Mutex<Option<RunError>>>,
 }
 
 impl ShutdownHandle {
@@ -257,10 +741,51 @@ impl ShutdownHandle {
     /// Sets the abort flag across workers.
     pub fn set_abort(&self) {
         self.should_stop.store(true, Ordering::Relaxed);
+        self.aborted.notify_waiters();
+    }
+
+    /// Waits until the abort flag is set.
+    pub async fn wait_abort(&self) {
+        let mut aborted = std::pin::pin!(self.aborted.notified());
+        aborted.as_mut().enable();
+        if !self.should_abort() {
+            aborted.await;
+        }
+    }
+
+    /// Sets the abort flag across workers, recording the error
+    /// if the benchmark hasn't already failed.
+    pub fn fail(&self, error: RunError) {
+        self.error.lock().expect("Lock error").get_or_insert(error);
+        self.set_abort();
+    }
+
+    /// The error which failed the benchmark, if any.
+    pub fn error(&self) -> Option<RunError> {
+        self.error.lock().expect("Lock error").clone()
     }
 }
 
 pub struct WorkerConnection {
+    /// The key of the next request to be sent on the connection.
+    next_key: RequestKey,
+    /// The latency threshold which marks a request as an outlier.
+    outlier_threshold: Option<Duration>,
+    /// Timestamps the outliers of the connection.
+    wall_clock: WallClock,
+    /// The policy for retrying requests, if any.
+    retry_policy: Option<Arc<RetryPolicy>>,
+    /// The source of server reported processing times, if any.
+    server_timing: Option<ServerTimingSource>,
+    /// The one-way delay estimation config and connection state, if enabled.
+    one_way_delay: Option<(OneWayDelay, OneWayDelayEstimator)>,
+    /// The response tracking config and the requests of the
+    /// connection awaiting a response, if enabled.
+    response_tracking: Option<(ResponseTracking, Arc<Mutex<ResponseLedger>>)>,
+    /// The response header capture config, if enabled.
+    header_capture: Option<HeaderCapture>,
+    /// The health of the benchmark target.
+    target_health: TargetHealth,
     /// The ReWrk benchmarking connection.
     conn: ReWrkConnection,
     /// The sample factory for producing metric samples.
@@ -271,6 +796,8 @@ pub struct WorkerConnection {
     validator: Arc<dyn ResponseValidator>,
     /// The request batch producer.
     producer: ProducerBatches,
+    /// The connection time used by each tag, if batches are scheduled by tag.
+    tag_usage: Option<TagUsage>,
     /// The point in time when the last sample was submitted to
     /// the collectors.
     last_sent_sample: Instant,
@@ -283,43 +810,161 @@ pub struct WorkerConnection {
     /// This is so that timings can be adjusted while waiting for
     /// benchmarking to start, which would otherwise skew results.
     is_first_batch: bool,
+    /// The maximum duration to run the benchmark for once started.
+    run_duration: Option<Duration>,
+    /// The period at the start of the benchmark whose samples are discarded.
+    warmup: Option<Duration>,
+    /// The point in time the connection's warm-up ends, while warming up.
+    warmup_until: Option<Instant>,
+    /// The phases of establishing the connection, until they are
+    /// recorded in the first sample after the warm-up.
+    connect_phases: Option<ConnectPhases>,
+    /// The point in time when the worker's connections should stop sending requests.
+    ///
+    /// This is set once the first batch has been received by any of the
+    /// worker's connections, so connections added while running share it.
+    deadline: Arc<OnceLock<Instant>>,
+    /// The permits of mirrored requests awaiting a response, if mirroring.
+    in_flight: Option<Arc<Semaphore>>,
+    /// The ramp applied to the connection's first requests and
+    /// the point in time it started, if enabled.
+    slow_start: Option<(SlowStart, Option<Instant>)>,
+    /// The maximum random delay before the connection sends its first request.
+    start_jitter: Option<Duration>,
+    /// The number of requests sent on the connection.
+    requests_sent: usize,
+    /// Paces requests across all workers to a target rate, if any.
+    request_limiter: Option<RateLimiter>,
 }
 
 impl WorkerConnection {
     /// Create a new worker instance
-    fn new(
-        conn: ReWrkConnection,
-        sample_factory: SampleFactory,
-        validator: Arc<dyn ResponseValidator>,
+    #[allow(clippy::too_many_arguments)]
+    fn new<P>(
+        next_key: RequestKey,
+        mut conn: ReWrkConnection,
+        mut sample_factory: SampleFactory,
         producer: ProducerBatches,
+        tag_usage: Option<TagUsage>,
         shutdown: ShutdownHandle,
-    ) -> Self {
+        deadline: Arc<OnceLock<Instant>>,
+        config: &WorkerConfig<P>,
+    ) -> Self
+    where
+        P: Producer + Clone,
+    {
         let sample = sample_factory.new_sample(0);
         let last_sent_sample = Instant::now();
+        conn.set_body_validator(config.validator.clone());
 
         Self {
+            next_key,
+            outlier_threshold: config.outlier_threshold,
+            wall_clock: WallClock::new(),
+            retry_policy: config.retry_policy.clone(),
+            server_timing: config.server_timing.clone(),
+            one_way_delay: config
+                .one_way_delay
+                .clone()
+                .map(|config| (config, OneWayDelayEstimator::default())),
+            response_tracking: config.response_tracking.clone().map(|config| {
+                (config, Arc::new(Mutex::new(ResponseLedger::new(next_key))))
+            }),
+            header_capture: config.header_capture.clone(),
+            target_health: config.target_health.clone(),
             conn,
             sample_factory,
             sample,
-            validator,
+            validator: config.validator.clone(),
             producer,
+            tag_usage,
             last_sent_sample,
             shutdown,
             timings: RuntimeTimings::default(),
             is_first_batch: true,
+            run_duration: config.run_duration,
+            warmup: config.warmup,
+            warmup_until: None,
+            connect_phases: None,
+            deadline,
+            in_flight: match config.send_mode {
+                SendMode::Full => None,
+                SendMode::Mirror { max_in_flight } => {
+                    Some(Arc::new(Semaphore::new(max_in_flight)))
+                },
+            },
+            slow_start: config.slow_start.map(|slow_start| (slow_start, None)),
+            start_jitter: config.start_jitter,
+            requests_sent: 0,
+            request_limiter: config.request_limiter.clone(),
         }
     }
 
+    /// The point in time the next request may be sent while the
+    /// connection is ramping up, if it is.
+    fn slow_start_at(&mut self) -> Option<Instant> {
+        let (slow_start, started) = self.slow_start.as_mut()?;
+        let started = *started.get_or_insert_with(Instant::now);
+        let delay = slow_start.delay(self.requests_sent)?;
+        Some(started + delay)
+    }
+
+    /// Checks if the connection has passed its run deadline.
+    fn deadline_elapsed(&self) -> bool {
+        self.deadline
+            .get()
+            .is_some_and(|deadline| Instant::now() >= *deadline)
+    }
+
     /// Sets the abort flag across workers.
     fn set_abort(&self) {
         self.shutdown.set_abort()
     }
 
+    /// Records the phases of establishing the connection in its first sample,
+    /// or the first sample after the warm-up if there is one.
+    fn record_connect_phases(&mut self, phases: ConnectPhases) {
+        if self.warmup.is_some() {
+            self.connect_phases = Some(phases);
+        } else {
+            self.sample.record_connect_phases(&phases);
+        }
+    }
+
+    /// Ends the warm-up once it has elapsed, discarding the
+    /// metrics recorded while warming up.
+    fn end_warmup_if_elapsed(&mut self) {
+        match self.warmup_until {
+            Some(until) if Instant::now() >= until => {},
+            _ => return,
+        }
+
+        self.warmup_until = None;
+        self.sample = self.sample_factory.new_sample(self.sample.tag());
+        if let Some(phases) = self.connect_phases.take() {
+            self.sample.record_connect_phases(&phases);
+        }
+        if let Some((_, ledger)) = self.response_tracking.as_ref() {
+            ledger.lock().expect("Lock ledger").take_counts();
+        }
+        self.last_sent_sample = Instant::now();
+    }
+
     /// Submit the current sample to the collectors and create a new
     /// sample with a given tag.
+    ///
+    /// The sample is discarded rather than submitted while warming up.
     fn submit_sample(&mut self, next_sample_tag: usize) -> bool {
         let new_sample = self.sample_factory.new_sample(next_sample_tag);
-        let old_sample = mem::replace(&mut self.sample, new_sample);
+        let mut old_sample = mem::replace(&mut self.sample, new_sample);
+        if let Some((_, ledger)) = self.response_tracking.as_ref() {
+            let counts = ledger.lock().expect("Lock ledger").take_counts();
+            old_sample.record_response_counts(counts);
+        }
+        if self.warmup_until.is_some() {
+            self.last_sent_sample = Instant::now();
+            return true;
+        }
         if self.sample_factory.submit_sample(old_sample).is_err() {
             return false;
         }
@@ -327,6 +972,13 @@ impl WorkerConnection {
         true
     }
 
+    /// Counts the requests still awaiting a response as missing.
+    fn finish_response_tracking(&self) {
+        if let Some((_, ledger)) = self.response_tracking.as_ref() {
+            ledger.lock().expect("Lock ledger").finish();
+        }
+    }
+
     /// Gets the next batch from the producer and submits it to be executed.
     ///
     /// The method returns if more batches are possibly available.
@@ -341,17 +993,56 @@ impl WorkerConnection {
 
         if self.is_first_batch {
             self.is_first_batch = false;
+            let warmup = self.warmup.unwrap_or_default();
+            if let Some(dur) = self.run_duration {
+                self.deadline.get_or_init(|| Instant::now() + warmup + dur);
+            }
+            if self.warmup.is_some() {
+                self.warmup_until = Some(Instant::now() + warmup);
+            }
+            if let Some(max) = self.start_jitter {
+                // Offsets the connection's sample windows from the other connections.
+                let offset = max.mul_f64(rand::thread_rng().gen::<f64>());
+                if !self.wait_until(Instant::now() + offset).await {
+                    return true;
+                }
+                self.last_sent_sample = Instant::now();
+            }
         } else {
             self.timings.producer_wait_runtime += producer_elapsed;
         }
 
+        let tag = batch.tag;
         let execute_start = Instant::now();
         self.execute_batch(batch).await;
-        self.timings.execute_wait_runtime += execute_start.elapsed();
+        let execute_elapsed = execute_start.elapsed();
+        self.timings.execute_wait_runtime += execute_elapsed;
+
+        if let Some(usage) = self.tag_usage.as_ref() {
+            usage.record(tag, execute_elapsed);
+        }
 
         true
     }
 
+    /// Waits until the given point in time.
+    ///
+    /// Returns `false` if the connection's deadline passes or
+    /// the benchmark is aborted first.
+    async fn wait_until(&self, at: Instant) -> bool {
+        let (until, reached) = match self.deadline.get().copied() {
+            Some(deadline) if deadline <= at => (deadline, false),
+            _ => (at, true),
+        };
+
+        let sleep = tokio::time::sleep_until(until.into());
+        let aborted = Box::pin(self.shutdown.wait_abort());
+        match select(Box::pin(sleep), aborted).await {
+            Either::Left(_) => reached,
+            Either::Right(_) => false,
+        }
+    }
+
     /// Executes a batch of requests to measure the metrics.
     async fn execute_batch(&mut self, batch: Batch) {
         if self.sample.tag() != batch.tag {
@@ -363,8 +1054,37 @@ impl WorkerConnection {
             }
         }
 
-        for request in batch.requests {
-            let result = self.send(request).await;
+        for request in batch.into_requests() {
+            if self.deadline_elapsed() {
+                return;
+            }
+            self.end_warmup_if_elapsed();
+
+            if let Some(NotBefore(at)) = request.extensions().get::<NotBefore>() {
+                if !self.wait_until(*at).await {
+                    return;
+                }
+            }
+
+            if let Some(at) = self.slow_start_at() {
+                if !self.wait_until(at).await {
+                    return;
+                }
+            }
+
+            let mut scheduled_at = None;
+            if let Some(limiter) = self.request_limiter.as_ref() {
+                let at = limiter.reserve_scheduled().into_std();
+                if !self.wait_until(at).await {
+                    return;
+                }
+                scheduled_at = Some(at);
+            }
+
+            self.wait_for_healthy_target().await;
+
+            self.requests_sent += 1;
+            let result = self.send(request, scheduled_at).await;
 
             match result {
                 Ok(should_continue) if !should_continue => {
@@ -381,15 +1101,209 @@ impl WorkerConnection {
         }
     }
 
+    /// Waits while the benchmark is paused due to the target
+    /// failing its health check.
+    async fn wait_for_healthy_target(&mut self) {
+        while self.target_health.should_pause() && !self.shutdown.should_abort() {
+            self.sample.mark_target_unhealthy();
+            tokio::time::sleep(HEALTH_PAUSE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Execute a HTTP request, retrying it if the retry policy allows.
+    ///
+    /// The latency of every attempt is recorded, the final response
+    /// is returned to be validated along with the total time spent
+    /// waiting for the connection to be ready to send.
+    async fn execute(
+        &mut self,
+        request: PreparedRequest,
+    ) -> Result<(Parts, Bytes, Duration), hyper::Error> {
+        let (policy, parts, body) = match request {
+            PreparedRequest::Once(request) => {
+                return self.execute_attempt(request).await
+            },
+            PreparedRequest::Retryable(policy, parts, body) => (policy, parts, body),
+        };
+
+        let mut attempts = 0;
+        let mut backpressure = Duration::ZERO;
+        loop {
+            let (head, resp_body, waited) =
+                self.execute_attempt(rebuild_request(&parts, &body)).await?;
+            backpressure += waited;
+            attempts += 1;
+
+            if !policy.should_retry(head.status, attempts) {
+                return Ok((head, resp_body, backpressure));
+            }
+
+            let backoff = policy.backoff().delay(attempts);
+            self.sample.record_retry(backoff);
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    /// Executes a single attempt of a request, returning the response along
+    /// with the client backpressure of the attempt.
+    ///
+    /// The backpressure is the time spent waiting for the connection to be
+    /// ready and the time the body was blocked by HTTP/2 flow control, it is
+    /// excluded from the attempt's latency.
+    async fn execute_attempt(
+        &mut self,
+        request: Request<Body>,
+    ) -> Result<(Parts, Bytes, Duration), hyper::Error> {
+        let waited = self.conn.ready().await?;
+        let start = Instant::now();
+        let (head, body) = self.conn.execute_req(request).await?;
+        let send_blocked = self.conn.response_phases().send_blocked;
+        let backpressure = waited + send_blocked;
+        self.sample.record_client_backpressure(backpressure);
+        self.record_attempt(&head, start.elapsed().saturating_sub(send_blocked));
+        Ok((head, body, backpressure))
+    }
+
+    /// Buffers the body of the request if the retry policy may re-send it.
+    async fn prepare(
+        &self,
+        request: Request<Body>,
+    ) -> Result<PreparedRequest, hyper::Error> {
+        match self.retry_policy.clone() {
+            Some(policy) if policy.allows_method(request.method()) => {
+                let (parts, body) = request.into_parts();
+                let body = read_body(body).await?;
+                Ok(PreparedRequest::Retryable(policy, parts, body))
+            },
+            _ => Ok(PreparedRequest::Once(request)),
+        }
+    }
+
+    /// Waits for the connection to be ready to send a request,
+    /// recording the time spent waiting as client backpressure.
+    async fn wait_until_ready(&mut self) -> Result<Duration, hyper::Error> {
+        let waited = self.conn.ready().await?;
+        self.sample.record_client_backpressure(waited);
+        Ok(waited)
+    }
+
+    /// Record the metrics of a single request attempt.
+    fn record_attempt(&mut self, head: &Parts, elapsed: Duration) {
+        let phases = self.conn.response_phases();
+        self.sample.record_attempt_latency(elapsed);
+        self.sample
+            .record_response_phases(phases.ttfb, phases.body_read);
+        if head.status == StatusCode::TOO_MANY_REQUESTS {
+            self.sample.record_rate_limited();
+        }
+    }
+
+    /// Send a request without waiting for its response.
+    ///
+    /// The response is read and discarded in the background, only the
+    /// request and any client backpressure are recorded.
+    async fn send_mirrored(
+        &mut self,
+        mut request: Request<Body>,
+        in_flight: Arc<Semaphore>,
+    ) -> Result<bool, hyper::Error> {
+        let permit = in_flight
+            .acquire_owned()
+            .await
+            .expect("Semaphore is never closed");
+        self.sample.record_total_request();
+
+        if let Err(e) = self.wait_until_ready().await {
+            if e.is_closed() || e.is_connect() {
+                self.sample.record_error(ValidationError::ConnectionAborted);
+                return Ok(false);
+            }
+            return Err(e);
+        }
+
+        let key = self.next_key;
+        self.next_key.request_id += 1;
+        let response_tracking = self.response_tracking.clone();
+        if let Some((config, ledger)) = response_tracking.as_ref() {
+            let mut ledger = ledger.lock().expect("Lock ledger");
+            ledger.stamp(config, key, request.headers_mut());
+        }
+
+        let response = self.conn.send_detached(request);
+        tokio::spawn(async move {
+            let response = response.await;
+            if let Some((config, ledger)) = response_tracking.as_ref() {
+                let mut ledger = ledger.lock().expect("Lock ledger");
+                match response.as_ref() {
+                    Ok(response) => ledger.record_response(config, response.headers()),
+                    Err(_) => ledger.forget(key),
+                }
+            }
+
+            if let Ok(response) = response {
+                let _ = drain_body(response.into_body()).await;
+            }
+            drop(permit);
+        });
+        self.sample.record_mirrored_request();
+
+        // Submit the sample if it's window interval has elapsed.
+        let batch_tag = self.sample.tag();
+        if self
+            .sample_factory
+            .should_submit(batch_tag, self.last_sent_sample)
+        {
+            let success = self.submit_sample(batch_tag);
+            return Ok(success);
+        }
+
+        Ok(true)
+    }
+
     /// Send a HTTP request and record the relevant metrics
-    async fn send(&mut self, request: Request<Body>) -> Result<bool, hyper::Error> {
-        let read_transfer_start = self.conn.usage().get_received_count();
-        let write_transfer_start = self.conn.usage().get_written_count();
+    ///
+    /// If the request was scheduled to be sent at a point in time which has
+    /// already passed, its latency is measured from the scheduled time so a
+    /// slow response delaying the requests after it is accounted for.
+    async fn send(
+        &mut self,
+        mut request: Request<Body>,
+        scheduled_at: Option<Instant>,
+    ) -> Result<bool, hyper::Error> {
+        if let Some(in_flight) = self.in_flight.clone() {
+            return self.send_mirrored(request, in_flight).await;
+        }
+
+        let sent_at = self
+            .one_way_delay
+            .as_ref()
+            .map(|(config, estimator)| estimator.stamp(config, request.headers_mut()));
+        let io_marker = self.conn.io_marker();
+        let key = self.next_key;
+        self.next_key.request_id += 1;
+        if let Some((config, ledger)) = self.response_tracking.as_ref() {
+            let mut ledger = ledger.lock().expect("Lock ledger");
+            ledger.stamp(config, key, request.headers_mut());
+        }
+        // The body is buffered before the request is timed.
+        let prepared = self.prepare(request).await;
         let start = Instant::now();
+        self.sample.record_total_request();
+        if self.target_health.is_unhealthy() {
+            self.sample.mark_target_unhealthy();
+        }
 
-        let (head, body) = match self.conn.execute_req(request).await {
+        let result = match prepared {
+            Ok(request) => self.execute(request).await,
+            Err(e) => Err(e),
+        };
+        let (mut head, body, backpressure) = match result {
             Ok(resp) => resp,
             Err(e) => {
+                if let Some((_, ledger)) = self.response_tracking.as_ref() {
+                    ledger.lock().expect("Lock ledger").forget(key);
+                }
+
                 if e.is_body_write_aborted() || e.is_closed() || e.is_connect() {
                     self.sample.record_error(ValidationError::ConnectionAborted);
                     return Ok(false);
@@ -411,29 +1325,75 @@ impl WorkerConnection {
             },
         };
 
-        let elapsed_time = start.elapsed();
-        let read_transfer_end = self.conn.usage().get_received_count();
-        let write_transfer_end = self.conn.usage().get_written_count();
+        if let Some((config, ledger)) = self.response_tracking.as_ref() {
+            let mut ledger = ledger.lock().expect("Lock ledger");
+            ledger.record_response(config, &head.headers);
+        }
+        if let Some(capture) = self.header_capture.as_ref() {
+            capture.record(&head.headers, &mut self.sample);
+        }
+
+        // Time spent queued within the client isn't part of the server's latency.
+        let elapsed_time = scheduled_at
+            .map_or(start, |at| at.min(start))
+            .elapsed()
+            .saturating_sub(backpressure);
+        let io = self.conn.take_request_io(io_marker);
+        head.extensions.insert(ResponseLatency(elapsed_time));
 
-        if let Err(e) = self.validator.validate(head, body) {
+        if let Some(threshold) = self.outlier_threshold {
+            if elapsed_time >= threshold {
+                self.sample.record_outlier(Outlier {
+                    key,
+                    timestamp: self.wall_clock.at(start),
+                    status: head.status.as_u16(),
+                    latency: elapsed_time,
+                });
+            }
+        }
+
+        let classification = self.validator.classify(&head, &body);
+        let server_time = self
+            .server_timing
+            .as_ref()
+            .and_then(|source| source.extract(&head.headers));
+        let one_way_delay = self.one_way_delay.as_mut().zip(sent_at).and_then(
+            |((config, estimator), sent_at)| {
+                estimator.estimate(config, sent_at, &head.headers)
+            },
+        );
+        let result = match self.conn.take_body_error() {
+            Some(e) => Err(e),
+            None => self.validator.validate(head, body),
+        };
+        if let Err(e) = result {
             self.sample.record_error(e);
         } else {
+            self.sample.record_successful_request();
             self.sample.record_latency(elapsed_time);
-            self.sample.record_read_transfer(
-                read_transfer_start,
-                read_transfer_end,
-                elapsed_time,
-            );
-            self.sample.record_write_transfer(
-                write_transfer_start,
-                write_transfer_end,
-                elapsed_time,
-            );
+            if let Some((forward, backward)) = one_way_delay {
+                self.sample.record_one_way_delay(forward, backward);
+            }
+            if let Some(server_time) = server_time {
+                self.sample.record_server_time(server_time, elapsed_time);
+            }
+            if let Some(classification) = classification {
+                self.sample
+                    .record_classified_latency(classification, elapsed_time);
+            }
+            self.sample
+                .record_read_transfer(0, io.socket.read, elapsed_time);
+            self.sample
+                .record_write_transfer(0, io.socket.written, elapsed_time);
+            self.sample.record_plaintext_transfer(io.plaintext);
         }
 
         // Submit the sample if it's window interval has elapsed.
-        if self.sample_factory.should_submit(self.last_sent_sample) {
-            let batch_tag = self.sample.tag();
+        let batch_tag = self.sample.tag();
+        if self
+            .sample_factory
+            .should_submit(batch_tag, self.last_sent_sample)
+        {
             let success = self.submit_sample(batch_tag);
             return Ok(success);
         }
@@ -441,3 +1401,80 @@ impl WorkerConnection {
         Ok(true)
     }
 }
+
+/// A request ready to be sent.
+enum PreparedRequest {
+    /// The request is sent once.
+    Once(Request<Body>),
+    /// The request is buffered so it can be re-sent by the retry policy.
+    Retryable(Arc<RetryPolicy>, request::Parts, Bytes),
+}
+
+/// Creates a copy of a buffered request.
+///
+/// Request extensions cannot be cloned, so only the extensions
+/// known to rewrk, i.e. [NotBefore] and [StickyKey], are carried over.
+fn rebuild_request(parts: &request::Parts, body: &Bytes) -> Request<Body> {
+    let mut request = Request::new(Body::from(body.clone()));
+    *request.method_mut() = parts.method.clone();
+    *request.uri_mut() = parts.uri.clone();
+    *request.version_mut() = parts.version;
+    *request.headers_mut() = parts.headers.clone();
+
+    let extensions = request.extensions_mut();
+    if let Some(not_before) = parts.extensions.get::<NotBefore>() {
+        extensions.insert(*not_before);
+    }
+    if let Some(key) = parts.extensions.get::<StickyKey>() {
+        extensions.insert(*key);
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_wait_abort() {
+        let shutdown = ShutdownHandle::default();
+        let waiter = shutdown.clone();
+        let handle = tokio::spawn(async move { waiter.wait_abort().await });
+
+        tokio::task::yield_now().await;
+        assert!(!handle.is_finished());
+        shutdown.set_abort();
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("Wake on abort")
+            .expect("Wait for abort");
+
+        // Already aborted handles return immediately.
+        shutdown.wait_abort().await;
+    }
+
+    #[test]
+    fn test_rebuild_request() {
+        let at = Instant::now();
+        let mut request = Request::post("/items")
+            .header("x-id", "1")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(NotBefore(at));
+        request.extensions_mut().insert(StickyKey(7));
+        let (parts, _) = request.into_parts();
+
+        let body = Bytes::from_static(b"payload");
+        let rebuilt = rebuild_request(&parts, &body);
+        assert_eq!(rebuilt.method(), parts.method);
+        assert_eq!(rebuilt.uri(), &parts.uri);
+        assert_eq!(rebuilt.headers(), &parts.headers);
+        assert_eq!(
+            rebuilt.extensions().get::<NotBefore>(),
+            Some(&NotBefore(at))
+        );
+        assert_eq!(rebuilt.extensions().get::<StickyKey>(), Some(&StickyKey(7)));
+    }
+}
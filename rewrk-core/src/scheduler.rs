@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::producer::Batch;
+
+#[derive(Debug, Clone, Default)]
+/// Shares the connection time of each worker fairly across tags.
+///
+/// By default batches are executed in the order the producer creates them,
+/// so when a producer interleaves tags a tag with slow requests takes up most
+/// of the connections' time. With a scheduler set via
+/// [ReWrkBenchmark::set_tag_scheduler](crate::ReWrkBenchmark::set_tag_scheduler)
+/// each worker buffers batches per tag and hands the next batch to the tag
+/// which has used the least connection time relative to its weight.
+///
+/// Tags have a weight of `1` unless configured otherwise, a tag with a weight
+/// of `2` gets twice the connection time of a tag with a weight of `1`.
+///
+/// The scheduler can only choose between the batches the producer has already
+/// created, by default it looks ahead by up to 4 batches per connection.
+pub struct TagScheduler {
+    weights: BTreeMap<usize, u32>,
+    lookahead: Option<usize>,
+}
+
+impl TagScheduler {
+    /// Creates a scheduler giving every tag an equal share of connection time.
+    pub fn fair() -> Self {
+        Self::default()
+    }
+
+    /// Sets the weight of the given tag.
+    ///
+    /// A weight of `0` is treated as `1`.
+    pub fn set_weight(&mut self, tag: usize, weight: u32) {
+        self.weights.insert(tag, weight.max(1));
+    }
+
+    /// The weight of the given tag.
+    pub fn weight(&self, tag: usize) -> u32 {
+        self.weights.get(&tag).copied().unwrap_or(1)
+    }
+
+    /// Sets the maximum number of batches each worker buffers
+    /// ahead of its connections to choose from.
+    ///
+    /// A larger lookahead lets the scheduler reorder producers which emit
+    /// long runs of the same tag, at the cost of holding more requests in memory.
+    pub fn set_lookahead(&mut self, n: usize) {
+        self.lookahead = Some(n.max(1));
+    }
+
+    /// The maximum number of batches each worker buffers, if set.
+    pub fn lookahead(&self) -> Option<usize> {
+        self.lookahead
+    }
+
+    /// Creates the usage tracker shared by a worker's producer and connections.
+    pub(crate) fn usage(&self) -> TagUsage {
+        TagUsage {
+            scheduler: Arc::new(self.clone()),
+            virtual_time: Arc::default(),
+        }
+    }
+}
+
+#[derive(Clone)]
+/// The connection time used by each tag within a worker.
+///
+/// Usage is tracked as virtual time, the connection time used
+/// divided by the weight of the tag.
+pub(crate) struct TagUsage {
+    scheduler: Arc<TagScheduler>,
+    virtual_time: Arc<Mutex<BTreeMap<usize, f64>>>,
+}
+
+impl TagUsage {
+    /// The scheduler the usage is tracked for.
+    pub fn scheduler(&self) -> &TagScheduler {
+        &self.scheduler
+    }
+
+    /// Records the time a connection spent executing a batch of the given tag.
+    pub fn record(&self, tag: usize, elapsed: Duration) {
+        let weight = self.scheduler.weight(tag) as f64;
+        let mut virtual_time = self.virtual_time.lock().unwrap();
+        *virtual_time.entry(tag).or_default() += elapsed.as_secs_f64() / weight;
+    }
+
+    /// Marks the tag as having batches waiting after being idle.
+    ///
+    /// The tag is caught up to the least used of the `active` tags so
+    /// an idle tag can't build up credit and starve the others once
+    /// it becomes active again.
+    fn activate(&self, tag: usize, active: impl Iterator<Item = usize>) {
+        let mut virtual_time = self.virtual_time.lock().unwrap();
+        let min = active
+            .filter_map(|tag| virtual_time.get(&tag).copied())
+            .min_by(f64::total_cmp);
+
+        let entry = virtual_time.entry(tag).or_default();
+        if let Some(min) = min {
+            *entry = entry.max(min);
+        }
+    }
+
+    /// Selects the tag with the least virtual time out of the given tags.
+    fn select(&self, tags: impl Iterator<Item = usize>) -> Option<usize> {
+        let virtual_time = self.virtual_time.lock().unwrap();
+        tags.min_by(|a, b| {
+            let a = virtual_time.get(a).copied().unwrap_or_default();
+            let b = virtual_time.get(b).copied().unwrap_or_default();
+            a.total_cmp(&b)
+        })
+    }
+}
+
+/// The batches waiting to be scheduled, grouped by tag.
+pub(crate) struct TagQueues {
+    usage: TagUsage,
+    queues: BTreeMap<usize, VecDeque<Batch>>,
+    len: usize,
+}
+
+impl TagQueues {
+    pub fn new(usage: TagUsage) -> Self {
+        Self {
+            usage,
+            queues: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    /// The total number of batches waiting.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn push(&mut self, batch: Batch) {
+        if !self.queues.contains_key(&batch.tag) {
+            self.usage.activate(batch.tag, self.queues.keys().copied());
+        }
+
+        self.len += 1;
+        self.queues.entry(batch.tag).or_default().push_back(batch);
+    }
+
+    /// Removes the next batch of the tag with the least connection time used.
+    pub fn pop(&mut self) -> Option<Batch> {
+        let tag = self.usage.select(self.queues.keys().copied())?;
+        let queue = self.queues.get_mut(&tag)?;
+        let batch = queue.pop_front();
+
+        if queue.is_empty() {
+            self.queues.remove(&tag);
+        }
+        if batch.is_some() {
+            self.len -= 1;
+        }
+
+        batch
+    }
+}
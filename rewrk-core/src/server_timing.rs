@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use http::header::HeaderName;
+use http::HeaderMap;
+
+/// The standard `Server-Timing` header name.
+static SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+
+#[derive(Debug, Clone)]
+/// Where the server reported processing time is read from in a response.
+///
+/// Server reported durations are recorded in their own histogram, along with
+/// the network overhead of the request, i.e. the client latency minus the
+/// time reported by the server.
+pub enum ServerTimingSource {
+    /// The standard `Server-Timing` header.
+    ///
+    /// If a metric name is given only the duration of that metric is used,
+    /// otherwise the durations of all metrics are summed.
+    ServerTiming(Option<Cow<'static, str>>),
+    /// A custom header containing a single duration, i.e. `X-Response-Time`.
+    ///
+    /// Values can have a `s`, `ms`, `us` or `ns` suffix, values without a
+    /// unit are treated as milliseconds.
+    Header(HeaderName),
+}
+
+impl ServerTimingSource {
+    /// Extracts the server reported duration from a set of response headers.
+    pub fn extract(&self, headers: &HeaderMap) -> Option<Duration> {
+        match self {
+            Self::ServerTiming(metric) => {
+                let mut total = None;
+                for value in headers.get_all(&SERVER_TIMING) {
+                    let value = value.to_str().ok()?;
+                    for entry in value.split(',') {
+                        if let Some(dur) = parse_server_timing_entry(entry, metric) {
+                            total = Some(total.unwrap_or(Duration::ZERO) + dur);
+                        }
+                    }
+                }
+                total
+            },
+            Self::Header(name) => {
+                let value = headers.get(name)?.to_str().ok()?;
+                parse_duration(value)
+            },
+        }
+    }
+}
+
+/// Parses a single `Server-Timing` metric entry, i.e. `db;desc="Database";dur=53.2`.
+fn parse_server_timing_entry(
+    entry: &str,
+    metric: &Option<Cow<'static, str>>,
+) -> Option<Duration> {
+    let mut params = entry.split(';').map(str::trim);
+    let name = params.next()?;
+
+    if let Some(metric) = metric {
+        if !name.eq_ignore_ascii_case(metric) {
+            return None;
+        }
+    }
+
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("dur"))
+        .and_then(|(_, value)| parse_millis(value.trim().trim_matches('"')))
+}
+
+/// Parses a duration with an optional unit suffix.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, scale) = if let Some(number) = value.strip_suffix("ms") {
+        (number, 1.0)
+    } else if let Some(number) = value
+        .strip_suffix("us")
+        .or_else(|| value.strip_suffix("µs"))
+    {
+        (number, 1e-3)
+    } else if let Some(number) = value.strip_suffix("ns") {
+        (number, 1e-6)
+    } else if let Some(number) = value.strip_suffix('s') {
+        (number, 1e3)
+    } else {
+        (value, 1.0)
+    };
+
+    let millis = number.trim().parse::<f64>().ok()? * scale;
+    millis_to_duration(millis)
+}
+
+/// Parses a duration in milliseconds.
+fn parse_millis(value: &str) -> Option<Duration> {
+    millis_to_duration(value.parse::<f64>().ok()?)
+}
+
+fn millis_to_duration(millis: f64) -> Option<Duration> {
+    if !millis.is_finite() || millis < 0.0 {
+        return None;
+    }
+
+    Some(Duration::from_secs_f64(millis / 1000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_server_timing_extraction() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            &SERVER_TIMING,
+            HeaderValue::from_static("db;desc=\"Database\";dur=53, app;dur=47.5"),
+        );
+        headers.append(
+            &SERVER_TIMING,
+            HeaderValue::from_static("cache;desc=\"Miss\""),
+        );
+
+        let total = ServerTimingSource::ServerTiming(None).extract(&headers);
+        assert_eq!(total, Some(Duration::from_micros(100_500)));
+
+        let db = ServerTimingSource::ServerTiming(Some(Cow::Borrowed("db")))
+            .extract(&headers);
+        assert_eq!(db, Some(Duration::from_millis(53)));
+
+        let missing = ServerTimingSource::ServerTiming(Some(Cow::Borrowed("cache")))
+            .extract(&headers);
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn test_custom_header_extraction() {
+        let name = HeaderName::from_static("x-response-time");
+        let source = ServerTimingSource::Header(name.clone());
+
+        let cases = [
+            ("12", Some(Duration::from_millis(12))),
+            ("12.5ms", Some(Duration::from_micros(12_500))),
+            ("1.5s", Some(Duration::from_millis(1_500))),
+            ("250us", Some(Duration::from_micros(250))),
+            ("-1ms", None),
+            ("fast", None),
+        ];
+
+        for (value, expected) in cases {
+            let mut headers = HeaderMap::new();
+            headers.insert(&name, HeaderValue::from_static(value));
+            assert_eq!(source.extract(&headers), expected, "Parsing {value:?}");
+        }
+    }
+}
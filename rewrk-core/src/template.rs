@@ -0,0 +1,422 @@
+//! Strings with placeholders substituted as each request is built.
+//!
+//! Benchmarking APIs which expect a unique ID per request would otherwise
+//! require a custom [Producer]. A [PlaceholderProducer] renders the path,
+//! headers and body of every request from [PlaceholderString]s instead,
+//! expanding the following placeholders:
+//!
+//! - `{{seq}}` a sequence number, unique across every worker's producer.
+//! - `{{uuid}}` a random version 4 UUID.
+//! - `{{rand_int(min,max)}}` a random integer between `min` and `max` inclusive.
+//!
+//! The sequence number and UUID are drawn once per request, so they can be used
+//! in several parts of the same request, i.e. both the path and the body.
+//! Random integers are drawn for every placeholder. A literal `{{` is written
+//! as `\{{`.
+//!
+//! ```
+//! use http::Method;
+//! use rewrk_core::template::PlaceholderProducer;
+//!
+//! let producer = PlaceholderProducer::new(Method::PUT, "/users/{{uuid}}")
+//!     .unwrap()
+//!     .header("x-request-id", "req-{{seq}}")
+//!     .unwrap()
+//!     .body(r#"{"id": "{{uuid}}", "age": {{rand_int(18,99)}}}"#)
+//!     .unwrap();
+//! ```
+
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use http::header::HeaderName;
+use http::{Method, Request};
+use hyper::Body;
+use rand::Rng;
+
+use crate::producer::{Batch, Producer, RequestBatch};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+/// The placeholder string could not be parsed.
+pub enum PlaceholderError {
+    #[error("The placeholder starting at byte {0} is missing the closing '}}}}'")]
+    /// A placeholder is missing its closing braces.
+    Unclosed(usize),
+    #[error("Unknown placeholder {0:?}")]
+    /// The placeholder is not one of the supported placeholders.
+    UnknownPlaceholder(String),
+    #[error("The placeholder {0:?} has invalid arguments")]
+    /// The arguments of the placeholder are missing or invalid.
+    InvalidArguments(String),
+    #[error("Invalid header name {0:?}")]
+    /// The header name is not a valid HTTP header name.
+    InvalidHeaderName(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A value substituted into a placeholder string.
+enum Placeholder {
+    /// The request's sequence number.
+    Seq,
+    /// The request's UUID.
+    Uuid,
+    /// A random integer within an inclusive range.
+    RandInt(i64, i64),
+}
+
+impl FromStr for Placeholder {
+    type Err = PlaceholderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let placeholder = s.trim();
+        match placeholder {
+            "seq" => return Ok(Self::Seq),
+            "uuid" => return Ok(Self::Uuid),
+            _ => {},
+        }
+
+        let args = match placeholder
+            .strip_prefix("rand_int(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            Some(args) => args,
+            None => return Err(PlaceholderError::UnknownPlaceholder(s.to_string())),
+        };
+
+        let invalid = || PlaceholderError::InvalidArguments(s.to_string());
+        let (min, max) = args.split_once(',').ok_or_else(invalid)?;
+        let min = min.trim().parse::<i64>().map_err(|_| invalid())?;
+        let max = max.trim().parse::<i64>().map_err(|_| invalid())?;
+        if min > max {
+            return Err(invalid());
+        }
+
+        Ok(Self::RandInt(min, max))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A string containing `{{placeholder}}`s which are substituted when rendered.
+///
+/// See the [module docs](self) for the supported placeholders.
+pub struct PlaceholderString {
+    segments: Vec<Segment>,
+}
+
+impl PlaceholderString {
+    /// Parses a placeholder string.
+    pub fn parse(s: &str) -> Result<Self, PlaceholderError> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find("{{") {
+            let inner = &rest[start + 2..];
+            if let Some(literal) = rest[..start].strip_suffix('\\') {
+                push_literal(&mut segments, literal);
+                push_literal(&mut segments, "{{");
+                rest = inner;
+                continue;
+            }
+
+            push_literal(&mut segments, &rest[..start]);
+            let offset = s.len() - rest.len() + start;
+            let end = inner.find("}}").ok_or(PlaceholderError::Unclosed(offset))?;
+            segments.push(Segment::Placeholder(inner[..end].parse()?));
+            rest = &inner[end + 2..];
+        }
+        push_literal(&mut segments, rest);
+
+        Ok(Self { segments })
+    }
+
+    /// Returns if the string has no placeholders.
+    pub fn is_static(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Literal(_)))
+    }
+
+    /// Renders the string using the values of a single request.
+    pub fn render(&self, vars: &RequestVars) -> String {
+        let mut rendered = String::new();
+        for segment in self.segments.iter() {
+            match segment {
+                Segment::Literal(literal) => rendered.push_str(literal),
+                Segment::Placeholder(Placeholder::Seq) => {
+                    let _ = write!(rendered, "{}", vars.seq);
+                },
+                Segment::Placeholder(Placeholder::Uuid) => rendered.push_str(&vars.uuid),
+                Segment::Placeholder(Placeholder::RandInt(min, max)) => {
+                    let value = rand::thread_rng().gen_range(*min..=*max);
+                    let _ = write!(rendered, "{value}");
+                },
+            }
+        }
+        rendered
+    }
+}
+
+impl FromStr for PlaceholderString {
+    type Err = PlaceholderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Appends the literal to the segments, merging it with the previous literal.
+fn push_literal(segments: &mut Vec<Segment>, literal: &str) {
+    if literal.is_empty() {
+        return;
+    }
+
+    match segments.last_mut() {
+        Some(Segment::Literal(previous)) => previous.push_str(literal),
+        _ => segments.push(Segment::Literal(literal.to_string())),
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The values substituted into the placeholder strings of a single request.
+pub struct RequestVars {
+    seq: u64,
+    uuid: String,
+}
+
+impl RequestVars {
+    /// Creates the values for a request with the given sequence number
+    /// and a new random UUID.
+    pub fn new(seq: u64) -> Self {
+        Self {
+            seq,
+            uuid: uuid_v4(),
+        }
+    }
+
+    /// The sequence number of the request.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The UUID of the request.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+}
+
+/// Generates a random version 4 UUID.
+fn uuid_v4() -> String {
+    let mut bytes = rand::thread_rng().gen::<[u8; 16]>();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let mut uuid = String::with_capacity(36);
+    for (i, byte) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+        let _ = write!(uuid, "{byte:02x}");
+    }
+    uuid
+}
+
+#[derive(Clone)]
+/// A producer which renders every request from placeholder strings.
+///
+/// By default batches contain a single request and the producer never ends,
+/// so the benchmark should be run with
+/// [ReWrkBenchmark::run_for](crate::ReWrkBenchmark::run_for) unless a limit
+/// is set with [PlaceholderProducer::max_requests].
+pub struct PlaceholderProducer {
+    method: Method,
+    path: PlaceholderString,
+    headers: Vec<(HeaderName, PlaceholderString)>,
+    body: Option<PlaceholderString>,
+    tag: usize,
+    batch_size: usize,
+    max_requests: Option<u64>,
+    /// The next sequence number, shared by every worker's producer.
+    seq: Arc<AtomicU64>,
+}
+
+impl PlaceholderProducer {
+    /// Creates a new producer sending requests to the given path.
+    ///
+    /// The path may include a query string.
+    pub fn new(method: Method, path: &str) -> Result<Self, PlaceholderError> {
+        Ok(Self {
+            method,
+            path: PlaceholderString::parse(path)?,
+            headers: Vec::new(),
+            body: None,
+            tag: 0,
+            batch_size: 1,
+            max_requests: None,
+            seq: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Adds a header with the given value to every request.
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self, PlaceholderError> {
+        let name = HeaderName::from_str(name)
+            .map_err(|_| PlaceholderError::InvalidHeaderName(name.to_string()))?;
+        self.headers.push((name, PlaceholderString::parse(value)?));
+        Ok(self)
+    }
+
+    /// Sets the body of every request.
+    pub fn body(mut self, body: &str) -> Result<Self, PlaceholderError> {
+        self.body = Some(PlaceholderString::parse(body)?);
+        Ok(self)
+    }
+
+    /// Sets the tag of the produced batches.
+    pub fn tag(mut self, tag: usize) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Sets the number of requests in each batch.
+    ///
+    /// This is clamped to at least `1`.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Ends the benchmark once the given number of requests have been
+    /// produced across all workers.
+    ///
+    /// The limit covers the whole benchmark rather than each round.
+    pub fn max_requests(mut self, max_requests: u64) -> Self {
+        self.max_requests = Some(max_requests);
+        self
+    }
+
+    /// Renders a single request.
+    fn render(&self, seq: u64) -> anyhow::Result<Request<Body>> {
+        let vars = RequestVars::new(seq);
+
+        let mut builder = Request::builder()
+            .method(self.method.clone())
+            .uri(self.path.render(&vars));
+        for (name, value) in self.headers.iter() {
+            builder = builder.header(name, value.render(&vars));
+        }
+
+        let body = match self.body.as_ref() {
+            None => Body::empty(),
+            Some(body) => Body::from(body.render(&vars)),
+        };
+        Ok(builder.body(body)?)
+    }
+}
+
+#[async_trait::async_trait]
+impl Producer for PlaceholderProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        let mut requests = Vec::with_capacity(self.batch_size);
+        for _ in 0..self.batch_size {
+            let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+            if self.max_requests.is_some_and(|max| seq >= max) {
+                break;
+            }
+            requests.push(self.render(seq)?);
+        }
+
+        if requests.is_empty() {
+            return Ok(RequestBatch::End);
+        }
+
+        Ok(RequestBatch::Batch(Batch {
+            tag: self.tag,
+            requests,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_placeholders() {
+        let placeholders =
+            PlaceholderString::parse("/users/{{ uuid }}?n={{rand_int(1, 3)}}")
+                .expect("Parse placeholders");
+        assert!(!placeholders.is_static());
+        assert_eq!(
+            placeholders.segments,
+            vec![
+                Segment::Literal("/users/".to_string()),
+                Segment::Placeholder(Placeholder::Uuid),
+                Segment::Literal("?n=".to_string()),
+                Segment::Placeholder(Placeholder::RandInt(1, 3)),
+            ],
+        );
+
+        assert!(PlaceholderString::parse("/static").unwrap().is_static());
+        assert_eq!(
+            PlaceholderString::parse("/{{seq"),
+            Err(PlaceholderError::Unclosed(1))
+        );
+        assert_eq!(
+            PlaceholderString::parse("{{id}}"),
+            Err(PlaceholderError::UnknownPlaceholder("id".to_string())),
+        );
+        assert_eq!(
+            PlaceholderString::parse("{{rand_int(5,1)}}"),
+            Err(PlaceholderError::InvalidArguments(
+                "rand_int(5,1)".to_string()
+            )),
+        );
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let placeholders =
+            PlaceholderString::parse("{{seq}}/{{uuid}}/{{uuid}}/{{rand_int(-2,2)}}")
+                .expect("Parse placeholders");
+        let vars = RequestVars::new(7);
+        let rendered = placeholders.render(&vars);
+
+        let parts = rendered.split('/').collect::<Vec<_>>();
+        assert_eq!(parts[0], "7");
+        // The UUID is the same throughout a request.
+        assert_eq!(parts[1], vars.uuid());
+        assert_eq!(parts[2], vars.uuid());
+        let value = parts[3].parse::<i64>().expect("Parse integer");
+        assert!((-2..=2).contains(&value));
+
+        let uuid = vars.uuid();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert_ne!(uuid, RequestVars::new(7).uuid());
+    }
+
+    #[test]
+    fn test_escaped_placeholder() {
+        let placeholders =
+            PlaceholderString::parse(r#"{"a": "\{{seq}}", "b": {{seq}}}"#)
+                .expect("Parse placeholders");
+        assert_eq!(
+            placeholders.render(&RequestVars::new(3)),
+            r#"{"a": "{{seq}}", "b": 3}"#,
+        );
+
+        // An escaped opening brace doesn't need to be closed.
+        let placeholders = PlaceholderString::parse(r"\{{").expect("Parse placeholders");
+        assert!(placeholders.is_static());
+        assert_eq!(placeholders.render(&RequestVars::new(0)), "{{");
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use crate::{Sample, ValidationErrorKind};
+
+/// Start a set of assertions on a sample.
+///
+/// Each assertion panics with a description of the sample if it fails.
+pub fn assert_sample(sample: &Sample) -> SampleAssertions<'_> {
+    SampleAssertions { sample }
+}
+
+/// Chainable assertions on a [Sample], see [assert_sample].
+pub struct SampleAssertions<'a> {
+    sample: &'a Sample,
+}
+
+impl<'a> SampleAssertions<'a> {
+    #[track_caller]
+    /// Assert the sample has the given tag.
+    pub fn tag(self, tag: usize) -> Self {
+        assert_eq!(self.sample.tag(), tag, "Unexpected sample tag");
+        self
+    }
+
+    #[track_caller]
+    /// Assert the total number of requests sent in the sample.
+    pub fn total_requests(self, n: u64) -> Self {
+        assert_eq!(
+            self.sample.total_requests(),
+            n,
+            "Unexpected total requests for sample: {:?}",
+            self.sample,
+        );
+        self
+    }
+
+    #[track_caller]
+    /// Assert the number of successful requests in the sample.
+    pub fn successful_requests(self, n: u64) -> Self {
+        assert_eq!(
+            self.sample.successful_requests(),
+            n,
+            "Unexpected successful requests for sample: {:?}",
+            self.sample,
+        );
+        self
+    }
+
+    #[track_caller]
+    /// Assert no errors were recorded in the sample.
+    pub fn no_errors(self) -> Self {
+        assert!(
+            self.sample.error_counts().is_empty(),
+            "Expected no errors but got {:?}, examples: {:?}",
+            self.sample.error_counts(),
+            self.sample.error_exemplars(),
+        );
+        self
+    }
+
+    #[track_caller]
+    /// Assert the number of errors of the given kind in the sample.
+    pub fn errors(self, kind: ValidationErrorKind, n: u64) -> Self {
+        let count = self
+            .sample
+            .error_counts()
+            .get(&kind)
+            .copied()
+            .unwrap_or_default();
+        assert_eq!(
+            count,
+            n,
+            "Unexpected number of {kind:?} errors, all errors: {:?}",
+            self.sample.error_counts(),
+        );
+        self
+    }
+
+    #[track_caller]
+    /// Assert every recorded request latency is at least the given duration.
+    pub fn min_latency_at_least(self, dur: Duration) -> Self {
+        let min = Duration::from_micros(self.sample.latency().min());
+        assert!(
+            !self.sample.latency().is_empty() && min >= dur,
+            "Expected a minimum latency of at least {dur:?} but got {min:?}",
+        );
+        self
+    }
+
+    #[track_caller]
+    /// Assert every recorded request latency is below the given duration.
+    pub fn max_latency_below(self, dur: Duration) -> Self {
+        let max = Duration::from_micros(self.sample.latency().max());
+        assert!(
+            max < dur,
+            "Expected a maximum latency below {dur:?} but got {max:?}",
+        );
+        self
+    }
+}
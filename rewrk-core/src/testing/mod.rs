@@ -0,0 +1,26 @@
+//! Utilities for testing producers, validators and collectors.
+//!
+//! This provides small HTTP servers which can be used as a benchmark
+//! target and assertion helpers for checking the recorded samples.
+//! This is enabled with the `testing` feature, usually as a dev-dependency.
+//!
+//! ```no_run
+//! use rewrk_core::testing::{assert_sample, TestServer};
+//! # use rewrk_core::Sample;
+//!
+//! # async fn run(sample: Sample) -> std::io::Result<()> {
+//! let server = TestServer::echo().await?;
+//! let uri = server.uri();
+//!
+//! // Run a benchmark against `uri`...
+//!
+//! assert_sample(&sample).no_errors().successful_requests(1);
+//! # Ok(())
+//! # }
+//! ```
+
+mod assertions;
+mod server;
+
+pub use self::assertions::{assert_sample, SampleAssertions};
+pub use self::server::{ScriptedResponse, TestServer};
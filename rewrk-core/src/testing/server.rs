@@ -0,0 +1,174 @@
+use std::convert::Infallible;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{Request, Response, StatusCode, Uri};
+use hyper::body::Bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone)]
+/// A response returned by a scripted [TestServer].
+pub struct ScriptedResponse {
+    /// The time to wait before responding.
+    pub delay: Duration,
+    /// The status of the response.
+    pub status: StatusCode,
+    /// The body of the response.
+    pub body: Bytes,
+}
+
+impl ScriptedResponse {
+    /// Create a new response with the given status and no delay or body.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            delay: Duration::ZERO,
+            status,
+            body: Bytes::new(),
+        }
+    }
+
+    /// Set the time to wait before responding.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set the body of the response.
+    pub fn with_body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+#[derive(Clone)]
+enum Behaviour {
+    /// Respond with the request body.
+    Echo,
+    /// Respond with each scripted response in turn, repeating
+    /// from the start once the end is reached.
+    Scripted(Arc<[ScriptedResponse]>),
+}
+
+/// A HTTP/1 and HTTP/2 server which can be used as a benchmark target in tests.
+///
+/// The server listens on a random local port and is shutdown when dropped.
+pub struct TestServer {
+    addr: SocketAddr,
+    requests: Arc<AtomicUsize>,
+    handle: JoinHandle<()>,
+}
+
+impl TestServer {
+    /// Start a server which responds to every request with its body.
+    pub async fn echo() -> io::Result<Self> {
+        Self::start(Behaviour::Echo).await
+    }
+
+    /// Start a server which responds to every request with the same
+    /// status and body.
+    pub async fn fixed(status: StatusCode, body: impl Into<Bytes>) -> io::Result<Self> {
+        Self::scripted(vec![ScriptedResponse::new(status).with_body(body)]).await
+    }
+
+    /// Start a server which responds with each of the scripted responses
+    /// in turn, repeating the script once the end is reached.
+    ///
+    /// The script is shared between all connections.
+    ///
+    /// # Panics
+    ///
+    /// If the script is empty.
+    pub async fn scripted(script: Vec<ScriptedResponse>) -> io::Result<Self> {
+        assert!(!script.is_empty(), "The server script must not be empty");
+        Self::start(Behaviour::Scripted(script.into())).await
+    }
+
+    async fn start(behaviour: Behaviour) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let listener = listener.into_std()?;
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        let make_service = make_service_fn(move |_| {
+            let behaviour = behaviour.clone();
+            let counter = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let index = counter.fetch_add(1, Ordering::Relaxed);
+                    respond(behaviour.clone(), index, request)
+                }))
+            }
+        });
+
+        let server = Server::from_tcp(listener)
+            .map_err(|e| io::Error::other(e.to_string()))?
+            .serve(make_service);
+        let handle = tokio::spawn(async move {
+            if let Err(e) = server.await {
+                warn!(error = ?e, "Test server exited with error.");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            requests,
+            handle,
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The `http` URI of the server root.
+    pub fn uri(&self) -> Uri {
+        Uri::builder()
+            .scheme("http")
+            .authority(self.addr.to_string())
+            .path_and_query("/")
+            .build()
+            .expect("Build URI")
+    }
+
+    /// The number of requests the server has received.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn respond(
+    behaviour: Behaviour,
+    index: usize,
+    request: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    match behaviour {
+        Behaviour::Echo => {
+            let body = hyper::body::to_bytes(request.into_body()).await?;
+            Ok(Response::new(Body::from(body)))
+        },
+        Behaviour::Scripted(script) => {
+            let scripted = &script[index % script.len()];
+            if !scripted.delay.is_zero() {
+                tokio::time::sleep(scripted.delay).await;
+            }
+
+            let mut response = Response::new(Body::from(scripted.body.clone()));
+            *response.status_mut() = scripted.status;
+            Ok(response)
+        },
+    }
+}
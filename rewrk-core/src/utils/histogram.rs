@@ -0,0 +1,74 @@
+//! Serializes histograms in the compact HdrHistogram V2 format.
+
+use std::collections::BTreeMap;
+
+use hdrhistogram::serialization::{
+    Deserializer as V2Deserializer,
+    Serializer as _,
+    V2Serializer,
+};
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+fn encode<E: serde::ser::Error>(hist: &Histogram<u32>) -> Result<Vec<u8>, E> {
+    let mut buffer = Vec::new();
+    V2Serializer::new()
+        .serialize(hist, &mut buffer)
+        .map_err(|e| E::custom(format!("{e:?}")))?;
+    Ok(buffer)
+}
+
+fn decode<E: serde::de::Error>(buffer: &[u8]) -> Result<Histogram<u32>, E> {
+    V2Deserializer::new()
+        .deserialize(&mut &buffer[..])
+        .map_err(|e| E::custom(format!("{e:?}")))
+}
+
+pub fn serialize<S>(hist: &Histogram<u32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_bytes(&encode::<S::Error>(hist)?)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Histogram<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let buffer = Vec::<u8>::deserialize(deserializer)?;
+    decode(&buffer)
+}
+
+/// Serializes maps of histograms.
+pub mod map {
+    use super::*;
+
+    pub fn serialize<K, S>(
+        hists: &BTreeMap<K, Histogram<u32>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        S: Serializer,
+    {
+        let mut encoded = Vec::with_capacity(hists.len());
+        for (key, hist) in hists {
+            encoded.push((key, encode::<S::Error>(hist)?));
+        }
+        encoded.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, K, D>(
+        deserializer: D,
+    ) -> Result<BTreeMap<K, Histogram<u32>>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        D: Deserializer<'de>,
+    {
+        let encoded = Vec::<(K, Vec<u8>)>::deserialize(deserializer)?;
+        encoded
+            .into_iter()
+            .map(|(key, buffer)| Ok((key, decode(&buffer)?)))
+            .collect()
+    }
+}
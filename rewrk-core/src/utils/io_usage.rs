@@ -1,4 +1,4 @@
-use std::io;
+use std::io::{self, IoSlice};
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -7,37 +7,100 @@ use std::task::{Context, Poll};
 use pin_project_lite::pin_project;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 /// A utility for wrapping streams and measuring the number of
 /// bytes being passed through the wrapped stream.
-pub(crate) struct IoUsageTracker {
-    received: Arc<AtomicU64>,
-    written: Arc<AtomicU64>,
+///
+/// The bytes read and written are counted separately, the counters are
+/// shared by every clone of the tracker and every stream it wraps.
+/// Counting writes can be disabled when only the bytes read are needed.
+///
+/// Each tracker's counters sit on their own cache line, so trackers for
+/// different connections never contend with each other. A tracker should
+/// be created for each connection rather than shared between them.
+///
+/// ```
+/// use rewrk_core::IoUsageTracker;
+/// use tokio::io::AsyncWriteExt;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> std::io::Result<()> {
+/// let tracker = IoUsageTracker::new();
+/// let mut stream = tracker.wrap_stream(Vec::new());
+/// stream.write_all(b"Hello, World!").await?;
+///
+/// assert_eq!(tracker.get_written_count(), 13);
+/// # Ok(())
+/// # }
+/// ```
+pub struct IoUsageTracker {
+    counters: Arc<Counters>,
+    count_writes: bool,
+}
+
+impl Default for IoUsageTracker {
+    fn default() -> Self {
+        Self {
+            counters: Arc::default(),
+            count_writes: true,
+        }
+    }
+}
+
+#[derive(Default)]
+#[repr(align(64))]
+/// The counters of a tracker, aligned to a cache line
+/// to avoid false sharing with other connections.
+struct Counters {
+    received: AtomicU64,
+    written: AtomicU64,
 }
 
 impl IoUsageTracker {
     /// Create a new usage tracker.
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self::default()
     }
 
+    /// Disable counting the bytes written to wrapped streams.
+    pub fn without_write_counting(mut self) -> Self {
+        self.count_writes = false;
+        self
+    }
+
     /// Wrap an existing stream with the usage tracker.
-    pub(crate) fn wrap_stream<I>(&self, stream: I) -> RecordStream<I> {
+    pub fn wrap_stream<I>(&self, stream: I) -> RecordStream<I> {
         RecordStream::new(stream, self.clone())
     }
 
     /// Get the current received usage count.
-    pub(crate) fn get_received_count(&self) -> u64 {
-        self.received.load(Ordering::SeqCst)
+    pub fn get_received_count(&self) -> u64 {
+        self.counters.received.load(Ordering::Relaxed)
     }
+
     /// Get the current written usage count.
-    pub(crate) fn get_written_count(&self) -> u64 {
-        self.written.load(Ordering::SeqCst)
+    ///
+    /// This is always `0` if write counting is disabled.
+    pub fn get_written_count(&self) -> u64 {
+        self.counters.written.load(Ordering::Relaxed)
+    }
+
+    fn record_received(&self, n: usize) {
+        self.counters
+            .received
+            .fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    fn record_written(&self, n: usize) {
+        if self.count_writes {
+            self.counters.written.fetch_add(n as u64, Ordering::Relaxed);
+        }
     }
 }
 
 pin_project! {
-    pub(crate) struct RecordStream<I> {
+    /// A stream counting the bytes passed through it with an [IoUsageTracker].
+    pub struct RecordStream<I> {
         #[pin]
         inner: I,
         usage: IoUsageTracker,
@@ -57,11 +120,10 @@ impl<I: AsyncRead> AsyncRead for RecordStream<I> {
         buf: &mut ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
         let this = self.project();
+        let filled = buf.filled().len();
         let poll_result = this.inner.poll_read(cx, buf);
 
-        this.usage
-            .received
-            .fetch_add(buf.filled().len() as u64, Ordering::SeqCst);
+        this.usage.record_received(buf.filled().len() - filled);
 
         poll_result
     }
@@ -73,10 +135,33 @@ impl<I: AsyncWrite> AsyncWrite for RecordStream<I> {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        self.usage
-            .written
-            .fetch_add(buf.len() as u64, Ordering::SeqCst);
-        self.project().inner.poll_write(cx, buf)
+        let this = self.project();
+        let poll_result = this.inner.poll_write(cx, buf);
+
+        if let Poll::Ready(Ok(n)) = poll_result {
+            this.usage.record_written(n);
+        }
+
+        poll_result
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let poll_result = this.inner.poll_write_vectored(cx, bufs);
+
+        if let Poll::Ready(Ok(n)) = poll_result {
+            this.usage.record_written(n);
+        }
+
+        poll_result
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
@@ -0,0 +1,76 @@
+//! Serializes durations as a whole number of microseconds.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S>(dur: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u64(dur.as_micros() as u64)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_micros)
+}
+
+/// Serializes optional durations as a whole number of microseconds.
+pub mod option {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(dur: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match dur {
+            Some(dur) => serializer.serialize_some(&(dur.as_micros() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<u64>::deserialize(deserializer).map(|v| v.map(Duration::from_micros))
+    }
+}
+
+/// Serializes maps of durations as a whole number of microseconds.
+pub mod map {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, K>(
+        map: &BTreeMap<K, Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Serialize,
+    {
+        serializer.collect_map(map.iter().map(|(k, dur)| (k, dur.as_micros() as u64)))
+    }
+
+    pub fn deserialize<'de, D, K>(
+        deserializer: D,
+    ) -> Result<BTreeMap<K, Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de> + Ord,
+    {
+        let map = BTreeMap::<K, u64>::deserialize(deserializer)?;
+        Ok(map
+            .into_iter()
+            .map(|(k, v)| (k, Duration::from_micros(v)))
+            .collect())
+    }
+}
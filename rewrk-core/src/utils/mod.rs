@@ -1,5 +1,9 @@
+pub(crate) mod histogram;
 mod io_usage;
+pub(crate) mod micros;
+mod rate_limiter;
 mod timings;
 
-pub(crate) use io_usage::IoUsageTracker;
-pub(crate) use timings::RuntimeTimings;
+pub use io_usage::{IoUsageTracker, RecordStream};
+pub use rate_limiter::RateLimiter;
+pub(crate) use timings::{RuntimeTimings, WallClock};
@@ -0,0 +1,225 @@
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Marks a limiter whose next slot starts at the next reservation.
+const UNSCHEDULED: u64 = u64::MAX;
+
+#[derive(Clone)]
+/// A rate limiter which spaces out events evenly across all clones
+/// of the limiter.
+///
+/// Slots are reserved without locking, the next free slot is kept as an
+/// offset in nanoseconds from the point in time the limiter was created.
+/// The rate can be changed while the limiter is in use, applying to every
+/// clone from their next reservation.
+pub struct RateLimiter {
+    /// The events allowed per second as the bits of a `f64`, `0` if unlimited.
+    rate: Arc<AtomicU64>,
+    origin: Instant,
+    next_slot: Arc<AtomicU64>,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter allowing `n` events per second.
+    pub fn per_second(n: NonZeroU32) -> Self {
+        let limiter = Self::unlimited();
+        limiter.set_rate(n.get() as f64);
+        limiter
+    }
+
+    /// Create a new rate limiter which allows every event immediately until
+    /// a rate is [set](RateLimiter::set_rate).
+    pub fn unlimited() -> Self {
+        Self {
+            rate: Arc::new(AtomicU64::new(0f64.to_bits())),
+            origin: Instant::now(),
+            next_slot: Arc::new(AtomicU64::new(UNSCHEDULED)),
+        }
+    }
+
+    /// The number of events allowed per second, `0` if unlimited.
+    pub fn rate(&self) -> f64 {
+        f64::from_bits(self.rate.load(Ordering::Relaxed))
+    }
+
+    /// Sets the number of events allowed per second, `0` removes the limit.
+    ///
+    /// The schedule [restarts](RateLimiter::restart) at the next reservation.
+    pub fn set_rate(&self, rate: f64) {
+        let rate = if rate > 0.0 { rate } else { 0.0 };
+        self.rate.store(rate.to_bits(), Ordering::Relaxed);
+        self.restart();
+    }
+
+    /// Waits until the next event is allowed to occur.
+    pub async fn acquire(&self) {
+        wait_until(self.reserve()).await;
+    }
+
+    /// Waits until the next slot of a fixed schedule, returning the point in
+    /// time the event was scheduled for.
+    ///
+    /// See [RateLimiter::reserve_scheduled], a missed slot is returned
+    /// without waiting.
+    pub async fn acquire_scheduled(&self) -> Instant {
+        let at = self.reserve_scheduled();
+        wait_until(at).await;
+        at
+    }
+
+    /// Reserves the next slot, returning the point in time the event may occur.
+    ///
+    /// Slots which passed without being reserved are skipped, so idle time
+    /// isn't saved up to allow a burst of events later.
+    pub fn reserve(&self) -> Instant {
+        let now = self.offset(Instant::now());
+        self.reserve_with(now, |next| {
+            if next == UNSCHEDULED {
+                now
+            } else {
+                next.max(now)
+            }
+        })
+    }
+
+    /// Reserves the next slot of a fixed schedule, returning the point in time
+    /// the event should occur.
+    ///
+    /// Unlike [RateLimiter::reserve], slots which passed without being reserved
+    /// are not skipped, so the returned slot may already be in the past. The
+    /// schedule starts at the first reservation after the limiter is created
+    /// or [restarted](RateLimiter::restart).
+    pub fn reserve_scheduled(&self) -> Instant {
+        let now = self.offset(Instant::now());
+        self.reserve_with(now, |next| if next == UNSCHEDULED { now } else { next })
+    }
+
+    /// Restarts the schedule of [RateLimiter::reserve_scheduled] at the
+    /// next reservation.
+    pub fn restart(&self) {
+        self.next_slot.store(UNSCHEDULED, Ordering::Relaxed);
+    }
+
+    fn reserve_with(&self, now: u64, slot: impl Fn(u64) -> u64) -> Instant {
+        let rate = self.rate();
+        if rate == 0.0 {
+            return self.origin + Duration::from_nanos(now);
+        }
+
+        let interval = ((1_000_000_000.0 / rate) as u64).max(1);
+        let previous = self
+            .next_slot
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| {
+                Some(slot(next) + interval)
+            })
+            .expect("Update always succeeds");
+        self.origin + Duration::from_nanos(slot(previous))
+    }
+
+    fn offset(&self, at: Instant) -> u64 {
+        at.saturating_duration_since(self.origin).as_nanos() as u64
+    }
+}
+
+/// Sleeps until the given point in time, without registering a timer if it
+/// has already passed.
+async fn wait_until(at: Instant) {
+    if at > Instant::now() {
+        tokio::time::sleep_until(at).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_spacing() {
+        let limiter = RateLimiter::per_second(NonZeroU32::new(10).unwrap());
+        assert_eq!(limiter.rate(), 10.0);
+
+        // Clones share the same slots.
+        let other = limiter.clone();
+        let first = limiter.reserve();
+        let second = other.reserve();
+        let third = limiter.reserve();
+        assert_eq!(second - first, Duration::from_millis(100));
+        assert_eq!(third - second, Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits() {
+        let limiter = RateLimiter::per_second(NonZeroU32::new(20).unwrap());
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        // The first event is allowed immediately.
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(400), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_reserve_skips_idle_time() {
+        let limiter = RateLimiter::per_second(NonZeroU32::new(20).unwrap());
+        limiter.reserve();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let now = Instant::now();
+        let first = limiter.reserve();
+        let second = limiter.reserve();
+        assert!(first >= now, "{:?}", now - first);
+        assert_eq!(second - first, Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_reserve_scheduled_keeps_missed_slots() {
+        let limiter = RateLimiter::per_second(NonZeroU32::new(20).unwrap());
+        let start = limiter.reserve_scheduled();
+
+        // Slots missed while stalled are still handed out, late.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let now = Instant::now();
+        let late = limiter.reserve_scheduled();
+        assert_eq!(late - start, Duration::from_millis(50));
+        assert!(late < now);
+
+        // Restarting begins a new schedule from the next reservation.
+        limiter.restart();
+        let now = Instant::now();
+        let first = limiter.reserve_scheduled();
+        let second = limiter.reserve_scheduled();
+        assert!(first >= now);
+        assert_eq!(second - first, Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_set_rate() {
+        let limiter = RateLimiter::unlimited();
+        let start = Instant::now();
+        for _ in 0..1_000 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        // Changing the rate applies to every clone and restarts the schedule.
+        let other = limiter.clone();
+        limiter.set_rate(20.0);
+        let now = Instant::now();
+        let first = other.reserve_scheduled();
+        let second = limiter.reserve_scheduled();
+        assert!(first >= now);
+        assert_eq!(second - first, Duration::from_millis(50));
+
+        // Removing the limit applies to the next reservation.
+        limiter.set_rate(0.0);
+        let start = Instant::now();
+        limiter.acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(10));
+    }
+}
@@ -1,5 +1,5 @@
 use std::ops::{Add, AddAssign};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug, Default)]
 pub struct RuntimeTimings {
@@ -37,3 +37,27 @@ impl FromIterator<Self> for RuntimeTimings {
         total
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+/// Derives wall clock times from monotonic instants.
+///
+/// The system clock is read once when created, rather than for every request.
+pub struct WallClock {
+    anchor: SystemTime,
+    start: Instant,
+}
+
+impl WallClock {
+    /// Anchors the clock at the current time.
+    pub fn new() -> Self {
+        Self {
+            anchor: SystemTime::now(),
+            start: Instant::now(),
+        }
+    }
+
+    /// The wall clock time of the given instant.
+    pub fn at(&self, instant: Instant) -> SystemTime {
+        self.anchor + instant.saturating_duration_since(self.start)
+    }
+}
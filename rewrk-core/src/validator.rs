@@ -1,11 +1,19 @@
 use std::borrow::Cow;
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 
+use http::header::{self, HeaderMap};
 use http::response::Parts;
 use hyper::body::Bytes;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, thiserror::Error, Clone)]
+#[derive(Debug, thiserror::Error, Clone, Serialize, Deserialize)]
 /// The provided request is invalid and should not be counted.
+///
+/// Archived samples are encoded with the variant's position, so new
+/// variants must be added at the end.
+#[non_exhaustive]
 pub enum ValidationError {
     #[error("The returned status code is not valid: {0}")]
     /// The returned status code is not valid
@@ -28,6 +36,51 @@ pub enum ValidationError {
     #[error("A validation error rejected the request: {0}")]
     /// A validation error rejected the request
     Other(Cow<'static, str>),
+    #[error("The response took {0:?} which exceeds the latency SLO")]
+    /// The response was otherwise valid but slower than the latency SLO
+    SloViolation(Duration),
+}
+
+impl ValidationError {
+    /// The kind of validation error.
+    pub fn kind(&self) -> ValidationErrorKind {
+        match self {
+            Self::InvalidStatus(_) => ValidationErrorKind::InvalidStatus,
+            Self::InvalidBody(_) => ValidationErrorKind::InvalidBody,
+            Self::MissingHeader(_) => ValidationErrorKind::MissingHeader,
+            Self::InvalidHeader(_) => ValidationErrorKind::InvalidHeader,
+            Self::ConnectionAborted => ValidationErrorKind::ConnectionAborted,
+            Self::Timeout => ValidationErrorKind::Timeout,
+            Self::Other(_) => ValidationErrorKind::Other,
+            Self::SloViolation(_) => ValidationErrorKind::SloViolation,
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+/// The kind of a [ValidationError] without any of the associated details.
+///
+/// Like [ValidationError], new kinds must be added at the end.
+#[non_exhaustive]
+pub enum ValidationErrorKind {
+    /// The returned status code is not valid
+    InvalidStatus,
+    /// The body of the request does not match the expected structure
+    InvalidBody,
+    /// The request is missing a required header
+    MissingHeader,
+    /// The request contained a header, but it was invalid
+    InvalidHeader,
+    /// The connection was aborted by the remote server
+    ConnectionAborted,
+    /// The connection took to long to respond
+    Timeout,
+    /// A validation error rejected the request
+    Other,
+    /// The response was otherwise valid but slower than the latency SLO
+    SloViolation,
 }
 
 /// A validating utility for checking responses returned by the webserver are correct.
@@ -61,8 +114,174 @@ pub enum ValidationError {
 /// ```
 pub trait ResponseValidator: Send + Sync + 'static {
     fn validate(&self, head: Parts, body: Bytes) -> Result<(), ValidationError>;
+
+    /// Classify a response into a labeled bucket.
+    ///
+    /// Responses which pass validation and have a classification have their
+    /// latency recorded in a separate histogram for the label, in addition to
+    /// the sample's main latency histogram. This allows, for example, cache hits
+    /// and misses to be compared within the same benchmark.
+    ///
+    /// This is called before [ResponseValidator::validate] and by default
+    /// no responses are classified.
+    ///
+    /// ```
+    /// use http::response::Parts;
+    /// use hyper::body::Bytes;
+    /// use rewrk_core::{Classification, ResponseValidator, ValidationError};
+    ///
+    /// #[derive(Debug)]
+    /// pub struct CacheValidator;
+    ///
+    /// impl ResponseValidator for CacheValidator {
+    ///     fn validate(&self, head: Parts, _body: Bytes) -> Result<(), ValidationError> {
+    ///         if head.status.is_success() {
+    ///             Ok(())
+    ///         } else {
+    ///             Err(ValidationError::InvalidStatus(head.status.as_u16()))
+    ///         }
+    ///     }
+    ///
+    ///     fn classify(&self, head: &Parts, _body: &Bytes) -> Option<Classification> {
+    ///         match head.headers.get("x-cache")?.as_bytes() {
+    ///             b"HIT" => Some(Classification::from("cache_hit")),
+    ///             _ => Some(Classification::from("cache_miss")),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn classify(&self, _head: &Parts, _body: &Bytes) -> Option<Classification> {
+        None
+    }
+
+    /// How the response body is read before it's validated.
+    ///
+    /// By default the whole body is buffered in memory and passed to
+    /// [ResponseValidator::validate], which isn't viable for endpoints serving
+    /// large downloads. With [BodyMode::Streaming] or [BodyMode::Discard] the
+    /// body is read chunk by chunk instead, and [ResponseValidator::validate]
+    /// and [ResponseValidator::classify] are passed an empty body.
+    ///
+    /// The bytes transferred are recorded in every mode.
+    fn body_mode(&self) -> BodyMode {
+        BodyMode::Buffered
+    }
+
+    /// Validate a chunk of a response body as it's received.
+    ///
+    /// This is only called with [BodyMode::Streaming]. Once a chunk is
+    /// rejected the rest of the body is discarded and the response is
+    /// counted as an error without calling [ResponseValidator::validate].
+    ///
+    /// ```
+    /// use http::response::Parts;
+    /// use hyper::body::Bytes;
+    /// use rewrk_core::{BodyMode, ResponseValidator, ValidationError};
+    ///
+    /// #[derive(Debug)]
+    /// pub struct NoNulValidator;
+    ///
+    /// impl ResponseValidator for NoNulValidator {
+    ///     fn validate(&self, head: Parts, _body: Bytes) -> Result<(), ValidationError> {
+    ///         if head.status.is_success() {
+    ///             Ok(())
+    ///         } else {
+    ///             Err(ValidationError::InvalidStatus(head.status.as_u16()))
+    ///         }
+    ///     }
+    ///
+    ///     fn body_mode(&self) -> BodyMode {
+    ///         BodyMode::Streaming
+    ///     }
+    ///
+    ///     fn validate_chunk(
+    ///         &self,
+    ///         _head: &Parts,
+    ///         chunk: &Bytes,
+    ///     ) -> Result<(), ValidationError> {
+    ///         if chunk.contains(&0) {
+    ///             return Err(ValidationError::InvalidBody("nul-byte".into()));
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    fn validate_chunk(
+        &self,
+        _head: &Parts,
+        _chunk: &Bytes,
+    ) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// The name of the validator.
+    ///
+    /// This is recorded in a [BenchmarkPlan](crate::BenchmarkPlan) so the validator
+    /// can be selected by name when the plan is imported. By default this is
+    /// the type name of the validator.
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(std::any::type_name::<Self>())
+    }
+}
+
+impl ResponseValidator for Arc<dyn ResponseValidator> {
+    fn validate(&self, head: Parts, body: Bytes) -> Result<(), ValidationError> {
+        self.as_ref().validate(head, body)
+    }
+
+    fn classify(&self, head: &Parts, body: &Bytes) -> Option<Classification> {
+        self.as_ref().classify(head, body)
+    }
+
+    fn body_mode(&self) -> BodyMode {
+        self.as_ref().body_mode()
+    }
+
+    fn validate_chunk(
+        &self,
+        head: &Parts,
+        chunk: &Bytes,
+    ) -> Result<(), ValidationError> {
+        self.as_ref().validate_chunk(head, chunk)
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.as_ref().name()
+    }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// How a [ResponseValidator] has response bodies read.
+pub enum BodyMode {
+    #[default]
+    /// The whole body is buffered and passed to [ResponseValidator::validate].
+    Buffered,
+    /// Each chunk of the body is passed to [ResponseValidator::validate_chunk]
+    /// as it's received and then dropped.
+    Streaming,
+    /// The body is read and dropped without being validated.
+    Discard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+/// A label used to bucket responses into separate latency histograms.
+pub struct Classification(pub Cow<'static, str>);
+
+impl From<&'static str> for Classification {
+    fn from(label: &'static str) -> Self {
+        Self(Cow::Borrowed(label))
+    }
+}
+
+impl From<String> for Classification {
+    fn from(label: String) -> Self {
+        Self(Cow::Owned(label))
+    }
+}
+
+/// The name of the [DefaultValidator].
+pub const DEFAULT_VALIDATOR_NAME: &str = "default";
+
 #[derive(Debug)]
 /// The default validator handler.
 pub struct DefaultValidator;
@@ -75,4 +294,309 @@ impl ResponseValidator for DefaultValidator {
             Err(ValidationError::InvalidStatus(head.status.as_u16()))
         }
     }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(DEFAULT_VALIDATOR_NAME)
+    }
+}
+
+/// The classification given to responses served from an intermediary cache
+/// by the [CacheHitValidator].
+pub const CACHE_HIT_CLASSIFICATION: &str = "intermediary_cache_hit";
+
+/// The headers caches and CDNs use to report a cache hit.
+const CACHE_STATUS_HEADERS: [&str; 4] = [
+    "x-cache",
+    "x-cache-status",
+    "cf-cache-status",
+    "x-proxy-cache",
+];
+
+/// Returns if the response headers show it was served from an
+/// intermediary cache such as a CDN or caching proxy.
+///
+/// A response is a cache hit if it has a non-zero `Age` header, or a
+/// cache status header such as `X-Cache` or `CF-Cache-Status` reporting a hit.
+pub fn is_intermediary_cache_hit(headers: &HeaderMap) -> bool {
+    let aged = headers
+        .get(header::AGE)
+        .and_then(|age| age.to_str().ok())
+        .and_then(|age| age.trim().parse::<u64>().ok())
+        .is_some_and(|age| age > 0);
+    if aged {
+        return true;
+    }
+
+    CACHE_STATUS_HEADERS.iter().any(|name| {
+        headers.get_all(*name).iter().any(|value| {
+            // e.g. `HIT`, `Hit from cloudfront` or `HIT, MISS` from chained caches.
+            value
+                .to_str()
+                .map(|value| value.to_ascii_lowercase().contains("hit"))
+                .unwrap_or_default()
+        })
+    })
+}
+
+#[derive(Debug)]
+/// A validator which flags responses served from an intermediary cache.
+///
+/// Unexpected CDN or proxy cache hits make a benchmark measure the cache
+/// rather than the target. Cache hits are classified as
+/// [CACHE_HIT_CLASSIFICATION] so they are counted separately in each sample's
+/// classified latencies, or rejected as errors with [CacheHitValidator::reject_hits].
+/// All other responses are validated and classified by the inner validator.
+///
+/// ```
+/// use rewrk_core::{CacheHitValidator, DefaultValidator, Sample};
+///
+/// let validator = CacheHitValidator::new(DefaultValidator);
+///
+/// fn cache_hits(sample: &Sample) -> u64 {
+///     sample
+///         .classified_latency(&CacheHitValidator::<DefaultValidator>::classification())
+///         .map(|hist| hist.len())
+///         .unwrap_or_default()
+/// }
+/// ```
+pub struct CacheHitValidator<V> {
+    inner: V,
+    reject_hits: bool,
+}
+
+impl<V> CacheHitValidator<V> {
+    /// Wraps an existing validator.
+    pub fn new(inner: V) -> Self {
+        Self {
+            inner,
+            reject_hits: false,
+        }
+    }
+
+    /// Reject cache hits with a validation error instead of only classifying them.
+    pub fn reject_hits(mut self) -> Self {
+        self.reject_hits = true;
+        self
+    }
+
+    /// The classification given to cache hits.
+    pub fn classification() -> Classification {
+        Classification::from(CACHE_HIT_CLASSIFICATION)
+    }
+}
+
+impl<V> ResponseValidator for CacheHitValidator<V>
+where
+    V: ResponseValidator,
+{
+    fn validate(&self, head: Parts, body: Bytes) -> Result<(), ValidationError> {
+        if self.reject_hits && is_intermediary_cache_hit(&head.headers) {
+            return Err(ValidationError::Other(Cow::Borrowed(
+                "served-from-intermediary-cache",
+            )));
+        }
+
+        self.inner.validate(head, body)
+    }
+
+    fn classify(&self, head: &Parts, body: &Bytes) -> Option<Classification> {
+        if is_intermediary_cache_hit(&head.headers) {
+            return Some(Self::classification());
+        }
+
+        self.inner.classify(head, body)
+    }
+
+    fn body_mode(&self) -> BodyMode {
+        self.inner.body_mode()
+    }
+
+    fn validate_chunk(
+        &self,
+        head: &Parts,
+        chunk: &Bytes,
+    ) -> Result<(), ValidationError> {
+        self.inner.validate_chunk(head, chunk)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The latency of a response, available in the response extensions
+/// when it is passed to [ResponseValidator::validate].
+///
+/// ```
+/// use http::response::Parts;
+/// use rewrk_core::ResponseLatency;
+///
+/// fn latency(head: &Parts) -> Option<ResponseLatency> {
+///     head.extensions.get::<ResponseLatency>().copied()
+/// }
+/// ```
+pub struct ResponseLatency(pub Duration);
+
+#[derive(Debug)]
+/// A validator which rejects otherwise valid responses slower than a threshold.
+///
+/// Responses which pass the inner validator but took longer than the
+/// threshold are rejected as [ValidationError::SloViolation], allowing
+/// error rate assertions to encode a latency SLO directly. The latency is
+/// read from the [ResponseLatency] extension of the response.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use rewrk_core::{DefaultValidator, WithLatencySlo};
+///
+/// let validator = WithLatencySlo::new(DefaultValidator, Duration::from_millis(250));
+/// ```
+pub struct WithLatencySlo<V> {
+    inner: V,
+    threshold: Duration,
+}
+
+impl<V> WithLatencySlo<V> {
+    /// Wraps an existing validator with the given latency threshold.
+    pub fn new(inner: V, threshold: Duration) -> Self {
+        Self { inner, threshold }
+    }
+
+    /// The latency threshold responses must complete within.
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+}
+
+impl<V> ResponseValidator for WithLatencySlo<V>
+where
+    V: ResponseValidator,
+{
+    fn validate(&self, head: Parts, body: Bytes) -> Result<(), ValidationError> {
+        let latency = head.extensions.get::<ResponseLatency>().copied();
+        self.inner.validate(head, body)?;
+
+        match latency {
+            Some(ResponseLatency(latency)) if latency > self.threshold => {
+                Err(ValidationError::SloViolation(latency))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn classify(&self, head: &Parts, body: &Bytes) -> Option<Classification> {
+        self.inner.classify(head, body)
+    }
+
+    fn body_mode(&self) -> BodyMode {
+        self.inner.body_mode()
+    }
+
+    fn validate_chunk(
+        &self,
+        head: &Parts,
+        chunk: &Bytes,
+    ) -> Result<(), ValidationError> {
+        self.inner.validate_chunk(head, chunk)
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderValue, Response, StatusCode};
+
+    use super::*;
+
+    fn parts(headers: &[(&'static str, &'static str)]) -> Parts {
+        let mut response = Response::new(());
+        for (name, value) in headers {
+            response
+                .headers_mut()
+                .append(*name, HeaderValue::from_static(value));
+        }
+        response.into_parts().0
+    }
+
+    #[test]
+    fn test_is_intermediary_cache_hit() {
+        assert!(!is_intermediary_cache_hit(&parts(&[]).headers));
+        assert!(!is_intermediary_cache_hit(&parts(&[("age", "0")]).headers));
+        assert!(is_intermediary_cache_hit(&parts(&[("age", "12")]).headers));
+        assert!(is_intermediary_cache_hit(
+            &parts(&[("x-cache", "HIT")]).headers
+        ));
+        assert!(is_intermediary_cache_hit(
+            &parts(&[("x-cache", "Hit from cloudfront")]).headers
+        ));
+        assert!(is_intermediary_cache_hit(
+            &parts(&[("cf-cache-status", "HIT")]).headers
+        ));
+        assert!(!is_intermediary_cache_hit(
+            &parts(&[("x-cache", "MISS")]).headers
+        ));
+    }
+
+    #[test]
+    fn test_cache_hit_validator() {
+        let validator = CacheHitValidator::new(DefaultValidator);
+        let hit = parts(&[("x-cache", "HIT")]);
+        let miss = parts(&[("x-cache", "MISS")]);
+
+        assert_eq!(
+            validator.classify(&hit, &Bytes::new()),
+            Some(CacheHitValidator::<DefaultValidator>::classification()),
+        );
+        assert_eq!(validator.classify(&miss, &Bytes::new()), None);
+        assert!(validator.validate(hit, Bytes::new()).is_ok());
+
+        let validator = validator.reject_hits();
+        let hit = parts(&[("x-cache", "HIT")]);
+        assert!(validator.validate(hit, Bytes::new()).is_err());
+        assert!(validator.validate(miss, Bytes::new()).is_ok());
+
+        let mut error = parts(&[]);
+        error.status = StatusCode::INTERNAL_SERVER_ERROR;
+        assert!(validator.validate(error, Bytes::new()).is_err());
+    }
+
+    #[test]
+    fn test_with_latency_slo() {
+        let validator =
+            WithLatencySlo::new(DefaultValidator, Duration::from_millis(100));
+        let with_latency = |latency: Duration| {
+            let mut head = parts(&[]);
+            head.extensions.insert(ResponseLatency(latency));
+            head
+        };
+
+        assert!(validator
+            .validate(with_latency(Duration::from_millis(50)), Bytes::new())
+            .is_ok());
+        assert!(validator.validate(parts(&[]), Bytes::new()).is_ok());
+
+        let error = validator
+            .validate(with_latency(Duration::from_millis(150)), Bytes::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::SloViolation);
+
+        let mut failed = with_latency(Duration::from_millis(150));
+        failed.status = StatusCode::INTERNAL_SERVER_ERROR;
+        let error = validator.validate(failed, Bytes::new()).unwrap_err();
+        assert_eq!(error.kind(), ValidationErrorKind::InvalidStatus);
+    }
+
+    #[test]
+    fn test_error_encoding_is_stable() {
+        // Archives written before the latency SLO was added must still decode.
+        let encoded = bincode::serialize(&ValidationError::Other("bad".into())).unwrap();
+        assert_eq!(encoded[..4], 6u32.to_le_bytes());
+        let encoded = bincode::serialize(&ValidationErrorKind::Other).unwrap();
+        assert_eq!(encoded, 6u32.to_le_bytes());
+
+        let encoded =
+            bincode::serialize(&ValidationError::SloViolation(Duration::ZERO)).unwrap();
+        assert_eq!(encoded[..4], 7u32.to_le_bytes());
+    }
 }
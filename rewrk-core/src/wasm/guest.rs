@@ -0,0 +1,209 @@
+use std::thread;
+
+use wasmtime::{
+    Instance,
+    Linker,
+    Memory,
+    Store,
+    StoreLimits,
+    StoreLimitsBuilder,
+    TypedFunc,
+};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::WasiCtxBuilder;
+
+use super::{WasmError, WasmPlugin, WASM_ABI_VERSION};
+
+type Job = Box<dyn FnOnce(&mut Guest) + Send>;
+
+struct GuestState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// A single instance of a plugin.
+pub(crate) struct Guest {
+    store: Store<GuestState>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    fuel_per_call: Option<u64>,
+}
+
+impl Guest {
+    /// Creates a new sandboxed instance of the plugin.
+    fn instantiate(plugin: &WasmPlugin) -> Result<Self, WasmError> {
+        let mut limits = StoreLimitsBuilder::new();
+        if let Some(limit) = plugin.memory_limit {
+            limits = limits.memory_size(limit);
+        }
+
+        let state = GuestState {
+            wasi: WasiCtxBuilder::new().build_p1(),
+            limits: limits.build(),
+        };
+        let mut store = Store::new(&plugin.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(u64::MAX).map_err(WasmError::Load)?;
+
+        let mut linker = Linker::new(&plugin.engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut GuestState| {
+            &mut state.wasi
+        })
+        .map_err(WasmError::Load)?;
+        let instance = linker
+            .instantiate(&mut store, &plugin.module)
+            .map_err(WasmError::Load)?;
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or(WasmError::MissingExport("memory"))?;
+        let alloc = get_func(&instance, &mut store, "rewrk_alloc")?;
+        let abi_version: TypedFunc<(), i32> =
+            get_func(&instance, &mut store, "rewrk_abi_version")?;
+
+        let mut guest = Self {
+            store,
+            instance,
+            memory,
+            alloc,
+            fuel_per_call: plugin.fuel_per_call,
+        };
+
+        guest.refuel()?;
+        let found = abi_version
+            .call(&mut guest.store, ())
+            .map_err(WasmError::Trap)?;
+        if found != WASM_ABI_VERSION {
+            return Err(WasmError::AbiVersion {
+                expected: WASM_ABI_VERSION,
+                found,
+            });
+        }
+
+        Ok(guest)
+    }
+
+    /// Gets a typed function exported by the plugin.
+    pub fn func<Params, Results>(
+        &mut self,
+        name: &'static str,
+    ) -> Result<TypedFunc<Params, Results>, WasmError>
+    where
+        Params: wasmtime::WasmParams,
+        Results: wasmtime::WasmResults,
+    {
+        get_func(&self.instance, &mut self.store, name)
+    }
+
+    /// Checks if the plugin exports the given item.
+    pub fn has_export(&mut self, name: &str) -> bool {
+        self.instance.get_export(&mut self.store, name).is_some()
+    }
+
+    /// Calls the given function with the fuel limit applied.
+    pub fn call<Params, Results>(
+        &mut self,
+        func: &TypedFunc<Params, Results>,
+        params: Params,
+    ) -> Result<Results, WasmError>
+    where
+        Params: wasmtime::WasmParams,
+        Results: wasmtime::WasmResults,
+    {
+        self.refuel()?;
+        func.call(&mut self.store, params).map_err(WasmError::Trap)
+    }
+
+    /// Copies the given bytes into a buffer allocated by the plugin.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(i32, i32), WasmError> {
+        let len = i32::try_from(bytes.len())
+            .map_err(|_| WasmError::InvalidResult("Input too large".to_string()))?;
+        let alloc = self.alloc.clone();
+        let ptr = self.call(&alloc, len)?;
+
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, bytes)
+            .map_err(|e| WasmError::InvalidResult(e.to_string()))?;
+        Ok((ptr, len))
+    }
+
+    /// Reads a packed pointer and length returned by the plugin.
+    pub fn read(&mut self, packed: i64) -> Result<Vec<u8>, WasmError> {
+        let ptr = (packed as u64 >> 32) as usize;
+        let len = (packed as u64 & u32::MAX as u64) as usize;
+
+        self.memory
+            .data(&self.store)
+            .get(ptr..ptr + len)
+            .map(|bytes| bytes.to_vec())
+            .ok_or_else(|| {
+                WasmError::InvalidResult("Result is out of bounds".to_string())
+            })
+    }
+
+    fn refuel(&mut self) -> Result<(), WasmError> {
+        let fuel = self.fuel_per_call.unwrap_or(u64::MAX);
+        self.store.set_fuel(fuel).map_err(WasmError::Trap)
+    }
+}
+
+fn get_func<Params, Results>(
+    instance: &Instance,
+    store: &mut Store<GuestState>,
+    name: &'static str,
+) -> Result<TypedFunc<Params, Results>, WasmError>
+where
+    Params: wasmtime::WasmParams,
+    Results: wasmtime::WasmResults,
+{
+    match instance.get_func(&mut *store, name) {
+        None => Err(WasmError::MissingExport(name)),
+        Some(func) => func.typed(&*store).map_err(WasmError::Load),
+    }
+}
+
+#[derive(Clone)]
+/// A set of plugin instances each running on their own thread.
+///
+/// The WASI host functions block on their own runtime, so plugins
+/// are run outside of the worker runtimes.
+pub(crate) struct GuestPool {
+    jobs: flume::Sender<Job>,
+}
+
+impl GuestPool {
+    /// Spawns `size` instances of the plugin.
+    pub fn spawn(plugin: &WasmPlugin, size: usize) -> Result<Self, WasmError> {
+        let (tx, rx) = flume::unbounded::<Job>();
+
+        for _ in 0..size.max(1) {
+            let mut guest = Guest::instantiate(plugin)?;
+            let rx = rx.clone();
+            thread::Builder::new()
+                .name(format!("rewrk-wasm-{}", plugin.name()))
+                .spawn(move || {
+                    while let Ok(job) = rx.recv() {
+                        job(&mut guest);
+                    }
+                })
+                .map_err(|e| WasmError::Load(e.into()))?;
+        }
+
+        Ok(Self { jobs: tx })
+    }
+
+    /// Runs the given function on the next available instance.
+    pub fn submit<R, F>(&self, func: F) -> flume::Receiver<Result<R, WasmError>>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Guest) -> Result<R, WasmError> + Send + 'static,
+    {
+        let (tx, rx) = flume::bounded(1);
+        let job: Job = Box::new(move |guest| {
+            let _ = tx.send(func(guest));
+        });
+        let _ = self.jobs.send(job);
+        rx
+    }
+}
@@ -0,0 +1,185 @@
+//! Producers and validators loaded from WebAssembly modules.
+//!
+//! These are enabled with the `wasm` feature. Plugins are run with `wasmtime`
+//! in a WASI sandbox with no access to the filesystem, network or environment,
+//! this allows benchmark logic to be distributed and run without recompiling
+//! the host binary, including untrusted scripts when combined with the fuel
+//! and memory limits of the [WasmPlugin].
+//!
+//! # Guest interface
+//!
+//! Plugins are core WebAssembly modules (i.e. built for `wasm32-wasip1`)
+//! exporting the following items, all pointers and lengths are `i32` values
+//! into the exported linear memory. Results are returned as an `i64` packing
+//! the pointer into the upper 32 bits and the length into the lower 32 bits.
+//!
+//! | Export | Signature | Description |
+//! |---|---|---|
+//! | `memory` | | The linear memory of the plugin. |
+//! | `rewrk_abi_version` | `() -> i32` | Must return [WASM_ABI_VERSION]. |
+//! | `rewrk_alloc` | `(len) -> ptr` | Allocates a buffer the host writes inputs to. |
+//! | `rewrk_producer_ready` | `()` | Optional, called when the benchmark starts. |
+//! | `rewrk_producer_next` | `() -> i64` | Returns the next batch as JSON or `0` once finished. |
+//! | `rewrk_validate` | `(head_ptr, head_len, body_ptr, body_len) -> i64` | Returns `0` if the response is valid or an error message. |
+//!
+//! Batches are encoded as a [WasmBatch] and response heads as a
+//! [WasmResponseHead], both as JSON.
+
+mod guest;
+mod producer;
+mod validator;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use wasmtime::{Config, Engine, Module};
+
+pub use self::producer::WasmProducer;
+pub use self::validator::WasmValidator;
+
+/// The version of the guest interface implemented by the host.
+pub const WASM_ABI_VERSION: i32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+/// An error raised by a WebAssembly plugin.
+pub enum WasmError {
+    #[error("Failed to load plugin: {0}")]
+    /// The plugin could not be read or compiled.
+    Load(anyhow::Error),
+    #[error("The plugin is missing the required export {0:?}")]
+    /// The plugin does not export a required item.
+    MissingExport(&'static str),
+    #[error("The plugin implements ABI version {found}, expected {expected}")]
+    /// The plugin implements a different version of the guest interface.
+    AbiVersion {
+        /// The version supported by the host.
+        expected: i32,
+        /// The version implemented by the plugin.
+        found: i32,
+    },
+    #[error("The plugin trapped: {0}")]
+    /// The plugin trapped or exceeded its limits while running.
+    Trap(anyhow::Error),
+    #[error("The plugin returned an invalid result: {0}")]
+    /// The plugin returned a result which could not be decoded.
+    InvalidResult(String),
+    #[error("The plugin has shutdown")]
+    /// The thread running the plugin has stopped.
+    Shutdown,
+}
+
+#[derive(Clone)]
+/// A compiled WebAssembly plugin.
+///
+/// Compiling a plugin is relatively expensive, the same plugin can be cheaply
+/// cloned and used to create any number of producers and validators.
+pub struct WasmPlugin {
+    name: Arc<str>,
+    engine: Engine,
+    module: Module,
+    fuel_per_call: Option<u64>,
+    memory_limit: Option<usize>,
+}
+
+impl WasmPlugin {
+    /// Loads and compiles a plugin from a `.wasm` or `.wat` file.
+    ///
+    /// The plugin is named after the file stem.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, WasmError> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let engine = create_engine()?;
+        let module = Module::from_file(&engine, path).map_err(WasmError::Load)?;
+        Ok(Self::new(name, engine, module))
+    }
+
+    /// Compiles a plugin from the given WebAssembly binary or text.
+    pub fn from_bytes(
+        name: impl Into<String>,
+        bytes: impl AsRef<[u8]>,
+    ) -> Result<Self, WasmError> {
+        let engine = create_engine()?;
+        let module = Module::new(&engine, bytes).map_err(WasmError::Load)?;
+        Ok(Self::new(name.into(), engine, module))
+    }
+
+    fn new(name: String, engine: Engine, module: Module) -> Self {
+        Self {
+            name: Arc::from(name),
+            engine,
+            module,
+            fuel_per_call: None,
+            memory_limit: None,
+        }
+    }
+
+    /// The name of the plugin.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets the amount of fuel available to each call into the plugin.
+    ///
+    /// Calls which run out of fuel trap, this bounds the amount of work an
+    /// untrusted plugin can do. By default calls are unlimited.
+    pub fn set_fuel_per_call(&mut self, fuel: Option<u64>) {
+        self.fuel_per_call = fuel;
+    }
+
+    /// Sets the maximum size of the plugin's linear memory in bytes.
+    ///
+    /// By default memory is unlimited.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+}
+
+fn create_engine() -> Result<Engine, WasmError> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).map_err(WasmError::Load)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A batch of requests returned by a plugin producer.
+pub struct WasmBatch {
+    #[serde(default)]
+    /// The tag of the batch.
+    pub tag: usize,
+    /// The batch requests.
+    pub requests: Vec<WasmRequest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A single request returned by a plugin producer.
+pub struct WasmRequest {
+    #[serde(default = "default_method")]
+    /// The request method, defaults to `GET`.
+    pub method: String,
+    /// The request path and query.
+    pub path: String,
+    #[serde(default)]
+    /// The request headers.
+    pub headers: Vec<(String, String)>,
+    #[serde(default)]
+    /// The request body.
+    pub body: String,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// The head of a response passed to a plugin validator.
+pub struct WasmResponseHead {
+    /// The response status code.
+    pub status: u16,
+    /// The response headers, values which are not valid UTF-8 are replaced lossily.
+    pub headers: Vec<(String, String)>,
+}
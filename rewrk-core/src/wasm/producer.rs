@@ -0,0 +1,100 @@
+use http::{Method, Request, Uri};
+use hyper::Body;
+
+use super::guest::GuestPool;
+use super::{WasmBatch, WasmError, WasmPlugin, WasmRequest};
+use crate::producer::{Batch, Producer, RequestBatch};
+
+/// A [Producer] which creates request batches with a [WasmPlugin].
+///
+/// Each clone of the producer, i.e. each worker, runs its own
+/// instance of the plugin which is created once the benchmark starts.
+pub struct WasmProducer {
+    plugin: WasmPlugin,
+    guest: Option<Result<GuestPool, String>>,
+}
+
+impl WasmProducer {
+    /// Create a new producer running the given plugin.
+    pub fn new(plugin: WasmPlugin) -> Self {
+        Self {
+            plugin,
+            guest: None,
+        }
+    }
+}
+
+impl Clone for WasmProducer {
+    fn clone(&self) -> Self {
+        Self::new(self.plugin.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl Producer for WasmProducer {
+    fn ready(&mut self) {
+        let guest = GuestPool::spawn(&self.plugin, 1).map_err(|e| e.to_string());
+
+        if let Ok(guest) = guest.as_ref() {
+            // Any error is returned by the next batch.
+            let _ = guest.submit(|guest| {
+                if guest.has_export("rewrk_producer_ready") {
+                    let ready = guest.func::<(), ()>("rewrk_producer_ready")?;
+                    guest.call(&ready, ())?;
+                }
+                Ok(())
+            });
+        }
+
+        self.guest = Some(guest);
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        let guest = match self.guest.as_ref() {
+            None => anyhow::bail!("The producer has not been started"),
+            Some(Err(e)) => anyhow::bail!("{e}"),
+            Some(Ok(guest)) => guest,
+        };
+
+        let batch = guest
+            .submit(|guest| {
+                let next = guest.func::<(), i64>("rewrk_producer_next")?;
+                match guest.call(&next, ())? {
+                    0 => Ok(None),
+                    packed => guest.read(packed).map(Some),
+                }
+            })
+            .recv_async()
+            .await
+            .map_err(|_| WasmError::Shutdown)??;
+
+        let batch = match batch {
+            None => return Ok(RequestBatch::End),
+            Some(batch) => serde_json::from_slice::<WasmBatch>(&batch)
+                .map_err(|e| WasmError::InvalidResult(e.to_string()))?,
+        };
+
+        let requests = batch
+            .requests
+            .into_iter()
+            .map(create_request)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(RequestBatch::Batch(Batch {
+            tag: batch.tag,
+            requests,
+        }))
+    }
+}
+
+fn create_request(request: WasmRequest) -> anyhow::Result<Request<Body>> {
+    let mut builder = Request::builder()
+        .method(Method::from_bytes(request.method.as_bytes())?)
+        .uri(request.path.parse::<Uri>()?);
+
+    for (name, value) in request.headers {
+        builder = builder.header(name, value);
+    }
+
+    Ok(builder.body(Body::from(request.body))?)
+}
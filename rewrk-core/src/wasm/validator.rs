@@ -0,0 +1,77 @@
+use std::borrow::Cow;
+
+use http::response::Parts;
+use hyper::body::Bytes;
+
+use super::guest::GuestPool;
+use super::{WasmError, WasmPlugin, WasmResponseHead};
+use crate::validator::{ResponseValidator, ValidationError};
+
+/// A [ResponseValidator] which validates responses with a [WasmPlugin].
+///
+/// Responses are validated by a fixed pool of plugin instances shared
+/// by all workers, the calling worker blocks until its response has
+/// been validated.
+pub struct WasmValidator {
+    name: String,
+    guests: GuestPool,
+}
+
+impl WasmValidator {
+    /// Create a new validator running `instances` copies of the plugin.
+    pub fn new(plugin: WasmPlugin, instances: usize) -> Result<Self, WasmError> {
+        let guests = GuestPool::spawn(&plugin, instances)?;
+
+        Ok(Self {
+            name: plugin.name().to_string(),
+            guests,
+        })
+    }
+}
+
+impl ResponseValidator for WasmValidator {
+    fn validate(&self, head: Parts, body: Bytes) -> Result<(), ValidationError> {
+        let head = WasmResponseHead {
+            status: head.status.as_u16(),
+            headers: head
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes());
+                    (name.to_string(), value.into_owned())
+                })
+                .collect(),
+        };
+        let head = serde_json::to_vec(&head)
+            .map_err(|e| ValidationError::Other(Cow::Owned(e.to_string())))?;
+
+        let result = self
+            .guests
+            .submit(move |guest| {
+                let validate =
+                    guest.func::<(i32, i32, i32, i32), i64>("rewrk_validate")?;
+                let (head_ptr, head_len) = guest.write(&head)?;
+                let (body_ptr, body_len) = guest.write(&body)?;
+
+                match guest.call(&validate, (head_ptr, head_len, body_ptr, body_len))? {
+                    0 => Ok(None),
+                    packed => guest.read(packed).map(Some),
+                }
+            })
+            .recv()
+            .map_err(|_| WasmError::Shutdown)
+            .and_then(|result| result);
+
+        match result {
+            Ok(None) => Ok(()),
+            Ok(Some(message)) => Err(ValidationError::Other(Cow::Owned(
+                String::from_utf8_lossy(&message).into_owned(),
+            ))),
+            Err(e) => Err(ValidationError::Other(Cow::Owned(e.to_string()))),
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Owned(self.name.clone())
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Annotation,
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[derive(Debug)]
+enum Event {
+    Sample,
+    Annotation(Annotation),
+}
+
+#[tokio::test]
+async fn test_annotations() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        AnnotationCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(50))
+        .expect("Set benchmark config");
+
+    assert!(benchmarker.annotate("benchmark started"));
+    tokio::join!(benchmarker.run(), async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(benchmarker.annotate("deploy v2 started"));
+    });
+
+    let collector = benchmarker.consume_collector().await;
+    let annotations = collector
+        .events
+        .iter()
+        .enumerate()
+        .filter_map(|(i, event)| match event {
+            Event::Annotation(annotation) => Some((i, annotation)),
+            Event::Sample => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(annotations.len(), 2);
+    assert_eq!(annotations[0].0, 0);
+    assert_eq!(annotations[0].1.message(), "benchmark started");
+    assert_eq!(annotations[1].1.message(), "deploy v2 started");
+    assert!(annotations[1].1.timestamp() > annotations[0].1.timestamp());
+
+    // The annotation is delivered between the samples recorded around it.
+    let (index, _) = annotations[1];
+    assert!(index > 1);
+    assert!(index < collector.events.len() - 1);
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 80;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count == 0 {
+            return Ok(RequestBatch::End);
+        }
+        self.count -= 1;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct AnnotationCollector {
+    events: Vec<Event>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for AnnotationCollector {
+    async fn process_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+        self.events.push(Event::Sample);
+        Ok(())
+    }
+
+    async fn process_annotation(
+        &mut self,
+        annotation: Annotation,
+    ) -> anyhow::Result<()> {
+        self.events.push(Event::Annotation(annotation));
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    ArchiveCollector,
+    ArchiveError,
+    ArchiveHeader,
+    ArchiveReader,
+    ArchiveWriter,
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleMetadata,
+    ARCHIVE_EXTENSION,
+};
+
+#[tokio::test]
+async fn test_archive_collector() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let path = archive_path("collector");
+    let server = TestServer::echo().await.expect("Start server");
+
+    let mut header = ArchiveHeader::default();
+    header.metadata.insert("commit".into(), "a1b2c3d".into());
+    let collector = ArchiveCollector::create(&path, &header).expect("Create archive");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        collector,
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_labels(HashMap::from([("build".to_string(), "1.2.3".to_string())]));
+    let plan = benchmarker.export_plan();
+    benchmarker.run().await;
+    benchmarker
+        .consume_collector()
+        .await
+        .finish()
+        .await
+        .expect("Finish archive");
+
+    let reader = ArchiveReader::open(&path).expect("Open archive");
+    assert_eq!(reader.header().metadata["commit"], "a1b2c3d");
+    assert!(reader.header().plan.is_none());
+    let samples = reader
+        .collect::<Result<Vec<Sample>, _>>()
+        .expect("Read samples");
+
+    let total: u64 = samples.iter().map(Sample::successful_requests).sum();
+    assert_eq!(total, 10);
+    let latencies: u64 = samples.iter().map(|s| s.latency().len()).sum();
+    assert_eq!(latencies, 10);
+    assert!(samples
+        .iter()
+        .all(|s| s.metadata().sample_window == plan.sample_window));
+    assert!(samples.iter().all(|s| s.labels() == &plan.labels));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_archive_checkpoint_resume() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let path = archive_path("checkpoint");
+    let server = TestServer::echo().await.expect("Start server");
+
+    let mut collector = ArchiveCollector::create(&path, &ArchiveHeader::default())
+        .expect("Create archive");
+    collector.set_checkpoint_interval(Duration::ZERO);
+    let collector = run_benchmark(&server, collector).await;
+    // Simulate a crash, the archive is never finished.
+    std::mem::forget(collector);
+
+    let mut reader = ArchiveReader::open(&path).expect("Open archive");
+    let samples = reader
+        .by_ref()
+        .collect::<Result<Vec<Sample>, _>>()
+        .expect("Read samples");
+    assert!(reader.is_truncated());
+    let total: u64 = samples.iter().map(Sample::successful_requests).sum();
+    assert_eq!(total, 10);
+
+    let collector = ArchiveCollector::resume(&path).expect("Resume archive");
+    assert_eq!(collector.resumed_samples(), samples.len());
+    run_benchmark(&server, collector)
+        .await
+        .finish()
+        .await
+        .expect("Finish archive");
+
+    let mut reader = ArchiveReader::open(&path).expect("Open archive");
+    let resumed = reader
+        .by_ref()
+        .collect::<Result<Vec<Sample>, _>>()
+        .expect("Read samples");
+    assert!(!reader.is_truncated());
+    let total: u64 = resumed.iter().map(Sample::successful_requests).sum();
+    assert_eq!(total, 20);
+
+    // The windows of the resumed run follow those of the crashed run.
+    let last_window = samples.iter().map(Sample::window_index).max().unwrap();
+    assert!(resumed[samples.len()..]
+        .iter()
+        .all(|sample| sample.window_index() > last_window));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_archive_round_trip() {
+    let server = TestServer::echo().await.expect("Start server");
+    let benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ArchiveCollector::create(archive_path("unused"), &ArchiveHeader::default())
+            .expect("Create archive"),
+    )
+    .await
+    .expect("Create benchmark");
+
+    let header = ArchiveHeader {
+        plan: Some(benchmarker.export_plan()),
+        ..ArchiveHeader::default()
+    };
+    let writer = ArchiveWriter::new(Vec::new(), &header).expect("Create archive");
+    let buffer = writer.finish().expect("Finish archive");
+
+    let mut reader = ArchiveReader::new(&buffer[..]).expect("Read archive");
+    assert_eq!(reader.header().plan, header.plan);
+    assert!(reader.read_sample().expect("Read sample").is_none());
+
+    let _ = std::fs::remove_file(archive_path("unused"));
+}
+
+#[test]
+fn test_archive_truncated() {
+    let mut writer = ArchiveWriter::new(Vec::new(), &ArchiveHeader::default())
+        .expect("Create archive");
+    for window in 0..4 {
+        let latencies = (1..=100).map(Duration::from_micros).collect::<Vec<_>>();
+        let sample = Sample::from_requests(
+            SampleMetadata {
+                worker_id: 0,
+                connection_id: window,
+                round: 0,
+                phase: 0,
+                sample_window: Duration::from_secs(1),
+                peer_addr: None,
+            },
+            &latencies,
+            1024,
+            [],
+        );
+        writer.write_sample(&sample).expect("Write sample");
+        writer.checkpoint().expect("Checkpoint archive");
+    }
+    let archive = writer.finish().expect("Finish archive");
+
+    // Cutting the archive anywhere after its header, i.e. part way through
+    // a frame or a deflate block, reads the complete samples before the cut.
+    let mut read = Vec::new();
+    for len in 0..archive.len() {
+        let mut reader = match ArchiveReader::new(&archive[..len]) {
+            Ok(reader) => reader,
+            Err(_) => {
+                assert!(read.is_empty(), "Header failed to read at {len} bytes");
+                continue;
+            },
+        };
+        let samples = reader
+            .by_ref()
+            .collect::<Result<Vec<Sample>, _>>()
+            .unwrap_or_else(|e| panic!("Read samples cut at {len} bytes: {e}"));
+        assert!(reader.is_truncated(), "Cut at {len} bytes");
+        read.push(samples.len());
+    }
+    assert!(read.windows(2).all(|counts| counts[0] <= counts[1]));
+    assert_eq!(read.first(), Some(&0));
+    assert_eq!(read.last(), Some(&4));
+}
+
+#[test]
+fn test_archive_invalid() {
+    assert!(matches!(
+        ArchiveReader::new(&b"{\"not\": \"an archive\"}"[..]),
+        Err(ArchiveError::InvalidMagic),
+    ));
+    assert!(matches!(
+        ArchiveReader::new(&b"REWRK\0\x09\x00"[..]),
+        Err(ArchiveError::UnsupportedVersion(9)),
+    ));
+}
+
+#[test]
+fn test_archive_frame_too_large() {
+    let writer = ArchiveWriter::new(Vec::new(), &ArchiveHeader::default())
+        .expect("Create archive");
+    let buffer = writer.finish().expect("Finish archive");
+
+    // Keep the magic bytes and version but replace the frames with a
+    // length prefix far larger than any valid frame.
+    let mut archive = buffer[..8].to_vec();
+    let mut encoder = GzEncoder::new(&mut archive, Compression::default());
+    encoder.write_all(&u32::MAX.to_le_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    assert!(matches!(
+        ArchiveReader::new(&archive[..]),
+        Err(ArchiveError::FrameTooLarge(len)) if len == u32::MAX as usize,
+    ));
+}
+
+async fn run_benchmark(
+    server: &TestServer,
+    collector: ArchiveCollector,
+) -> ArchiveCollector {
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        collector,
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+    benchmarker.consume_collector().await
+}
+
+fn archive_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "rewrk-test-{name}-{}.{ARCHIVE_EXTENSION}",
+        std::process::id()
+    ))
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 10;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
@@ -1,9 +1,12 @@
+use std::time::Duration;
+
 use axum::routing::get;
 use axum::Router;
 use http::{Method, Request, Uri};
 use hyper::Body;
 use rewrk_core::{
     Batch,
+    ConfigError,
     HttpProtocol,
     Producer,
     ReWrkBenchmark,
@@ -36,7 +39,9 @@ async fn test_basic_benchmark() {
     )
     .await
     .expect("Create benchmark");
-    benchmarker.set_num_workers(1);
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
     benchmarker.run().await;
 
     let mut collector = benchmarker.consume_collector().await;
@@ -45,6 +50,57 @@ async fn test_basic_benchmark() {
     assert_eq!(sample.latency().len(), 1);
     assert_eq!(sample.read_transfer().len(), 1);
     assert_eq!(sample.write_transfer().len(), 1);
+    assert_eq!(sample.total_requests(), 1);
+    assert_eq!(sample.successful_requests(), 1);
+    assert_eq!(sample.failed_requests(), 0);
+    assert!(sample.error_counts().is_empty());
+    assert_eq!(sample.window_index(), 0);
+    assert!(sample.is_truncated());
+    assert!(sample.duration() > Duration::ZERO);
+}
+
+#[tokio::test]
+async fn test_invalid_config() {
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    assert_eq!(
+        benchmarker.set_num_workers(0),
+        Err(ConfigError::ZeroWorkers)
+    );
+    assert_eq!(
+        benchmarker.set_sample_window(Duration::ZERO),
+        Err(ConfigError::ZeroSampleWindow),
+    );
+    assert_eq!(
+        benchmarker.set_producer_wait_warning_threshold(150.0),
+        Err(ConfigError::InvalidWaitWarningThreshold(150.0)),
+    );
+    assert_eq!(benchmarker.set_rounds(0), Err(ConfigError::ZeroRounds));
+    assert_eq!(
+        benchmarker.set_retry_backoff(Duration::from_secs(2), Duration::from_secs(1)),
+        Err(ConfigError::InvalidRetryBackoff {
+            min: Duration::from_secs(2),
+            max: Duration::from_secs(1),
+        }),
+    );
+    assert!(benchmarker
+        .set_producer_wait_warning_threshold(50.0)
+        .is_ok());
 }
 
 async fn run_server() {
@@ -0,0 +1,101 @@
+use axum::routing::get;
+use axum::Router;
+use http::{Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::{BenchConnection, BenchConnectionError, HttpProtocol};
+
+static ADDR: &str = "127.0.0.1:20012";
+
+#[tokio::test]
+async fn test_bench_connection() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut conn = BenchConnection::connect(uri.clone(), HttpProtocol::HTTP1)
+        .await
+        .expect("Connect to server");
+    assert_eq!(conn.addr(), ADDR.parse().unwrap());
+
+    for _ in 0..2 {
+        let request = Request::builder()
+            .uri(uri.clone())
+            .body(Body::empty())
+            .expect("Create request");
+        let response = conn.send(request).await.expect("Send request");
+
+        assert_eq!(response.head.status, StatusCode::OK);
+        assert_eq!(response.body.as_ref(), b"Hello, World!");
+        assert!(response.latency > std::time::Duration::ZERO);
+        assert!(response.io.read > 0);
+        assert!(response.io.written > 0);
+        assert_eq!(response.plaintext_io, response.io);
+    }
+
+    let counters = conn.io_counters();
+    assert!(counters.read > 0);
+    assert!(counters.written > 0);
+}
+
+#[tokio::test]
+async fn test_bench_connection_http2_stream_io() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut conn = BenchConnection::connect(uri.clone(), HttpProtocol::HTTP2)
+        .await
+        .expect("Connect to server");
+
+    // The first request shares the socket with the connection preface and
+    // settings frames, those bytes should not be attributed to it.
+    let mut responses = Vec::new();
+    for _ in 0..2 {
+        let request = Request::builder()
+            .uri(uri.clone())
+            .body(Body::empty())
+            .expect("Create request");
+        responses.push(conn.send(request).await.expect("Send request"));
+    }
+
+    assert_eq!(responses[0].io, responses[1].io);
+    assert!(responses[0].io.read > b"Hello, World!".len() as u64);
+    assert!(responses[0].io.written > 0);
+    assert_eq!(responses[0].plaintext_io, responses[0].io);
+}
+
+#[tokio::test]
+async fn test_bench_connection_invalid_target() {
+    let uri = Uri::from_static("ftp://127.0.0.1:20012/");
+    let result = BenchConnection::connect(uri, HttpProtocol::HTTP1).await;
+    assert!(matches!(
+        result,
+        Err(BenchConnectionError::InvalidTarget(_))
+    ));
+}
+
+async fn run_server() {
+    // build our application with a single route
+    let app = Router::new().route("/", get(|| async { "Hello, World!" }));
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
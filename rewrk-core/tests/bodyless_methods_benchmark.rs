@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    read_body,
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20022";
+static CONTENT_LENGTH_ADDR: &str = "127.0.0.1:20034";
+const NUM_REQUESTS: u64 = 40;
+
+#[tokio::test]
+async fn test_bodyless_methods() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    // `HEAD` responses advertise the length of the `GET` body without sending it,
+    // reading it as a body would stall the connection or fail the next request.
+    assert_eq!(total.total_requests(), NUM_REQUESTS);
+    assert_eq!(total.successful_requests(), NUM_REQUESTS);
+    assert_eq!(total.total_errors(), 0);
+}
+
+#[tokio::test]
+async fn test_content_length() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_content_length_server());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(CONTENT_LENGTH_ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        ContentLengthProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    // Every request is sent on the same connection, so a body which is read
+    // too short or too long fails the requests which follow it.
+    let requests = CONTENT_LENGTH_REQUESTS.len() as u64 * 4;
+    assert_eq!(total.total_requests(), requests);
+    assert_eq!(total.successful_requests(), requests);
+    assert_eq!(total.total_errors(), 0);
+}
+
+#[tokio::test]
+async fn test_read_body() {
+    let body = read_body(Body::empty()).await.unwrap();
+    assert!(body.is_empty());
+
+    let body = read_body(Body::from("")).await.unwrap();
+    assert!(body.is_empty());
+
+    let body = read_body(Body::from("Hello, World!")).await.unwrap();
+    assert_eq!(body, "Hello, World!");
+
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        sender.send_data("Hello, ".into()).await.unwrap();
+        sender.send_data("World!".into()).await.unwrap();
+    });
+    let body = read_body(body).await.unwrap();
+    assert_eq!(body, "Hello, World!");
+}
+
+/// The method, path and body of each request sent to the content length server.
+const CONTENT_LENGTH_REQUESTS: [(Method, &str, &str); 6] = [
+    (Method::HEAD, "/length", ""),
+    (Method::GET, "/length", ""),
+    (Method::GET, "/empty", ""),
+    (Method::GET, "/no-content", ""),
+    (Method::POST, "/echo", "Hello, World!"),
+    (Method::GET, "/echo", ""),
+];
+
+/// A server which checks the content length of every request and responds
+/// with a mix of empty, bodyless and sized bodies.
+async fn run_content_length_server() {
+    let app = Router::new()
+        .route("/length", get(|| async { "Hello, World!" }))
+        .route(
+            "/empty",
+            get(|| async { ([(header::CONTENT_LENGTH, "0")], "") }),
+        )
+        .route("/no-content", get(|| async { StatusCode::NO_CONTENT }))
+        .route("/echo", get(echo).post(echo));
+
+    axum::Server::bind(&CONTENT_LENGTH_ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+/// Echoes the request body, rejecting requests whose content length
+/// doesn't match their body or which send a length without a body.
+async fn echo(headers: HeaderMap, body: Bytes) -> (StatusCode, Bytes) {
+    let content_length = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    let is_valid = match content_length {
+        Some(len) => len == body.len() && !body.is_empty(),
+        None => body.is_empty() && !headers.contains_key(header::TRANSFER_ENCODING),
+    };
+
+    if is_valid {
+        (StatusCode::OK, body)
+    } else {
+        (StatusCode::BAD_REQUEST, Bytes::new())
+    }
+}
+
+async fn run_server() {
+    let app = Router::new().route(
+        "/",
+        get(|| async { "Hello, World!" }).options(|| async {
+            (
+                StatusCode::NO_CONTENT,
+                [(header::ALLOW, "GET, HEAD, OPTIONS")],
+            )
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let requests = [Method::HEAD, Method::GET, Method::OPTIONS, Method::HEAD]
+                .into_iter()
+                .cycle()
+                .take(NUM_REQUESTS as usize)
+                .map(|method| {
+                    Request::builder()
+                        .method(method)
+                        .uri(Uri::from_static("/"))
+                        .body(Body::empty())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct ContentLengthProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for ContentLengthProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let requests = CONTENT_LENGTH_REQUESTS
+                .iter()
+                .cycle()
+                .take(CONTENT_LENGTH_REQUESTS.len() * 4)
+                .map(|(method, path, body)| {
+                    Request::builder()
+                        .method(method)
+                        .uri(Uri::from_static(path))
+                        .body(Body::from(*body))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
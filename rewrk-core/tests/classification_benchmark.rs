@@ -0,0 +1,153 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use axum::routing::get;
+use axum::Router;
+use http::response::Parts;
+use http::{Method, Request, Uri};
+use hyper::body::Bytes;
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    Classification,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    ResponseValidator,
+    Sample,
+    SampleCollector,
+    ValidationError,
+};
+
+static ADDR: &str = "127.0.0.1:20008";
+static REQUEST_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[tokio::test]
+async fn test_response_classification() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_validator(CacheValidator);
+    benchmarker.run().await;
+
+    let mut collector = benchmarker.consume_collector().await;
+    let sample = collector.samples.remove(0);
+    assert_eq!(sample.successful_requests(), 4);
+    assert_eq!(sample.latency().len(), 4);
+
+    let hits = sample
+        .classified_latency(&Classification::from("cache_hit"))
+        .expect("Get cache hit latency");
+    let misses = sample
+        .classified_latency(&Classification::from("cache_miss"))
+        .expect("Get cache miss latency");
+    assert_eq!(hits.len(), 2);
+    assert_eq!(misses.len(), 2);
+    assert_eq!(sample.classified_latencies().count(), 2);
+}
+
+async fn run_server() {
+    // build our application with a single route
+    let app = Router::new().route(
+        "/",
+        get(|| async {
+            // Every other request is a cache hit.
+            if REQUEST_COUNT
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(2)
+            {
+                ([("x-cache", "HIT")], "Hello, World!")
+            } else {
+                ([("x-cache", "MISS")], "Hello, World!")
+            }
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+pub struct CacheValidator;
+
+impl ResponseValidator for CacheValidator {
+    fn validate(&self, head: Parts, _body: Bytes) -> Result<(), ValidationError> {
+        if head.status.is_success() {
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidStatus(head.status.as_u16()))
+        }
+    }
+
+    fn classify(&self, head: &Parts, _body: &Bytes) -> Option<Classification> {
+        match head.headers.get("x-cache")?.as_bytes() {
+            b"HIT" => Some(Classification::from("cache_hit")),
+            _ => Some(Classification::from("cache_miss")),
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let requests = (0..4)
+                .map(|_| {
+                    Request::builder()
+                        .method(Method::GET)
+                        .uri(uri.clone())
+                        .body(Body::empty())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_collector_drain_timeout() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        HangingCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_collector_drain_timeout(Duration::from_millis(100));
+    benchmarker.run().await;
+
+    let start = Instant::now();
+    let drained = benchmarker.drain_collector().await;
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(drained.is_partial());
+
+    // The sample being processed when aborted is dropped along
+    // with the samples waiting in the mailbox.
+    let dropped = drained.dropped_samples();
+    let collector = drained.into_inner();
+    assert_eq!(collector.received, 1);
+    assert!(collector.samples.is_empty());
+    assert!(dropped >= collector.received);
+}
+
+#[tokio::test]
+async fn test_collector_drain_complete() {
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_collector_drain_timeout(Duration::from_secs(10));
+    benchmarker.run().await;
+
+    let drained = benchmarker.drain_collector().await;
+    assert!(!drained.is_partial());
+    assert_eq!(drained.dropped_samples(), 0);
+
+    let collector = drained.into_inner();
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    assert_eq!(total_requests, 10);
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 10;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
+
+/// A collector which never finishes processing a sample.
+#[derive(Default)]
+pub struct HangingCollector {
+    received: usize,
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for HangingCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.received += 1;
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+        self.samples.push(sample);
+        Ok(())
+    }
+}
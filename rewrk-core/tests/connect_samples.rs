@@ -0,0 +1,391 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Backoff,
+    Batch,
+    BoxedTransportStream,
+    ConfigError,
+    ConnectErrorKind,
+    ConnectSample,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    Transport,
+};
+
+#[tokio::test]
+async fn test_connect_samples() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        4,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ConnectCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.connects.len(), 4);
+    assert!(!collector.samples.is_empty());
+
+    let mut connections = BTreeSet::new();
+    for sample in collector.connects.iter() {
+        assert!(sample.is_success(), "{sample:?}");
+        assert_eq!(sample.attempts(), 1);
+        assert!(sample.tcp().is_some());
+        assert!(sample.tls().is_none());
+        assert!(sample.handshake().is_some());
+        assert!(sample.duration() >= sample.tcp().unwrap());
+        assert_eq!(sample.peer_addr(), Some(server.addr()));
+        assert!(!sample.is_ipv6());
+
+        let metadata = sample.metadata();
+        connections.insert((metadata.worker_id, metadata.connection_id));
+    }
+    assert_eq!(connections.len(), 4);
+
+    // Errors and latencies within each sample can be attributed to the peer.
+    let mut combined = collector.samples[0].clone();
+    for (i, sample) in collector.samples.iter().enumerate() {
+        assert_eq!(sample.metadata().peer_addr, Some(server.addr()));
+        if i > 0 {
+            combined += sample.clone();
+        }
+    }
+
+    // The connect phases of every connection are recorded in its samples.
+    assert_eq!(combined.connect_latency().len(), 4);
+    assert!(combined.tls_handshake().is_empty());
+
+    // Each request attempt is split into its time to first byte and body read.
+    let attempts = combined.attempt_latency().len();
+    assert_eq!(attempts, combined.total_requests());
+    assert_eq!(combined.time_to_first_byte().len(), attempts);
+    assert_eq!(combined.body_read().len(), attempts);
+}
+
+#[tokio::test]
+async fn test_connect_samples_rotate_addrs() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let first = TestServer::echo().await.expect("Start server");
+    let second = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        first.uri(),
+        4,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ConnectCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    assert_eq!(
+        benchmarker.set_target_addrs(Vec::new()),
+        Err(ConfigError::NoTargetAddrs),
+    );
+    benchmarker
+        .set_target_addrs(vec![first.addr(), second.addr()])
+        .expect("Set target addresses");
+    benchmarker.run().await;
+
+    // Connections are spread evenly across the addresses.
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.connects.len(), 4);
+    let mut peers = BTreeSet::new();
+    for sample in collector.connects.iter() {
+        assert!(sample.is_success(), "{sample:?}");
+        peers.insert(sample.peer_addr().expect("Peer address"));
+    }
+    assert_eq!(peers, BTreeSet::from([first.addr(), second.addr()]));
+    assert!(first.requests() > 0);
+    assert!(second.requests() > 0);
+}
+
+#[tokio::test]
+async fn test_connect_samples_failure() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Bind");
+    let addr = listener.local_addr().expect("Get address");
+    drop(listener);
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        format!("http://{addr}").parse().expect("Parse URI"),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ConnectCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_connection_retry_max(0);
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.connects.len(), 1);
+
+    let sample = &collector.connects[0];
+    assert!(!sample.is_success());
+    assert!(sample.error().is_some());
+    assert_eq!(sample.error_kind(), Some(ConnectErrorKind::Refused));
+    assert!(sample.tcp().is_none());
+    assert_eq!(sample.peer_addr(), Some(addr));
+}
+
+#[tokio::test]
+async fn test_max_connect_rate() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        4,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ConnectCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    assert!(matches!(
+        benchmarker.set_max_connect_rate(0),
+        Err(ConfigError::ZeroConnectRate),
+    ));
+    benchmarker
+        .set_max_connect_rate(10)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.connects.len(), 4);
+
+    // The limit is shared across workers, so connects are 100ms apart.
+    let connected_at = collector
+        .connects
+        .iter()
+        .map(|sample| sample.timestamp() + sample.duration())
+        .collect::<Vec<_>>();
+    let first = connected_at.iter().min().unwrap();
+    let last = connected_at.iter().max().unwrap();
+    let spread = last.duration_since(*first).unwrap();
+    assert!(spread >= Duration::from_millis(250), "{spread:?}");
+}
+
+#[tokio::test]
+async fn test_connect_backoff() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Bind");
+    let addr = listener.local_addr().expect("Get address");
+    drop(listener);
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        format!("http://{addr}").parse().expect("Parse URI"),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ConnectCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_connection_retry_max(2);
+    assert!(matches!(
+        benchmarker.set_connect_backoff(Backoff::ExponentialJitter {
+            min: Duration::from_secs(1),
+            max: Duration::from_millis(1),
+        }),
+        Err(ConfigError::InvalidRetryBackoff { .. }),
+    ));
+    benchmarker
+        .set_connect_backoff(Backoff::Fixed(Duration::from_millis(150)))
+        .expect("Set benchmark config");
+
+    let start = Instant::now();
+    benchmarker.run().await;
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+
+    // Every attempt is recorded.
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.connects.len(), 3);
+    for (i, sample) in collector.connects.iter().enumerate() {
+        assert!(!sample.is_success());
+        assert_eq!(sample.error_kind(), Some(ConnectErrorKind::Refused));
+        assert_eq!(sample.attempts(), i + 1);
+    }
+}
+
+#[tokio::test]
+async fn test_connect_timeout() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        Uri::from_static("http://127.0.0.1:8080"),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ConnectCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_connection_retry_max(0);
+    benchmarker.set_transport(PendingTransport);
+    assert!(matches!(
+        benchmarker.set_connect_timeout(Duration::ZERO),
+        Err(ConfigError::ZeroConnectTimeout),
+    ));
+    benchmarker
+        .set_connect_timeout(Duration::from_millis(100))
+        .expect("Set benchmark config");
+
+    let start = Instant::now();
+    benchmarker.run().await;
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.connects.len(), 1);
+
+    let sample = &collector.connects[0];
+    assert_eq!(sample.error_kind(), Some(ConnectErrorKind::ConnectTimeout));
+    assert!(sample.tcp().is_none());
+}
+
+#[tokio::test]
+async fn test_handshake_timeout() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    // The server accepts connections but never completes the TLS handshake.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Bind");
+    let addr = listener.local_addr().expect("Get address");
+    let server = tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            streams.push(stream);
+        }
+    });
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        format!("https://{addr}").parse().expect("Parse URI"),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        ConnectCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_connection_retry_max(0);
+    benchmarker
+        .set_handshake_timeout(Duration::from_millis(100))
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.connects.len(), 1);
+
+    let sample = &collector.connects[0];
+    assert_eq!(
+        sample.error_kind(),
+        Some(ConnectErrorKind::HandshakeTimeout)
+    );
+    assert!(sample.tcp().is_some());
+    assert!(sample.tls().is_none());
+    server.abort();
+}
+
+/// A transport which never finishes opening a stream.
+pub struct PendingTransport;
+
+#[rewrk_core::async_trait]
+impl Transport for PendingTransport {
+    async fn connect(&self) -> io::Result<BoxedTransportStream> {
+        std::future::pending().await
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 10;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ConnectCollector {
+    samples: Vec<Sample>,
+    connects: Vec<ConnectSample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for ConnectCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+
+    async fn process_connect_sample(
+        &mut self,
+        sample: ConnectSample,
+    ) -> anyhow::Result<()> {
+        self.connects.push(sample);
+        Ok(())
+    }
+}
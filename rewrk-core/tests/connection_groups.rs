@@ -0,0 +1,182 @@
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use axum::extract::Path;
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    ConnectionGroup,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20018";
+
+const FAST_TAG: usize = 0;
+const SLOW_TAG: usize = 1;
+
+#[tokio::test]
+async fn test_connection_groups() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        2,
+        HttpProtocol::HTTP1,
+        TimedProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .add_connection_group(ConnectionGroup::new([SLOW_TAG], 1))
+        .expect("Add connection group");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let connections = |tag| {
+        collector
+            .samples
+            .iter()
+            .filter(|sample| sample.tag() == tag && sample.total_requests() > 0)
+            .map(|sample| sample.metadata().connection_id)
+            .collect::<BTreeSet<_>>()
+    };
+    let total_requests = |tag| {
+        collector
+            .samples
+            .iter()
+            .filter(|sample| sample.tag() == tag)
+            .map(|sample| sample.total_requests())
+            .sum::<u64>()
+    };
+
+    // The group's connection is opened after the 2 shared connections.
+    assert_eq!(connections(FAST_TAG), BTreeSet::from([0, 1]));
+    assert_eq!(connections(SLOW_TAG), BTreeSet::from([2]));
+    assert!(total_requests(SLOW_TAG) > 0);
+    assert!(total_requests(FAST_TAG) > total_requests(SLOW_TAG));
+}
+
+#[tokio::test]
+async fn test_connection_group_config() {
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        TimedProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    let err = benchmarker
+        .add_connection_group(ConnectionGroup::new([SLOW_TAG], 0))
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::ZeroGroupConnections));
+
+    benchmarker
+        .add_connection_group(ConnectionGroup::new([SLOW_TAG, 2], 1))
+        .expect("Add connection group");
+    let err = benchmarker
+        .add_connection_group(ConnectionGroup::new([3, 2], 1))
+        .unwrap_err();
+    assert!(matches!(err, ConfigError::DuplicateGroupTag(2)));
+}
+
+async fn run_server() {
+    let app = Router::new().route(
+        "/:speed",
+        get(|Path(speed): Path<String>| async move {
+            if speed == "slow" {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            "Hello, World!"
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct TimedProducer {
+    deadline: Option<Instant>,
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for TimedProducer {
+    fn ready(&mut self) {
+        self.deadline = Some(Instant::now() + Duration::from_millis(300));
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self
+            .deadline
+            .is_none_or(|deadline| Instant::now() >= deadline)
+        {
+            return Ok(RequestBatch::End);
+        }
+
+        // Produce a slow batch for every 10 fast batches.
+        self.count += 1;
+        let (tag, path) = if self.count.is_multiple_of(10) {
+            (SLOW_TAG, "/slow")
+        } else {
+            (FAST_TAG, "/fast")
+        };
+
+        let uri = Uri::builder().path_and_query(path).build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::borrow::Cow;
+
+use axum::http::HeaderMap;
+use axum::routing::get;
+use axum::Router;
+use http::response::Parts;
+use http::{HeaderValue, Method, Request, Uri};
+use hyper::body::Bytes;
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    ResponseValidator,
+    Sample,
+    SampleCollector,
+    ValidationError,
+};
+
+static ADDR: &str = "127.0.0.1:20002";
+
+#[tokio::test]
+async fn test_default_headers() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_user_agent(HeaderValue::from_static("custom-agent"));
+    benchmarker.set_validator(BodyValidator("custom-agent"));
+    benchmarker.run().await;
+
+    let mut collector = benchmarker.consume_collector().await;
+    let sample = collector.samples.remove(0);
+    assert_eq!(sample.total_requests(), 1);
+    assert_eq!(
+        sample.successful_requests(),
+        1,
+        "{:?}",
+        sample.error_exemplars()
+    );
+}
+
+async fn run_server() {
+    // Echo the user agent back to the client.
+    let app = Router::new().route(
+        "/",
+        get(|headers: HeaderMap| async move {
+            headers
+                .get("user-agent")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+pub struct BodyValidator(&'static str);
+
+impl ResponseValidator for BodyValidator {
+    fn validate(&self, _head: Parts, body: Bytes) -> Result<(), ValidationError> {
+        if body == self.0 {
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidBody(Cow::Owned(format!(
+                "{body:?}"
+            ))))
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
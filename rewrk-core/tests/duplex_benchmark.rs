@@ -0,0 +1,105 @@
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    DuplexTransport,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+// Nothing listens on this address, all requests are served in-process.
+static ADDR: &str = "127.0.0.1:20013";
+
+#[tokio::test]
+async fn test_duplex_benchmark_http1() {
+    run_benchmark(HttpProtocol::HTTP1).await;
+}
+
+#[tokio::test]
+async fn test_duplex_benchmark_http2() {
+    run_benchmark(HttpProtocol::HTTP2).await;
+}
+
+async fn run_benchmark(protocol: HttpProtocol) {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        protocol,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+
+    let app = Router::new().route("/", get(|| async { "Hello, World!" }));
+    benchmarker.set_transport(DuplexTransport::new(app));
+    benchmarker.run().await;
+
+    let mut collector = benchmarker.consume_collector().await;
+    let sample = collector.samples.remove(0);
+    assert_eq!(sample.latency().len(), 1);
+    assert_eq!(sample.successful_requests(), 1);
+    assert_eq!(sample.failed_requests(), 0);
+    assert!(sample.error_counts().is_empty());
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use http::Uri;
+use rewrk_core::{
+    DefaultValidator,
+    EtagWorkload,
+    HttpProtocol,
+    ReWrkBenchmark,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20023";
+
+#[derive(Clone, Default)]
+struct Store {
+    version: Arc<Mutex<u64>>,
+    conflicts: Arc<AtomicU64>,
+}
+
+#[tokio::test]
+async fn test_etag_workload() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let store = Store::default();
+    tokio::spawn(run_server(store.clone()));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let workload = EtagWorkload::new(Uri::from_static("/item"), "{}");
+    let producer = workload
+        .producer(Duration::from_millis(250))
+        .with_batch_size(10);
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        4,
+        HttpProtocol::HTTP1,
+        producer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_validator(workload.validator(DefaultValidator));
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    let conflicts = total
+        .classified_latency(&EtagWorkload::classification())
+        .map(|hist| hist.len())
+        .unwrap_or_default();
+    assert_eq!(total.total_errors(), 0);
+    assert_eq!(conflicts, store.conflicts.load(Ordering::Relaxed));
+    assert!(*store.version.lock().unwrap() > 0);
+    assert!(workload.latest_etag().is_some());
+}
+
+async fn run_server(store: Store) {
+    let app = Router::new()
+        .route("/item", get(read_item).put(write_item))
+        .with_state(store);
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+async fn read_item(State(store): State<Store>) -> impl IntoResponse {
+    let version = *store.version.lock().unwrap();
+    ([(header::ETAG, format!("\"v{version}\""))], "{}")
+}
+
+/// Writes the item if the `If-Match` header matches the current version.
+async fn write_item(
+    State(store): State<Store>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let mut version = store.version.lock().unwrap();
+    let current = format!("\"v{}\"", *version);
+    if headers.get(header::IF_MATCH).map(|v| v.as_bytes()) != Some(current.as_bytes()) {
+        store.conflicts.fetch_add(1, Ordering::Relaxed);
+        return (StatusCode::PRECONDITION_FAILED, [(header::ETAG, current)]);
+    }
+
+    *version += 1;
+    let etag = format!("\"v{}\"", *version);
+    (StatusCode::OK, [(header::ETAG, etag)])
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
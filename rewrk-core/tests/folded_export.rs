@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    FoldedStacks,
+    Phase,
+    Producer,
+    RequestBatch,
+    RequestKey,
+    Sample,
+    SampleCollector,
+    SimulatedResponse,
+    Simulation,
+};
+
+const NUM_BATCHES: usize = 10;
+const BATCH_SIZE: usize = 10;
+
+#[tokio::test]
+async fn test_folded_export() {
+    let model = |_key: RequestKey, request: &Request<Body>| {
+        // Tag 0 requests take 1ms, tag 1 requests take 2ms.
+        let latency = if request.uri().path() == "/slow" {
+            Duration::from_millis(2)
+        } else {
+            Duration::from_millis(1)
+        };
+        SimulatedResponse::new(StatusCode::OK, latency)
+    };
+
+    let simulation = Simulation::new(2, BasicProducer::default(), model);
+    let collector = simulation
+        .run(FoldedCollector::default())
+        .await
+        .expect("Run simulation");
+    let mut stacks = collector.stacks;
+
+    let requests_per_tag = (NUM_BATCHES * BATCH_SIZE / 2) as u64;
+    let expected = [
+        ("tag_0;phase_0", requests_per_tag * 1_000),
+        ("tag_1;phase_0", requests_per_tag * 2_000),
+    ];
+    assert_stacks(&stacks.to_string(), &expected);
+
+    stacks.set_tag_label(1, "GET /users; slow");
+    stacks.set_phases(&[Phase::new(
+        "soak",
+        Duration::from_secs(60),
+        Duration::from_secs(1),
+    )]);
+
+    let mut output = Vec::new();
+    stacks.write_to(&mut output).expect("Write stacks");
+    let expected = [
+        ("tag_0;soak", requests_per_tag * 1_000),
+        ("GET_/users__slow;soak", requests_per_tag * 2_000),
+    ];
+    assert_stacks(&String::from_utf8(output).unwrap(), &expected);
+}
+
+/// Checks the stacks match, allowing for the precision of the latency histograms.
+fn assert_stacks(folded: &str, expected: &[(&str, u64)]) {
+    let lines = folded.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), expected.len(), "{folded}");
+
+    for (line, (stack, total)) in lines.iter().zip(expected) {
+        let (actual_stack, actual_total) = line.rsplit_once(' ').unwrap();
+        let actual_total = actual_total.parse::<u64>().unwrap();
+        assert_eq!(actual_stack, *stack);
+        assert!(actual_total.abs_diff(*total) <= total / 100, "{line}");
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = NUM_BATCHES;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let tag = self.count / (NUM_BATCHES / 2);
+            let path = if tag == 1 { "/slow" } else { "/" };
+
+            let mut requests = Vec::with_capacity(BATCH_SIZE);
+            for _ in 0..BATCH_SIZE {
+                let uri = Uri::builder().path_and_query(path).build()?;
+                let request = Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())?;
+                requests.push(request);
+            }
+
+            Ok(RequestBatch::Batch(Batch { tag, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FoldedCollector {
+    stacks: FoldedStacks,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for FoldedCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.stacks.add_sample(&sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use axum::routing::get;
+use axum::Router;
+use http::header::HeaderName;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HeaderCapture,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    OTHER_HEADER_VALUE,
+};
+
+static ADDR: &str = "127.0.0.1:20021";
+
+#[tokio::test]
+async fn test_header_capture() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_header_capture(
+        HeaderCapture::new([HeaderName::from_static("x-served-by")]).with_max_values(2),
+    );
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    let served_by = total
+        .captured_header("x-served-by")
+        .expect("Captured header");
+    assert_eq!(served_by.len(), 3);
+    assert_eq!(served_by["backend-a"], 2);
+    assert_eq!(served_by["backend-b"], 1);
+    // The third backend exceeds the maximum number of distinct values.
+    assert_eq!(served_by[OTHER_HEADER_VALUE], 1);
+    assert_eq!(total.captured_headers().count(), 1);
+}
+
+async fn run_server() {
+    let app = Router::new()
+        .route(
+            "/a",
+            get(|| async { ([("x-served-by", "backend-a")], "Hello, World!") }),
+        )
+        .route(
+            "/b",
+            get(|| async { ([("x-served-by", "backend-b")], "Hello, World!") }),
+        )
+        .route(
+            "/c",
+            get(|| async { ([("x-served-by", "backend-c")], "Hello, World!") }),
+        )
+        .route("/", get(|| async { "Hello, World!" }));
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let requests = ["/a", "/a", "/b", "/c", "/"]
+                .into_iter()
+                .map(|path| {
+                    Request::builder()
+                        .method(Method::GET)
+                        .uri(Uri::from_static(path))
+                        .body(Body::empty())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HealthCheck,
+    HealthCheckAction,
+    HttpProtocol,
+    Phase,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20010";
+static HEALTH_CHECKS: AtomicUsize = AtomicUsize::new(0);
+
+#[tokio::test]
+async fn test_health_check_pause() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+
+    let health_uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/health")
+        .build()
+        .expect("Create URI");
+    let check = HealthCheck::new(
+        health_uri,
+        Duration::from_millis(50),
+        HealthCheckAction::Pause,
+    );
+    benchmarker
+        .set_health_check(check)
+        .expect("Set health check");
+
+    let phases = vec![Phase::new(
+        "main",
+        Duration::from_millis(600),
+        Duration::from_millis(100),
+    )];
+    benchmarker.run_phases(phases).await;
+
+    let collector = benchmarker.consume_collector().await;
+    assert!(collector
+        .samples
+        .iter()
+        .any(|sample| sample.is_target_unhealthy()));
+    assert!(collector
+        .samples
+        .iter()
+        .any(|sample| !sample.is_target_unhealthy()));
+}
+
+/// Binds the server up front, so it accepts connections as soon as it's spawned.
+fn run_server() -> impl Future<Output = hyper::Result<()>> {
+    // build our application with a single route
+    let app = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route(
+            "/health",
+            get(|| async {
+                // The target fails the health checks shortly after starting.
+                match HEALTH_CHECKS.fetch_add(1, Ordering::Relaxed) {
+                    2..=4 => StatusCode::SERVICE_UNAVAILABLE,
+                    _ => StatusCode::OK,
+                }
+            }),
+        );
+
+    axum::Server::bind(&ADDR.parse().unwrap()).serve(app.into_make_service())
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer;
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
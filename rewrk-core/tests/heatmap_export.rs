@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    LatencyHeatmap,
+    Producer,
+    RequestBatch,
+    RequestKey,
+    Sample,
+    SampleCollector,
+    SimulatedResponse,
+    Simulation,
+};
+use serde_json::Value;
+
+const NUM_BATCHES: usize = 10;
+const BATCH_SIZE: usize = 100;
+
+#[tokio::test]
+async fn test_heatmap_export() {
+    let model = |_key: RequestKey, request: &Request<Body>| {
+        let latency = if request.uri().path() == "/slow" {
+            Duration::from_millis(20)
+        } else {
+            Duration::from_millis(1)
+        };
+        SimulatedResponse::new(StatusCode::OK, latency)
+    };
+
+    let mut simulation = Simulation::new(1, BasicProducer::default(), model);
+    simulation
+        .set_sample_window(Duration::from_secs(1))
+        .expect("Set sample window");
+    simulation.set_labels(HashMap::from([
+        ("build".to_string(), "1.2.3".to_string()),
+        ("scenario".to_string(), "slow, then fast".to_string()),
+    ]));
+    let collector = HeatmapCollector {
+        heatmap: LatencyHeatmap::with_bounds([
+            Duration::from_millis(50),
+            Duration::from_millis(5),
+        ]),
+    };
+    let collector = simulation.run(collector).await.expect("Run simulation");
+    let heatmap = collector.heatmap;
+
+    assert_eq!(
+        heatmap.bounds().collect::<Vec<_>>(),
+        [Duration::from_millis(5), Duration::from_millis(50)],
+    );
+
+    let mut csv = Vec::new();
+    heatmap.write_csv(&mut csv).expect("Write csv");
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("build,scenario,round,phase,window,start_us,5000,50000,+Inf")
+    );
+    // The fast requests take 0.5s, leaving room for 25 slow requests.
+    let labels = "1.2.3,\"slow, then fast\"";
+    assert_eq!(
+        lines.next(),
+        Some(format!("{labels},0,0,0,0,500,25,0").as_str())
+    );
+    for window in 1..10 {
+        let expected = format!("{labels},0,0,{window},{},0,50,0", window * 1_000_000);
+        assert_eq!(lines.next(), Some(expected.as_str()));
+    }
+    assert_eq!(
+        lines.next(),
+        Some(format!("{labels},0,0,10,10000000,0,25,0").as_str())
+    );
+    assert_eq!(lines.next(), None);
+
+    let mut json = Vec::new();
+    heatmap.write_json(&mut json).expect("Write json");
+    let json: Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["labels"]["build"], "1.2.3");
+    assert_eq!(json["buckets_us"], serde_json::json!([5000, 50000, null]));
+    assert_eq!(json["windows"].as_array().unwrap().len(), 11);
+    assert_eq!(
+        json["windows"][0]["counts"],
+        serde_json::json!([500, 25, 0])
+    );
+    assert_eq!(json["windows"][3]["start_us"], 3_000_000);
+}
+
+#[test]
+fn test_default_heatmap_buckets() {
+    let bounds = LatencyHeatmap::default().bounds().collect::<Vec<_>>();
+    assert_eq!(bounds.len(), 16);
+    assert_eq!(bounds[0], Duration::from_micros(100));
+    assert_eq!(bounds[1], Duration::from_micros(200));
+    assert_eq!(bounds[15], Duration::from_micros(100 << 15));
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = NUM_BATCHES;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            // The first half of the batches are fast, the second half slow.
+            let path = if self.count < NUM_BATCHES / 2 {
+                "/slow"
+            } else {
+                "/"
+            };
+
+            let mut requests = Vec::with_capacity(BATCH_SIZE);
+            for _ in 0..BATCH_SIZE {
+                let uri = Uri::builder().path_and_query(path).build()?;
+                let request = Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())?;
+                requests.push(request);
+            }
+
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+pub struct HeatmapCollector {
+    heatmap: LatencyHeatmap,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for HeatmapCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.heatmap.add_sample(&sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::routing::get;
+use axum::Router;
+use http::{header, HeaderMap, HeaderValue, Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20025";
+
+type SeenHosts = Arc<Mutex<BTreeSet<String>>>;
+
+#[tokio::test]
+async fn test_host_header() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let seen = SeenHosts::default();
+    tokio::spawn(run_server(seen.clone()));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The port isn't the scheme's default, so it is included.
+    let benchmarker = create_benchmark().await;
+    benchmarker.run().await;
+    assert_eq!(take_hosts(&seen), [ADDR.to_string()].into());
+
+    let mut benchmarker = create_benchmark().await;
+    benchmarker.set_host_header(HeaderValue::from_static("example.com"));
+    benchmarker.run().await;
+    assert_eq!(take_hosts(&seen), ["example.com".to_string()].into());
+}
+
+async fn create_benchmark() -> ReWrkBenchmark<BasicProducer, BasicCollector> {
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector,
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+}
+
+fn take_hosts(seen: &SeenHosts) -> BTreeSet<String> {
+    std::mem::take(&mut *seen.lock().unwrap())
+}
+
+async fn run_server(seen: SeenHosts) {
+    let app = Router::new().route("/", get(record_host)).with_state(seen);
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+async fn record_host(State(seen): State<SeenHosts>, headers: HeaderMap) -> &'static str {
+    if let Some(host) = headers.get(header::HOST) {
+        let host = host.to_str().unwrap().to_string();
+        seen.lock().unwrap().insert(host);
+    }
+    "Hello, World!"
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 5;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count == 0 {
+            return Ok(RequestBatch::End);
+        }
+        self.count -= 1;
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(Uri::from_static("/"))
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+pub struct BasicCollector;
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
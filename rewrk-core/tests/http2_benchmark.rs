@@ -1,6 +1,11 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
 use axum::routing::get;
 use axum::Router;
-use http::{Method, Request, Uri};
+use http::{Method, Request, Response, Uri};
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
 use hyper::Body;
 use rewrk_core::{
     Batch,
@@ -13,6 +18,7 @@ use rewrk_core::{
 };
 
 static ADDR: &str = "127.0.0.1:20000";
+static SMALL_WINDOW_ADDR: &str = "127.0.0.1:20033";
 
 #[tokio::test]
 async fn test_basic_benchmark() {
@@ -36,7 +42,9 @@ async fn test_basic_benchmark() {
     )
     .await
     .expect("Create benchmark");
-    benchmarker.set_num_workers(1);
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
     benchmarker.run().await;
 
     let mut collector = benchmarker.consume_collector().await;
@@ -45,6 +53,69 @@ async fn test_basic_benchmark() {
     assert_eq!(sample.latency().len(), 1);
     assert_eq!(sample.read_transfer().len(), 1);
     assert_eq!(sample.write_transfer().len(), 1);
+    assert_eq!(sample.client_backpressure().len(), 1);
+    assert!(sample.client_backpressure_duration() < sample.latency_mean());
+}
+
+#[tokio::test]
+async fn test_send_window_backpressure() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_small_window_server());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(SMALL_WINDOW_ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP2,
+        UploadProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let mut collector = benchmarker.consume_collector().await;
+    let sample = collector.samples.remove(0);
+    assert_eq!(sample.latency().len(), 1);
+    assert_eq!(sample.error_counts().len(), 0);
+    // The server reads the 8KB body in 1KB windows, sleeping between each.
+    assert!(
+        sample.client_backpressure_duration() >= Duration::from_millis(100),
+        "Expected the send window to block the body, got {:?}",
+        sample.client_backpressure_duration(),
+    );
+    assert!(sample.latency_mean() < sample.client_backpressure_duration());
+}
+
+/// A HTTP/2 server with a 1KB stream window which reads request bodies slowly.
+async fn run_small_window_server() {
+    let make_svc = make_service_fn(|_conn| async {
+        Ok::<_, Infallible>(service_fn(|mut req: Request<Body>| async move {
+            while let Some(chunk) = req.body_mut().data().await {
+                chunk?;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            Ok::<_, hyper::Error>(Response::new(Body::from("Hello, World!")))
+        }))
+    });
+
+    hyper::Server::bind(&SMALL_WINDOW_ADDR.parse().unwrap())
+        .http2_only(true)
+        .http2_initial_stream_window_size(1024)
+        .serve(make_svc)
+        .await
+        .unwrap();
 }
 
 async fn run_server() {
@@ -87,6 +158,36 @@ impl Producer for BasicProducer {
     }
 }
 
+#[derive(Default, Clone)]
+pub struct UploadProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for UploadProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .body(Body::from(vec![0u8; 8 * 1024]))?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct BasicCollector {
     samples: Vec<Sample>,
@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    ImbalanceCause,
+    ImbalanceDetector,
+    ImbalanceMetric,
+    ImbalanceScope,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    RequestKey,
+    Sample,
+    SampleCollector,
+    SimulatedResponse,
+    Simulation,
+    DEFAULT_IMBALANCE_THRESHOLD,
+};
+
+const CONCURRENCY: usize = 4;
+const NUM_BATCHES: usize = 40;
+const BATCH_SIZE: usize = 100;
+const SLOW_CONNECTION: usize = 3;
+
+#[tokio::test]
+async fn test_slow_connection_is_detected() {
+    let model = |key: RequestKey, _request: &Request<Body>| {
+        let latency = if key.connection_id == SLOW_CONNECTION {
+            Duration::from_millis(10)
+        } else {
+            Duration::from_millis(1)
+        };
+        SimulatedResponse::new(StatusCode::OK, latency)
+    };
+
+    let collector = run_simulation(model).await;
+    let warnings = collector.detector.warnings();
+    assert_eq!(
+        warnings.len(),
+        2,
+        "Expected a throughput and latency warning"
+    );
+
+    let scope = ImbalanceScope::Connection {
+        worker_id: 0,
+        connection_id: SLOW_CONNECTION,
+    };
+    for warning in &warnings {
+        assert_eq!(warning.scope, scope);
+        assert_eq!(warning.causes, [ImbalanceCause::TargetRouting]);
+    }
+
+    match warnings[0].metric {
+        ImbalanceMetric::Throughput {
+            requests_per_sec,
+            median,
+        } => {
+            assert!((requests_per_sec - 100.0).abs() < 1.0, "{requests_per_sec}");
+            assert!((median - 1000.0).abs() < 10.0, "{median}");
+        },
+        other => panic!("Expected a throughput warning, got {other:?}"),
+    }
+    assert!((warnings[0].skew - 0.9).abs() < 0.01);
+
+    match warnings[1].metric {
+        ImbalanceMetric::P99Latency { p99, median } => {
+            assert!(p99 >= Duration::from_millis(10), "{p99:?}");
+            assert!(median < Duration::from_millis(2), "{median:?}");
+        },
+        other => panic!("Expected a latency warning, got {other:?}"),
+    }
+
+    let message = warnings[0].to_string();
+    assert!(
+        message.starts_with("connection 3 of worker 0 handled 100.00 req/sec"),
+        "{message}"
+    );
+    assert!(message.contains("90% below the median"), "{message}");
+}
+
+#[tokio::test]
+async fn test_balanced_connections_have_no_warnings() {
+    let model = |_key: RequestKey, _request: &Request<Body>| {
+        SimulatedResponse::new(StatusCode::OK, Duration::from_millis(1))
+    };
+
+    let collector = run_simulation(model).await;
+    assert!(collector.detector.warnings().is_empty());
+}
+
+#[tokio::test]
+async fn test_imbalance_detection_is_opt_in() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    for enabled in [false, true] {
+        let mut benchmarker = ReWrkBenchmark::create(
+            server.uri(),
+            2,
+            HttpProtocol::HTTP1,
+            BasicProducer::default(),
+            ImbalanceCollector::default(),
+        )
+        .await
+        .expect("Create benchmark");
+        benchmarker
+            .set_num_workers(1)
+            .expect("Set benchmark config");
+        if enabled {
+            benchmarker.enable_imbalance_detection(DEFAULT_IMBALANCE_THRESHOLD);
+        }
+        benchmarker.run().await;
+
+        let drained = benchmarker.drain_collector().await;
+        assert_eq!(drained.imbalance().is_some(), enabled);
+    }
+}
+
+async fn run_simulation(
+    model: impl Fn(RequestKey, &Request<Body>) -> SimulatedResponse + Send + 'static,
+) -> ImbalanceCollector {
+    let mut simulation = Simulation::new(CONCURRENCY, BasicProducer::default(), model);
+    simulation
+        .set_sample_window(Duration::from_secs(1))
+        .expect("Set sample window");
+    simulation
+        .run(ImbalanceCollector::default())
+        .await
+        .expect("Run simulation")
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = NUM_BATCHES;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let mut requests = Vec::with_capacity(BATCH_SIZE);
+            for _ in 0..BATCH_SIZE {
+                let uri = Uri::builder().path_and_query("/").build()?;
+                let request = Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())?;
+                requests.push(request);
+            }
+
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ImbalanceCollector {
+    detector: ImbalanceDetector,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for ImbalanceCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.detector.add_sample(&sample);
+        Ok(())
+    }
+}
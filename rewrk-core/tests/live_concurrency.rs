@@ -0,0 +1,138 @@
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_live_concurrency() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        TimedProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(50))
+        .expect("Set benchmark config");
+
+    tokio::join!(benchmarker.run(), async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        benchmarker.set_live_concurrency(3).expect("Scale up");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        benchmarker.set_live_concurrency(1).expect("Scale down");
+    });
+    assert_eq!(benchmarker.live_concurrency(), 1);
+
+    let collector = benchmarker.consume_collector().await;
+    let connections = collector
+        .samples
+        .iter()
+        .filter(|sample| sample.total_requests() > 0)
+        .map(|sample| sample.metadata().connection_id)
+        .collect::<BTreeSet<_>>();
+    assert_eq!(connections, BTreeSet::from([0, 1, 2]));
+
+    // The connections added while running should have been drained
+    // well before the original connection finished.
+    let runtime = |connection_id| {
+        collector
+            .samples
+            .iter()
+            .filter(|sample| sample.metadata().connection_id == connection_id)
+            .map(|sample| sample.duration())
+            .sum::<Duration>()
+    };
+    assert!(runtime(2) < runtime(0));
+}
+
+#[tokio::test]
+async fn test_live_concurrency_below_workers() {
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        4,
+        HttpProtocol::HTTP1,
+        TimedProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+
+    let err = benchmarker.set_live_concurrency(1).unwrap_err();
+    assert!(matches!(
+        err,
+        ConfigError::ConcurrencyBelowWorkers {
+            concurrency: 1,
+            num_workers: 2
+        }
+    ));
+    assert_eq!(benchmarker.live_concurrency(), 4);
+}
+
+#[derive(Default, Clone)]
+pub struct TimedProducer {
+    deadline: Option<Instant>,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for TimedProducer {
+    fn ready(&mut self) {
+        self.deadline = Some(Instant::now() + Duration::from_millis(600));
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() < deadline)
+        {
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    MemoryLimitAction,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20007";
+
+#[tokio::test]
+async fn test_memory_limit_abort() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_memory_limit(1, MemoryLimitAction::Abort)
+        .expect("Set benchmark config");
+
+    // The producer never ends, so this only completes once the
+    // memory watchdog aborts the benchmark.
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    assert!(!collector.samples.is_empty());
+}
+
+async fn run_server() {
+    // build our application with a single route
+    let app = Router::new().route("/", get(|| async { "Hello, World!" }));
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer;
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
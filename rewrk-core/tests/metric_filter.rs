@@ -0,0 +1,141 @@
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Metric,
+    MetricFilter,
+    MetricKind,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_metric_filter_tags_and_kinds() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let filter = MetricFilter::all()
+        .with_kinds([MetricKind::Sample])
+        .with_tags([1]);
+    let collector = run_benchmark(filter).await;
+
+    assert!(!collector.metrics.is_empty());
+    for metric in &collector.metrics {
+        match metric {
+            Metric::Sample(sample) => assert_eq!(sample.tag(), 1),
+            other => panic!("Unexpected record {other:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_metric_filter_worker_ids() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let collector = run_benchmark(MetricFilter::all().with_worker_ids([1])).await;
+
+    let mut kinds = Vec::new();
+    for metric in &collector.metrics {
+        assert_eq!(metric.metadata().map(|m| m.worker_id), Some(1));
+        kinds.push(metric.kind());
+    }
+    assert!(kinds.contains(&MetricKind::Sample));
+    assert!(kinds.contains(&MetricKind::ConnectSample));
+    assert!(kinds.contains(&MetricKind::WorkerReport));
+}
+
+#[tokio::test]
+async fn test_metric_filter_all() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let collector = run_benchmark(MetricFilter::all()).await;
+
+    let mut tags = collector
+        .metrics
+        .iter()
+        .filter_map(|metric| match metric {
+            Metric::Sample(sample) => Some(sample.tag()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    tags.sort_unstable();
+    tags.dedup();
+    assert_eq!(tags, [0, 1]);
+}
+
+async fn run_benchmark(filter: MetricFilter) -> FilteredCollector {
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        FilteredCollector {
+            filter,
+            metrics: Vec::new(),
+        },
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    benchmarker.consume_collector().await
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 10;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: self.count % 2,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+pub struct FilteredCollector {
+    filter: MetricFilter,
+    metrics: Vec<Metric>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for FilteredCollector {
+    async fn process_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn filter(&self) -> MetricFilter {
+        self.filter.clone()
+    }
+
+    async fn process_metric(&mut self, metric: Metric) -> anyhow::Result<()> {
+        self.metrics.push(metric);
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Metric,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_metric_stream() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        4,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        MetricCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.process_sample_calls, 0);
+
+    let mut samples = 0;
+    let mut connects = 0;
+    let mut reports = Vec::new();
+    for metric in collector.metrics {
+        match metric {
+            Metric::Sample(_) => samples += 1,
+            Metric::ConnectSample(_) => connects += 1,
+            Metric::WorkerReport(report) => reports.push(report),
+            _ => {},
+        }
+    }
+
+    assert!(samples > 0);
+    assert_eq!(connects, 4);
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports.iter().map(|r| r.connections()).sum::<usize>(), 4);
+    assert!(reports.iter().all(|r| !r.execute_wait().is_zero()));
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 10;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MetricCollector {
+    metrics: Vec<Metric>,
+    process_sample_calls: usize,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for MetricCollector {
+    async fn process_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+        self.process_sample_calls += 1;
+        Ok(())
+    }
+
+    async fn process_metric(&mut self, metric: Metric) -> anyhow::Result<()> {
+        self.metrics.push(metric);
+        Ok(())
+    }
+}
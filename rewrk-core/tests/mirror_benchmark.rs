@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::testing::{ScriptedResponse, TestServer};
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    SendMode,
+};
+
+const RESPONSE_DELAY: Duration = Duration::from_millis(200);
+
+#[tokio::test]
+async fn test_mirror_benchmark() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::scripted(vec![
+        ScriptedResponse::new(StatusCode::OK).with_delay(RESPONSE_DELAY)
+    ])
+    .await
+    .expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP2,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_send_mode(SendMode::Mirror { max_in_flight: 10 })
+        .expect("Set send mode");
+
+    let start = Instant::now();
+    benchmarker.run().await;
+    let elapsed = start.elapsed();
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    // Waiting for each response in turn would take 20x the response delay.
+    assert!(elapsed < RESPONSE_DELAY * 10, "{elapsed:?}");
+    assert_eq!(total.total_requests(), 20);
+    assert_eq!(total.mirrored_requests(), 20);
+    assert_eq!(total.successful_requests(), 0);
+    assert_eq!(total.failed_requests(), 0);
+    assert!(total.latency().is_empty());
+}
+
+#[tokio::test]
+async fn test_mirror_benchmark_config() {
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    assert_eq!(
+        benchmarker.set_send_mode(SendMode::Mirror { max_in_flight: 0 }),
+        Err(ConfigError::ZeroMirrorInFlight),
+    );
+    assert_eq!(benchmarker.set_send_mode(SendMode::Full), Ok(()));
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 20;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
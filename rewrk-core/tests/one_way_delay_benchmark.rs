@@ -0,0 +1,122 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    OneWayDelay,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20009";
+
+#[tokio::test]
+async fn test_one_way_delay() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_one_way_delay(OneWayDelay::default());
+    benchmarker.run().await;
+
+    let mut collector = benchmarker.consume_collector().await;
+    let sample = collector.samples.remove(0);
+    assert_eq!(sample.successful_requests(), 3);
+    assert_eq!(sample.forward_delay().len(), 3);
+    assert_eq!(sample.backward_delay().len(), 3);
+    // The first request sets the baseline so has no added delay.
+    assert_eq!(sample.forward_delay().min(), 0);
+    assert_eq!(sample.backward_delay().min(), 0);
+}
+
+async fn run_server() {
+    // build our application with a single route
+    let app = Router::new().route(
+        "/",
+        get(|| async {
+            let received_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_micros();
+            (
+                [("x-rewrk-received-at", received_at.to_string())],
+                "Hello, World!",
+            )
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let requests = (0..3)
+                .map(|_| {
+                    Request::builder()
+                        .method(Method::GET)
+                        .uri(uri.clone())
+                        .body(Body::empty())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
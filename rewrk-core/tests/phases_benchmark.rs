@@ -0,0 +1,129 @@
+use std::time::{Duration, Instant};
+
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Phase,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20006";
+
+#[tokio::test]
+async fn test_benchmark_phases() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+
+    let phases = vec![
+        Phase::new(
+            "short",
+            Duration::from_millis(200),
+            Duration::from_millis(50),
+        ),
+        Phase::new(
+            "long",
+            Duration::from_millis(400),
+            Duration::from_millis(200),
+        ),
+    ];
+    let start = Instant::now();
+    benchmarker.run_phases(phases).await;
+    assert!(start.elapsed() >= Duration::from_millis(600));
+
+    let collector = benchmarker.consume_collector().await;
+    let short = collector
+        .samples
+        .iter()
+        .filter(|sample| sample.metadata().phase == 0)
+        .count();
+    let long = collector
+        .samples
+        .iter()
+        .filter(|sample| sample.metadata().phase == 1)
+        .count();
+    assert!(short >= 3, "Expected at least 3 samples got {short}");
+    assert!(long >= 2, "Expected at least 2 samples got {long}");
+    assert!(short > long);
+    assert_eq!(
+        collector.phases,
+        vec![(0, "short".to_string()), (1, "long".to_string())]
+    );
+}
+
+async fn run_server() {
+    // build our application with a single route
+    let app = Router::new().route("/", get(|| async { "Hello, World!" }));
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer;
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+    phases: Vec<(usize, String)>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+
+    async fn start_phase(&mut self, index: usize, phase: &Phase) -> anyhow::Result<()> {
+        self.phases.push((index, phase.label().to_string()));
+        Ok(())
+    }
+}
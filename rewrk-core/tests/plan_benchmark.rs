@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use http::header::HeaderName;
+use http::{HeaderValue, Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Backoff,
+    Batch,
+    BenchmarkPlan,
+    ConnectionGroup,
+    HeaderCapture,
+    HealthCheck,
+    HealthCheckAction,
+    HttpProtocol,
+    MemoryLimitAction,
+    OneWayDelay,
+    Phase,
+    PlanError,
+    PrimingPlan,
+    Producer,
+    ProducerEnd,
+    ReWrkBenchmark,
+    Registry,
+    RequestBatch,
+    ResponseTracking,
+    RetryPolicy,
+    Sample,
+    SampleCollector,
+    SendMode,
+    ServerTimingSource,
+};
+
+static ADDR: &str = "127.0.0.1:20014";
+
+#[tokio::test]
+async fn test_plan_round_trip() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        4,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(250))
+        .expect("Set benchmark config");
+    benchmarker.set_rounds(3).expect("Set benchmark config");
+    benchmarker.set_round_cooldown(Duration::from_secs(2));
+    benchmarker
+        .set_max_connect_rate(50)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_target_rps(1_000)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_memory_limit(512 << 20, MemoryLimitAction::Abort)
+        .expect("Set benchmark config");
+    benchmarker.set_warmup(Duration::from_secs(1));
+    benchmarker.set_outlier_threshold(Duration::from_millis(20));
+    benchmarker.set_default_header(
+        HeaderName::from_static("x-api-key"),
+        HeaderValue::from_static("secret"),
+    );
+    benchmarker
+        .set_health_check(HealthCheck::new(
+            Uri::from_static("http://127.0.0.1:20014/health"),
+            Duration::from_millis(500),
+            HealthCheckAction::Pause,
+        ))
+        .expect("Set health check");
+    benchmarker.set_server_timing(ServerTimingSource::Header(HeaderName::from_static(
+        "x-response-time",
+    )));
+    benchmarker.set_one_way_delay(OneWayDelay::default());
+    benchmarker.set_labels(HashMap::from([("region".to_string(), "eu".to_string())]));
+
+    let mut policy = RetryPolicy::default();
+    policy.set_statuses(vec![StatusCode::SERVICE_UNAVAILABLE]);
+    policy.set_max_attempts(5);
+    policy.set_backoff(Backoff::Exponential {
+        min: Duration::from_millis(5),
+        max: Duration::from_millis(100),
+    });
+    benchmarker.set_retry_policy(policy);
+
+    benchmarker
+        .set_slow_start(10, Duration::from_millis(100))
+        .expect("Set benchmark config");
+    benchmarker
+        .set_start_jitter(Duration::from_millis(30))
+        .expect("Set benchmark config");
+    benchmarker.set_sticky_routing(true);
+    benchmarker
+        .set_send_mode(SendMode::Mirror { max_in_flight: 8 })
+        .expect("Set benchmark config");
+    benchmarker.set_producer_end(ProducerEnd::Drain);
+    benchmarker
+        .set_tag_sample_window(1, Duration::from_millis(50))
+        .expect("Set benchmark config");
+    benchmarker
+        .set_worker_sample_window(0, Duration::from_millis(500))
+        .expect("Set benchmark config");
+    benchmarker
+        .set_window_stats(Duration::from_secs(1))
+        .expect("Set benchmark config");
+    benchmarker
+        .add_connection_group(ConnectionGroup::new([1, 2], 2))
+        .expect("Set benchmark config");
+    benchmarker.set_collector_drain_timeout(Duration::from_secs(5));
+    benchmarker.set_host_header(HeaderValue::from_static("example.com"));
+    benchmarker.set_response_tracking(ResponseTracking::new(HeaderName::from_static(
+        "x-request-key",
+    )));
+    benchmarker.set_header_capture(
+        HeaderCapture::new([HeaderName::from_static("x-served-by")]).with_max_values(4),
+    );
+    benchmarker.set_phases(vec![
+        Phase::new("ramp", Duration::from_secs(5), Duration::from_millis(100)),
+        Phase::new("soak", Duration::from_secs(60), Duration::from_secs(1)),
+    ]);
+
+    let mut plan = benchmarker.export_plan();
+    assert_eq!(plan.target, "http://127.0.0.1:20014/");
+    assert_eq!(plan.concurrency, 4);
+    assert_eq!(plan.num_workers, 2);
+    assert_eq!(plan.validator, rewrk_core::DEFAULT_VALIDATOR_NAME);
+    assert_eq!(plan.labels["region"], "eu");
+    assert_eq!(plan.host_header.as_deref(), Some("example.com"));
+    assert_eq!(plan.phases.len(), 2);
+    assert_eq!(plan.window_stats, Some(Duration::from_secs(1)));
+
+    // Priming producers are only part of the plan when created from the registry.
+    assert_eq!(plan.priming, None);
+    plan.priming = Some(PrimingPlan {
+        producer: "basic".to_string(),
+        options: serde_json::json!({ "requests": 3 }),
+        duration: Duration::from_secs(2),
+    });
+
+    let json = serde_json::to_string(&plan).expect("Serialize plan");
+    let decoded: BenchmarkPlan = serde_json::from_str(&json).expect("Deserialize plan");
+    assert_eq!(decoded, plan);
+
+    let mut registry = Registry::default();
+    registry.register_producer("basic", |_| Ok(BasicProducer::default()));
+    let imported = ReWrkBenchmark::from_plan_with_registry(
+        &decoded,
+        &registry,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark from plan");
+    assert_eq!(imported.export_plan(), plan);
+    assert_eq!(imported.phases()[1].label(), "soak");
+}
+
+#[tokio::test]
+async fn test_plan_defaults() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    // Older plans without the newer settings use their defaults.
+    let mut json =
+        serde_json::to_value(benchmarker.export_plan()).expect("Serialize plan");
+    let object = json.as_object_mut().unwrap();
+    for key in [
+        "host_header",
+        "send_mode",
+        "producer_end",
+        "phases",
+        "slow_start",
+    ] {
+        object.remove(key);
+    }
+    let decoded: BenchmarkPlan = serde_json::from_value(json).expect("Deserialize plan");
+    assert_eq!(decoded, benchmarker.export_plan());
+}
+
+#[tokio::test]
+async fn test_plan_errors() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    let plan = benchmarker.export_plan();
+
+    let mut unknown_validator = plan.clone();
+    unknown_validator.validator = "does-not-exist".to_string();
+    let error = ReWrkBenchmark::from_plan(
+        &unknown_validator,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .err()
+    .expect("Reject unknown validator");
+    assert!(
+        matches!(error, PlanError::UnknownValidator(name) if name == "does-not-exist")
+    );
+
+    let mut zero_rounds = plan;
+    zero_rounds.rounds = 0;
+    let error = ReWrkBenchmark::from_plan(
+        &zero_rounds,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .err()
+    .expect("Reject invalid config");
+    assert!(matches!(error, PlanError::Config(_)));
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use std::borrow::Cow;
+
+use axum::routing::get;
+use axum::Router;
+use http::response::Parts;
+use http::{Method, Request, StatusCode, Uri, Version};
+use hyper::body::Bytes;
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    DefaultValidator,
+    HttpProtocol,
+    PreflightError,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    ResponseValidator,
+    Sample,
+    SampleCollector,
+    ValidationError,
+};
+
+static ADDR: &str = "127.0.0.1:20011";
+static HTTP2_ADDR: &str = "127.0.0.1:20032";
+
+#[tokio::test]
+async fn test_preflight() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    let report = benchmarker.preflight().await.expect("Pre-flight check");
+    assert_eq!(report.status, StatusCode::OK);
+    assert_eq!(report.version, Version::HTTP_11);
+    assert_eq!(report.addr, ADDR.parse().unwrap());
+
+    // The probe is sent with the benchmark's method.
+    benchmarker.set_preflight_method(Method::POST);
+    let error = benchmarker.preflight().await.expect_err("Reject method");
+    assert!(matches!(
+        error,
+        PreflightError::Validation {
+            status: StatusCode::METHOD_NOT_ALLOWED,
+            ..
+        }
+    ));
+    benchmarker.set_preflight_method(Method::GET);
+
+    benchmarker.set_validator(RejectingValidator);
+    let error = benchmarker.preflight().await.expect_err("Reject response");
+    assert!(matches!(
+        error,
+        PreflightError::Validation {
+            status: StatusCode::OK,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn test_preflight_http2() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server_at(HTTP2_ADDR));
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(HTTP2_ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP2,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    let report = benchmarker.preflight().await.expect("Pre-flight check");
+    assert_eq!(report.status, StatusCode::OK);
+    assert_eq!(report.version, Version::HTTP_2);
+}
+
+#[tokio::test]
+async fn test_preflight_connect_error() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority("127.0.0.1:1")
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker.set_connection_retry_max(0);
+    benchmarker.set_validator(DefaultValidator);
+
+    let error = benchmarker.preflight().await.expect_err("Fail to connect");
+    assert!(matches!(error, PreflightError::Connect { .. }));
+}
+
+pub struct RejectingValidator;
+
+impl ResponseValidator for RejectingValidator {
+    fn validate(&self, _head: Parts, _body: Bytes) -> Result<(), ValidationError> {
+        Err(ValidationError::Other(Cow::Borrowed("rejected")))
+    }
+}
+
+async fn run_server() {
+    run_server_at(ADDR).await
+}
+
+async fn run_server_at(addr: &str) {
+    // build our application with a single route
+    let app = Router::new().route("/", get(|| async { "Hello, World!" }));
+
+    axum::Server::bind(&addr.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_priming_producer() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        CountingProducer::new("measured", 5, log.clone()),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_priming_producer(
+        CountingProducer::new("priming", 20, log.clone()),
+        Duration::from_secs(10),
+    );
+
+    benchmarker.run().await;
+    let collector = benchmarker.consume_collector().await;
+
+    assert_eq!(*log.lock().unwrap(), ["priming", "measured"]);
+    assert_eq!(server.requests(), 25);
+
+    let total_requests: u64 = collector.samples.iter().map(Sample::total_requests).sum();
+    assert_eq!(
+        total_requests, 5,
+        "Priming requests should not be collected"
+    );
+    assert_eq!(collector.rounds_started, 1);
+}
+
+#[tokio::test]
+async fn test_priming_producer_duration() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        CountingProducer::new("measured", 5, log.clone()),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_priming_producer(
+        CountingProducer::new("priming", usize::MAX, log.clone()),
+        Duration::from_millis(200),
+    );
+
+    tokio::time::timeout(Duration::from_secs(5), benchmarker.run())
+        .await
+        .expect("Priming should stop after its duration");
+    let collector = benchmarker.consume_collector().await;
+
+    let total_requests: u64 = collector.samples.iter().map(Sample::total_requests).sum();
+    assert_eq!(total_requests, 5);
+    assert!(server.requests() > 5);
+}
+
+/// A producer which sends a fixed number of requests, logging when it starts.
+#[derive(Clone)]
+pub struct CountingProducer {
+    name: &'static str,
+    remaining: usize,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl CountingProducer {
+    fn new(
+        name: &'static str,
+        requests: usize,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    ) -> Self {
+        Self {
+            name,
+            remaining: requests,
+            log,
+        }
+    }
+}
+
+#[rewrk_core::async_trait]
+impl Producer for CountingProducer {
+    fn ready(&mut self) {
+        self.log.lock().unwrap().push(self.name);
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.remaining == 0 {
+            return Ok(RequestBatch::End);
+        }
+        self.remaining -= 1;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+    rounds_started: usize,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+
+    async fn start_round(&mut self, _round: usize) -> anyhow::Result<()> {
+        self.rounds_started += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::extract::Path;
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20019";
+static REQUESTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+const NUM_BULK_BATCHES: usize = 4;
+
+#[tokio::test]
+async fn test_priority_batches() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        PriorityProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    assert_eq!(total_requests, NUM_BULK_BATCHES as u64 + 1);
+
+    // The priority batch is produced last but skips the bulk
+    // batches still waiting for the connection.
+    let requests = REQUESTS.lock().unwrap();
+    let position = requests
+        .iter()
+        .position(|path| path == "priority")
+        .expect("Priority request sent");
+    assert!(position < 2, "{requests:?}");
+}
+
+async fn run_server() {
+    let app = Router::new().route(
+        "/:kind",
+        get(|Path(kind): Path<String>| async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            REQUESTS.lock().unwrap().push(kind);
+            "Hello, World!"
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct PriorityProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for PriorityProducer {
+    fn ready(&mut self) {
+        self.count = 0;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        self.count += 1;
+        if self.count > NUM_BULK_BATCHES + 1 {
+            return Ok(RequestBatch::End);
+        }
+
+        let path = if self.count > NUM_BULK_BATCHES {
+            "/priority"
+        } else {
+            "/bulk"
+        };
+        let uri = Uri::builder().path_and_query(path).build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        let batch = Batch {
+            tag: 0,
+            requests: vec![request],
+        };
+
+        if self.count > NUM_BULK_BATCHES {
+            Ok(RequestBatch::Priority(batch))
+        } else {
+            Ok(RequestBatch::Batch(batch))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ProducerEnd,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+/// How long the producers which don't end early keep producing.
+const RUN_DURATION: Duration = Duration::from_millis(750);
+
+#[tokio::test]
+async fn test_producer_end_benchmark() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let (elapsed, _) = run_benchmark(ProducerEnd::Benchmark).await;
+    assert!(elapsed < RUN_DURATION / 2, "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn test_producer_end_worker() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let (elapsed, _) = run_benchmark(ProducerEnd::Worker).await;
+    assert!(elapsed >= RUN_DURATION, "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn test_producer_end_drain() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let (elapsed, (produced, sent)) = run_benchmark(ProducerEnd::Drain).await;
+    assert!(elapsed < RUN_DURATION / 2, "{elapsed:?}");
+    assert_eq!(sent, produced, "Every produced request should be sent");
+}
+
+/// Runs the benchmark, returning the elapsed time along with
+/// the number of requests produced and sent.
+async fn run_benchmark(end: ProducerEnd) -> (Duration, (u64, u64)) {
+    let server = TestServer::echo().await.expect("Start server");
+    let producer = MixedProducer::default();
+    let produced = producer.produced.clone();
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        producer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker.set_producer_end(end);
+
+    let start = Instant::now();
+    benchmarker.run().await;
+    let elapsed = start.elapsed();
+
+    let collector = benchmarker.consume_collector().await;
+    assert!(!collector.samples.is_empty());
+
+    let sent = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum();
+    (elapsed, (produced.load(Ordering::Relaxed) as u64, sent))
+}
+
+/// A producer where only the first clone to start ends early.
+#[derive(Default)]
+pub struct MixedProducer {
+    started: Arc<AtomicUsize>,
+    produced: Arc<AtomicUsize>,
+    remaining: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl Clone for MixedProducer {
+    fn clone(&self) -> Self {
+        Self {
+            started: self.started.clone(),
+            produced: self.produced.clone(),
+            remaining: None,
+            deadline: None,
+        }
+    }
+}
+
+#[rewrk_core::async_trait]
+impl Producer for MixedProducer {
+    fn ready(&mut self) {
+        if self.started.fetch_add(1, Ordering::Relaxed) == 0 {
+            self.remaining = Some(5);
+        }
+        self.deadline = Some(Instant::now() + RUN_DURATION);
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if let Some(remaining) = self.remaining.as_mut() {
+            if *remaining == 0 {
+                return Ok(RequestBatch::End);
+            }
+            *remaining -= 1;
+        }
+
+        if self
+            .deadline
+            .is_none_or(|deadline| Instant::now() >= deadline)
+        {
+            return Ok(RequestBatch::End);
+        }
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        self.produced.fetch_add(1, Ordering::Relaxed);
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
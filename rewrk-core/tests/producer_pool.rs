@@ -0,0 +1,233 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20024";
+static SLOW_ADDR: &str = "127.0.0.1:20035";
+const SLOW_PRODUCER_DELAY: Duration = Duration::from_millis(500);
+const NUM_WORKERS: usize = 2;
+const NUM_BATCHES: usize = 25;
+
+#[tokio::test]
+async fn test_producer_pool() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let producer = PooledProducer::default();
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        NUM_WORKERS * 2,
+        HttpProtocol::HTTP1,
+        producer.clone(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(NUM_WORKERS)
+        .expect("Set benchmark config");
+    assert_eq!(
+        benchmarker.set_producer_pool(0, 4),
+        Err(ConfigError::ZeroProducerThreads),
+    );
+    assert_eq!(
+        benchmarker.set_producer_pool(2, 0),
+        Err(ConfigError::ZeroProducerHandoff),
+    );
+    // The last handoff is only partially filled when the producers end.
+    benchmarker
+        .set_producer_pool(2, 4)
+        .expect("Set producer pool");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+
+    // Every batch including the priority batch is sent exactly once.
+    let expected = NUM_WORKERS * (NUM_BATCHES + 1);
+    assert_eq!(total_requests, expected as u64);
+    assert_eq!(
+        producer.pooled_batches.load(Ordering::Relaxed),
+        NUM_WORKERS * NUM_BATCHES,
+        "Expected every batch to be produced on the producer pool",
+    );
+}
+
+#[tokio::test]
+async fn test_producer_pool_flushes_partial_handoff() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let first_request = Arc::new(OnceLock::new());
+    tokio::spawn(run_timed_server(first_request.clone()));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(SLOW_ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let producer = SlowProducer::default();
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        producer.clone(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_producer_pool(1, 100)
+        .expect("Set producer pool");
+    benchmarker.run().await;
+
+    // The first batch is sent while the producer is still working on the
+    // next, rather than waiting for the handoff to fill or the producer to end.
+    let first_request = *first_request.get().expect("A request is sent");
+    let ended = *producer.ended.get().expect("The producer ends");
+    assert!(
+        first_request + SLOW_PRODUCER_DELAY / 2 < ended,
+        "Expected the batch to be handed off before the producer ended",
+    );
+}
+
+async fn run_timed_server(first_request: Arc<OnceLock<Instant>>) {
+    let app = Router::new().route(
+        "/",
+        get(move || {
+            let _ = first_request.set(Instant::now());
+            async { "Hello, World!" }
+        }),
+    );
+
+    axum::Server::bind(&SLOW_ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+async fn run_server() {
+    let app = Router::new().route("/", get(|| async { "Hello, World!" }));
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct PooledProducer {
+    count: usize,
+    pooled_batches: Arc<AtomicUsize>,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for PooledProducer {
+    fn ready(&mut self) {
+        self.count = NUM_BATCHES + 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count == 0 {
+            return Ok(RequestBatch::End);
+        }
+        self.count -= 1;
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(Uri::from_static("/"))
+            .body(Body::empty())?;
+        let batch = Batch {
+            tag: 0,
+            requests: vec![request],
+        };
+
+        if self.count == NUM_BATCHES / 2 {
+            return Ok(RequestBatch::Priority(batch));
+        }
+
+        let thread = std::thread::current();
+        if thread.name() == Some("rewrk-producer") {
+            self.pooled_batches.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(RequestBatch::Batch(batch))
+    }
+}
+
+/// Produces a single batch and then takes a long time to produce the next.
+#[derive(Default, Clone)]
+pub struct SlowProducer {
+    count: usize,
+    ended: Arc<OnceLock<Instant>>,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for SlowProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count == 0 {
+            tokio::time::sleep(SLOW_PRODUCER_DELAY).await;
+            let _ = self.ended.set(Instant::now());
+            return Ok(RequestBatch::End);
+        }
+        self.count -= 1;
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(Uri::from_static("/"))
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
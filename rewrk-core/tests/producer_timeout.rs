@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    RunError,
+    Sample,
+    SampleCollector,
+};
+
+const NUM_BATCHES: usize = 10;
+
+#[tokio::test]
+async fn test_producer_timeout() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        StallingProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    assert!(matches!(
+        benchmarker.set_producer_timeout(Duration::ZERO),
+        Err(ConfigError::ZeroProducerTimeout),
+    ));
+    benchmarker
+        .set_producer_timeout(Duration::from_millis(200))
+        .expect("Set benchmark config");
+
+    let start = Instant::now();
+    benchmarker.run().await;
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(
+        benchmarker.run_error(),
+        Some(RunError::ProducerStalled {
+            worker_id: 0,
+            timeout: Duration::from_millis(200),
+        }),
+    );
+
+    // The batches produced before the producer stalled are still sent.
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    assert_eq!(total_requests, NUM_BATCHES as u64);
+}
+
+#[tokio::test]
+async fn test_producer_within_timeout() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        StallingProducer {
+            stall_after: None,
+            count: 0,
+        },
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_producer_timeout(Duration::from_millis(200))
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+    assert_eq!(benchmarker.run_error(), None);
+}
+
+#[derive(Clone)]
+pub struct StallingProducer {
+    stall_after: Option<usize>,
+    count: usize,
+}
+
+impl Default for StallingProducer {
+    fn default() -> Self {
+        Self {
+            stall_after: Some(NUM_BATCHES),
+            count: 0,
+        }
+    }
+}
+
+#[rewrk_core::async_trait]
+impl Producer for StallingProducer {
+    fn ready(&mut self) {
+        self.count = 0;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        match self.stall_after {
+            Some(stall_after) if self.count == stall_after => {
+                tokio::time::sleep(Duration::from_secs(3600)).await;
+            },
+            None if self.count == NUM_BATCHES => return Ok(RequestBatch::End),
+            _ => {},
+        }
+        self.count += 1;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ProgressSnapshot,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_progress_handler() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        PacedProducer,
+        BasicCollector,
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(50))
+        .expect("Set benchmark config");
+
+    let progress = Arc::new(Mutex::new(Vec::<ProgressSnapshot>::new()));
+    let handler_progress = progress.clone();
+    benchmarker.set_progress_handler(move |snapshot| {
+        handler_progress.lock().unwrap().push(snapshot);
+    });
+    benchmarker.run_for(Duration::from_millis(400)).await;
+
+    let progress = progress.lock().unwrap();
+    assert!(
+        progress.len() >= 4,
+        "Expected progress every window, got {progress:?}"
+    );
+    for (previous, snapshot) in progress.iter().zip(progress.iter().skip(1)) {
+        assert!(snapshot.elapsed() > previous.elapsed());
+        assert!(snapshot.requests_sent() >= previous.requests_sent());
+    }
+
+    // The final progress is reported once every sample has been processed.
+    let last = progress.last().unwrap();
+    assert_eq!(last.requests_sent(), server.requests() as u64);
+    assert_eq!(last.errors(), 0);
+    assert!(last.elapsed() >= Duration::from_millis(400));
+    assert!(last.requests_per_sec() > 0.0);
+}
+
+#[derive(Clone)]
+pub struct PacedProducer;
+
+#[rewrk_core::async_trait]
+impl Producer for PacedProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+pub struct BasicCollector;
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
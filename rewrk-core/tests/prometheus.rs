@@ -0,0 +1,105 @@
+#![cfg(feature = "prometheus")]
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::{Body, Client};
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_prometheus_metrics() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        BasicProducer,
+        BasicCollector,
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(50))
+        .expect("Set benchmark config");
+    benchmarker.set_labels(HashMap::from([(
+        "service".to_string(),
+        "checkout".to_string(),
+    )]));
+
+    let metrics = benchmarker
+        .serve_metrics(([127, 0, 0, 1], 0).into())
+        .await
+        .expect("Serve metrics");
+    let uri = format!("http://{}/metrics", metrics.addr());
+
+    benchmarker.run_for(Duration::from_millis(300)).await;
+
+    let client = Client::new();
+    let response = client.get(uri.parse().unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    let requests = body
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix(r#"rewrk_requests_total{service="checkout",tag="0"} "#)
+        })
+        .expect("Find requests metric");
+    assert_eq!(requests.parse::<usize>().unwrap(), server.requests());
+    assert!(body.contains("# TYPE rewrk_latency_seconds summary"));
+    assert!(body.contains(
+        r#"rewrk_latency_seconds{service="checkout",tag="0",quantile="0.99"}"#
+    ));
+    assert!(body.contains("rewrk_requests_per_second{"));
+
+    let other = format!("http://{}/", metrics.addr());
+    let response = client.get(other.parse().unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[derive(Clone)]
+pub struct BasicProducer;
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+pub struct BasicCollector;
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
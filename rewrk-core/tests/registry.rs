@@ -0,0 +1,232 @@
+use std::borrow::Cow;
+
+use http::response::Parts;
+use http::{Method, Request, StatusCode, Uri};
+use hyper::body::Bytes;
+use hyper::Body;
+use rewrk_core::testing::{assert_sample, TestServer};
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    PlanError,
+    Producer,
+    ReWrkBenchmark,
+    Registry,
+    RegistryError,
+    RequestBatch,
+    ResponseValidator,
+    Sample,
+    SampleCollector,
+    ValidationError,
+    ValidationErrorKind,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_registry_components() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::fixed(StatusCode::ACCEPTED, "Accepted")
+        .await
+        .expect("Start server");
+    let registry = create_registry();
+
+    let producer = registry
+        .create_producer("basic", &json!({ "requests": 3 }))
+        .expect("Create producer");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        producer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+
+    let mut plan = benchmarker.export_plan();
+    plan.validator = "status".to_string();
+    plan.validator_options = json!({ "status": 202 });
+
+    let producer = registry
+        .create_producer("basic", &json!({ "requests": 3 }))
+        .expect("Create producer");
+    let benchmarker = ReWrkBenchmark::from_plan_with_registry(
+        &plan,
+        &registry,
+        producer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark from plan");
+    let exported = benchmarker.export_plan();
+    assert_eq!(exported.validator, "status");
+    assert_eq!(exported.validator_options, plan.validator_options);
+    benchmarker.run().await;
+
+    let samples = benchmarker.consume_collector().await.samples;
+    assert_eq!(server.requests(), 3);
+    assert_sample(&samples[0])
+        .total_requests(3)
+        .successful_requests(3)
+        .no_errors();
+
+    // The default validator rejects the `202` status.
+    let validator = registry
+        .create_validator("status", &json!({ "status": 200 }))
+        .expect("Create validator");
+    let producer = registry
+        .create_producer("basic", &json!({ "requests": 1 }))
+        .expect("Create producer");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        producer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_validator(validator);
+    assert!(benchmarker.export_plan().validator_options.is_null());
+    benchmarker.run().await;
+
+    let samples = benchmarker.consume_collector().await.samples;
+    assert_sample(&samples[0])
+        .total_requests(1)
+        .errors(ValidationErrorKind::InvalidStatus, 1);
+}
+
+#[tokio::test]
+async fn test_registry_errors() {
+    let registry = create_registry();
+
+    let names = registry.validators().collect::<Vec<_>>();
+    assert_eq!(names, ["default", "status"]);
+    let names = registry.producers().collect::<Vec<_>>();
+    assert_eq!(names, ["basic"]);
+
+    let error = registry
+        .create_producer("missing", &json!({}))
+        .err()
+        .expect("Reject unknown producer");
+    assert!(matches!(error, RegistryError::UnknownProducer(name) if name == "missing"));
+
+    let error = registry
+        .create_validator("status", &json!({ "status": "ok" }))
+        .err()
+        .expect("Reject invalid options");
+    assert!(
+        matches!(error, RegistryError::InvalidOptions { name, .. } if name == "status")
+    );
+
+    let uri = Uri::from_static("http://127.0.0.1:20015/");
+    let producer = registry
+        .create_producer("basic", &json!({}))
+        .expect("Create producer");
+    let benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        producer.clone(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    let mut plan = benchmarker.export_plan();
+    plan.validator = "status".to_string();
+    plan.validator_options = json!({ "status": 1000 });
+    let error = ReWrkBenchmark::from_plan_with_registry(
+        &plan,
+        &registry,
+        producer,
+        BasicCollector::default(),
+    )
+    .await
+    .err()
+    .expect("Reject invalid options");
+    assert!(matches!(error, PlanError::Registry(_)));
+}
+
+fn create_registry() -> Registry {
+    let mut registry = Registry::default();
+    registry.register_validator("status", |options| {
+        let status = options["status"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing `status` option"))?;
+        let status = StatusCode::from_u16(status as u16)?;
+        Ok(StatusValidator { status })
+    });
+    registry.register_producer("basic", |options| {
+        let count = options["requests"].as_u64().unwrap_or(1) as usize;
+        Ok(BasicProducer { count })
+    });
+    registry
+}
+
+pub struct StatusValidator {
+    status: StatusCode,
+}
+
+impl ResponseValidator for StatusValidator {
+    fn validate(&self, head: Parts, _body: Bytes) -> Result<(), ValidationError> {
+        if head.status == self.status {
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidStatus(head.status.as_u16()))
+        }
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("status")
+    }
+}
+
+#[derive(Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use http::{Method, Request, Uri};
+use hyper::body::Bytes;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+const NUM_BATCHES: usize = 3;
+const BATCH_SIZE: usize = 50;
+
+#[tokio::test]
+async fn test_repeat_batch() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        RepeatProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    let successful_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.successful_requests())
+        .sum::<u64>();
+
+    // Every copy of the repeated request is sent.
+    let expected = (NUM_BATCHES * BATCH_SIZE) as u64;
+    assert_eq!(total_requests, expected);
+    assert_eq!(successful_requests, expected);
+    assert_eq!(server.requests() as u64, expected);
+}
+
+#[derive(Default, Clone)]
+pub struct RepeatProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for RepeatProducer {
+    fn ready(&mut self) {
+        self.count = NUM_BATCHES;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count == 0 {
+            return Ok(RequestBatch::End);
+        }
+        self.count -= 1;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Bytes::from_static(b"Hello, World!"))?;
+        Ok(RequestBatch::Batch(Batch::repeat(request, BATCH_SIZE)))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
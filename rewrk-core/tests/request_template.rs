@@ -0,0 +1,147 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use http::header::{HeaderName, CONTENT_TYPE, HOST, USER_AGENT};
+use http::{HeaderMap, HeaderValue, Request, Uri};
+use hyper::Body;
+use rewrk_core::RequestTemplate;
+
+/// Counts the allocations made by the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const NUM_REQUESTS: usize = 1_000;
+
+#[test]
+fn test_request_template_allocations() {
+    let target = Uri::from_static("http://127.0.0.1:8080");
+    let host = HeaderValue::from_static("127.0.0.1:8080");
+    let mut defaults = HeaderMap::new();
+    defaults.insert(USER_AGENT, HeaderValue::from_static("rewrk-core"));
+    for i in 0..6 {
+        let name = HeaderName::try_from(format!("x-default-{i}")).unwrap();
+        defaults.insert(name, HeaderValue::from_static("value"));
+    }
+    let template = RequestTemplate::new(&target, host.clone(), &defaults);
+
+    // Requests without headers take a copy of the template's
+    // header map rather than growing their own for each header.
+    let naive = measure(false, |r| prepare_naive(&target, &host, &defaults, r));
+    let pooled = measure(false, |r| template.prepare(r));
+    assert!(
+        pooled < naive,
+        "Expected fewer allocations with the template, got {pooled} vs {naive}",
+    );
+
+    let naive = measure(true, |r| prepare_naive(&target, &host, &defaults, r));
+    let pooled = measure(true, |r| template.prepare(r));
+    assert!(
+        pooled <= naive,
+        "Expected no more allocations with the template, got {pooled} vs {naive}",
+    );
+
+    let mut request = build_request(0, true);
+    template.prepare(&mut request);
+    let mut expected = build_request(0, true);
+    prepare_naive(&target, &host, &defaults, &mut expected);
+    assert_eq!(request.uri(), expected.uri());
+    assert_eq!(request.headers(), expected.headers());
+}
+
+#[test]
+fn test_request_template_headers() {
+    let mut defaults = HeaderMap::new();
+    defaults.insert(USER_AGENT, HeaderValue::from_static("rewrk-core"));
+    defaults.insert(HOST, HeaderValue::from_static("ignored"));
+    let template = RequestTemplate::new(
+        &Uri::from_static("https://example.com"),
+        HeaderValue::from_static("example.com"),
+        &defaults,
+    );
+
+    let mut request = build_request(1, false);
+    template.prepare(&mut request);
+    assert_eq!(request.uri(), "https://example.com/items/1?page=1");
+    assert_eq!(request.headers()[HOST], "example.com");
+    assert_eq!(request.headers()[USER_AGENT], "rewrk-core");
+
+    // Headers set on the request take priority, except for the `Host` header.
+    let mut request = build_request(2, true);
+    request
+        .headers_mut()
+        .insert(USER_AGENT, HeaderValue::from_static("custom"));
+    request
+        .headers_mut()
+        .insert(HOST, HeaderValue::from_static("other.com"));
+    template.prepare(&mut request);
+    assert_eq!(request.headers()[HOST], "example.com");
+    assert_eq!(request.headers()[USER_AGENT], "custom");
+    assert_eq!(request.headers()[CONTENT_TYPE], "application/json");
+}
+
+/// The number of allocations made preparing a set of requests.
+fn measure(with_header: bool, mut prepare: impl FnMut(&mut Request<Body>)) -> usize {
+    let mut requests = (0..NUM_REQUESTS)
+        .map(|i| build_request(i, with_header))
+        .collect::<Vec<_>>();
+
+    let start = ALLOCATIONS.with(Cell::get);
+    for request in requests.iter_mut() {
+        prepare(request);
+    }
+    ALLOCATIONS.with(Cell::get) - start
+}
+
+fn build_request(id: usize, with_header: bool) -> Request<Body> {
+    let mut builder = Request::builder().uri(format!("/items/{id}?page=1"));
+    if with_header {
+        builder = builder.header(CONTENT_TYPE, "application/json");
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+/// Prepares the request by building its URI and inserting its headers one by one.
+fn prepare_naive(
+    target: &Uri,
+    host: &HeaderValue,
+    defaults: &HeaderMap,
+    request: &mut Request<Body>,
+) {
+    let mut builder = Uri::builder()
+        .scheme(target.scheme().unwrap().clone())
+        .authority(target.authority().unwrap().clone());
+    if let Some(path) = request.uri().path_and_query() {
+        builder = builder.path_and_query(path.clone());
+    }
+    *request.uri_mut() = builder.build().unwrap();
+
+    let headers = request.headers_mut();
+    headers.insert(HOST, host.clone());
+    for (name, value) in defaults.iter() {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+}
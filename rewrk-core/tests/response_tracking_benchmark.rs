@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    ResponseTracking,
+    Sample,
+    SampleCollector,
+    DEFAULT_REQUEST_KEY_HEADER,
+};
+
+static ADDR: &str = "127.0.0.1:20020";
+
+#[tokio::test]
+async fn test_response_tracking() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_response_tracking(ResponseTracking::default());
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    assert_eq!(total.successful_requests(), 5);
+    // The stale response repeats the key of the first request.
+    assert_eq!(total.duplicate_responses(), 1);
+    // Neither the stale request nor the untracked request got their own response.
+    assert_eq!(total.missing_responses(), 2);
+    assert_eq!(total.out_of_order_responses(), 0);
+}
+
+async fn run_server() {
+    let app = Router::new()
+        .route(
+            "/",
+            get(|headers: HeaderMap| async move {
+                let key = headers[&DEFAULT_REQUEST_KEY_HEADER].clone();
+                ([(DEFAULT_REQUEST_KEY_HEADER.clone(), key)], "Hello, World!")
+            }),
+        )
+        .route(
+            "/stale",
+            get(|| async {
+                (
+                    [(DEFAULT_REQUEST_KEY_HEADER.clone(), "0-0-0")],
+                    "Hello, World!",
+                )
+            }),
+        )
+        .route("/untracked", get(|| async { "Hello, World!" }));
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let requests = ["/", "/", "/", "/stale", "/untracked"]
+                .into_iter()
+                .map(|path| {
+                    Request::builder()
+                        .method(Method::GET)
+                        .uri(Uri::from_static(path))
+                        .body(Body::empty())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
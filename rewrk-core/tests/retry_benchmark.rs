@@ -0,0 +1,130 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Backoff,
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    RetryPolicy,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20003";
+static REQUEST_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[tokio::test]
+async fn test_retry_policy() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+
+    let mut policy = RetryPolicy::default();
+    policy.set_backoff(Backoff::Fixed(Duration::from_millis(5)));
+    benchmarker.set_retry_policy(policy);
+    benchmarker.run().await;
+
+    let mut collector = benchmarker.consume_collector().await;
+    let sample = collector.samples.remove(0);
+    assert_eq!(sample.total_requests(), 3);
+    assert_eq!(sample.successful_requests(), 3);
+    assert_eq!(sample.retries(), 3);
+    assert_eq!(sample.rate_limited_responses(), 3);
+    assert_eq!(sample.backoff_duration(), Duration::from_millis(15));
+    assert_eq!(sample.latency().len(), 3);
+    assert_eq!(sample.attempt_latency().len(), 6);
+}
+
+async fn run_server() {
+    // build our application with a single route
+    let app = Router::new().route(
+        "/",
+        get(|| async {
+            // Every other request is rate limited.
+            if REQUEST_COUNT
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(2)
+            {
+                (StatusCode::TOO_MANY_REQUESTS, "Slow down!")
+            } else {
+                (StatusCode::OK, "Hello, World!")
+            }
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let requests = (0..3)
+                .map(|_| {
+                    Request::builder()
+                        .method(Method::GET)
+                        .uri(uri.clone())
+                        .body(Body::empty())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+const NUM_ROUNDS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Event {
+    StartRound(usize),
+    Sample(usize),
+    EndRound(usize),
+}
+
+#[tokio::test]
+async fn test_round_barriers() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        RoundCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker.set_rounds(NUM_ROUNDS).expect("Set rounds");
+    benchmarker.set_round_cooldown(Duration::from_millis(10));
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let events = collector.events;
+
+    let mut expected_round = 0;
+    let mut in_round = false;
+    let mut samples = 0;
+    for event in events.iter().copied() {
+        match event {
+            Event::StartRound(round) => {
+                assert!(!in_round, "{events:?}");
+                assert_eq!(round, expected_round, "{events:?}");
+                in_round = true;
+            },
+            Event::Sample(round) => {
+                assert!(in_round, "{events:?}");
+                assert_eq!(round, expected_round, "{events:?}");
+                samples += 1;
+            },
+            Event::EndRound(round) => {
+                assert!(in_round, "{events:?}");
+                assert_eq!(round, expected_round, "{events:?}");
+                in_round = false;
+                expected_round += 1;
+            },
+        }
+    }
+    assert!(!in_round);
+    assert_eq!(expected_round, NUM_ROUNDS);
+    assert!(samples >= NUM_ROUNDS);
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 10;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RoundCollector {
+    events: Vec<Event>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for RoundCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.events.push(Event::Sample(sample.metadata().round));
+        Ok(())
+    }
+
+    async fn start_round(&mut self, round: usize) -> anyhow::Result<()> {
+        self.events.push(Event::StartRound(round));
+        Ok(())
+    }
+
+    async fn end_round(&mut self, round: usize) -> anyhow::Result<()> {
+        self.events.push(Event::EndRound(round));
+        Ok(())
+    }
+}
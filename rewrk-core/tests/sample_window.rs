@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+const WINDOW: Duration = Duration::from_millis(50);
+
+#[tokio::test]
+async fn test_tag_sample_window() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let (_server, mut benchmarker) = create_benchmark(1).await;
+    benchmarker
+        .set_tag_sample_window(1, WINDOW)
+        .expect("Set tag window");
+    benchmarker
+        .set_worker_sample_window(0, Duration::from_secs(5))
+        .expect("Set worker window");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let samples = collector.samples();
+    assert!(samples.len() > 2, "Samples should be submitted every 50ms");
+    for sample in &samples {
+        assert_eq!(sample.tag(), 1);
+        assert_eq!(sample.metadata().sample_window, WINDOW);
+    }
+}
+
+#[tokio::test]
+async fn test_worker_sample_window() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let (_server, mut benchmarker) = create_benchmark(2).await;
+    benchmarker
+        .set_worker_sample_window(1, WINDOW)
+        .expect("Set worker window");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let samples = collector.samples();
+    let mut worker_samples = [0, 0];
+    for sample in &samples {
+        let metadata = sample.metadata();
+        worker_samples[metadata.worker_id] += 1;
+
+        let expected = if metadata.worker_id == 1 {
+            WINDOW
+        } else {
+            Duration::from_secs(10)
+        };
+        assert_eq!(metadata.sample_window, expected);
+    }
+    assert_eq!(worker_samples[0], 1);
+    assert!(worker_samples[1] > 1);
+}
+
+#[tokio::test]
+async fn test_zero_sample_window() {
+    let (_server, mut benchmarker) = create_benchmark(1).await;
+    assert_eq!(
+        benchmarker.set_tag_sample_window(1, Duration::ZERO),
+        Err(ConfigError::ZeroSampleWindow),
+    );
+    assert_eq!(
+        benchmarker.set_worker_sample_window(0, Duration::ZERO),
+        Err(ConfigError::ZeroSampleWindow),
+    );
+}
+
+async fn create_benchmark(
+    num_workers: usize,
+) -> (TestServer, ReWrkBenchmark<SlowProducer, BasicCollector>) {
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        num_workers,
+        HttpProtocol::HTTP1,
+        SlowProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(num_workers)
+        .expect("Set benchmark config");
+    (server, benchmarker)
+}
+
+/// A producer which takes a while to produce each batch so the
+/// benchmark spans several short sample windows.
+#[derive(Default, Clone)]
+pub struct SlowProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for SlowProducer {
+    fn ready(&mut self) {
+        self.count = 10;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 1,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+impl BasicCollector {
+    /// The samples which recorded any requests.
+    fn samples(&self) -> Vec<Sample> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.total_requests() > 0)
+            .cloned()
+            .collect()
+    }
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    NotBefore,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+const NUM_BATCHES: u32 = 3;
+const INTERVAL: Duration = Duration::from_millis(100);
+
+#[tokio::test]
+async fn test_scheduled_requests() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        ScheduledProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+
+    let start = Instant::now();
+    benchmarker.run().await;
+    let elapsed = start.elapsed();
+    assert!(elapsed >= INTERVAL * NUM_BATCHES, "{elapsed:?}");
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    assert_eq!(total_requests, NUM_BATCHES as u64);
+
+    // Waiting for the scheduled time is not included in the latency.
+    for sample in collector.samples.iter() {
+        if sample.total_requests() > 0 {
+            assert!(sample.latency_mean() < INTERVAL, "{sample:?}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_scheduled_requests_shutdown() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        ScheduledProducer {
+            interval: Duration::from_secs(60),
+            ..Default::default()
+        },
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+
+    // Shutting down stops waiting for the scheduled time.
+    let start = Instant::now();
+    tokio::join!(benchmarker.run(), async {
+        tokio::time::sleep(INTERVAL).await;
+        benchmarker.shutdown();
+    });
+    let elapsed = start.elapsed();
+    assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+    assert_eq!(server.requests(), 0);
+}
+
+#[derive(Clone)]
+pub struct ScheduledProducer {
+    start: Option<Instant>,
+    count: u32,
+    interval: Duration,
+}
+
+impl Default for ScheduledProducer {
+    fn default() -> Self {
+        Self {
+            start: None,
+            count: 0,
+            interval: INTERVAL,
+        }
+    }
+}
+
+#[rewrk_core::async_trait]
+impl Producer for ScheduledProducer {
+    fn ready(&mut self) {
+        self.start = Some(Instant::now());
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count >= NUM_BATCHES {
+            return Ok(RequestBatch::End);
+        }
+        self.count += 1;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let mut request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        let at = self.start.unwrap() + self.interval * self.count;
+        request.extensions_mut().insert(NotBefore(at));
+
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
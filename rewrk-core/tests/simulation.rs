@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    DefaultValidator,
+    Producer,
+    RequestBatch,
+    RequestKey,
+    ResponseModel,
+    Sample,
+    SampleCollector,
+    SimulatedResponse,
+    Simulation,
+    ValidationErrorKind,
+    WithLatencySlo,
+};
+
+const NUM_BATCHES: usize = 1_000;
+const BATCH_SIZE: usize = 100;
+
+#[tokio::test]
+async fn test_simulation() {
+    let samples = run_simulation().await;
+
+    let total_requests: u64 = samples.iter().map(|s| s.total_requests()).sum();
+    assert_eq!(total_requests, (NUM_BATCHES * BATCH_SIZE) as u64);
+
+    // Every 10th request fails validation.
+    let total_errors: u64 = samples
+        .iter()
+        .filter_map(|s| s.error_counts().get(&ValidationErrorKind::InvalidStatus))
+        .sum();
+    assert_eq!(total_errors, total_requests / 10);
+
+    // The first and second half of the batches have different tags.
+    assert!(samples.iter().any(|s| s.tag() == 0));
+    assert!(samples.iter().any(|s| s.tag() == 1));
+
+    // Full sample windows end on the first request completing after
+    // one second of simulated time.
+    assert!(samples.iter().any(|s| !s.is_truncated()));
+    for sample in samples.iter().filter(|s| !s.is_truncated()) {
+        assert!(sample.duration() >= Duration::from_secs(1));
+        assert!(sample.duration() <= Duration::from_micros(1_001_400));
+    }
+    let connections = samples
+        .iter()
+        .map(|s| s.metadata().connection_id)
+        .max()
+        .unwrap();
+    assert_eq!(connections, 9);
+}
+
+#[tokio::test]
+async fn test_simulation_latency_slo() {
+    let mut simulation = simulation();
+    simulation.set_validator(WithLatencySlo::new(
+        DefaultValidator,
+        Duration::from_micros(1_000),
+    ));
+    let samples = simulation
+        .run(BasicCollector::default())
+        .await
+        .expect("Run simulation")
+        .samples;
+
+    let total_requests: u64 = samples.iter().map(|s| s.total_requests()).sum();
+    let errors = |kind| -> u64 {
+        samples
+            .iter()
+            .filter_map(|s| s.error_counts().get(&kind))
+            .sum()
+    };
+
+    // Requests 6 to 8 of every 10 are slower than the SLO, while
+    // the 10th request fails validation before the SLO is checked.
+    assert_eq!(
+        errors(ValidationErrorKind::SloViolation),
+        total_requests * 3 / 10
+    );
+    assert_eq!(
+        errors(ValidationErrorKind::InvalidStatus),
+        total_requests / 10
+    );
+}
+
+#[tokio::test]
+async fn test_simulation_is_deterministic() {
+    let first = run_simulation().await;
+    let second = run_simulation().await;
+
+    assert_eq!(first.len(), second.len());
+    for (a, b) in first.iter().zip(second.iter()) {
+        assert_eq!(a.tag(), b.tag());
+        assert_eq!(a.metadata().connection_id, b.metadata().connection_id);
+        assert_eq!(a.window_index(), b.window_index());
+        assert_eq!(a.duration(), b.duration());
+        assert_eq!(a.total_requests(), b.total_requests());
+        assert_eq!(a.latency(), b.latency());
+    }
+}
+
+async fn run_simulation() -> Vec<Sample> {
+    simulation()
+        .run(BasicCollector::default())
+        .await
+        .expect("Run simulation")
+        .samples
+}
+
+fn simulation() -> Simulation<BasicProducer, impl ResponseModel> {
+    let model = |key: RequestKey, _request: &Request<Body>| {
+        let latency = Duration::from_micros(500 + (key.request_id % 10) * 100);
+        let status = if key.request_id % 10 == 9 {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        };
+        SimulatedResponse::new(status, latency)
+    };
+
+    let mut simulation = Simulation::new(10, BasicProducer::default(), model);
+    simulation
+        .set_sample_window(Duration::from_secs(1))
+        .expect("Set sample window");
+    simulation
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = NUM_BATCHES;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let mut requests = Vec::with_capacity(BATCH_SIZE);
+            for _ in 0..BATCH_SIZE {
+                let uri = Uri::builder().path_and_query("/").build()?;
+                let request = Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())?;
+                requests.push(request);
+            }
+
+            Ok(RequestBatch::Batch(Batch {
+                tag: self.count / (NUM_BATCHES / 2),
+                requests,
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_snapshot() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        TimedProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(50))
+        .expect("Set benchmark config");
+
+    // Snapshots are disabled by default.
+    assert!(benchmarker.snapshot().await.is_none());
+    benchmarker.enable_snapshots();
+
+    let empty = benchmarker.snapshot().await.expect("Take snapshot");
+    assert_eq!(empty.samples_processed(), 0);
+    assert!(empty.total().is_none());
+
+    let (_, during) = tokio::join!(benchmarker.run(), async {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        benchmarker.snapshot().await.expect("Take snapshot")
+    });
+    let after = benchmarker.snapshot().await.expect("Take snapshot");
+
+    let during_total = during.total().expect("Samples collected during run");
+    let after_total = after.total().expect("Samples collected after run");
+    assert!(during.samples_processed() > 0);
+    assert!(during_total.total_requests() > 0);
+    assert!(after_total.total_requests() > during_total.total_requests());
+    assert_eq!(
+        after.get(0).unwrap().total_requests(),
+        after_total.total_requests()
+    );
+    assert_eq!(after_total.total_requests(), server.requests() as u64);
+
+    let collector = benchmarker.consume_collector().await;
+    assert_eq!(collector.samples.len(), after.samples_processed());
+}
+
+#[derive(Default, Clone)]
+pub struct TimedProducer {
+    deadline: Option<Instant>,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for TimedProducer {
+    fn ready(&mut self) {
+        self.deadline = Some(Instant::now() + Duration::from_millis(600));
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() < deadline)
+        {
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
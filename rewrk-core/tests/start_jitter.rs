@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, State};
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+static ADDR: &str = "127.0.0.1:20027";
+const NUM_CONNECTIONS: usize = 8;
+
+/// The point in time each connection sent its first request.
+type FirstRequests = Arc<Mutex<BTreeMap<u16, Instant>>>;
+
+#[tokio::test]
+async fn test_start_jitter() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let first_requests = FirstRequests::default();
+    tokio::spawn(run_server(first_requests.clone()));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        NUM_CONNECTIONS,
+        HttpProtocol::HTTP1,
+        BasicProducer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    assert!(matches!(
+        benchmarker.set_start_jitter(Duration::ZERO),
+        Err(ConfigError::ZeroStartJitter),
+    ));
+    benchmarker
+        .set_start_jitter(Duration::from_millis(300))
+        .expect("Set benchmark config");
+    benchmarker.run_for(Duration::from_millis(500)).await;
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    assert!(total_requests > 0);
+
+    // The connections start at different points in time.
+    let first_requests = first_requests.lock().unwrap();
+    assert_eq!(first_requests.len(), NUM_CONNECTIONS);
+    let earliest = first_requests.values().min().unwrap();
+    let latest = first_requests.values().max().unwrap();
+    let spread = latest.duration_since(*earliest);
+    assert!(
+        spread >= Duration::from_millis(20),
+        "Expected connection starts to be staggered, got {spread:?}",
+    );
+    assert!(spread < Duration::from_millis(500), "{spread:?}");
+}
+
+async fn run_server(first_requests: FirstRequests) {
+    let app = Router::new()
+        .route("/", get(record_first_request))
+        .with_state(first_requests);
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
+}
+
+async fn record_first_request(
+    State(first_requests): State<FirstRequests>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> &'static str {
+    first_requests
+        .lock()
+        .unwrap()
+        .entry(addr.port())
+        .or_insert_with(Instant::now);
+    "Hello, World!"
+}
+
+#[derive(Clone)]
+pub struct BasicProducer;
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
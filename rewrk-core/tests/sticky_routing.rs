@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{ConnectInfo, State};
+use axum::routing::get;
+use axum::Router;
+use http::{HeaderMap, Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    StickyKey,
+};
+
+static ADDR: &str = "127.0.0.1:20026";
+const NUM_CONNECTIONS: usize = 4;
+const NUM_SESSIONS: usize = 7;
+const NUM_BATCHES: usize = 60;
+
+/// The client ports each session's requests were received from.
+type Sessions = Arc<Mutex<BTreeMap<String, BTreeSet<u16>>>>;
+
+#[tokio::test]
+async fn test_sticky_routing() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let sessions = Sessions::default();
+    tokio::spawn(run_server(sessions.clone()));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        NUM_CONNECTIONS,
+        HttpProtocol::HTTP1,
+        SessionProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_sticky_routing(true);
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    assert_eq!(total_requests, NUM_BATCHES as u64);
+
+    let sessions = sessions.lock().unwrap();
+    let mut ports = BTreeSet::<u16>::new();
+    for (session, session_ports) in sessions.iter() {
+        if session == "none" {
+            continue;
+        }
+
+        // Every request of a session is executed on the same connection.
+        assert_eq!(session_ports.len(), 1, "{session}: {session_ports:?}");
+        ports.extend(session_ports);
+    }
+    assert!(
+        ports.len() > 1,
+        "Expected sessions to be spread over connections"
+    );
+}
+
+async fn run_server(sessions: Sessions) {
+    let app = Router::new()
+        .route("/", get(record_session))
+        .with_state(sessions);
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
+}
+
+async fn record_session(
+    State(sessions): State<Sessions>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> &'static str {
+    let session = headers
+        .get("x-session")
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_else(|| "none".to_string());
+    sessions
+        .lock()
+        .unwrap()
+        .entry(session)
+        .or_default()
+        .insert(addr.port());
+    "Hello, World!"
+}
+
+#[derive(Default, Clone)]
+pub struct SessionProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for SessionProducer {
+    fn ready(&mut self) {
+        self.count = 0;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count == NUM_BATCHES {
+            return Ok(RequestBatch::End);
+        }
+        self.count += 1;
+
+        // Every third batch has no session and can use any connection.
+        let mut builder = Request::builder().method(Method::GET).uri("/");
+        let session = format!("session-{}", self.count % NUM_SESSIONS);
+        let sticky = !self.count.is_multiple_of(3);
+        if sticky {
+            builder = builder.header("x-session", &session);
+        }
+
+        let mut request = builder.body(Body::empty())?;
+        if sticky {
+            request.extensions_mut().insert(StickyKey::new(session));
+        }
+
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
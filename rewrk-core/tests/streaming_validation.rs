@@ -0,0 +1,248 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::routing::get;
+use axum::Router;
+use http::response::Parts;
+use http::{Method, Request, Uri};
+use hyper::body::Bytes;
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    BodyMode,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    ResponseValidator,
+    Sample,
+    SampleCollector,
+    ValidationError,
+    ValidationErrorKind,
+};
+
+const BODY_SIZE: usize = 1 << 20;
+const NUM_BATCHES: usize = 20;
+
+#[tokio::test]
+async fn test_streaming_validation() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let addr = "127.0.0.1:20028";
+    tokio::spawn(run_server(addr));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    for protocol in [HttpProtocol::HTTP1, HttpProtocol::HTTP2] {
+        let validator = ChunkValidator::new(BodyMode::Streaming);
+        let collector =
+            run_benchmark(addr, protocol, "/download", validator.clone()).await;
+
+        let sample = collector.combined();
+        assert_eq!(sample.total_requests(), NUM_BATCHES as u64);
+        assert_eq!(sample.total_errors(), 0, "{protocol:?}");
+
+        // Every chunk is validated without the body being buffered.
+        let validated = validator.validated.load(Ordering::Relaxed);
+        assert_eq!(validated, (NUM_BATCHES * BODY_SIZE) as u64);
+        assert!(validator.chunks.load(Ordering::Relaxed) > NUM_BATCHES as u64);
+
+        // The transfer is still recorded.
+        assert!(sample.read_bytes() >= validated, "{protocol:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_streaming_validation_rejected_chunk() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let addr = "127.0.0.1:20029";
+    tokio::spawn(run_server(addr));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let validator = ChunkValidator::new(BodyMode::Streaming);
+    let collector =
+        run_benchmark(addr, HttpProtocol::HTTP1, "/corrupt", validator.clone()).await;
+
+    // The connection is reused after a rejected body.
+    let sample = collector.combined();
+    assert_eq!(sample.total_requests(), NUM_BATCHES as u64);
+    assert_eq!(sample.total_errors(), NUM_BATCHES as u64);
+    assert_eq!(
+        sample.error_counts().get(&ValidationErrorKind::InvalidBody),
+        Some(&(NUM_BATCHES as u64)),
+    );
+}
+
+#[tokio::test]
+async fn test_discarded_body() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let addr = "127.0.0.1:20030";
+    tokio::spawn(run_server(addr));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let validator = ChunkValidator::new(BodyMode::Discard);
+    let collector =
+        run_benchmark(addr, HttpProtocol::HTTP1, "/corrupt", validator.clone()).await;
+
+    let sample = collector.combined();
+    assert_eq!(sample.total_requests(), NUM_BATCHES as u64);
+    assert_eq!(sample.total_errors(), 0);
+    assert_eq!(validator.chunks.load(Ordering::Relaxed), 0);
+    assert!(sample.read_bytes() >= (NUM_BATCHES * BODY_SIZE) as u64);
+}
+
+async fn run_benchmark(
+    addr: &str,
+    protocol: HttpProtocol,
+    path: &'static str,
+    validator: ChunkValidator,
+) -> BasicCollector {
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(addr)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        2,
+        protocol,
+        BasicProducer::new(path),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_validator(validator);
+    benchmarker.run().await;
+    benchmarker.consume_collector().await
+}
+
+async fn run_server(addr: &str) {
+    let app = Router::new()
+        .route("/download", get(|| async { vec![b'a'; BODY_SIZE] }))
+        .route(
+            "/corrupt",
+            get(|| async {
+                let mut body = vec![b'a'; BODY_SIZE];
+                body[BODY_SIZE / 2] = 0;
+                body
+            }),
+        );
+
+    axum::Server::bind(&addr.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Clone)]
+pub struct ChunkValidator {
+    mode: BodyMode,
+    chunks: Arc<AtomicU64>,
+    validated: Arc<AtomicU64>,
+}
+
+impl ChunkValidator {
+    fn new(mode: BodyMode) -> Self {
+        Self {
+            mode,
+            chunks: Arc::default(),
+            validated: Arc::default(),
+        }
+    }
+}
+
+impl ResponseValidator for ChunkValidator {
+    fn validate(&self, head: Parts, body: Bytes) -> Result<(), ValidationError> {
+        assert!(body.is_empty());
+        if head.status.is_success() {
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidStatus(head.status.as_u16()))
+        }
+    }
+
+    fn body_mode(&self) -> BodyMode {
+        self.mode
+    }
+
+    fn validate_chunk(
+        &self,
+        _head: &Parts,
+        chunk: &Bytes,
+    ) -> Result<(), ValidationError> {
+        self.chunks.fetch_add(1, Ordering::Relaxed);
+        self.validated
+            .fetch_add(chunk.len() as u64, Ordering::Relaxed);
+        if chunk.contains(&0) {
+            return Err(ValidationError::InvalidBody("nul-byte".into()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct BasicProducer {
+    path: &'static str,
+    count: usize,
+}
+
+impl BasicProducer {
+    fn new(path: &'static str) -> Self {
+        Self { path, count: 0 }
+    }
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = 0;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count == NUM_BATCHES {
+            return Ok(RequestBatch::End);
+        }
+        self.count += 1;
+
+        let uri = Uri::builder().path_and_query(self.path).build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+impl BasicCollector {
+    fn combined(&self) -> Sample {
+        let mut samples = self.samples.iter().cloned();
+        let mut combined = samples.next().expect("Get sample");
+        for sample in samples {
+            combined += sample;
+        }
+        combined
+    }
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::extract::Path;
+use axum::routing::get;
+use axum::Router;
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    TagScheduler,
+};
+
+static ADDR: &str = "127.0.0.1:20017";
+static REQUESTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+const NUM_BATCHES: usize = 20;
+const SLOW_TAG: usize = 0;
+const FAST_TAG: usize = 1;
+
+#[tokio::test]
+async fn test_tag_scheduler() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    tokio::spawn(run_server());
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let mut scheduler = TagScheduler::fair();
+    scheduler.set_lookahead(NUM_BATCHES * 2);
+    assert_eq!(scheduler.weight(FAST_TAG), 1);
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        1,
+        HttpProtocol::HTTP1,
+        BurstProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_tag_scheduler(scheduler);
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = |tag| {
+        collector
+            .samples
+            .iter()
+            .filter(|sample| sample.tag() == tag)
+            .map(|sample| sample.total_requests())
+            .sum::<u64>()
+    };
+    assert_eq!(total_requests(SLOW_TAG), NUM_BATCHES as u64);
+    assert_eq!(total_requests(FAST_TAG), NUM_BATCHES as u64);
+
+    // The producer creates all the slow batches first, the fast batches should
+    // be scheduled once the slow tag has used its share of the connection.
+    let requests = REQUESTS.lock().unwrap();
+    assert_eq!(requests.len(), NUM_BATCHES * 2);
+    let first_fast = requests
+        .iter()
+        .position(|path| path == "fast")
+        .expect("Fast requests sent");
+    assert!(first_fast < 5, "{requests:?}");
+}
+
+async fn run_server() {
+    let app = Router::new().route(
+        "/:speed",
+        get(|Path(speed): Path<String>| async move {
+            if speed == "slow" {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            REQUESTS.lock().unwrap().push(speed);
+            "Hello, World!"
+        }),
+    );
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+#[derive(Default, Clone)]
+pub struct BurstProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BurstProducer {
+    fn ready(&mut self) {
+        self.count = 0;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count >= NUM_BATCHES * 2 {
+            return Ok(RequestBatch::End);
+        }
+
+        let (tag, path) = if self.count < NUM_BATCHES {
+            (SLOW_TAG, "/slow")
+        } else {
+            (FAST_TAG, "/fast")
+        };
+        self.count += 1;
+
+        let uri = Uri::builder().path_and_query(path).build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::testing::{ScriptedResponse, TestServer};
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+const NUM_REQUESTS: usize = 25;
+
+#[tokio::test]
+async fn test_target_rps() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        4,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker.set_target_rps(100).expect("Set target rps");
+
+    let start = Instant::now();
+    benchmarker.run().await;
+    let elapsed = start.elapsed();
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    // Both workers share the rate, so the 50th request is sent after ~490ms.
+    assert!(elapsed >= Duration::from_millis(450), "{elapsed:?}");
+    assert_eq!(total.successful_requests(), 2 * NUM_REQUESTS as u64);
+    assert!(
+        total.latency_percentile(100.0) < Duration::from_millis(100),
+        "The pacing should not be included in the latency"
+    );
+}
+
+#[tokio::test]
+async fn test_target_rps_includes_missed_slots() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    // The first response holds up the connection for ~20 slots.
+    let mut script = vec![
+        ScriptedResponse::new(StatusCode::OK).with_delay(Duration::from_millis(200))
+    ];
+    script.resize(NUM_REQUESTS, ScriptedResponse::new(StatusCode::OK));
+    let server = TestServer::scripted(script).await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_target_rps(100).expect("Set target rps");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let mut total = collector.samples[0].clone();
+    for sample in &collector.samples[1..] {
+        total += sample;
+    }
+
+    // The requests scheduled while the first was stalled are timed from their
+    // slots, rather than looking as fast as the requests after them.
+    assert_eq!(total.successful_requests(), NUM_REQUESTS as u64);
+    let median = total.latency_percentile(50.0);
+    assert!(median >= Duration::from_millis(40), "{median:?}");
+}
+
+#[tokio::test]
+async fn test_target_rps_config() {
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer::default(),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+
+    assert_eq!(
+        benchmarker.set_target_rps(0),
+        Err(ConfigError::ZeroTargetRps),
+    );
+}
+
+#[derive(Default, Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {
+        self.count = NUM_REQUESTS;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::routing::put;
+use axum::Router;
+use http::{Method, Uri};
+use rewrk_core::template::PlaceholderProducer;
+use rewrk_core::{HttpProtocol, ReWrkBenchmark, Sample, SampleCollector};
+
+static ADDR: &str = "127.0.0.1:20031";
+const NUM_REQUESTS: u64 = 25;
+
+/// The path ID, request ID header and body of each request received.
+type Received = Arc<Mutex<Vec<(String, String, String)>>>;
+
+#[tokio::test]
+async fn test_placeholder_producer() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let received = Received::default();
+    tokio::spawn(run_server(received.clone()));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let uri = Uri::builder()
+        .scheme("http")
+        .authority(ADDR)
+        .path_and_query("/")
+        .build()
+        .expect("Create URI");
+
+    let producer = PlaceholderProducer::new(Method::PUT, "/users/{{uuid}}")
+        .expect("Parse placeholders")
+        .header("x-request-id", "req-{{seq}}")
+        .expect("Parse placeholders")
+        .body("{{uuid}}:{{rand_int(1,10)}}")
+        .expect("Parse placeholders")
+        .batch_size(2)
+        .max_requests(NUM_REQUESTS);
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        uri,
+        4,
+        HttpProtocol::HTTP1,
+        producer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(2)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+    assert_eq!(total_requests, NUM_REQUESTS);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len() as u64, NUM_REQUESTS);
+
+    // Sequence numbers are unique across workers.
+    let request_ids = received
+        .iter()
+        .map(|(_, request_id, _)| request_id.clone())
+        .collect::<BTreeSet<_>>();
+    let expected = (0..NUM_REQUESTS)
+        .map(|seq| format!("req-{seq}"))
+        .collect::<BTreeSet<_>>();
+    assert_eq!(request_ids, expected);
+
+    let ids = received
+        .iter()
+        .map(|(id, _, _)| id.clone())
+        .collect::<BTreeSet<_>>();
+    assert_eq!(ids.len() as u64, NUM_REQUESTS);
+    for (id, _, body) in received.iter() {
+        // The UUID is the same in the path and body of a request.
+        let (body_id, value) = body.split_once(':').expect("Split body");
+        assert_eq!(body_id, id);
+        let value = value.parse::<u32>().expect("Parse integer");
+        assert!((1..=10).contains(&value));
+    }
+}
+
+async fn run_server(received: Received) {
+    let app = Router::new()
+        .route("/users/:id", put(record_request))
+        .with_state(received);
+
+    axum::Server::bind(&ADDR.parse().unwrap())
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+async fn record_request(
+    State(received): State<Received>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> &'static str {
+    let request_id = headers
+        .get("x-request-id")
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    received.lock().unwrap().push((id, request_id, body));
+    "Hello, World!"
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use http::{Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rewrk_core::testing::{assert_sample, ScriptedResponse, TestServer};
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    ValidationErrorKind,
+};
+
+#[tokio::test]
+async fn test_echo_server() {
+    let server = TestServer::echo().await.expect("Start server");
+    let samples = run_benchmark(&server, 1).await;
+
+    assert_eq!(server.requests(), 1);
+    assert_sample(&samples[0])
+        .tag(0)
+        .total_requests(1)
+        .successful_requests(1)
+        .no_errors();
+}
+
+#[tokio::test]
+async fn test_scripted_server() {
+    let server = TestServer::scripted(vec![
+        ScriptedResponse::new(StatusCode::OK).with_delay(Duration::from_millis(20)),
+        ScriptedResponse::new(StatusCode::INTERNAL_SERVER_ERROR),
+    ])
+    .await
+    .expect("Start server");
+    let samples = run_benchmark(&server, 4).await;
+
+    assert_eq!(server.requests(), 4);
+    assert_sample(&samples[0])
+        .total_requests(4)
+        .successful_requests(2)
+        .errors(ValidationErrorKind::InvalidStatus, 2)
+        .min_latency_at_least(Duration::from_millis(20));
+}
+
+async fn run_benchmark(server: &TestServer, requests: usize) -> Vec<Sample> {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        BasicProducer { count: requests },
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.run().await;
+
+    benchmarker.consume_collector().await.samples
+}
+
+#[derive(Clone)]
+pub struct BasicProducer {
+    count: usize,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for BasicProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self.count > 0 {
+            self.count -= 1;
+
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            Ok(RequestBatch::Batch(Batch {
+                tag: 0,
+                requests: vec![request],
+            }))
+        } else {
+            Ok(RequestBatch::End)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
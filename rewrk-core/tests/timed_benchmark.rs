@@ -38,8 +38,12 @@ async fn test_basic_benchmark() {
     )
     .await
     .expect("Create benchmark");
-    benchmarker.set_sample_window(Duration::from_secs(30));
-    benchmarker.set_num_workers(1);
+    benchmarker
+        .set_sample_window(Duration::from_secs(30))
+        .expect("Set benchmark config");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
 
     let start = Instant::now();
     benchmarker.run().await;
@@ -54,6 +58,22 @@ async fn test_basic_benchmark() {
         sample.latency().max(),
         sample.latency().stdev(),
     );
+
+    assert_eq!(sample.error_rate(), 0.0);
+    assert!(sample.requests_per_sec() > 0.0);
+    assert!(sample.read_bytes_per_sec() > 0.0);
+    assert_eq!(sample.plaintext_read_bytes(), sample.read_bytes());
+    assert_eq!(sample.plaintext_written_bytes(), sample.written_bytes());
+    assert_eq!(sample.tls_overhead_bytes(), 0);
+    assert!(sample.latency_percentile(99.0) >= sample.latency_percentile(50.0));
+
+    let summary = sample.latency_summary();
+    assert_eq!(summary.count, sample.latency().len());
+    assert!(summary.p999 >= summary.p99);
+
+    let exported = serde_json::to_string(&summary).expect("Serialize summary");
+    let imported = serde_json::from_str(&exported).expect("Deserialize summary");
+    assert_eq!(summary, imported);
 }
 
 async fn run_server() {
@@ -0,0 +1,104 @@
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+};
+
+#[tokio::test]
+async fn test_warmup() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        PacedProducer,
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(50))
+        .expect("Set benchmark config");
+    benchmarker.set_warmup(Duration::from_millis(300));
+
+    // The warm-up is not included in the run duration.
+    let start = Instant::now();
+    benchmarker.run_for(Duration::from_millis(300)).await;
+    assert!(start.elapsed() >= Duration::from_millis(600));
+
+    let collector = benchmarker.consume_collector().await;
+    let total_requests = collector
+        .samples
+        .iter()
+        .map(|sample| sample.total_requests())
+        .sum::<u64>();
+
+    // Roughly half of the requests are sent while warming up and discarded.
+    let sent = server.requests() as u64;
+    assert!(total_requests > 0);
+    assert!(
+        total_requests < sent * 3 / 4,
+        "Expected warm-up requests to be discarded, got {total_requests} of {sent}",
+    );
+    assert!(collector
+        .samples
+        .iter()
+        .all(|sample| sample.window_index() > 0));
+
+    // The connect phases are kept rather than discarded with the warm-up.
+    let connects = collector
+        .samples
+        .iter()
+        .map(|sample| sample.connect_latency().len())
+        .sum::<u64>();
+    assert_eq!(connects, 2);
+}
+
+#[derive(Clone)]
+pub struct PacedProducer;
+
+#[rewrk_core::async_trait]
+impl Producer for PacedProducer {
+    fn ready(&mut self) {}
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let uri = Uri::builder().path_and_query("/").build()?;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .body(Body::empty())?;
+        Ok(RequestBatch::Batch(Batch {
+            tag: 0,
+            requests: vec![request],
+        }))
+    }
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
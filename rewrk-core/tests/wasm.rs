@@ -0,0 +1,158 @@
+#![cfg(feature = "wasm")]
+
+use rewrk_core::testing::{assert_sample, TestServer};
+use rewrk_core::wasm::{WasmError, WasmPlugin, WasmProducer, WasmValidator};
+use rewrk_core::{
+    HttpProtocol,
+    ReWrkBenchmark,
+    Sample,
+    SampleCollector,
+    ValidationErrorKind,
+};
+
+static BATCH: &str = r#"{"tag":1,"requests":[{"path":"/"},{"method":"POST","path":"/submit","body":"hi"}]}"#;
+
+/// A bump allocator shared by the test plugins, wrapping back
+/// to the start of the heap once the first page is full.
+static ALLOC: &str = r#"
+  (global $heap (mut i32) (i32.const 1024))
+  (func (export "rewrk_abi_version") (result i32) i32.const 1)
+  (func (export "rewrk_alloc") (param $len i32) (result i32) (local $ptr i32)
+    (if (i32.gt_u (i32.add (global.get $heap) (local.get $len)) (i32.const 65536))
+      (then (global.set $heap (i32.const 1024))))
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+"#;
+
+fn producer_plugin() -> WasmPlugin {
+    let wat = format!(
+        r#"(module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "{data}")
+          (global $remaining (mut i32) (i32.const 0))
+          {ALLOC}
+          (func (export "rewrk_producer_ready") (global.set $remaining (i32.const 3)))
+          (func (export "rewrk_producer_next") (result i64)
+            (if (i32.eqz (global.get $remaining)) (then (return (i64.const 0))))
+            (global.set $remaining (i32.sub (global.get $remaining) (i32.const 1)))
+            (i64.const {len})))"#,
+        data = BATCH.replace('"', "\\\""),
+        len = BATCH.len(),
+    );
+    WasmPlugin::from_bytes("producer", wat).expect("Compile plugin")
+}
+
+fn validator_plugin() -> WasmPlugin {
+    let wat = format!(
+        r#"(module
+          (memory (export "memory") 1)
+          (data (i32.const 512) "empty body")
+          {ALLOC}
+          (func (export "rewrk_validate")
+            (param i32 i32 i32) (param $body_len i32) (result i64)
+            (if (result i64) (i32.eqz (local.get $body_len))
+              (then (i64.const {error}))
+              (else (i64.const 0)))))"#,
+        error = (512i64 << 32) | 10,
+    );
+    WasmPlugin::from_bytes("non-empty", wat).expect("Compile plugin")
+}
+
+#[tokio::test]
+async fn test_wasm_plugins() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let validator = WasmValidator::new(validator_plugin(), 2).expect("Create validator");
+
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        1,
+        HttpProtocol::HTTP1,
+        WasmProducer::new(producer_plugin()),
+        BasicCollector::default(),
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker.set_validator(validator);
+    assert_eq!(benchmarker.export_plan().validator, "non-empty");
+    benchmarker.run().await;
+
+    let samples = benchmarker.consume_collector().await.samples;
+    let sample = samples
+        .iter()
+        .find(|sample| sample.tag() == 1)
+        .expect("Sample for the plugin batch tag");
+    assert_eq!(server.requests(), 6);
+    assert_sample(sample)
+        .tag(1)
+        .total_requests(6)
+        .successful_requests(3)
+        .errors(ValidationErrorKind::Other, 3);
+    assert_eq!(
+        sample.error_exemplars()[0].to_string(),
+        "A validation error rejected the request: empty body",
+    );
+}
+
+#[test]
+fn test_wasm_plugin_errors() {
+    let wat = format!(r#"(module (memory (export "memory") 1) {ALLOC})"#);
+    let plugin = WasmPlugin::from_bytes("empty", wat).expect("Compile plugin");
+    let validator = WasmValidator::new(plugin, 1).expect("Create validator");
+    let head = http::Response::new(()).into_parts().0;
+    let error =
+        rewrk_core::ResponseValidator::validate(&validator, head, Default::default())
+            .expect_err("Missing export");
+    assert!(error.to_string().contains("rewrk_validate"), "{error}");
+
+    let wat = ALLOC.replace("i32.const 1)", "i32.const 2)");
+    let wat = format!(r#"(module (memory (export "memory") 1) {wat})"#);
+    let plugin = WasmPlugin::from_bytes("future", wat).expect("Compile plugin");
+    let error = WasmValidator::new(plugin, 1)
+        .err()
+        .expect("Reject ABI version");
+    assert!(matches!(
+        error,
+        WasmError::AbiVersion {
+            expected: 1,
+            found: 2
+        }
+    ));
+
+    let wat = r#"(module
+      (memory (export "memory") 1)
+      (func (export "rewrk_abi_version") (result i32) i32.const 1)
+      (func (export "rewrk_alloc") (param i32) (result i32) (loop $spin (br $spin)) i32.const 0)
+      (func (export "rewrk_validate") (param i32 i32 i32 i32) (result i64) i64.const 0))"#;
+    let mut plugin = WasmPlugin::from_bytes("spin", wat).expect("Compile plugin");
+    plugin.set_fuel_per_call(Some(10_000));
+    let validator = WasmValidator::new(plugin, 1).expect("Create validator");
+    let head = http::Response::new(()).into_parts().0;
+    let error =
+        rewrk_core::ResponseValidator::validate(&validator, head, Default::default())
+            .expect_err("Run out of fuel");
+    assert!(error.to_string().contains("trapped"), "{error}");
+
+    let error = WasmPlugin::from_bytes("invalid", "(module")
+        .err()
+        .expect("Reject module");
+    assert!(matches!(error, WasmError::Load(_)));
+}
+
+#[derive(Default)]
+pub struct BasicCollector {
+    samples: Vec<Sample>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+}
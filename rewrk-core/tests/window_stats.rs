@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::testing::TestServer;
+use rewrk_core::{
+    Batch,
+    ConfigError,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    WindowStats,
+};
+
+#[tokio::test]
+async fn test_window_stats() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let server = TestServer::echo().await.expect("Start server");
+    let producer = AdaptiveProducer::default();
+    let mut benchmarker = ReWrkBenchmark::create(
+        server.uri(),
+        2,
+        HttpProtocol::HTTP1,
+        producer.clone(),
+        BasicCollector,
+    )
+    .await
+    .expect("Create benchmark");
+    benchmarker
+        .set_num_workers(1)
+        .expect("Set benchmark config");
+    benchmarker
+        .set_sample_window(Duration::from_millis(20))
+        .expect("Set benchmark config");
+    assert!(matches!(
+        benchmarker.set_window_stats(Duration::ZERO),
+        Err(ConfigError::ZeroWindowStatsInterval),
+    ));
+    benchmarker
+        .set_window_stats(Duration::from_millis(100))
+        .expect("Set benchmark config");
+    benchmarker.run_for(Duration::from_millis(600)).await;
+
+    let seen = producer.seen.lock().unwrap();
+    assert!(seen.len() >= 2, "Expected several windows, got {seen:?}");
+    for (previous, stats) in seen.iter().zip(seen.iter().skip(1)) {
+        assert!(stats.window_index > previous.window_index);
+    }
+    for stats in seen.iter() {
+        assert!(stats.total_requests > 0);
+        assert_eq!(stats.latency.count, stats.total_requests);
+        assert!(stats.duration >= Duration::from_millis(100));
+        assert!(stats.requests_per_sec() > 0.0);
+        assert_eq!(stats.error_rate(), 0.0);
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct AdaptiveProducer {
+    batch_size: usize,
+    seen: Arc<Mutex<Vec<WindowStats>>>,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for AdaptiveProducer {
+    fn ready(&mut self) {
+        self.batch_size = 1;
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        tokio::time::sleep(Duration::from_millis(2)).await;
+
+        let mut requests = Vec::with_capacity(self.batch_size);
+        for _ in 0..self.batch_size {
+            let uri = Uri::builder().path_and_query("/").build()?;
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())?;
+            requests.push(request);
+        }
+
+        Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+    }
+
+    fn window_stats(&mut self, stats: &WindowStats) {
+        self.seen.lock().unwrap().push(*stats);
+
+        // Back off while the target is slow, otherwise ramp up.
+        if stats.latency.p99 > Duration::from_millis(50) {
+            self.batch_size = (self.batch_size / 2).max(1);
+        } else {
+            self.batch_size = (self.batch_size + 1).min(8);
+        }
+    }
+}
+
+pub struct BasicCollector;
+
+#[rewrk_core::async_trait]
+impl SampleCollector for BasicCollector {
+    async fn process_sample(&mut self, _sample: Sample) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+/*
+ * C bindings for rewrk-core, built by the `rewrk-ffi` crate.
+ *
+ * Functions returning an `int` return 0 on success and -1 on error,
+ * functions returning a pointer return NULL on error. The error message
+ * of the last failed call on the current thread is returned by
+ * `rewrk_last_error`.
+ */
+
+#ifndef REWRK_H
+#define REWRK_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RewrkBenchmark RewrkBenchmark;
+
+/* A summary of a sample or a completed benchmark, latencies are in microseconds. */
+typedef struct RewrkSummary {
+    uint64_t tag;
+    uint64_t total_requests;
+    uint64_t successful_requests;
+    uint64_t failed_requests;
+    double duration_secs;
+    double requests_per_sec;
+    uint64_t read_bytes;
+    uint64_t written_bytes;
+    uint64_t latency_min_us;
+    uint64_t latency_mean_us;
+    uint64_t latency_stdev_us;
+    uint64_t latency_p50_us;
+    uint64_t latency_p90_us;
+    uint64_t latency_p99_us;
+    uint64_t latency_max_us;
+} RewrkSummary;
+
+/* Invoked from a background thread with the summary of each collected sample. */
+typedef void (*RewrkSampleCallback)(void *user_data, const RewrkSummary *summary);
+
+RewrkBenchmark *rewrk_benchmark_new(const char *uri, size_t concurrency, bool http2);
+void rewrk_benchmark_free(RewrkBenchmark *benchmark);
+
+int rewrk_benchmark_set_num_workers(RewrkBenchmark *benchmark, size_t num_workers);
+int rewrk_benchmark_set_duration_ms(RewrkBenchmark *benchmark, uint64_t duration_ms);
+int rewrk_benchmark_set_request(RewrkBenchmark *benchmark, const char *method, const char *path);
+int rewrk_benchmark_set_sample_callback(
+    RewrkBenchmark *benchmark,
+    RewrkSampleCallback callback,
+    void *user_data
+);
+
+int rewrk_benchmark_run(RewrkBenchmark *benchmark);
+int rewrk_benchmark_summary(RewrkBenchmark *benchmark, RewrkSummary *summary);
+
+const char *rewrk_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* REWRK_H */
@@ -0,0 +1,522 @@
+//! C bindings for embedding the `rewrk-core` benchmarking engine.
+//!
+//! This crate is built as a `cdylib` that can be loaded from other languages,
+//! i.e. Python via `cffi` or Node via `ffi-napi`. The matching C declarations
+//! are in `include/rewrk.h`.
+//!
+//! A benchmark sends the same request to the target for a fixed duration,
+//! each sample is passed to an optional callback as it is collected and the
+//! combined summary can be fetched once the benchmark has completed.
+//!
+//! Functions returning a `c_int` return `0` on success and `-1` on error,
+//! functions returning a pointer return null on error. The error message of
+//! the last failed call on the current thread is returned by [rewrk_last_error].
+//! Panics never unwind across the C boundary, they are reported as errors.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::time::{Duration, Instant};
+
+use http::{Method, Request, Uri};
+use hyper::Body;
+use rewrk_core::{
+    Batch,
+    HttpProtocol,
+    Producer,
+    ReWrkBenchmark,
+    RequestBatch,
+    Sample,
+    SampleCollector,
+    SampleMerger,
+};
+use tokio::runtime::Runtime;
+
+/// The default duration of a benchmark created with [rewrk_benchmark_new].
+const DEFAULT_DURATION: Duration = Duration::from_secs(10);
+/// The number of requests in each batch produced for the workers.
+const BATCH_SIZE: usize = 64;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A callback invoked with the summary of each collected sample.
+///
+/// The summary pointer is only valid for the duration of the call.
+pub type RewrkSampleCallback =
+    extern "C" fn(user_data: *mut c_void, summary: *const RewrkSummary);
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// A summary of a sample or a completed benchmark.
+///
+/// All latencies are in microseconds.
+pub struct RewrkSummary {
+    /// The tag of the sample, this is always `0` for the benchmark summary.
+    pub tag: u64,
+    /// The total number of requests sent.
+    pub total_requests: u64,
+    /// The number of requests which passed validation.
+    pub successful_requests: u64,
+    /// The number of requests which failed validation.
+    pub failed_requests: u64,
+    /// The duration of the sample in seconds.
+    pub duration_secs: f64,
+    /// The average number of requests per second.
+    pub requests_per_sec: f64,
+    /// The total number of bytes read.
+    pub read_bytes: u64,
+    /// The total number of bytes written.
+    pub written_bytes: u64,
+    /// The minimum recorded latency.
+    pub latency_min_us: u64,
+    /// The mean recorded latency.
+    pub latency_mean_us: u64,
+    /// The standard deviation of the recorded latency.
+    pub latency_stdev_us: u64,
+    /// The 50th percentile latency.
+    pub latency_p50_us: u64,
+    /// The 90th percentile latency.
+    pub latency_p90_us: u64,
+    /// The 99th percentile latency.
+    pub latency_p99_us: u64,
+    /// The maximum recorded latency.
+    pub latency_max_us: u64,
+}
+
+impl From<&Sample> for RewrkSummary {
+    fn from(sample: &Sample) -> Self {
+        let latency = sample.latency_summary();
+
+        Self {
+            tag: sample.tag() as u64,
+            total_requests: sample.total_requests(),
+            successful_requests: sample.successful_requests(),
+            failed_requests: sample.failed_requests(),
+            duration_secs: sample.duration().as_secs_f64(),
+            requests_per_sec: sample.requests_per_sec(),
+            read_bytes: sample.read_bytes(),
+            written_bytes: sample.written_bytes(),
+            latency_min_us: latency.min.as_micros() as u64,
+            latency_mean_us: latency.mean.as_micros() as u64,
+            latency_stdev_us: latency.stdev.as_micros() as u64,
+            latency_p50_us: latency.p50.as_micros() as u64,
+            latency_p90_us: latency.p90.as_micros() as u64,
+            latency_p99_us: latency.p99.as_micros() as u64,
+            latency_max_us: latency.max.as_micros() as u64,
+        }
+    }
+}
+
+/// A benchmark created with [rewrk_benchmark_new].
+pub struct RewrkBenchmark {
+    runtime: Runtime,
+    uri: Uri,
+    concurrency: usize,
+    protocol: HttpProtocol,
+    num_workers: Option<usize>,
+    producer: FfiProducer,
+    callback: Option<SampleCallback>,
+    summary: Option<RewrkSummary>,
+}
+
+/// Creates a new benchmark sending `GET` requests to the given URI.
+///
+/// The benchmark must be freed with [rewrk_benchmark_free].
+///
+/// # Safety
+///
+/// `uri` must be a valid, null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_new(
+    uri: *const c_char,
+    concurrency: usize,
+    http2: bool,
+) -> *mut RewrkBenchmark {
+    let result = catch_panic(|| {
+        let uri = read_str(uri, "uri")?
+            .parse::<Uri>()
+            .map_err(|e| format!("Invalid URI: {e}"))?;
+        if concurrency == 0 {
+            return Err("The concurrency must be greater than zero".to_string());
+        }
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to create runtime: {e}"))?;
+
+        let path = uri
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or("/")
+            .parse::<Uri>()
+            .map_err(|e| format!("Invalid URI: {e}"))?;
+
+        Ok(RewrkBenchmark {
+            runtime,
+            uri,
+            concurrency,
+            protocol: if http2 {
+                HttpProtocol::HTTP2
+            } else {
+                HttpProtocol::HTTP1
+            },
+            num_workers: None,
+            producer: FfiProducer {
+                method: Method::GET,
+                path,
+                duration: DEFAULT_DURATION,
+                deadline: None,
+            },
+            callback: None,
+            summary: None,
+        })
+    });
+
+    match result {
+        Ok(benchmark) => Box::into_raw(Box::new(benchmark)),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Frees a benchmark created with [rewrk_benchmark_new].
+///
+/// # Safety
+///
+/// `benchmark` must be null or a pointer returned by [rewrk_benchmark_new]
+/// which has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_free(benchmark: *mut RewrkBenchmark) {
+    if !benchmark.is_null() {
+        let result = catch_panic(|| {
+            drop(Box::from_raw(benchmark));
+            Ok(())
+        });
+        if let Err(e) = result {
+            set_last_error(e);
+        }
+    }
+}
+
+/// Sets the number of worker threads used by the benchmark.
+///
+/// # Safety
+///
+/// `benchmark` must be a valid pointer returned by [rewrk_benchmark_new].
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_set_num_workers(
+    benchmark: *mut RewrkBenchmark,
+    num_workers: usize,
+) -> c_int {
+    with_benchmark(benchmark, |benchmark| {
+        if num_workers == 0 {
+            return Err("The number of workers must be greater than zero".to_string());
+        }
+        benchmark.num_workers = Some(num_workers);
+        Ok(())
+    })
+}
+
+/// Sets the duration of the benchmark in milliseconds.
+///
+/// # Safety
+///
+/// `benchmark` must be a valid pointer returned by [rewrk_benchmark_new].
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_set_duration_ms(
+    benchmark: *mut RewrkBenchmark,
+    duration_ms: u64,
+) -> c_int {
+    with_benchmark(benchmark, |benchmark| {
+        benchmark.producer.duration = Duration::from_millis(duration_ms);
+        Ok(())
+    })
+}
+
+/// Sets the method and path of the request sent by the benchmark.
+///
+/// # Safety
+///
+/// `benchmark` must be a valid pointer returned by [rewrk_benchmark_new],
+/// `method` and `path` must be valid, null terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_set_request(
+    benchmark: *mut RewrkBenchmark,
+    method: *const c_char,
+    path: *const c_char,
+) -> c_int {
+    with_benchmark(benchmark, |benchmark| {
+        let method = read_str(method, "method")?
+            .parse::<Method>()
+            .map_err(|e| format!("Invalid method: {e}"))?;
+        let path = read_str(path, "path")?
+            .parse::<Uri>()
+            .map_err(|e| format!("Invalid path: {e}"))?;
+
+        benchmark.producer.method = method;
+        benchmark.producer.path = path;
+        Ok(())
+    })
+}
+
+/// Sets the callback invoked with the summary of each collected sample.
+///
+/// The callback is invoked from a background thread while
+/// [rewrk_benchmark_run] is running, passing a null callback removes it.
+///
+/// # Safety
+///
+/// `benchmark` must be a valid pointer returned by [rewrk_benchmark_new],
+/// `user_data` must be safe to access from the background thread.
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_set_sample_callback(
+    benchmark: *mut RewrkBenchmark,
+    callback: Option<RewrkSampleCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    with_benchmark(benchmark, |benchmark| {
+        benchmark.callback = callback.map(|callback| SampleCallback {
+            callback,
+            user_data,
+        });
+        Ok(())
+    })
+}
+
+/// Runs the benchmark, blocking until it has completed.
+///
+/// # Safety
+///
+/// `benchmark` must be a valid pointer returned by [rewrk_benchmark_new].
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_run(benchmark: *mut RewrkBenchmark) -> c_int {
+    with_benchmark(benchmark, |benchmark| {
+        let collector = FfiCollector {
+            merger: SampleMerger::default(),
+            callback: benchmark.callback,
+        };
+
+        let samples = benchmark.runtime.block_on(async {
+            let mut benchmarker = ReWrkBenchmark::create(
+                benchmark.uri.clone(),
+                benchmark.concurrency,
+                benchmark.protocol,
+                benchmark.producer.clone(),
+                collector,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            if let Some(num_workers) = benchmark.num_workers {
+                benchmarker
+                    .set_num_workers(num_workers)
+                    .map_err(|e| e.to_string())?;
+            }
+
+            benchmarker.run().await;
+            let collector = benchmarker.consume_collector().await;
+            Ok::<_, String>(collector.merger.into_samples())
+        })?;
+
+        let summary = samples
+            .into_iter()
+            .reduce(|mut total, sample| {
+                total += &sample;
+                total
+            })
+            .map(|total| {
+                let mut summary = RewrkSummary::from(&total);
+                summary.tag = 0;
+                summary
+            })
+            .unwrap_or_default();
+        benchmark.summary = Some(summary);
+
+        Ok(())
+    })
+}
+
+/// Writes the summary of the last completed run to `summary`.
+///
+/// # Safety
+///
+/// `benchmark` must be a valid pointer returned by [rewrk_benchmark_new],
+/// `summary` must be a valid pointer to a [RewrkSummary].
+#[no_mangle]
+pub unsafe extern "C" fn rewrk_benchmark_summary(
+    benchmark: *mut RewrkBenchmark,
+    summary: *mut RewrkSummary,
+) -> c_int {
+    with_benchmark(benchmark, |benchmark| {
+        if summary.is_null() {
+            return Err("The summary pointer is null".to_string());
+        }
+
+        let result = benchmark
+            .summary
+            .ok_or_else(|| "The benchmark has not been run".to_string())?;
+        summary.write(result);
+        Ok(())
+    })
+}
+
+/// Returns the error message of the last failed call on the current thread.
+///
+/// The returned string is valid until the next call on the same thread,
+/// null is returned if no call has failed.
+#[no_mangle]
+pub extern "C" fn rewrk_last_error() -> *const c_char {
+    panic::catch_unwind(|| {
+        LAST_ERROR.with(|error| {
+            error
+                .borrow()
+                .as_ref()
+                .map(|error| error.as_ptr())
+                .unwrap_or(ptr::null())
+        })
+    })
+    .unwrap_or(ptr::null())
+}
+
+fn set_last_error(error: String) {
+    let error = CString::new(error).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(error));
+}
+
+unsafe fn with_benchmark(
+    benchmark: *mut RewrkBenchmark,
+    func: impl FnOnce(&mut RewrkBenchmark) -> Result<(), String>,
+) -> c_int {
+    let result = match benchmark.as_mut() {
+        None => Err("The benchmark pointer is null".to_string()),
+        Some(benchmark) => catch_panic(|| func(benchmark)),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            set_last_error(e);
+            -1
+        },
+    }
+}
+
+/// Runs the function, converting a panic into an error so it never
+/// unwinds across the C boundary.
+fn catch_panic<T>(func: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(func))
+        .unwrap_or_else(|payload| Err(panic_message(payload)))
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("Panicked: {message}")
+}
+
+unsafe fn read_str<'a>(value: *const c_char, name: &str) -> Result<&'a str, String> {
+    if value.is_null() {
+        return Err(format!("The {name} pointer is null"));
+    }
+
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| format!("The {name} is not valid UTF-8"))
+}
+
+#[derive(Clone, Copy)]
+struct SampleCallback {
+    callback: RewrkSampleCallback,
+    user_data: *mut c_void,
+}
+
+// Safety: The caller of `rewrk_benchmark_set_sample_callback` guarantees
+// the user data can be accessed from the collector thread.
+unsafe impl Send for SampleCallback {}
+
+struct FfiCollector {
+    merger: SampleMerger,
+    callback: Option<SampleCallback>,
+}
+
+#[rewrk_core::async_trait]
+impl SampleCollector for FfiCollector {
+    async fn process_sample(&mut self, sample: Sample) -> anyhow::Result<()> {
+        if let Some(callback) = self.callback {
+            let summary = RewrkSummary::from(&sample);
+            (callback.callback)(callback.user_data, &summary);
+        }
+
+        self.merger.add_sample(sample);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+/// Produces the same request until the benchmark duration has elapsed.
+struct FfiProducer {
+    method: Method,
+    path: Uri,
+    duration: Duration,
+    deadline: Option<Instant>,
+}
+
+#[rewrk_core::async_trait]
+impl Producer for FfiProducer {
+    fn ready(&mut self) {
+        self.deadline = Some(Instant::now() + self.duration);
+    }
+
+    async fn create_batch(&mut self) -> anyhow::Result<RequestBatch> {
+        if self
+            .deadline
+            .map_or(true, |deadline| Instant::now() >= deadline)
+        {
+            return Ok(RequestBatch::End);
+        }
+
+        let requests = (0..BATCH_SIZE)
+            .map(|_| {
+                Request::builder()
+                    .method(self.method.clone())
+                    .uri(self.path.clone())
+                    .body(Body::empty())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RequestBatch::Batch(Batch { tag: 0, requests }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic(|| Ok(1)), Ok(1));
+        assert_eq!(
+            catch_panic::<()>(|| Err("failed".to_string())),
+            Err("failed".to_string())
+        );
+        assert_eq!(
+            catch_panic::<()>(|| panic!("oops")),
+            Err("Panicked: oops".to_string()),
+        );
+        assert_eq!(
+            catch_panic::<()>(|| panic!("code {}", 1)),
+            Err("Panicked: code 1".to_string()),
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::ffi::{c_void, CStr, CString};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rewrk_core::testing::TestServer;
+use rewrk_ffi::*;
+
+extern "C" fn count_requests(user_data: *mut c_void, summary: *const RewrkSummary) {
+    let counter = unsafe { &*(user_data as *const AtomicU64) };
+    let summary = unsafe { &*summary };
+    counter.fetch_add(summary.total_requests, Ordering::Relaxed);
+}
+
+#[test]
+fn test_ffi_benchmark() {
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let runtime = tokio::runtime::Runtime::new().expect("Create runtime");
+    let server = runtime.block_on(TestServer::echo()).expect("Start server");
+    let uri = CString::new(server.uri().to_string()).unwrap();
+    let counter = AtomicU64::new(0);
+
+    unsafe {
+        let benchmark = rewrk_benchmark_new(uri.as_ptr(), 2, false);
+        assert!(!benchmark.is_null());
+        assert_eq!(rewrk_benchmark_set_num_workers(benchmark, 1), 0);
+        assert_eq!(rewrk_benchmark_set_duration_ms(benchmark, 250), 0);
+        assert_eq!(
+            rewrk_benchmark_set_sample_callback(
+                benchmark,
+                Some(count_requests),
+                &counter as *const AtomicU64 as *mut c_void,
+            ),
+            0,
+        );
+
+        let mut summary = RewrkSummary::default();
+        assert_eq!(rewrk_benchmark_summary(benchmark, &mut summary), -1);
+        assert!(!rewrk_last_error().is_null());
+
+        assert_eq!(rewrk_benchmark_run(benchmark), 0);
+        assert_eq!(rewrk_benchmark_summary(benchmark, &mut summary), 0);
+        rewrk_benchmark_free(benchmark);
+
+        assert!(summary.total_requests > 0);
+        assert_eq!(summary.total_requests, summary.successful_requests);
+        assert_eq!(summary.total_requests, counter.load(Ordering::Relaxed));
+        assert_eq!(summary.total_requests, server.requests() as u64);
+        assert!(summary.latency_max_us >= summary.latency_min_us);
+    }
+}
+
+#[test]
+fn test_ffi_errors() {
+    unsafe {
+        let uri = CString::new("not a uri").unwrap();
+        let benchmark = rewrk_benchmark_new(uri.as_ptr(), 1, false);
+        assert!(benchmark.is_null());
+
+        let error = CStr::from_ptr(rewrk_last_error()).to_str().unwrap();
+        assert!(error.starts_with("Invalid URI"), "{error}");
+
+        let uri = CString::new("http://127.0.0.1:20016/").unwrap();
+        let benchmark = rewrk_benchmark_new(uri.as_ptr(), 1, false);
+        let method = CString::new("GET").unwrap();
+        let path = CString::new("/ping?x=1").unwrap();
+        assert_eq!(
+            rewrk_benchmark_set_request(benchmark, method.as_ptr(), path.as_ptr()),
+            0,
+        );
+        assert_eq!(rewrk_benchmark_set_num_workers(benchmark, 0), -1);
+        rewrk_benchmark_free(benchmark);
+    }
+}
@@ -1,13 +1,21 @@
 use std::fmt::Display;
-use std::time::Duration;
+use std::fs::File;
+use std::io::BufWriter;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use ::http::{HeaderMap, Method};
 use anyhow::{anyhow, Result};
 use colored::*;
 use futures_util::StreamExt;
 use hyper::body::Bytes;
+use rewrk_core::{ArchiveHeader, ArchiveWriter, ImbalanceDetector};
 
+use crate::control::{Command, Controller};
 use crate::results::WorkerResult;
+use crate::shard::Shard;
+use crate::sweep::{self, GoalSeek, SweepResult};
 use crate::utils::div_mod;
 use crate::{http, runtime};
 
@@ -47,30 +55,202 @@ pub struct BenchmarkSettings {
 
     /// Request body.
     pub body: Bytes,
+
+    /// Reconnect for every request rather than re-using connections.
+    pub no_keepalive: bool,
+
+    /// Slow down reconnects when the ephemeral ports are exhausted.
+    pub adaptive_connect: bool,
+
+    /// The connection counts to run the benchmark at, overriding
+    /// `connections` when set.
+    pub sweep: Option<Vec<usize>>,
+
+    /// Step the connection count upward from `connections` until the
+    /// p99 latency breaches a target.
+    pub goal_seek: Option<GoalSeek>,
+
+    /// The file to write the consolidated sweep results to as a CSV.
+    pub sweep_csv: Option<PathBuf>,
+
+    /// Read runtime control commands from stdin.
+    pub control: bool,
+
+    /// The address to serve the control API on.
+    pub control_addr: Option<SocketAddr>,
+
+    /// The total request rate limit in requests per second, `0` if unlimited.
+    pub rate: u64,
+
+    /// The share of the workload run by this process, connection counts
+    /// are already split while request rates are split when applied.
+    pub shard: Option<Shard>,
+
+    /// The `.rewrk` archive to write the result of every round to.
+    pub archive: Option<PathBuf>,
+
+    /// Checkpoints the archive at most once per interval.
+    pub checkpoint_interval: Option<Duration>,
+
+    /// Resume the archive, appending rounds after those already archived.
+    pub resume: bool,
 }
 
 /// Builds the runtime with the given settings and blocks on the main future.
 pub fn start_benchmark(settings: BenchmarkSettings) {
     let rt = runtime::get_rt(settings.threads);
-    let rounds = settings.rounds;
-    let is_json = settings.display_json;
-    for i in 0..rounds {
-        if !is_json {
-            println!("Beginning round {}...", i + 1);
+    let mut control = {
+        let _guard = rt.enter();
+        match Controller::new(settings.control, settings.control_addr, settings.rate) {
+            Ok(control) => control,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            },
         }
-
-        if let Err(e) = rt.block_on(run(settings.clone())) {
-            eprintln!();
+    };
+    let (mut archive, mut archived_rounds) = match settings
+        .archive
+        .as_ref()
+        .map(|path| create_archive(path, &settings))
+    {
+        None => (None, 0),
+        Some(Ok((archive, rounds))) => (Some(archive), rounds),
+        Some(Err(e)) => {
             eprintln!("{}", e);
             return;
+        },
+    };
+    let mut last_checkpoint = Instant::now();
+    let rounds = settings.rounds;
+    let is_json = settings.display_json;
+    let mut levels = settings
+        .sweep
+        .clone()
+        .unwrap_or_else(|| vec![settings.connections])
+        .into_iter();
+
+    let mut sweep_results = Vec::new();
+    let mut next_level = levels.next();
+    'levels: while let Some(connections) = next_level {
+        let settings = BenchmarkSettings {
+            connections,
+            ..settings.clone()
+        };
+
+        for i in 0..rounds {
+            if !is_json {
+                println!("Beginning round {}...", i + 1);
+            }
+
+            match rt.block_on(run(settings.clone(), &mut control)) {
+                Ok(result) => {
+                    if let Some(writer) = archive.as_mut() {
+                        if let Err(e) =
+                            writer.write_sample(&result.to_sample(archived_rounds, 0))
+                        {
+                            eprintln!("failed to write the round to the archive: {}", e);
+                        }
+                        archived_rounds += 1;
+
+                        let interval = settings.checkpoint_interval;
+                        if interval.is_some_and(|i| last_checkpoint.elapsed() >= i) {
+                            if let Err(e) = writer.checkpoint() {
+                                eprintln!("failed to checkpoint the archive: {}", e);
+                            }
+                            last_checkpoint = Instant::now();
+                        }
+                    }
+
+                    sweep_results.push(SweepResult::from_result(
+                        connections,
+                        i,
+                        &result,
+                    ));
+                },
+                Err(e) => {
+                    eprintln!();
+                    eprintln!("{}", e);
+                    finish_archive(archive);
+                    return;
+                },
+            }
+
+            // Adds a line separator between rounds unless it's formatting
+            // as a json, for readability.
+            if !is_json {
+                println!();
+            };
+
+            if control.is_stopped() {
+                break 'levels;
+            }
         }
 
-        // Adds a line separator between rounds unless it's formatting
-        // as a json, for readability.
-        if !is_json {
-            println!();
+        next_level = match settings.goal_seek.as_ref() {
+            Some(goal) => goal.next_level(connections, &sweep_results),
+            None => levels.next(),
         };
     }
+    finish_archive(archive);
+
+    if settings.sweep.is_none() && settings.goal_seek.is_none() {
+        return;
+    }
+
+    if !is_json {
+        sweep::display_sweep_table(&sweep_results);
+    }
+
+    if let Some(goal) = settings.goal_seek.as_ref() {
+        if is_json {
+            sweep::display_goal_seek_json(goal, &sweep_results);
+        } else {
+            sweep::display_goal_seek(goal, &sweep_results);
+        }
+    }
+
+    if let Some(path) = settings.sweep_csv.as_ref() {
+        if let Err(e) = sweep::write_csv(path, &sweep_results) {
+            eprintln!("failed to write sweep results to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Creates the archive the results of each round are written to.
+///
+/// When resuming, this returns the number of rounds already archived.
+fn create_archive(
+    path: &PathBuf,
+    settings: &BenchmarkSettings,
+) -> Result<(ArchiveWriter<BufWriter<File>>, usize)> {
+    if settings.resume {
+        let (writer, resumed) = ArchiveWriter::resume(path).map_err(|e| {
+            anyhow!("failed to resume archive {}: {}", path.display(), e)
+        })?;
+        return Ok((writer, resumed.rounds));
+    }
+
+    let mut header = ArchiveHeader::default();
+    header
+        .metadata
+        .insert("host".to_string(), settings.host.trim().to_string());
+    if let Some(shard) = settings.shard {
+        header
+            .metadata
+            .insert("shard".to_string(), shard.to_string());
+    }
+
+    let writer = ArchiveWriter::create(path, &header)
+        .map_err(|e| anyhow!("failed to create archive {}: {}", path.display(), e))?;
+    Ok((writer, 0))
+}
+
+/// Completes the archive, if one is being written.
+fn finish_archive(archive: Option<ArchiveWriter<BufWriter<File>>>) {
+    if let Some(Err(e)) = archive.map(ArchiveWriter::finish) {
+        eprintln!("failed to finish the archive: {}", e);
+    }
 }
 
 /// Controls the benchmark itself.
@@ -82,10 +262,13 @@ pub fn start_benchmark(settings: BenchmarkSettings) {
 /// extracted from the handle.
 ///
 /// The results are then merged into a single set of averages across workers.
-async fn run(settings: BenchmarkSettings) -> Result<()> {
+async fn run(
+    settings: BenchmarkSettings,
+    control: &mut Controller,
+) -> Result<WorkerResult> {
     let predict_size = settings.duration.as_secs() * 10_000;
 
-    let handles = http::start_tasks(
+    let tasks = http::start_tasks(
         settings.duration,
         settings.connections,
         settings.host.trim().to_string(),
@@ -93,11 +276,14 @@ async fn run(settings: BenchmarkSettings) -> Result<()> {
         settings.method,
         settings.headers,
         settings.body,
+        settings.no_keepalive,
+        settings.adaptive_connect,
         predict_size as usize,
+        control.status().clone(),
     )
     .await;
 
-    let mut handles = match handles {
+    let mut tasks = match tasks {
         Ok(v) => v,
         Err(e) => return Err(anyhow!("error parsing uri: {}", e)),
     };
@@ -109,30 +295,56 @@ async fn run(settings: BenchmarkSettings) -> Result<()> {
             settings.host,
             humanize(settings.duration),
         );
+
+        if let Some(shard) = settings.shard {
+            println!("Running as shard {}", shard.to_string().cyan());
+        }
     }
 
+    tasks.set_rate(shard_rate(settings.shard, control.rate()));
+    control.status().start_round(settings.connections);
+
+    let start = Instant::now();
     let mut combiner = WorkerResult::default();
-    while let Some(result) = handles.next().await {
-        match result.unwrap() {
-            Ok(stats) => combiner = combiner.combine(stats),
-            Err(e) => return Err(anyhow!("connection error: {}", e)),
+    let mut imbalance = ImbalanceDetector::default();
+    let mut connection_id = 0;
+    loop {
+        tokio::select! {
+            result = tasks.handles.next() => match result {
+                None => break,
+                Some(result) => match result.unwrap() {
+                    Ok(stats) => {
+                        imbalance.add_sample(&stats.to_sample(0, connection_id));
+                        connection_id += 1;
+                        combiner = combiner.combine(stats);
+                    },
+                    Err(e) => return Err(anyhow!("connection error: {}", e)),
+                },
+            },
+            command = control.next() => {
+                apply_command(command, &mut tasks, settings.shard, start)
+            },
         }
     }
+    control.status().finish_round();
 
     if settings.display_json {
-        combiner.display_json();
-        return Ok(());
+        combiner.display_json(settings.shard);
+        display_imbalance(&imbalance);
+        return Ok(combiner);
     }
 
     // prevent div-by-zero panics
     if combiner.total_requests() == 0 {
         println!("No requests completed successfully");
-        return Ok(());
+        return Ok(combiner);
     }
 
     combiner.display_latencies();
+    combiner.display_connect_latencies();
     combiner.display_requests();
     combiner.display_transfer();
+    combiner.display_connections();
 
     if settings.display_percentile {
         combiner.display_percentile_table();
@@ -140,8 +352,64 @@ async fn run(settings: BenchmarkSettings) -> Result<()> {
 
     // Display errors last.
     combiner.display_errors();
+    display_imbalance(&imbalance);
+
+    Ok(combiner)
+}
+
+/// Warns about connections which handled noticeably fewer requests or had
+/// a higher p99 latency than the others.
+///
+/// Output is written to stderr so it doesn't mix with the JSON results.
+fn display_imbalance(imbalance: &ImbalanceDetector) {
+    let warnings = imbalance.warnings();
+    if !warnings.is_empty() {
+        eprintln!();
+    }
+    for warning in warnings {
+        eprintln!("{} {}", "warning:".bright_yellow(), warning);
+    }
+}
+
+/// Applies a control command to the running benchmark.
+///
+/// Output is written to stderr so it doesn't mix with the JSON results.
+fn apply_command(
+    command: Command,
+    tasks: &mut http::Tasks,
+    shard: Option<Shard>,
+    start: Instant,
+) {
+    match command {
+        Command::Rate(0) => {
+            tasks.set_rate(0.0);
+            eprintln!("control: removed the request rate limit");
+        },
+        Command::Rate(rate) => {
+            let rate = shard_rate(shard, rate);
+            tasks.set_rate(rate);
+            eprintln!(
+                "control: limited the request rate to {} req/sec",
+                string(rate)
+            );
+        },
+        Command::Concurrency(n) => {
+            tasks.set_concurrency(n);
+            eprintln!("control: set the concurrency to {} connections", n);
+        },
+        Command::Annotate(message) => {
+            eprintln!("[{:.2}s] {}", start.elapsed().as_secs_f64(), message);
+        },
+        Command::Stop => {
+            tasks.stop();
+            eprintln!("control: stopping the benchmark");
+        },
+    }
+}
 
-    Ok(())
+/// The share of the total request rate for the given shard.
+fn shard_rate(shard: Option<Shard>, rate: u64) -> f64 {
+    shard.map_or(rate as f64, |shard| shard.split_rate(rate))
 }
 
 /// Uber lazy way of just stringing everything and limiting it to 2 d.p
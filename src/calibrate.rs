@@ -0,0 +1,327 @@
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ::http::{HeaderMap, Method};
+use anyhow::{anyhow, Result};
+use colored::*;
+use futures_util::StreamExt;
+use hyper::body::Bytes;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server};
+use serde_json::json;
+use tokio::runtime::Builder;
+
+use crate::control::Status;
+use crate::results::WorkerResult;
+use crate::{http, runtime};
+
+/// The number of timestamps taken when measuring the timer overhead.
+const TIMER_SAMPLES: u32 = 1_000_000;
+
+/// The settings of a calibration run.
+#[derive(Clone, Debug)]
+pub struct CalibrateSettings {
+    /// The number of worker threads given to the client's runtime.
+    pub threads: usize,
+
+    /// The amount of concurrent connections to the in-process server.
+    pub connections: usize,
+
+    /// The duration of the calibration run.
+    pub duration: Duration,
+
+    /// The bench mark type e.g. http1 only.
+    pub bench_type: http::BenchType,
+
+    /// Display the result data as a json.
+    pub display_json: bool,
+}
+
+/// The client's own ceiling on the current machine.
+struct Calibration {
+    result: WorkerResult,
+    timer_overhead: Duration,
+    cpu_per_request: Option<Duration>,
+}
+
+/// Benchmarks a built-in nil-latency server running in the same process.
+///
+/// The server responds immediately with an empty body, so the results are
+/// the ceiling of the client on the current machine rather than the server.
+/// Benchmarks approaching this ceiling are likely client-bound.
+pub fn run(settings: CalibrateSettings) -> Result<()> {
+    let server_cpu = Arc::new(AtomicU64::new(0));
+    let server_rt = {
+        let server_cpu = server_cpu.clone();
+        Builder::new_multi_thread()
+            .enable_all()
+            .worker_threads(settings.threads)
+            .on_thread_stop(move || {
+                if let Some(cpu) = thread_cpu_time() {
+                    server_cpu.fetch_add(cpu.as_nanos() as u64, Ordering::Relaxed);
+                }
+            })
+            .build()?
+    };
+    let addr = {
+        let _guard = server_rt.enter();
+        spawn_server()?
+    };
+
+    if !settings.display_json {
+        println!(
+            "Calibrating {} connections against a nil-latency server @ {} for {}s",
+            settings.connections.to_string().cyan(),
+            addr,
+            settings.duration.as_secs(),
+        );
+    }
+
+    let timer_overhead = measure_timer_overhead();
+
+    let client_rt = runtime::get_rt(settings.threads);
+    let cpu_start = process_cpu_time();
+    let result = client_rt.block_on(benchmark(&settings, addr))?;
+    let cpu_end = process_cpu_time();
+
+    // Stopping the server's threads records their CPU time.
+    server_rt.shutdown_timeout(Duration::from_secs(1));
+    let server_cpu = Duration::from_nanos(server_cpu.load(Ordering::Relaxed));
+
+    if result.total_requests() == 0 {
+        return Err(anyhow!("no requests completed during calibration"));
+    }
+
+    let cpu_per_request = cpu_start.zip(cpu_end).map(|(start, end)| {
+        let client_cpu = end.saturating_sub(start).saturating_sub(server_cpu);
+        client_cpu / result.total_requests() as u32
+    });
+
+    let calibration = Calibration {
+        result,
+        timer_overhead,
+        cpu_per_request,
+    };
+    if settings.display_json {
+        calibration.display_json(&settings);
+    } else {
+        calibration.display(&settings);
+    }
+
+    Ok(())
+}
+
+async fn benchmark(
+    settings: &CalibrateSettings,
+    addr: SocketAddr,
+) -> Result<WorkerResult> {
+    let mut tasks = http::start_tasks(
+        settings.duration,
+        settings.connections,
+        format!("http://{}/", addr),
+        settings.bench_type,
+        Method::GET,
+        HeaderMap::new(),
+        Bytes::new(),
+        false,
+        false,
+        0,
+        Arc::new(Status::default()),
+    )
+    .await?;
+
+    let mut combiner = WorkerResult::default();
+    while let Some(result) = tasks.handles.next().await {
+        match result.unwrap() {
+            Ok(stats) => combiner = combiner.combine(stats),
+            Err(e) => return Err(anyhow!("connection error: {}", e)),
+        }
+    }
+
+    Ok(combiner)
+}
+
+/// Serves empty responses on a random local port.
+fn spawn_server() -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    let make_service = make_service_fn(|_| async {
+        Ok::<_, Infallible>(service_fn(|_request| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }))
+    });
+
+    let server = Server::from_tcp(listener)?.serve(make_service);
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            eprintln!("calibrate: the server failed: {}", e);
+        }
+    });
+
+    Ok(addr)
+}
+
+/// Measures the average time taken to read the clock, which is
+/// paid at least twice by every request the client times.
+fn measure_timer_overhead() -> Duration {
+    let start = Instant::now();
+    let mut last = start;
+    for _ in 0..TIMER_SAMPLES {
+        last = std::hint::black_box(Instant::now());
+    }
+
+    last.duration_since(start) / TIMER_SAMPLES
+}
+
+/// The CPU time used by the process so far.
+#[cfg(unix)]
+fn process_cpu_time() -> Option<Duration> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let usage = unsafe { usage.assume_init() };
+
+    let to_duration = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec as u64)
+            + Duration::from_micros(time.tv_usec as u64)
+    };
+    Some(to_duration(usage.ru_utime) + to_duration(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+fn process_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// The CPU time used by the current thread so far.
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } != 0 {
+        return None;
+    }
+
+    Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+impl Calibration {
+    fn display(&self, settings: &CalibrateSettings) {
+        let modifier = 1000_f64;
+        let requests_per_sec = self.result.avg_request_per_sec();
+
+        println!();
+        println!("  Client Ceiling:");
+        println!(
+            "    {:<13}  {:<13}  {:<13}  {:<15}  {:<15}",
+            "Req/Sec".bright_green(),
+            "Avg Latency".bright_yellow(),
+            "P99 Latency".bright_red(),
+            "Timer Overhead".bright_magenta(),
+            "CPU/Request".bright_cyan(),
+        );
+        println!(
+            "    {:<13}  {:<13}  {:<13}  {:<15}  {:<15}",
+            format!("{:.2}", requests_per_sec),
+            format!(
+                "{:.2}ms",
+                self.result.avg_request_latency().as_secs_f64() * modifier
+            ),
+            format!(
+                "{:.2}ms",
+                self.result.p99_latency().as_secs_f64() * modifier
+            ),
+            format!("{}ns", self.timer_overhead.as_nanos()),
+            self.cpu_per_request
+                .map(|cpu| format!("{:.2}us", cpu.as_secs_f64() * 1_000_000.0))
+                .unwrap_or_else(|| "unavailable".to_string()),
+        );
+
+        let errors = self.result.total_errors();
+        if errors > 0 {
+            println!("    {} requests failed during calibration", errors);
+        }
+
+        println!();
+        println!(
+            "  Benchmarks with {} thread(s) and {} connection(s) approaching {} req/sec \
+             on this machine are likely client-bound.",
+            settings.threads,
+            settings.connections,
+            format!("{:.2}", requests_per_sec).bright_green(),
+        );
+    }
+
+    fn display_json(&self, settings: &CalibrateSettings) {
+        let modifier = 1000_f64;
+
+        let out = json!({
+            "threads": settings.threads,
+            "connections": settings.connections,
+            "duration_secs": settings.duration.as_secs_f64(),
+            "requests_total": self.result.total_requests(),
+            "requests_avg": self.result.avg_request_per_sec(),
+            "errors_total": self.result.total_errors(),
+            "latency_avg": self.result.avg_request_latency().as_secs_f64() * modifier,
+            "latency_p99": self.result.p99_latency().as_secs_f64() * modifier,
+            "timer_overhead_ns": self.timer_overhead.as_nanos() as u64,
+            "cpu_per_request_us": self
+                .cpu_per_request
+                .map(|cpu| cpu.as_secs_f64() * 1_000_000.0),
+        });
+
+        println!("{}", out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_nil_server() {
+        let settings = CalibrateSettings {
+            threads: 1,
+            connections: 2,
+            duration: Duration::from_millis(200),
+            bench_type: http::BenchType::HTTP1,
+            display_json: false,
+        };
+
+        let rt = runtime::get_rt(1);
+        let result = rt
+            .block_on(async {
+                let addr = spawn_server()?;
+                benchmark(&settings, addr).await
+            })
+            .unwrap();
+        assert!(result.total_requests() > 0);
+        assert_eq!(result.total_errors(), 0);
+        assert!(result.p99_latency() <= result.max_request_latency());
+    }
+
+    #[test]
+    fn test_timer_overhead() {
+        let overhead = measure_timer_overhead();
+        assert!(overhead < Duration::from_millis(1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cpu_time() {
+        assert!(process_cpu_time().is_some());
+        assert!(thread_cpu_time().is_some());
+    }
+}
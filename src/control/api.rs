@@ -0,0 +1,178 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use http::{header, Method, Request, Response, StatusCode};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use super::{Command, Status};
+
+/// Serves the control API on the given address.
+///
+/// The API has the following endpoints:
+/// - `GET /status` the state and settings of the benchmark.
+/// - `GET /stats` the request counts and latencies of the current round.
+/// - `POST /rate` with `{"rate": 5000}`, `0` removes the limit.
+/// - `POST /concurrency` with `{"connections": 256}`.
+/// - `POST /annotate` with `{"message": "deploy v2 started"}`.
+/// - `POST /stop` ends the benchmark early.
+pub fn spawn_server(
+    addr: SocketAddr,
+    commands: mpsc::UnboundedSender<Command>,
+    status: Arc<Status>,
+) -> Result<()> {
+    let builder = Server::try_bind(&addr)
+        .map_err(|e| anyhow!("failed to bind the control API to {}: {}", addr, e))?;
+
+    let make_service = make_service_fn(move |_| {
+        let commands = commands.clone();
+        let status = status.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle(request, commands.clone(), status.clone())
+            }))
+        }
+    });
+
+    let server = builder.serve(make_service);
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            eprintln!("control: the API server failed: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle(
+    request: Request<Body>,
+    commands: mpsc::UnboundedSender<Command>,
+    status: Arc<Status>,
+) -> Result<Response<Body>, Infallible> {
+    let route = (request.method().clone(), request.uri().path().to_string());
+    let response = match (&route.0, route.1.as_str()) {
+        (&Method::GET, "/status") => json_response(StatusCode::OK, status.status_json()),
+        (&Method::GET, "/stats") => json_response(StatusCode::OK, status.stats_json()),
+        (&Method::POST, "/stop") => send(&commands, Command::Stop),
+        (&Method::POST, "/rate" | "/concurrency" | "/annotate") => {
+            match parse_command(route.1.as_str(), request).await {
+                Ok(command) => send(&commands, command),
+                Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+            }
+        },
+        _ => error_response(StatusCode::NOT_FOUND, anyhow!("unknown endpoint")),
+    };
+
+    Ok(response)
+}
+
+/// Parses the command for the given endpoint from the json request body.
+async fn parse_command(path: &str, request: Request<Body>) -> Result<Command> {
+    let body = hyper::body::to_bytes(request.into_body()).await?;
+    let body: Value = serde_json::from_slice(&body)
+        .map_err(|e| anyhow!("invalid json body: {}", e))?;
+
+    let command = match path {
+        "/rate" => Command::Rate(
+            body["rate"]
+                .as_u64()
+                .ok_or_else(|| anyhow!("expected a 'rate' integer"))?,
+        ),
+        "/concurrency" => match body["connections"].as_u64() {
+            Some(0) | None => {
+                return Err(anyhow!("expected a positive 'connections' integer"))
+            },
+            Some(n) => Command::Concurrency(n as usize),
+        },
+        _ => match body["message"].as_str() {
+            Some(message) if !message.is_empty() => {
+                Command::Annotate(message.to_string())
+            },
+            _ => return Err(anyhow!("expected a 'message' string")),
+        },
+    };
+
+    Ok(command)
+}
+
+fn send(commands: &mpsc::UnboundedSender<Command>, command: Command) -> Response<Body> {
+    match commands.send(command) {
+        Ok(()) => json_response(StatusCode::ACCEPTED, json!({ "accepted": true })),
+        Err(_) => error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            anyhow!("the benchmark has finished"),
+        ),
+    }
+}
+
+fn error_response(status: StatusCode, error: anyhow::Error) -> Response<Body> {
+    json_response(status, json!({ "error": error.to_string() }))
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn call(
+        method: Method,
+        path: &str,
+        body: &'static str,
+    ) -> (StatusCode, Value, Option<Command>) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let status = Arc::new(Status::default());
+        status.start_round(1);
+        status.record_request(std::time::Duration::from_millis(3));
+
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::from(body))
+            .unwrap();
+        let response = handle(request, tx, status).await.unwrap();
+        let code = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = serde_json::from_slice(&body).unwrap();
+        (code, body, rx.try_recv().ok())
+    }
+
+    #[tokio::test]
+    async fn test_stats_endpoint() {
+        let (code, body, command) = call(Method::GET, "/stats", "").await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["requests"], 1);
+        assert_eq!(body["latency_avg_ms"], 3.0);
+        assert!(command.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_command_endpoints() {
+        let (code, _, command) = call(Method::POST, "/rate", r#"{"rate": 500}"#).await;
+        assert_eq!(code, StatusCode::ACCEPTED);
+        assert!(matches!(command, Some(Command::Rate(500))));
+
+        let (code, body, command) =
+            call(Method::POST, "/concurrency", r#"{"connections": 0}"#).await;
+        assert_eq!(code, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+        assert!(command.is_none());
+
+        let (code, _, command) = call(Method::POST, "/stop", "").await;
+        assert_eq!(code, StatusCode::ACCEPTED);
+        assert!(matches!(command, Some(Command::Stop)));
+
+        let (code, _, _) = call(Method::GET, "/unknown", "").await;
+        assert_eq!(code, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,204 @@
+use std::io::BufRead;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, Error, Result};
+use tokio::sync::mpsc;
+
+pub use self::status::Status;
+
+mod api;
+mod status;
+
+/// A command sent to the running benchmark over the control channel.
+#[derive(Clone, Debug)]
+pub enum Command {
+    /// Limit the total request rate to n requests per second, `0` removes the limit.
+    Rate(u64),
+
+    /// Change the number of concurrent connections.
+    Concurrency(usize),
+
+    /// Print a timestamped note alongside the benchmark output.
+    Annotate(String),
+
+    /// End the benchmark early, skipping any remaining rounds.
+    Stop,
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (name, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+
+        let command =
+            match name {
+                "rate" => Self::Rate(arg.parse().map_err(|_| {
+                    anyhow!("invalid rate {:?}, expected an integer", arg)
+                })?),
+                "concurrency" => match arg.parse() {
+                    Ok(0) | Err(_) => {
+                        return Err(anyhow!(
+                            "invalid concurrency {:?}, expected a positive integer",
+                            arg
+                        ))
+                    },
+                    Ok(n) => Self::Concurrency(n),
+                },
+                "annotate" if !arg.is_empty() => Self::Annotate(arg.to_string()),
+                "annotate" => return Err(anyhow!("missing annotation message")),
+                "stop" => Self::Stop,
+                _ => return Err(anyhow!("unknown command {:?}", name)),
+            };
+
+        Ok(command)
+    }
+}
+
+/// Reads commands from stdin, one per line, until stdin is closed.
+///
+/// Invalid commands are reported on stderr and skipped.
+///
+/// Reading stdin blocks, so this runs on a detached thread rather than the
+/// runtime's blocking pool, which would wait for the read to complete before
+/// the process could exit.
+fn spawn_stdin_reader(tx: mpsc::UnboundedSender<Command>) -> Result<()> {
+    thread::Builder::new()
+        .name("rewrk-stdin".to_string())
+        .spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                match line.parse() {
+                    Ok(command) => {
+                        if tx.send(command).is_err() {
+                            break;
+                        }
+                    },
+                    Err(e) => eprintln!("control: {}", e),
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+/// The runtime control state shared by the rounds of a benchmark.
+pub struct Controller {
+    commands: Option<mpsc::UnboundedReceiver<Command>>,
+    status: Arc<Status>,
+    rate: u64,
+    stopped: bool,
+}
+
+impl Controller {
+    /// Creates a controller receiving commands from stdin and/or
+    /// the HTTP API bound to the given address, starting at the given
+    /// request rate limit.
+    ///
+    /// This must be called within the Tokio runtime.
+    pub fn new(stdin: bool, api_addr: Option<SocketAddr>, rate: u64) -> Result<Self> {
+        let status = Arc::new(Status::default());
+        status.set_rate(rate);
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        if let Some(addr) = api_addr {
+            api::spawn_server(addr, tx.clone(), status.clone())?;
+        }
+        if stdin {
+            spawn_stdin_reader(tx.clone())?;
+        }
+
+        Ok(Self {
+            commands: (stdin || api_addr.is_some()).then_some(rx),
+            status,
+            rate,
+            stopped: false,
+        })
+    }
+
+    /// The live status of the benchmark shared with the HTTP API.
+    pub fn status(&self) -> &Arc<Status> {
+        &self.status
+    }
+
+    /// The request rate limit set by `--rate` or the last `rate` command,
+    /// `0` if unlimited.
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Returns if a `stop` command has been received.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Waits for the next command.
+    ///
+    /// This never completes once the control channel is closed.
+    pub async fn next(&mut self) -> Command {
+        if let Some(commands) = self.commands.as_mut() {
+            if let Some(command) = commands.recv().await {
+                match command {
+                    Command::Rate(rate) => {
+                        self.rate = rate;
+                        self.status.set_rate(rate);
+                    },
+                    Command::Concurrency(n) => self.status.set_connections(n),
+                    Command::Stop => self.stopped = true,
+                    Command::Annotate(_) => {},
+                }
+                return command;
+            }
+        }
+
+        self.commands = None;
+        std::future::pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert!(matches!("rate 500".parse(), Ok(Command::Rate(500))));
+        assert!(matches!(" rate  0 ".parse(), Ok(Command::Rate(0))));
+        assert!(matches!(
+            "concurrency 64".parse(),
+            Ok(Command::Concurrency(64))
+        ));
+        assert!(matches!("stop".parse(), Ok(Command::Stop)));
+
+        let command = "annotate deploy  started".parse::<Command>().unwrap();
+        assert!(matches!(command, Command::Annotate(note) if note == "deploy  started"));
+    }
+
+    #[test]
+    fn test_parse_command_invalid() {
+        let error = "rate fast".parse::<Command>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid rate \"fast\", expected an integer"
+        );
+        assert!("rate -1".parse::<Command>().is_err());
+        assert!("concurrency 0".parse::<Command>().is_err());
+        assert!("concurrency".parse::<Command>().is_err());
+        assert!("annotate".parse::<Command>().is_err());
+
+        let error = "pause".parse::<Command>().unwrap_err();
+        assert_eq!(error.to_string(), "unknown command \"pause\"");
+    }
+}
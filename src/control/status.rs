@@ -0,0 +1,144 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::time::Instant;
+
+/// The live status of the running benchmark.
+///
+/// Request counts only cover the current round.
+#[derive(Default)]
+pub struct Status {
+    running: AtomicBool,
+    rounds_started: AtomicUsize,
+    connections: AtomicUsize,
+    rate: AtomicU64,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_total_micros: AtomicU64,
+    latency_max_micros: AtomicU64,
+    round_start: Mutex<Option<Instant>>,
+}
+
+impl Status {
+    /// Marks the start of a new round with the given number of connections.
+    pub fn start_round(&self, connections: usize) {
+        *self.round_start.lock().unwrap() = Some(Instant::now());
+        self.requests.store(0, Ordering::Relaxed);
+        self.errors.store(0, Ordering::Relaxed);
+        self.latency_total_micros.store(0, Ordering::Relaxed);
+        self.latency_max_micros.store(0, Ordering::Relaxed);
+        self.connections.store(connections, Ordering::Relaxed);
+        self.rounds_started.fetch_add(1, Ordering::Relaxed);
+        self.running.store(true, Ordering::Relaxed);
+    }
+
+    /// Marks the current round as finished.
+    pub fn finish_round(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    pub fn set_connections(&self, connections: usize) {
+        self.connections.store(connections, Ordering::Relaxed);
+    }
+
+    pub fn set_rate(&self, rate: u64) {
+        self.rate.store(rate, Ordering::Relaxed);
+    }
+
+    /// Records a completed request and its latency.
+    pub fn record_request(&self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.latency_total_micros
+            .fetch_add(micros, Ordering::Relaxed);
+        self.latency_max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Records a failed request.
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The state and settings of the benchmark as a json value.
+    pub fn status_json(&self) -> Value {
+        let rate = self.rate.load(Ordering::Relaxed);
+        let round = self.rounds_started.load(Ordering::Relaxed);
+
+        json!({
+            "running": self.running.load(Ordering::Relaxed),
+            "round": round.checked_sub(1),
+            "connections": self.connections.load(Ordering::Relaxed),
+            "rate_limit": (rate > 0).then_some(rate),
+        })
+    }
+
+    /// The request counts and latencies of the current round as a json value.
+    ///
+    /// Latencies are in milliseconds and `null` until a request has completed.
+    pub fn stats_json(&self) -> Value {
+        let elapsed = self
+            .round_start
+            .lock()
+            .unwrap()
+            .map(|start| start.elapsed().as_secs_f64())
+            .unwrap_or_default();
+        let requests = self.requests.load(Ordering::Relaxed);
+        let errors = self.errors.load(Ordering::Relaxed);
+        let requests_per_sec = if elapsed > 0.0 {
+            requests as f64 / elapsed
+        } else {
+            0.0
+        };
+        let total_micros = self.latency_total_micros.load(Ordering::Relaxed);
+        let max_micros = self.latency_max_micros.load(Ordering::Relaxed);
+        let (latency_avg, latency_max) = match requests {
+            0 => (None, None),
+            n => (
+                Some(total_micros as f64 / n as f64 / 1000.0),
+                Some(max_micros as f64 / 1000.0),
+            ),
+        };
+
+        json!({
+            "elapsed_secs": elapsed,
+            "requests": requests,
+            "errors": errors,
+            "requests_per_sec": requests_per_sec,
+            "latency_avg_ms": latency_avg,
+            "latency_max_ms": latency_max,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_json() {
+        let status = Status::default();
+        status.start_round(4);
+        let stats = status.stats_json();
+        assert_eq!(stats["requests"], 0);
+        assert!(stats["latency_avg_ms"].is_null());
+
+        status.record_request(Duration::from_millis(2));
+        status.record_request(Duration::from_millis(6));
+        status.record_error();
+        let stats = status.stats_json();
+        assert_eq!(stats["requests"], 2);
+        assert_eq!(stats["errors"], 1);
+        assert_eq!(stats["latency_avg_ms"], 4.0);
+        assert_eq!(stats["latency_max_ms"], 6.0);
+
+        // Each round starts from zero.
+        status.start_round(8);
+        let stats = status.stats_json();
+        assert_eq!(stats["requests"], 0);
+        assert!(stats["latency_max_ms"].is_null());
+        assert_eq!(status.status_json()["round"], 1);
+        assert_eq!(status.status_json()["connections"], 8);
+    }
+}
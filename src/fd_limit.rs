@@ -0,0 +1,66 @@
+use anyhow::Result;
+
+/// The number of file descriptors reserved for things other than
+/// benchmark connections, i.e. stdio, the runtime and DNS lookups.
+const RESERVED_FDS: usize = 64;
+
+/// Checks the open file limit of the process can support the given
+/// number of connections, raising the soft limit if required.
+///
+/// If the limit cannot be raised far enough an error is returned explaining
+/// how to raise it, as the OS would otherwise cap the number of connections
+/// and cause a cascade of connect errors.
+#[cfg(unix)]
+pub fn ensure_fd_limit(connections: usize) -> Result<()> {
+    use anyhow::Error;
+
+    let required = (connections + RESERVED_FDS) as libc::rlim_t;
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return Err(Error::msg(format!(
+            "failed to read the open file limit: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    if limit.rlim_cur == libc::RLIM_INFINITY || limit.rlim_cur >= required {
+        return Ok(());
+    }
+
+    let target = if limit.rlim_max == libc::RLIM_INFINITY {
+        required
+    } else {
+        required.min(limit.rlim_max)
+    };
+
+    let raised = libc::rlimit {
+        rlim_cur: target,
+        rlim_max: limit.rlim_max,
+    };
+    let is_raised = unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &raised) } == 0;
+
+    if is_raised && target >= required {
+        return Ok(());
+    }
+
+    let available = if is_raised { target } else { limit.rlim_cur };
+    Err(Error::msg(format!(
+        "the open file limit ({}) is too low for {} connections, at least {} \
+        file descriptors are required. Raise the limit with 'ulimit -n {}' \
+        or reduce the number of connections.",
+        available, connections, required, required,
+    )))
+}
+
+/// Checks the open file limit of the process can support the given
+/// number of connections.
+///
+/// This is a no-op on platforms without `RLIMIT_NOFILE`.
+#[cfg(not(unix))]
+pub fn ensure_fd_limit(_connections: usize) -> Result<()> {
+    Ok(())
+}
@@ -1,15 +1,18 @@
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use std::{io, mem};
 
 use anyhow::anyhow;
 use futures_util::stream::FuturesUnordered;
 use futures_util::TryFutureExt;
-use http::header::{self, HeaderMap};
+use http::header::{self, HeaderMap, HeaderValue};
 use http::{Method, Request};
 use hyper::body::Bytes;
 use hyper::client::conn::{self, SendRequest};
 use hyper::Body;
+use rewrk_core::{read_body, IoUsageTracker, RateLimiter};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 use tokio::task::JoinHandle;
@@ -18,13 +21,17 @@ use tokio::time::{sleep, timeout_at, Instant};
 use tower::util::ServiceExt;
 use tower::Service;
 
-use self::usage::Usage;
-use self::user_input::{Scheme, UserInput};
+use self::user_input::{Addrs, Scheme, UserInput};
+use crate::control::Status;
 use crate::results::WorkerResult;
 
-mod usage;
 mod user_input;
 
+/// The initial delay between connects once the ephemeral ports are exhausted.
+const CONNECT_BACKOFF_MIN: Duration = Duration::from_millis(10);
+/// The maximum delay between connects while the ephemeral ports are exhausted.
+const CONNECT_BACKOFF_MAX: Duration = Duration::from_secs(1);
+
 pub type Handle = JoinHandle<anyhow::Result<WorkerResult>>;
 
 /// The type of bench that is being ran.
@@ -47,6 +54,7 @@ impl BenchType {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start_tasks(
     time_for: Duration,
     connections: usize,
@@ -55,44 +63,125 @@ pub async fn start_tasks(
     method: Method,
     headers: HeaderMap,
     body: Bytes,
+    no_keepalive: bool,
+    adaptive_connect: bool,
     _predicted_size: usize,
-) -> anyhow::Result<FuturesUnordered<Handle>> {
+    status: Arc<Status>,
+) -> anyhow::Result<Tasks> {
     let deadline = Instant::now() + time_for;
     let user_input =
         UserInput::new(bench_type, uri_string, method, headers, body).await?;
 
-    let handles = FuturesUnordered::new();
+    let mut tasks = Tasks {
+        handles: FuturesUnordered::new(),
+        stop_flags: Vec::new(),
+        limiter: RateLimiter::unlimited(),
+        status,
+        deadline,
+        bench_type,
+        no_keepalive,
+        adaptive_connect,
+        user_input,
+    };
+    tasks.set_concurrency(connections);
+
+    Ok(tasks)
+}
+
+/// The running connection tasks of a benchmark.
+pub struct Tasks {
+    /// The handles of every connection task started, including stopped ones.
+    pub handles: FuturesUnordered<Handle>,
+    /// The stop flags of the connections which are still running.
+    stop_flags: Vec<Arc<AtomicBool>>,
+    /// Spaces out requests across all connections to hold a total request rate.
+    limiter: RateLimiter,
+    status: Arc<Status>,
+    deadline: Instant,
+    bench_type: BenchType,
+    no_keepalive: bool,
+    adaptive_connect: bool,
+    user_input: UserInput,
+}
+
+impl Tasks {
+    /// Starts or stops connections until n connections are running.
+    ///
+    /// Stopped connections finish their in-flight request first and
+    /// their results are still included.
+    pub fn set_concurrency(&mut self, n: usize) {
+        while self.stop_flags.len() > n {
+            let stop = self.stop_flags.pop().unwrap();
+            stop.store(true, Ordering::Relaxed);
+        }
 
-    for _ in 0..connections {
-        let handle = tokio::spawn(benchmark(deadline, bench_type, user_input.clone()));
+        while self.stop_flags.len() < n {
+            let stop = Arc::new(AtomicBool::new(false));
+            let handle = tokio::spawn(benchmark(
+                self.deadline,
+                self.bench_type,
+                self.no_keepalive,
+                self.adaptive_connect,
+                self.user_input.clone(),
+                self.limiter.clone(),
+                self.status.clone(),
+                stop.clone(),
+            ));
+
+            self.handles.push(handle);
+            self.stop_flags.push(stop);
+        }
+    }
 
-        handles.push(handle);
+    /// Limits the total request rate across all connections in requests
+    /// per second, `0` removes the limit.
+    pub fn set_rate(&self, rate: f64) {
+        self.limiter.set_rate(rate);
     }
 
-    Ok(handles)
+    /// Stops all connections.
+    pub fn stop(&mut self) {
+        self.set_concurrency(0);
+    }
 }
 
 // Futures must not be awaited without timeout.
+#[allow(clippy::too_many_arguments)]
 async fn benchmark(
     deadline: Instant,
     bench_type: BenchType,
+    no_keepalive: bool,
+    adaptive_connect: bool,
     user_input: UserInput,
+    limiter: RateLimiter,
+    status: Arc<Status>,
+    stop: Arc<AtomicBool>,
 ) -> anyhow::Result<WorkerResult> {
     let benchmark_start = Instant::now();
-    let connector = RewrkConnector::new(
+    let mut connector = RewrkConnector::new(
         deadline,
         bench_type,
-        user_input.addr,
+        user_input.addrs,
         user_input.scheme,
         user_input.host,
+        adaptive_connect,
     );
 
+    let connect_start = Instant::now();
     let (mut send_request, mut connection_task) =
         match timeout_at(deadline, connector.connect()).await {
             Ok(result) => result?,
             Err(_elapsed) => return Ok(WorkerResult::default()),
         };
 
+    // When keep-alive is disabled the connection setup time is included
+    // in the latency of the request sent on the connection.
+    let mut connect_times = Vec::new();
+    let mut pending_connect_time = Duration::default();
+    if no_keepalive {
+        pending_connect_time = connect_start.elapsed();
+    }
+
     let mut request_headers = HeaderMap::new();
 
     // Set "host" header for HTTP/1.
@@ -102,14 +191,32 @@ async fn benchmark(
 
     request_headers.extend(user_input.headers);
 
+    if no_keepalive {
+        request_headers.insert(header::CONNECTION, HeaderValue::from_static("close"));
+    }
+
     let mut request_times = Vec::new();
     let mut error_map = HashMap::new();
 
+    // The number of requests served by each connection, and the current one.
+    let mut connection_requests = Vec::new();
+    let mut served = 0;
+
     // Benchmark loop.
     // Futures must not be awaited without timeout.
-    loop {
+    while !stop.load(Ordering::Relaxed) {
+        let send_at = match timeout_at(deadline, limiter.acquire_scheduled()).await {
+            Ok(send_at) => send_at,
+            Err(_elapsed) => break,
+        };
+
         // Create request from **parsed** data.
-        let mut request = Request::new(Body::from(user_input.body.clone()));
+        let body = if user_input.body.is_empty() {
+            Body::empty()
+        } else {
+            Body::from(user_input.body.clone())
+        };
+        let mut request = Request::new(body);
         *request.method_mut() = user_input.method.clone();
         *request.uri_mut() = user_input.uri.clone();
         *request.headers_mut() = request_headers.clone();
@@ -120,12 +227,18 @@ async fn benchmark(
             // Call the service.
             .and_then(|sr| sr.call(request))
             // Read response body completely.
-            .and_then(|response| hyper::body::to_bytes(response.into_body()));
+            .and_then(|response| read_body(response.into_body()));
 
         // ResponseFuture of send_request might return channel closed error instead of real error
         // in the case of connection_task being finished. This future will check if connection_task
         // is finished first.
         let future = async {
+            // The server closes the connection once the response is complete
+            // so the connection task finishing is expected.
+            if no_keepalive {
+                return future.await.map(|_| ()).map_err(Into::into);
+            }
+
             tokio::select! {
                 biased;
                 result = (&mut connection_task) => {
@@ -138,12 +251,19 @@ async fn benchmark(
             }
         };
 
-        let request_start = Instant::now();
+        // A request sent late by the limiter is timed from its scheduled slot.
+        let request_start = send_at.min(Instant::now());
+
+        // The time taken to replace the connection if the request failed.
+        let mut reconnect_time = None;
 
         // Try to resolve future before benchmark deadline is elapsed.
         if let Ok(result) = timeout_at(deadline, future).await {
+            served += 1;
+
             if let Err(e) = result {
                 let error = e.to_string();
+                status.record_error();
 
                 // Insert/add error string to error log.
                 match error_map.get_mut(&error) {
@@ -153,11 +273,15 @@ async fn benchmark(
                     },
                 }
 
-                // Try reconnecting.
+                // Try reconnecting, with keep-alive disabled this is also
+                // the connection of the next request.
+                connection_requests.push(mem::take(&mut served));
+                let connect_start = Instant::now();
                 match connector.try_connect_until().await {
                     Ok((sr, task)) => {
                         send_request = sr;
                         connection_task = task;
+                        reconnect_time = Some(connect_start.elapsed());
                     },
                     Err(_elapsed) => break,
                 };
@@ -167,52 +291,95 @@ async fn benchmark(
             break;
         }
 
-        request_times.push(request_start.elapsed());
+        let latency = request_start.elapsed() + pending_connect_time;
+        request_times.push(latency);
+        status.record_request(latency);
+
+        if no_keepalive {
+            connect_times.push(pending_connect_time);
+
+            // A failed request has already closed and replaced the connection.
+            if let Some(connect_time) = reconnect_time {
+                pending_connect_time = connect_time;
+                continue;
+            }
+
+            connection_requests.push(mem::take(&mut served));
+
+            // Retry failed connects rather than sending the next request
+            // on the closed connection.
+            let connect_start = Instant::now();
+            match connector.try_connect_until().await {
+                Ok((sr, task)) => {
+                    send_request = sr;
+                    connection_task = task;
+                    pending_connect_time = connect_start.elapsed();
+                },
+                Err(_elapsed) => break,
+            }
+        }
+    }
+
+    // Connections which never served a request, e.g. opened just
+    // before the deadline, don't count towards the distribution.
+    if served > 0 {
+        connection_requests.push(served);
     }
 
     Ok(WorkerResult {
         total_times: vec![benchmark_start.elapsed()],
         request_times,
+        connect_times,
+        connection_requests,
         buffer_sizes: vec![connector.get_received_bytes()],
         error_map,
+        port_exhaustion_errors: connector.port_exhaustion_errors,
     })
 }
 
 struct RewrkConnector {
     deadline: Instant,
     bench_type: BenchType,
-    addr: SocketAddr,
+    addrs: Addrs,
     scheme: Scheme,
     host: String,
-    usage: Usage,
+    usage: IoUsageTracker,
+    adaptive_connect: bool,
+    connect_backoff: Duration,
+    port_exhaustion_errors: usize,
 }
 
 impl RewrkConnector {
     fn new(
         deadline: Instant,
         bench_type: BenchType,
-        addr: SocketAddr,
+        addrs: Addrs,
         scheme: Scheme,
         host: String,
+        adaptive_connect: bool,
     ) -> Self {
-        let usage = Usage::new();
+        let usage = IoUsageTracker::new().without_write_counting();
 
         Self {
             deadline,
             bench_type,
-            addr,
+            addrs,
             scheme,
             host,
             usage,
+            adaptive_connect,
+            connect_backoff: Duration::default(),
+            port_exhaustion_errors: 0,
         }
     }
 
     async fn try_connect_until(
-        &self,
+        &mut self,
     ) -> Result<(SendRequest<Body>, JoinHandle<hyper::Result<()>>), Elapsed> {
+        let deadline = self.deadline;
         let future = async {
             loop {
-                if let Ok(v) = self.connect().await {
+                if let Ok(v) = self.reconnect().await {
                     return v;
                 }
 
@@ -220,7 +387,33 @@ impl RewrkConnector {
             }
         };
 
-        timeout_at(self.deadline, future).await
+        timeout_at(deadline, future).await
+    }
+
+    /// Re-establishes a connection while tracking ephemeral port exhaustion.
+    ///
+    /// When adaptive connecting is enabled the connect rate is slowed down
+    /// with an exponential backoff while the local ports are exhausted.
+    async fn reconnect(
+        &mut self,
+    ) -> anyhow::Result<(SendRequest<Body>, JoinHandle<hyper::Result<()>>)> {
+        let result = self.connect().await;
+
+        match result {
+            Ok(_) => self.connect_backoff = Duration::default(),
+            Err(ref e) if is_port_exhaustion(e) => {
+                self.port_exhaustion_errors += 1;
+
+                if self.adaptive_connect {
+                    self.connect_backoff = (self.connect_backoff * 2)
+                        .clamp(CONNECT_BACKOFF_MIN, CONNECT_BACKOFF_MAX);
+                    sleep(self.connect_backoff).await;
+                }
+            },
+            Err(_) => {},
+        }
+
+        result
     }
 
     async fn connect(
@@ -232,7 +425,7 @@ impl RewrkConnector {
             conn_builder.http2_only(true);
         }
 
-        let stream = TcpStream::connect(self.addr).await?;
+        let stream = TcpStream::connect(self.addrs.next_addr()).await?;
         let stream = self.usage.wrap_stream(stream);
 
         let send_request = match self.scheme {
@@ -247,7 +440,7 @@ impl RewrkConnector {
     }
 
     fn get_received_bytes(&self) -> usize {
-        self.usage.get_received_bytes()
+        self.usage.get_received_count() as usize
     }
 }
 
@@ -262,3 +455,153 @@ where
     let connection_task = tokio::spawn(connection);
     Ok((send_request, connection_task))
 }
+
+/// The Windows error returned once the ephemeral ports are exhausted
+/// and no buffer space is left for new sockets.
+const WSAENOBUFS: i32 = 10055;
+
+/// Checks if a connect error was caused by the OS running out of
+/// ephemeral ports, i.e. `EADDRNOTAVAIL` on Unix or `WSAEADDRINUSE`
+/// and `WSAENOBUFS` on Windows.
+fn is_port_exhaustion(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<io::Error>().is_some_and(|e| {
+        matches!(
+            e.kind(),
+            io::ErrorKind::AddrNotAvailable | io::ErrorKind::AddrInUse
+        ) || (cfg!(windows) && e.raw_os_error() == Some(WSAENOBUFS))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::atomic::AtomicUsize;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Starts a server which closes every connection after the first request,
+    /// failing the requests of every other connection by not responding.
+    ///
+    /// Returns the address of the server and the number of accepted connections.
+    async fn start_server() -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let n = counter.fetch_add(1, Ordering::Relaxed);
+                tokio::spawn(async move {
+                    let mut buffer = [0; 1024];
+                    let _ = stream.read(&mut buffer).await;
+                    if n.is_multiple_of(2) {
+                        let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\
+                            connection: close\r\n\r\nok";
+                        let _ = stream.write_all(response).await;
+                    }
+                });
+            }
+        });
+
+        (addr, accepted)
+    }
+
+    async fn run_benchmark(addr: SocketAddr, no_keepalive: bool) -> WorkerResult {
+        let user_input = UserInput::new(
+            BenchType::HTTP1,
+            format!("http://{}/", addr),
+            Method::GET,
+            HeaderMap::new(),
+            Bytes::new(),
+        )
+        .await
+        .unwrap();
+
+        benchmark(
+            Instant::now() + Duration::from_millis(300),
+            BenchType::HTTP1,
+            no_keepalive,
+            false,
+            user_input,
+            RateLimiter::unlimited(),
+            Arc::default(),
+            Arc::default(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_no_keepalive_reconnects_once_per_request() {
+        let (addr, accepted) = start_server().await;
+        let result = run_benchmark(addr, true).await;
+
+        let requests = result.request_times.len();
+        assert!(requests > 1);
+        assert!(!result.error_map.is_empty());
+        assert_eq!(result.connect_times.len(), requests);
+
+        // Every request is sent on its own connection, besides a connection
+        // which may have been opened just before the deadline.
+        let accepted = accepted.load(Ordering::Relaxed);
+        assert!(
+            accepted == requests || accepted == requests + 1,
+            "{} connections accepted for {} requests",
+            accepted,
+            requests,
+        );
+
+        // Each connection served a single request.
+        let served: usize = result.connection_requests.iter().sum();
+        assert!(result.connection_requests.iter().all(|n| *n == 1));
+        assert!(served == requests || served == requests + 1);
+        assert_eq!(result.connection_reuse_ratio(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_connection_reuse() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_service = hyper::service::make_service_fn(|_| async {
+            Ok::<_, hyper::Error>(hyper::service::service_fn(|_| async {
+                Ok::<_, hyper::Error>(hyper::Response::new(Body::from("ok")))
+            }))
+        });
+        let server = hyper::Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_service);
+        tokio::spawn(server);
+
+        let result = run_benchmark(addr, false).await;
+
+        // Every request is served by the same connection.
+        let requests = result.request_times.len();
+        assert!(requests > 1);
+        assert_eq!(result.connection_requests, vec![requests]);
+        let expected = (requests - 1) as f64 / requests as f64;
+        assert_eq!(result.connection_reuse_ratio(), expected);
+    }
+
+    #[test]
+    fn test_is_port_exhaustion() {
+        let error = |kind| anyhow::Error::from(io::Error::from(kind));
+        assert!(is_port_exhaustion(&error(io::ErrorKind::AddrNotAvailable)));
+        assert!(is_port_exhaustion(&error(io::ErrorKind::AddrInUse)));
+        assert!(!is_port_exhaustion(&error(
+            io::ErrorKind::ConnectionRefused
+        )));
+        assert!(!is_port_exhaustion(&anyhow::Error::msg("not an io error")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_is_port_exhaustion_windows() {
+        let error = io::Error::from_raw_os_error(WSAENOBUFS);
+        assert!(is_port_exhaustion(&anyhow::Error::from(error)));
+    }
+}
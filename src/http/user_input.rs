@@ -1,5 +1,7 @@
 use std::convert::TryFrom;
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
 use http::header::HeaderValue;
@@ -26,9 +28,27 @@ impl Scheme {
     }
 }
 
+/// The resolved addresses of the server.
+///
+/// New connections rotate through the addresses, so they are spread
+/// across every backend of a DNS balanced or dual-stack host.
+#[derive(Clone, Debug)]
+pub(crate) struct Addrs {
+    addrs: Arc<[SocketAddr]>,
+    next: Arc<AtomicUsize>,
+}
+
+impl Addrs {
+    /// The address the next connection is made to.
+    pub(crate) fn next_addr(&self) -> SocketAddr {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        self.addrs[next % self.addrs.len()]
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct UserInput {
-    pub(crate) addr: SocketAddr,
+    pub(crate) addrs: Addrs,
     pub(crate) scheme: Scheme,
     pub(crate) host: String,
     pub(crate) host_header: HeaderValue,
@@ -91,21 +111,25 @@ impl UserInput {
         let port = authority
             .port_u16()
             .unwrap_or_else(|| scheme.default_port());
-        let host_header = HeaderValue::from_str(&host)?;
+        let host_header = match authority.port_u16() {
+            Some(port) if port != scheme.default_port() => {
+                HeaderValue::from_str(&format!("{}:{}", host, port))?
+            },
+            _ => HeaderValue::from_str(&host)?,
+        };
 
-        // Prefer ipv4.
-        let addr_iter = (host.as_str(), port).to_socket_addrs()?;
-        let mut last_addr = None;
-        for addr in addr_iter {
-            last_addr = Some(addr);
-            if addr.is_ipv4() {
-                break;
-            }
+        let addrs: Arc<[SocketAddr]> =
+            (host.as_str(), port).to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(anyhow!("hostname lookup failed"));
         }
-        let addr = last_addr.ok_or_else(|| anyhow!("hostname lookup failed"))?;
+        let addrs = Addrs {
+            addrs,
+            next: Arc::default(),
+        };
 
         Ok(Self {
-            addr,
+            addrs,
             scheme,
             host,
             host_header,
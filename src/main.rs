@@ -1,27 +1,44 @@
 extern crate clap;
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use ::http::header::HeaderName;
 use ::http::{HeaderMap, HeaderValue, Method};
-use anyhow::{Context, Error, Result};
-use clap::{App, Arg, ArgMatches};
+use anyhow::{anyhow, Context, Error, Result};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use hyper::body::Bytes;
 use regex::Regex;
 use tokio::time::Duration;
 
 mod bench;
+mod calibrate;
+mod control;
+mod fd_limit;
 mod http;
+mod merge;
+mod options;
+mod report;
 mod results;
 mod runtime;
+mod shard;
+mod sweep;
 mod utils;
 
+use crate::calibrate::CalibrateSettings;
 use crate::http::BenchType;
+use crate::options::Options;
+use crate::shard::Shard;
+use crate::sweep::GoalSeek;
 
 /// Matches a string like '12d 24h 5m 45s' to a regex capture.
 static DURATION_MATCH: &str =
     "(?P<days>[0-9]+)d|(?P<hours>[0-9]+)h|(?P<minutes>[0-9]+)m|(?P<seconds>[0-9]+)s";
 
+/// The default maximum number of connections a goal seek steps up to.
+const DEFAULT_GOAL_SEEK_MAX_CONNECTIONS: usize = 1024;
+
 /// ReWrk
 ///
 /// Captures CLI arguments and build benchmarking settings and runtime to
@@ -29,6 +46,45 @@ static DURATION_MATCH: &str =
 fn main() {
     let args = parse_args();
 
+    // The subcommands fall back to the environment in the same way as the benchmark.
+    match args.subcommand() {
+        ("merge", Some(merge_args)) => {
+            let merge_args = Options::new(merge_args.clone());
+            let result = parse_files(&merge_args)
+                .and_then(|files| merge::run(&files, merge_args.is_present("json")));
+            if let Err(e) = result {
+                eprintln!("failed to merge results: {:#}", e);
+            }
+            return;
+        },
+        ("report", Some(report_args)) => {
+            let report_args = Options::new(report_args.clone());
+            let result = parse_files(&report_args).and_then(|files| {
+                let format = parse_report_format(&report_args)?;
+                report::run(&files, format)
+            });
+            if let Err(e) = result {
+                eprintln!("failed to create report: {:#}", e);
+            }
+            return;
+        },
+        ("calibrate", Some(calibrate_args)) => {
+            let calibrate_args = Options::new(calibrate_args.clone());
+            let result =
+                parse_calibrate_settings(&calibrate_args).and_then(|settings| {
+                    fd_limit::ensure_fd_limit(settings.connections)?;
+                    calibrate::run(settings)
+                });
+            if let Err(e) = result {
+                eprintln!("failed to calibrate: {:#}", e);
+            }
+            return;
+        },
+        _ => {},
+    }
+
+    let args = Options::new(args);
+
     let threads: usize = match args.value_of("threads").unwrap_or("1").trim().parse() {
         Ok(v) => v,
         Err(_) => {
@@ -64,7 +120,13 @@ fn main() {
         BenchType::HTTP1
     };
 
-    let duration: &str = args.value_of("duration").unwrap_or("1s");
+    let duration: &str = match args.value_of("duration") {
+        Some(v) => v,
+        None => {
+            eprintln!("missing 'duration' parameter.");
+            return;
+        },
+    };
     let duration = match parse_duration(duration) {
         Ok(dur) => dur,
         Err(e) => {
@@ -95,7 +157,11 @@ fn main() {
     };
 
     let headers = if let Some(headers) = args.values_of("header") {
-        match headers.map(parse_header).collect::<Result<HeaderMap<_>>>() {
+        match headers
+            .into_iter()
+            .map(parse_header)
+            .collect::<Result<HeaderMap<_>>>()
+        {
             Ok(headers) => headers,
             Err(e) => {
                 eprintln!("failed to parse header: {}", e);
@@ -108,6 +174,155 @@ fn main() {
 
     let body: &str = args.value_of("body").unwrap_or_default();
     let body = Bytes::copy_from_slice(body.as_bytes());
+    if method == Method::HEAD && !body.is_empty() {
+        eprintln!("a request body cannot be sent with a HEAD request.");
+        return;
+    }
+
+    let no_keepalive: bool = args.is_present("no-keepalive");
+    if no_keepalive && http2 {
+        eprintln!(
+            "the 'no-keepalive' flag is only supported for HTTP/1 and will be ignored."
+        );
+    }
+
+    let adaptive_connect: bool = args.is_present("adaptive-connect");
+
+    let sweep = match args.value_of("sweep").map(parse_sweep).transpose() {
+        Ok(sweep) => sweep,
+        Err(e) => {
+            eprintln!("failed to parse sweep parameter: {}", e);
+            return;
+        },
+    };
+
+    let goal_seek_target =
+        match args.value_of("goal-seek").map(parse_latency).transpose() {
+            Ok(target) => target,
+            Err(e) => {
+                eprintln!("failed to parse goal-seek parameter: {}", e);
+                return;
+            },
+        };
+
+    let goal_seek_max = match args
+        .value_of("goal-seek-max")
+        .map(|max| max.trim().parse::<usize>())
+        .transpose()
+    {
+        Ok(Some(0)) => {
+            eprintln!("the 'goal-seek-max' parameter must be a positive integer.");
+            return;
+        },
+        Ok(max) => max.unwrap_or(DEFAULT_GOAL_SEEK_MAX_CONNECTIONS),
+        Err(e) => {
+            eprintln!("failed to parse goal-seek-max parameter: {}", e);
+            return;
+        },
+    };
+
+    if goal_seek_target.is_some() && sweep.is_some() {
+        eprintln!("the 'goal-seek' and 'sweep' options cannot be used together.");
+        return;
+    }
+
+    let sweep_csv = args.value_of("sweep-csv").map(PathBuf::from);
+    if sweep_csv.is_some() && sweep.is_none() && goal_seek_target.is_none() {
+        eprintln!(
+            "the 'sweep-csv' option requires 'sweep' or 'goal-seek' to be set and will be ignored."
+        );
+    }
+
+    let control_addr = match args
+        .value_of("control-addr")
+        .map(SocketAddr::from_str)
+        .transpose()
+    {
+        Ok(addr) => addr,
+        Err(e) => {
+            eprintln!("failed to parse control-addr parameter: {}", e);
+            return;
+        },
+    };
+
+    let rate = match args.value_of("rate").map(u64::from_str).transpose() {
+        Ok(rate) => rate.unwrap_or(0),
+        Err(e) => {
+            eprintln!("failed to parse rate parameter: {}", e);
+            return;
+        },
+    };
+
+    let archive = args.value_of("archive").map(PathBuf::from);
+    let checkpoint_interval = match args
+        .value_of("checkpoint-interval")
+        .map(parse_duration)
+        .transpose()
+    {
+        Ok(interval) => interval,
+        Err(e) => {
+            eprintln!("failed to parse checkpoint-interval parameter: {}", e);
+            return;
+        },
+    };
+    let resume = args.is_present("resume");
+    if archive.is_none() && (checkpoint_interval.is_some() || resume) {
+        eprintln!(
+            "the 'checkpoint-interval' and 'resume' options require 'archive' to be set and will be ignored."
+        );
+    }
+
+    let shard = match args.value_of("shard").map(Shard::from_str).transpose() {
+        Ok(shard) => shard,
+        Err(e) => {
+            eprintln!("failed to parse shard parameter: {}", e);
+            return;
+        },
+    };
+
+    // Connection counts are totals across all shards.
+    let (conns, sweep, goal_seek_max) = match shard {
+        None => (conns, sweep, goal_seek_max),
+        Some(shard) => {
+            let levels = std::iter::once(conns)
+                .chain(sweep.iter().flatten().copied())
+                .chain(goal_seek_target.map(|_| goal_seek_max));
+            if levels.clone().any(|level| shard.split(level) == 0) {
+                eprintln!(
+                    "every connection count must be at least the number of shards ({}).",
+                    shard.count
+                );
+                return;
+            }
+
+            let sweep = sweep.map(|levels| {
+                levels.into_iter().map(|level| shard.split(level)).collect()
+            });
+            (shard.split(conns), sweep, shard.split(goal_seek_max))
+        },
+    };
+
+    if goal_seek_target.is_some() && goal_seek_max < conns {
+        eprintln!(
+            "the 'goal-seek-max' parameter must be at least the starting connections."
+        );
+        return;
+    }
+
+    let goal_seek = goal_seek_target.map(|p99_target| GoalSeek {
+        p99_target,
+        max_connections: goal_seek_max,
+    });
+
+    let max_conns = sweep
+        .as_ref()
+        .and_then(|levels| levels.iter().max().copied())
+        .or(goal_seek.map(|goal| goal.max_connections))
+        .unwrap_or(conns);
+    if let Err(e) = fd_limit::ensure_fd_limit(max_conns) {
+        eprintln!("{}", e);
+        return;
+    }
 
     let settings = bench::BenchmarkSettings {
         threads,
@@ -121,11 +336,72 @@ fn main() {
         method,
         headers,
         body,
+        no_keepalive: no_keepalive && !http2,
+        adaptive_connect,
+        sweep,
+        goal_seek,
+        sweep_csv,
+        control: args.is_present("control"),
+        control_addr,
+        rate,
+        shard,
+        archive,
+        checkpoint_interval,
+        resume,
     };
 
     bench::start_benchmark(settings);
 }
 
+/// Parses the files of the `merge` and `report` subcommands.
+fn parse_files<'a>(args: &'a Options) -> Result<Vec<&'a str>> {
+    match args.values_of("files") {
+        Some(files) if !files.is_empty() => Ok(files),
+        _ => Err(anyhow!("missing 'files' parameter")),
+    }
+}
+
+/// Parses the format of the `report` subcommand.
+fn parse_report_format(args: &Options) -> Result<report::Format> {
+    args.value_of("format").unwrap_or("table").trim().parse()
+}
+
+/// Parses the settings of the calibrate command.
+fn parse_calibrate_settings(args: &Options) -> Result<CalibrateSettings> {
+    let threads = args
+        .value_of("threads")
+        .unwrap_or("1")
+        .trim()
+        .parse::<usize>()
+        .context(
+            "invalid parameter for 'threads' given, input type must be a integer",
+        )?;
+    let connections = args
+        .value_of("connections")
+        .unwrap_or("64")
+        .trim()
+        .parse::<usize>()
+        .context(
+            "invalid parameter for 'connections' given, input type must be a integer",
+        )?;
+    let duration = parse_duration(args.value_of("duration").unwrap_or("5s"))
+        .context("failed to parse duration parameter")?;
+
+    let bench_type = if args.is_present("http2") {
+        BenchType::HTTP2
+    } else {
+        BenchType::HTTP1
+    };
+
+    Ok(CalibrateSettings {
+        threads,
+        connections,
+        duration,
+        bench_type,
+        display_json: args.is_present("json"),
+    })
+}
+
 /// Parses a duration string from the CLI to a Duration.
 /// '11d 3h 32m 4s' -> Duration
 ///
@@ -172,6 +448,56 @@ fn parse_duration(duration: &str) -> Result<Duration> {
     Ok(dur)
 }
 
+/// Parses a comma separated list of connection counts for a sweep.
+/// '1,8,64' -> [1, 8, 64]
+fn parse_sweep(value: &str) -> Result<Vec<usize>> {
+    let levels = value
+        .split(',')
+        .map(|level| {
+            let level = level.trim();
+            match level.parse::<usize>() {
+                Ok(0) | Err(_) => Err(Error::msg(format!(
+                    "invalid connection count {:?}, expected a positive integer",
+                    level
+                ))),
+                Ok(level) => Ok(level),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(levels)
+}
+
+/// Parses a latency target with a unit suffix.
+/// '250ms' -> Duration
+///
+/// Supported units are 's', 'ms' and 'us'.
+fn parse_latency(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let (number, scale) = if let Some(number) = value.strip_suffix("ms") {
+        (number, 1e-3)
+    } else if let Some(number) = value.strip_suffix("us") {
+        (number, 1e-6)
+    } else if let Some(number) = value.strip_suffix('s') {
+        (number, 1.0)
+    } else {
+        return Err(Error::msg(format!(
+            "invalid latency {:?}, expected a unit of 's', 'ms' or 'us' e.g. '250ms'",
+            value
+        )));
+    };
+
+    match number.trim().parse::<f64>() {
+        Ok(number) if number.is_finite() && number > 0.0 => {
+            Ok(Duration::from_secs_f64(number * scale))
+        },
+        _ => Err(Error::msg(format!(
+            "invalid latency {:?}, expected a positive number e.g. '250ms'",
+            value
+        ))),
+    }
+}
+
 fn parse_header(value: &str) -> Result<(HeaderName, HeaderValue)> {
     let (key, value) = value
         .split_once(": ")
@@ -181,35 +507,125 @@ fn parse_header(value: &str) -> Result<(HeaderName, HeaderValue)> {
     Ok((key, value))
 }
 
-/// Contains Clap's app setup.
+/// Describes the environment variables in the help menu.
+const ENV_HELP: &str = concat!(
+    "ENVIRONMENT:\n",
+    "    Every option can also be set with an environment variable named after its long\n",
+    "    name, e.g. '--no-keepalive' is 'REWRK_NO_KEEPALIVE=true' and '--host' is\n",
+    "    'REWRK_HOST=http://127.0.0.1:5050'. Options given on the command line take\n",
+    "    priority. Repeated options such as '--header' take one value per line, as do the\n",
+    "    files of the 'merge' and 'report' subcommands with 'REWRK_FILES'.",
+);
+
+/// Parses the CLI arguments.
 fn parse_args() -> ArgMatches<'static> {
+    app().get_matches()
+}
+
+/// Contains Clap's app setup.
+fn app() -> App<'static, 'static> {
     App::new("ReWrk")
         .version("0.3.1")
         .author("Harrison Burt <hburt2003@gmail.com>")
         .about("Benchmark HTTP/1 and HTTP/2 frameworks without pipelining bias.")
+        .after_help(ENV_HELP)
+        .setting(AppSettings::ArgsNegateSubcommands)
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about(
+                    "Combines the json results or archives of several benchmarks, e.g. from \
+                     multiple machines or shards, into one report",
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .help("The '.rewrk' archives or files containing '--json' results")
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Displays the merged results in a json format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("calibrate")
+                .about(
+                    "Benchmarks a built-in nil-latency server to measure the client's own \
+                     ceiling on this machine, use the same threads and connections as the \
+                     benchmark being checked",
+                )
+                .arg(
+                    Arg::with_name("threads")
+                        .short("t")
+                        .long("threads")
+                        .help("Set the amount of threads to use e.g. '-t 12' [default: 1]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("connections")
+                        .short("c")
+                        .long("connections")
+                        .help("Set the amount of concurrent e.g. '-c 512' [default: 64]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("duration")
+                        .short("d")
+                        .long("duration")
+                        .help("Set the duration of the calibration e.g. '-d 10s' [default: 5s]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("http2")
+                        .long("http2")
+                        .help("Calibrate using HTTP/2 only"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Displays the calibration in a json format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about(
+                    "Renders a report from archived samples or json results without \
+                     re-running the benchmark",
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .help("The '.rewrk' archives or files containing '--json' results")
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .short("f")
+                        .help("The format the report is rendered in [default: table]")
+                        .takes_value(true)
+                        .possible_values(report::FORMATS),
+                ),
+        )
         .arg(
             Arg::with_name("threads")
                 .short("t")
                 .long("threads")
-                .help("Set the amount of threads to use e.g. '-t 12'")
-                .takes_value(true)
-                .default_value("1"),
+                .help("Set the amount of threads to use e.g. '-t 12' [default: 1]")
+                .takes_value(true),
         )
         .arg(
             Arg::with_name("connections")
                 .short("c")
                 .long("connections")
-                .help("Set the amount of concurrent e.g. '-c 512'")
-                .takes_value(true)
-                .default_value("1"),
+                .help("Set the amount of concurrent e.g. '-c 512' [default: 1]")
+                .takes_value(true),
         )
         .arg(
             Arg::with_name("host")
                 .short("h")
                 .long("host")
                 .help("Set the host to bench e.g. '-h http://127.0.0.1:5050'")
-                .takes_value(true)
-                .required(true),
+                .takes_value(true),
         )
         .arg(
             Arg::with_name("http2")
@@ -223,8 +639,7 @@ fn parse_args() -> ArgMatches<'static> {
                 .short("d")
                 .long("duration")
                 .help("Set the duration of the benchmark.")
-                .takes_value(true)
-                .required(true),
+                .takes_value(true),
         )
         .arg(
             Arg::with_name("pct")
@@ -252,7 +667,7 @@ fn parse_args() -> ArgMatches<'static> {
             Arg::with_name("method")
                 .long("method")
                 .short("m")
-                .help("Set request method e.g. '-m get'")
+                .help("Set request method e.g. '-m get', '-m head' or '-m options'")
                 .takes_value(true)
                 .required(false)
                 .multiple(true),
@@ -274,6 +689,94 @@ fn parse_args() -> ArgMatches<'static> {
                 .takes_value(true)
                 .required(false),
         )
+        .arg(
+            Arg::with_name("no-keepalive")
+                .long("no-keepalive")
+                .help(
+                    "Sends 'Connection: close' and reconnects for every request. \
+                     (HTTP/1 only) e.g. '--no-keepalive'",
+                )
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("adaptive-connect")
+                .long("adaptive-connect")
+                .help(
+                    "Slows down reconnects when the OS runs out of ephemeral ports \
+                     e.g. '--adaptive-connect'",
+                )
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("sweep")
+                .long("sweep")
+                .help(
+                    "Runs the benchmark at each of the given connection counts, \
+                     overriding '-c', e.g. '--sweep 1,8,64,256'",
+                )
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("goal-seek")
+                .long("goal-seek")
+                .help(
+                    "Doubles the connections from '-c' until the p99 latency breaches \
+                     the target, reporting the highest concurrency meeting it \
+                     e.g. '--goal-seek 50ms'",
+                )
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("goal-seek-max")
+                .long("goal-seek-max")
+                .help(
+                    "The maximum connections a goal seek steps up to \
+                     e.g. '--goal-seek-max 4096' [default: 1024]",
+                )
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("sweep-csv")
+                .long("sweep-csv")
+                .help("Writes the sweep results to a CSV file e.g. '--sweep-csv sweep.csv'")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("control")
+                .long("control")
+                .help(
+                    "Reads commands from stdin while running, one per line: \
+                     'rate <req/sec>', 'concurrency <n>', 'annotate <message>' or 'stop'",
+                )
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("control-addr")
+                .long("control-addr")
+                .help(
+                    "Serves a HTTP API to monitor and control the benchmark while running \
+                     e.g. '--control-addr 127.0.0.1:9095'",
+                )
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("rate")
+                .long("rate")
+                .help(
+                    "Limits the total request rate across all connections in requests per \
+                     second, which the 'rate' control command can change e.g. '--rate 5000'",
+                )
+                .takes_value(true)
+                .required(false),
+        )
         //.arg(
         //    Arg::with_name("random")
         //        .long("rand")
@@ -285,5 +788,149 @@ fn parse_args() -> ArgMatches<'static> {
         //        .takes_value(false)
         //        .required(false)
         //)
-        .get_matches()
+        .arg(
+            Arg::with_name("archive")
+                .long("archive")
+                .help(
+                    "Writes the results of every round to a '.rewrk' archive, \
+                     which can be read by the 'report' and 'merge' subcommands",
+                )
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("checkpoint-interval")
+                .long("checkpoint-interval")
+                .help(
+                    "Flushes the archive at most once per interval so the rounds written \
+                     so far survive a crash e.g. '--checkpoint-interval 30s'",
+                )
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .help(
+                    "Resumes the archive given by '--archive' after a crash, appending \
+                     new rounds after those already archived",
+                )
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("shard")
+                .long("shard")
+                .help(
+                    "Runs this process as shard 'i' of 'N' independently launched processes, \
+                     splitting the connections and request rate between them and tagging \
+                     the results e.g. '--shard 0/4'",
+                )
+                .takes_value(true)
+                .required(false),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_latency() {
+        assert_eq!(parse_latency("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_latency(" 2s ").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_latency("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_latency("500us").unwrap(), Duration::from_micros(500));
+    }
+
+    fn calibrate_settings(args: &[&str]) -> Result<CalibrateSettings> {
+        let args = app().get_matches_from(["rewrk", "calibrate"].iter().chain(args));
+        let args = args.subcommand_matches("calibrate").unwrap().clone();
+        let args = Options::with_env(args, Vec::new());
+        parse_calibrate_settings(&args)
+    }
+
+    #[test]
+    fn test_parse_calibrate_settings() {
+        let settings =
+            calibrate_settings(&["-t", "4", "-c", "8", "-d", "2s", "--http2"]).unwrap();
+        assert_eq!(settings.threads, 4);
+        assert_eq!(settings.connections, 8);
+        assert_eq!(settings.duration, Duration::from_secs(2));
+        assert!(matches!(settings.bench_type, BenchType::HTTP2));
+        assert!(!settings.display_json);
+
+        assert!(calibrate_settings(&["-c", "many"]).is_err());
+    }
+
+    fn report_format(args: &[&str]) -> Result<report::Format> {
+        report_format_with_env(args, None)
+    }
+
+    fn report_format_with_env(
+        args: &[&str],
+        format: Option<&str>,
+    ) -> Result<report::Format> {
+        let args = ["rewrk", "report"]
+            .iter()
+            .chain(args)
+            .chain(&["results.json"]);
+        let args = app().get_matches_from(args);
+        let args = args.subcommand_matches("report").unwrap().clone();
+        let env = format.map(|format| ("REWRK_FORMAT".to_string(), format.to_string()));
+        parse_report_format(&Options::with_env(args, env))
+    }
+
+    #[test]
+    fn test_parse_report_format() {
+        assert!(matches!(report_format(&[]), Ok(report::Format::Table)));
+        assert!(matches!(
+            report_format(&["-f", "html"]),
+            Ok(report::Format::Html)
+        ));
+
+        // The environment applies to subcommands, the command line takes priority.
+        assert!(matches!(
+            report_format_with_env(&[], Some("percentiles")),
+            Ok(report::Format::Percentiles)
+        ));
+        assert!(matches!(
+            report_format_with_env(&["--format", "table"], Some("percentiles")),
+            Ok(report::Format::Table)
+        ));
+        assert!(report_format_with_env(&[], Some("pdf")).is_err());
+    }
+
+    #[test]
+    fn test_parse_files() {
+        let files = |args: &[&str], env: Option<&str>| {
+            let args = app().get_matches_from(["rewrk", "merge"].iter().chain(args));
+            let args = args.subcommand_matches("merge").unwrap().clone();
+            let env = env.map(|files| ("REWRK_FILES".to_string(), files.to_string()));
+            let args = Options::with_env(args, env);
+            parse_files(&args).map(|files| files.join(","))
+        };
+
+        assert_eq!(
+            files(&["a.json", "b.rewrk"], None).unwrap(),
+            "a.json,b.rewrk"
+        );
+        assert_eq!(
+            files(&[], Some("a.json\nb.rewrk")).unwrap(),
+            "a.json,b.rewrk"
+        );
+        assert_eq!(files(&["c.json"], Some("a.json")).unwrap(), "c.json");
+        assert!(files(&[], None).is_err());
+        assert!(files(&[], Some("")).is_err());
+    }
+
+    #[test]
+    fn test_parse_latency_invalid() {
+        assert!(parse_latency("250").is_err());
+        assert!(parse_latency("ms").is_err());
+        assert!(parse_latency("0ms").is_err());
+        assert!(parse_latency("-5ms").is_err());
+        assert!(parse_latency("infs").is_err());
+        assert!(parse_latency("5m").is_err());
+    }
 }
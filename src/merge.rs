@@ -0,0 +1,582 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use hdrhistogram::Histogram;
+use rewrk_core::{ArchiveError, ArchiveHeader, ArchiveReader, Sample};
+use serde_json::{json, Value};
+
+use crate::shard::Shard;
+use crate::utils::{decode_histogram, encode_histogram, format_data};
+
+/// The percentiles displayed in the percentile table.
+pub const PERCENTILES: [(&str, f64); 6] = [
+    ("99.9%", 0.999),
+    ("99%", 0.99),
+    ("95%", 0.95),
+    ("90%", 0.9),
+    ("75%", 0.75),
+    ("50%", 0.5),
+];
+
+/// The key the samples of an archive are grouped by, their round, phase
+/// and sample window index.
+pub type WindowKey = (usize, usize, usize);
+
+/// The totals of the archived samples of a single sample window, across
+/// every worker and connection which recorded them.
+pub struct ArchiveWindow {
+    pub histogram: Histogram<u64>,
+    pub requests: u64,
+    pub transfer: f64,
+    pub errors: u64,
+    pub duration_secs: f64,
+}
+
+impl ArchiveWindow {
+    fn new() -> Self {
+        Self {
+            histogram: Histogram::new(3).expect("Create histogram"),
+            requests: 0,
+            transfer: 0.0,
+            errors: 0,
+            duration_secs: 0.0,
+        }
+    }
+
+    fn add(&mut self, sample: &Sample) -> Result<()> {
+        for value in sample.latency().iter_recorded() {
+            self.histogram
+                .record_n(value.value_iterated_to(), value.count_at_value() as u64)
+                .map_err(|e| anyhow!("failed to merge histogram: {:?}", e))?;
+        }
+        self.requests += sample.successful_requests();
+        self.errors += sample.failed_requests();
+        self.transfer += sample.read_bytes() as f64;
+        // The samples of a window are recorded concurrently.
+        self.duration_secs = self.duration_secs.max(sample.duration().as_secs_f64());
+        Ok(())
+    }
+}
+
+/// Reads every sample of a `.rewrk` archive, combining the samples of
+/// each sample window.
+///
+/// Each sample is also passed to `on_sample`, e.g. to build a heatmap.
+pub fn read_archive<R: Read>(
+    archive: ArchiveReader<R>,
+    mut on_sample: impl FnMut(&Sample),
+) -> Result<BTreeMap<WindowKey, ArchiveWindow>> {
+    let mut windows = BTreeMap::new();
+    for sample in archive {
+        let sample = sample?;
+        on_sample(&sample);
+
+        let metadata = sample.metadata();
+        let key = (metadata.round, metadata.phase, sample.window_index());
+        windows
+            .entry(key)
+            .or_insert_with(ArchiveWindow::new)
+            .add(&sample)?;
+    }
+
+    Ok(windows)
+}
+
+/// The totals of several json results, e.g. from multiple machines or shards
+/// which ran against the same target at the same time.
+pub struct Merged {
+    pub histogram: Histogram<u64>,
+    pub requests: u64,
+    pub transfer: f64,
+    pub errors: u64,
+    pub duration_secs: f64,
+    pub results: usize,
+    /// The file each shard's results were read from.
+    shards: BTreeMap<u64, String>,
+    shard_count: Option<u64>,
+}
+
+impl Merged {
+    pub fn new() -> Self {
+        Self {
+            histogram: Histogram::new(3).expect("Create histogram"),
+            requests: 0,
+            transfer: 0.0,
+            errors: 0,
+            duration_secs: 0.0,
+            results: 0,
+            shards: BTreeMap::new(),
+            shard_count: None,
+        }
+    }
+
+    /// Adds a single json result produced by `rewrk --json` read from the given file.
+    ///
+    /// A file may contain several results of the same shard, e.g. its rounds,
+    /// but each shard's results must all be in the same file and every shard
+    /// must have the same shard count.
+    pub fn add(&mut self, file: &str, result: &Value) -> Result<()> {
+        if let (Some(index), Some(count)) = (
+            result["shard"]["index"].as_u64(),
+            result["shard"]["count"].as_u64(),
+        ) {
+            self.add_shard(file, index, count)?;
+        }
+
+        let requests = result["requests_total"].as_u64().unwrap_or_default();
+        let histogram = match result["latency_histogram"].as_str() {
+            Some(encoded) => Some(decode_histogram(encoded)?),
+            None if requests > 0 => {
+                return Err(anyhow!(
+                    "the result has no latency histogram, \
+                     it must be exported by a version of rewrk which supports merging"
+                ))
+            },
+            None => None,
+        };
+
+        self.add_totals(
+            histogram.as_ref(),
+            requests,
+            result["transfer_total"].as_f64().unwrap_or_default(),
+            result["errors_total"].as_u64().unwrap_or_default(),
+            result["duration_secs"].as_f64().unwrap_or_default(),
+        )
+    }
+
+    /// Adds the sample windows of a `.rewrk` archive read from the given file.
+    ///
+    /// The windows are expected to have run one after another, so their
+    /// durations are summed. Archives written by sharded runs must follow the
+    /// same rules as the json results of shards, see [Merged::add].
+    pub fn add_archive(
+        &mut self,
+        file: &str,
+        header: &ArchiveHeader,
+        windows: &BTreeMap<WindowKey, ArchiveWindow>,
+    ) -> Result<()> {
+        if let Some(shard) = header.metadata.get("shard") {
+            let shard = Shard::from_str(shard)
+                .with_context(|| format!("invalid archive shard {:?}", shard))?;
+            self.add_shard(file, shard.index as u64, shard.count as u64)?;
+        }
+
+        let mut histogram = Histogram::new(3).expect("Create histogram");
+        let mut requests = 0;
+        let mut transfer = 0.0;
+        let mut errors = 0;
+        let mut duration_secs = 0.0;
+        for window in windows.values() {
+            histogram
+                .add(&window.histogram)
+                .map_err(|e| anyhow!("failed to merge histogram: {:?}", e))?;
+            requests += window.requests;
+            transfer += window.transfer;
+            errors += window.errors;
+            duration_secs += window.duration_secs;
+        }
+
+        self.add_totals(Some(&histogram), requests, transfer, errors, duration_secs)
+    }
+
+    /// Records the shard of a result, rejecting duplicated and mismatched shards.
+    fn add_shard(&mut self, file: &str, index: u64, count: u64) -> Result<()> {
+        let expected = *self.shard_count.get_or_insert(count);
+        if expected != count {
+            return Err(anyhow!(
+                "the result is from shard {}/{} but other results are from {} shards",
+                index,
+                count,
+                expected
+            ));
+        }
+
+        match self.shards.get(&index) {
+            Some(other) if other != file => Err(anyhow!(
+                "the results of shard {}/{} are duplicated in {:?} and {:?}",
+                index,
+                count,
+                other,
+                file
+            )),
+            _ => {
+                self.shards.insert(index, file.to_string());
+                Ok(())
+            },
+        }
+    }
+
+    /// Adds the totals of a single result.
+    pub fn add_totals(
+        &mut self,
+        histogram: Option<&Histogram<u64>>,
+        requests: u64,
+        transfer: f64,
+        errors: u64,
+        duration_secs: f64,
+    ) -> Result<()> {
+        if let Some(histogram) = histogram {
+            self.histogram
+                .add(histogram)
+                .map_err(|e| anyhow!("failed to merge histogram: {:?}", e))?;
+        }
+
+        self.requests += requests;
+        self.transfer += transfer;
+        self.errors += errors;
+        // The results are expected to have run concurrently.
+        self.duration_secs = self.duration_secs.max(duration_secs);
+        self.results += 1;
+
+        Ok(())
+    }
+
+    /// The shards which are missing from the merged results.
+    fn missing_shards(&self) -> Vec<u64> {
+        let count = match self.shard_count {
+            Some(count) => count,
+            None => return Vec::new(),
+        };
+
+        (0..count)
+            .filter(|index| !self.shards.contains_key(index))
+            .collect()
+    }
+
+    pub fn requests_per_sec(&self) -> f64 {
+        if self.duration_secs > 0.0 {
+            self.requests as f64 / self.duration_secs
+        } else {
+            0.0
+        }
+    }
+
+    pub fn transfer_rate(&self) -> f64 {
+        if self.duration_secs > 0.0 {
+            self.transfer / self.duration_secs
+        } else {
+            0.0
+        }
+    }
+
+    /// A latency from the histogram in milliseconds.
+    pub fn millis(micros: u64) -> f64 {
+        micros as f64 / 1000.0
+    }
+
+    fn display(&self) {
+        println!(
+            "Merged {} results ({} across {:.2}s)",
+            self.results.to_string().cyan(),
+            format!("{} requests", self.requests).cyan(),
+            self.duration_secs,
+        );
+
+        self.display_summary();
+        if self.requests > 0 {
+            println!();
+            self.display_percentile_table();
+        }
+
+        if self.errors > 0 {
+            println!();
+            println!("{} Errors", self.errors);
+        }
+    }
+
+    /// Displays the latencies, requests and transfer.
+    pub fn display_summary(&self) {
+        if self.requests > 0 {
+            println!("  Latencies:");
+            println!(
+                "    {:<7}  {:<7}  {:<7}  {:<7}  ",
+                "Avg".bright_yellow(),
+                "Stdev".bright_magenta(),
+                "Min".bright_green(),
+                "Max".bright_red(),
+            );
+            println!(
+                "    {:<7}  {:<7}  {:<7}  {:<7}  ",
+                format!("{:.2}ms", self.histogram.mean() / 1000.0),
+                format!("{:.2}ms", self.histogram.stdev() / 1000.0),
+                format!("{:.2}ms", Self::millis(self.histogram.min())),
+                format!("{:.2}ms", Self::millis(self.histogram.max())),
+            );
+        }
+
+        println!("  Requests:");
+        println!(
+            "    Total: {:^7} Req/Sec: {:^7}",
+            format!("{}", self.requests).as_str().bright_cyan(),
+            format!("{:.2}", self.requests_per_sec())
+                .as_str()
+                .bright_cyan()
+        );
+
+        println!("  Transfer:");
+        println!(
+            "    Total: {:^7} Transfer Rate: {:^7}",
+            format_data(self.transfer).as_str().bright_cyan(),
+            format!("{}/Sec", format_data(self.transfer_rate()))
+                .as_str()
+                .bright_cyan()
+        );
+    }
+
+    pub fn display_percentile_table(&self) {
+        println!("+ {:-^15} + {:-^15} +", "", "",);
+        println!(
+            "| {:^15} | {:^15} |",
+            "Percentile".bright_cyan(),
+            "Avg Latency".bright_yellow(),
+        );
+        println!("+ {:-^15} + {:-^15} +", "", "",);
+
+        for (name, quantile) in PERCENTILES {
+            let latency = Self::millis(self.histogram.value_at_quantile(quantile));
+            println!("| {:^15} | {:^15} |", name, format!("{:.2}ms", latency));
+        }
+
+        println!("+ {:-^15} + {:-^15} +", "", "",);
+    }
+
+    fn display_json(&self) {
+        let mut out = json!({
+            "latency_avg": null,
+            "latency_max": null,
+            "latency_min": null,
+            "latency_std_deviation": null,
+
+            "transfer_total": self.transfer,
+            "transfer_rate": self.transfer_rate(),
+
+            "requests_total": self.requests,
+            "requests_avg": self.requests_per_sec(),
+
+            "errors_total": self.errors,
+            "duration_secs": self.duration_secs,
+            "latency_histogram": null,
+
+            "merged_results": self.results,
+        });
+
+        if self.requests > 0 {
+            out["latency_avg"] = json!(self.histogram.mean() / 1000.0);
+            out["latency_max"] = json!(Self::millis(self.histogram.max()));
+            out["latency_min"] = json!(Self::millis(self.histogram.min()));
+            out["latency_std_deviation"] = json!(self.histogram.stdev() / 1000.0);
+            out["latency_histogram"] = json!(encode_histogram(&self.histogram));
+        }
+
+        println!("{}", out);
+    }
+}
+
+/// Reads the json results in the content of a results file along with their line numbers.
+///
+/// Lines which aren't results, e.g. the sweep summary, are skipped.
+pub fn read_results(file: &str, content: &str) -> Result<Vec<(usize, Value)>> {
+    let mut results = Vec::new();
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let result: Value = serde_json::from_str(line).with_context(|| {
+            format!("invalid json result in {:?} on line {}", file, line_no + 1)
+        })?;
+        if result.get("requests_total").is_some() {
+            results.push((line_no + 1, result));
+        }
+    }
+
+    Ok(results)
+}
+
+/// Combines the json results or `.rewrk` archives in the given files into
+/// a single report.
+///
+/// Each file may contain several results, one per line, e.g. the rounds of
+/// a sweep; every result is merged into the same report.
+pub fn run(files: &[&str], json: bool) -> Result<()> {
+    let mut merged = Merged::new();
+
+    for file in files {
+        match ArchiveReader::open(file) {
+            Ok(archive) => {
+                let header = archive.header().clone();
+                read_archive(archive, |_| {})
+                    .and_then(|windows| merged.add_archive(file, &header, &windows))
+                    .with_context(|| {
+                        format!("failed to merge the archive {:?}", file)
+                    })?;
+                continue;
+            },
+            Err(ArchiveError::InvalidMagic) => {},
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to read archive {:?}", file))
+            },
+        }
+
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("failed to read results file {:?}", file))?;
+
+        for (line_no, result) in read_results(file, &content)? {
+            merged.add(file, &result).with_context(|| {
+                format!(
+                    "failed to merge the result in {:?} on line {}",
+                    file, line_no
+                )
+            })?;
+        }
+    }
+
+    if merged.results == 0 {
+        return Err(anyhow!("no results found in the given files"));
+    }
+
+    let missing = merged.missing_shards();
+    if !missing.is_empty() {
+        eprintln!(
+            "warning: the results of shards {:?} of {} are missing",
+            missing,
+            merged.shard_count.unwrap_or_default(),
+        );
+    }
+
+    if json {
+        merged.display_json();
+    } else {
+        merged.display();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rewrk_core::ArchiveWriter;
+
+    use super::*;
+    use crate::results::WorkerResult;
+
+    fn result(requests: u64, shard: Option<(u64, u64)>) -> Value {
+        let mut histogram = Histogram::<u64>::new(3).unwrap();
+        histogram.record_n(1_000, requests).unwrap();
+
+        let mut result = json!({
+            "requests_total": requests,
+            "transfer_total": 100.0,
+            "errors_total": 1,
+            "duration_secs": 2.0,
+            "latency_histogram": encode_histogram(&histogram),
+        });
+        if let Some((index, count)) = shard {
+            result["shard"] = json!({ "index": index, "count": count });
+        }
+        result
+    }
+
+    #[test]
+    fn test_merge_results() {
+        let mut merged = Merged::new();
+        merged.add("a.json", &result(10, None)).unwrap();
+        merged.add("b.json", &result(30, None)).unwrap();
+
+        assert_eq!(merged.results, 2);
+        assert_eq!(merged.requests, 40);
+        assert_eq!(merged.errors, 2);
+        assert_eq!(merged.histogram.len(), 40);
+        assert_eq!(merged.requests_per_sec(), 20.0);
+        assert!(merged.missing_shards().is_empty());
+    }
+
+    #[test]
+    fn test_merge_shards() {
+        let mut merged = Merged::new();
+        merged.add("0.json", &result(10, Some((0, 3)))).unwrap();
+        // The rounds of a shard are in the same file.
+        merged.add("0.json", &result(10, Some((0, 3)))).unwrap();
+        merged.add("2.json", &result(10, Some((2, 3)))).unwrap();
+        assert_eq!(merged.missing_shards(), [1]);
+    }
+
+    #[test]
+    fn test_merge_duplicate_shard() {
+        let mut merged = Merged::new();
+        merged.add("0.json", &result(10, Some((0, 2)))).unwrap();
+
+        let error = merged
+            .add("copy.json", &result(10, Some((0, 2))))
+            .unwrap_err();
+        assert!(error.to_string().contains("duplicated"), "{}", error);
+        assert_eq!(merged.results, 1);
+    }
+
+    fn archive(requests: usize, shard: Option<&str>) -> Vec<u8> {
+        let mut header = ArchiveHeader::default();
+        if let Some(shard) = shard {
+            header
+                .metadata
+                .insert("shard".to_string(), shard.to_string());
+        }
+
+        let mut result = WorkerResult::default();
+        result.total_times.push(Duration::from_secs(2));
+        result.request_times = vec![Duration::from_millis(1); requests];
+        result.buffer_sizes.push(100);
+        result.error_map.insert("timeout".to_string(), 1);
+
+        let mut writer = ArchiveWriter::new(Vec::new(), &header).unwrap();
+        for round in 0..2 {
+            writer.write_sample(&result.to_sample(round, 0)).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn add_archive(merged: &mut Merged, file: &str, buffer: &[u8]) -> Result<()> {
+        let archive = ArchiveReader::new(buffer).unwrap();
+        let header = archive.header().clone();
+        let windows = read_archive(archive, |_| {})?;
+        assert_eq!(windows.len(), 2);
+        merged.add_archive(file, &header, &windows)
+    }
+
+    #[test]
+    fn test_merge_archives() {
+        let mut merged = Merged::new();
+        add_archive(&mut merged, "0.rewrk", &archive(10, Some("0/2"))).unwrap();
+        merged.add("1.json", &result(20, Some((1, 2)))).unwrap();
+
+        assert_eq!(merged.results, 2);
+        assert_eq!(merged.requests, 40);
+        assert_eq!(merged.errors, 3);
+        assert_eq!(merged.histogram.len(), 40);
+        assert_eq!(merged.transfer, 300.0);
+        // The rounds of the archive ran one after another.
+        assert_eq!(merged.duration_secs, 4.0);
+        assert!(merged.missing_shards().is_empty());
+
+        let error = add_archive(&mut merged, "copy.rewrk", &archive(10, Some("0/2")))
+            .unwrap_err();
+        assert!(error.to_string().contains("duplicated"), "{}", error);
+    }
+
+    #[test]
+    fn test_merge_mismatched_shard_count() {
+        let mut merged = Merged::new();
+        merged.add("0.json", &result(10, Some((0, 2)))).unwrap();
+
+        let error = merged.add("1.json", &result(10, Some((1, 4)))).unwrap_err();
+        assert!(error.to_string().contains("from 2 shards"), "{}", error);
+        assert_eq!(merged.results, 1);
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::env;
+
+use clap::ArgMatches;
+
+/// The prefix of the environment variables which can be used
+/// in place of the CLI options.
+const ENV_PREFIX: &str = "REWRK_";
+
+/// The parsed CLI options, falling back to the `REWRK_*` environment
+/// variables for any option which was not given on the command line.
+///
+/// The variable of an option is its long name in upper case with dashes
+/// replaced by underscores, e.g. `--no-keepalive` becomes `REWRK_NO_KEEPALIVE`.
+/// Flags are enabled by any value other than `0`, `false`, `no` or an
+/// empty string, options which can be repeated take one value per line.
+pub struct Options<'a> {
+    args: ArgMatches<'a>,
+    env: HashMap<String, String>,
+}
+
+impl<'a> Options<'a> {
+    /// Creates the options falling back to the process' environment.
+    pub fn new(args: ArgMatches<'a>) -> Self {
+        Self::with_env(args, env::vars())
+    }
+
+    /// Creates the options falling back to the given environment variables.
+    pub fn with_env(
+        args: ArgMatches<'a>,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        let env = vars
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let name = key.strip_prefix(ENV_PREFIX)?;
+                Some((name.to_lowercase().replace('_', "-"), value))
+            })
+            .collect();
+
+        Self { args, env }
+    }
+
+    /// The value of the option.
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        self.args
+            .value_of(name)
+            .or_else(|| self.env.get(name).map(String::as_str))
+    }
+
+    /// The values of an option which can be repeated.
+    pub fn values_of(&self, name: &str) -> Option<Vec<&str>> {
+        if let Some(values) = self.args.values_of(name) {
+            return Some(values.collect());
+        }
+
+        let values = self
+            .env
+            .get(name)?
+            .lines()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .collect();
+        Some(values)
+    }
+
+    /// Returns if the flag is set.
+    pub fn is_present(&self, name: &str) -> bool {
+        if self.args.is_present(name) {
+            return true;
+        }
+
+        match self.env.get(name) {
+            None => false,
+            Some(value) => {
+                let value = value.trim().to_lowercase();
+                !matches!(value.as_str(), "" | "0" | "false" | "no")
+            },
+        }
+    }
+}
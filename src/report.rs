@@ -0,0 +1,590 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Error, Result};
+use colored::Colorize;
+use hdrhistogram::Histogram;
+use rewrk_core::{ArchiveError, ArchiveReader, ImbalanceDetector, LatencyHeatmap};
+
+use crate::merge::{read_archive, read_results, Merged, WindowKey, PERCENTILES};
+use crate::utils::{decode_histogram, format_data};
+
+/// The names of the formats a report can be rendered in.
+pub const FORMATS: &[&str] = &[
+    "table",
+    "percentiles",
+    "html",
+    "markdown",
+    "timeline",
+    "heatmap-csv",
+    "heatmap-json",
+];
+
+/// The format a report is rendered in.
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    /// The standard latency, request and transfer summary.
+    Table,
+    /// The summary along with the percentile table.
+    Percentiles,
+    /// A standalone html page.
+    Html,
+    /// Markdown tables, e.g. for pull requests.
+    Markdown,
+    /// The requests and latencies of each sample window.
+    Timeline,
+    /// The number of requests in each latency bucket of each sample window as CSV.
+    HeatmapCsv,
+    /// The number of requests in each latency bucket of each sample window as JSON.
+    HeatmapJson,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "table" => Ok(Self::Table),
+            "percentiles" => Ok(Self::Percentiles),
+            "html" => Ok(Self::Html),
+            "markdown" => Ok(Self::Markdown),
+            "timeline" => Ok(Self::Timeline),
+            "heatmap-csv" => Ok(Self::HeatmapCsv),
+            "heatmap-json" => Ok(Self::HeatmapJson),
+            other => Err(anyhow!("unknown report format {:?}", other)),
+        }
+    }
+}
+
+/// Where the windows of the report's timeline were loaded from.
+///
+/// The windows of archives and json results don't line up with each other,
+/// so they're kept apart rather than combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum WindowSource {
+    Archive,
+    Results,
+}
+
+/// A single point of the report's timeline.
+///
+/// For archives this is a sample window, for json results it's a single result,
+/// e.g. a round of a sweep. Windows with the same source and position in several
+/// files are combined as they're expected to have run concurrently.
+struct Window {
+    histogram: Histogram<u64>,
+    requests: u64,
+    errors: u64,
+    duration_secs: f64,
+}
+
+impl Window {
+    fn new() -> Self {
+        Self {
+            histogram: Histogram::new(3).expect("Create histogram"),
+            requests: 0,
+            errors: 0,
+            duration_secs: 0.0,
+        }
+    }
+
+    fn add(
+        &mut self,
+        histogram: Option<&Histogram<u64>>,
+        requests: u64,
+        errors: u64,
+        duration_secs: f64,
+    ) -> Result<()> {
+        if let Some(histogram) = histogram {
+            self.histogram
+                .add(histogram)
+                .map_err(|e| anyhow!("failed to merge histogram: {:?}", e))?;
+        }
+        self.requests += requests;
+        self.errors += errors;
+        self.duration_secs = self.duration_secs.max(duration_secs);
+
+        Ok(())
+    }
+
+    fn requests_per_sec(&self) -> f64 {
+        if self.duration_secs > 0.0 {
+            self.requests as f64 / self.duration_secs
+        } else {
+            0.0
+        }
+    }
+
+    /// The latency at the given quantile formatted in milliseconds.
+    fn latency(&self, quantile: f64) -> String {
+        if self.histogram.is_empty() {
+            return "-".to_string();
+        }
+
+        let latency = Merged::millis(self.histogram.value_at_quantile(quantile));
+        format!("{:.2}ms", latency)
+    }
+}
+
+/// A table rendered as markdown or html.
+struct Table {
+    title: &'static str,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+/// The results loaded from archives and json results files.
+struct Report {
+    totals: Merged,
+    windows: BTreeMap<(WindowSource, WindowKey), Window>,
+    /// The latency heatmap of the archived samples.
+    heatmap: LatencyHeatmap,
+    /// The per-worker and per-connection load of the archived samples.
+    imbalance: ImbalanceDetector,
+    archives: usize,
+}
+
+impl Report {
+    fn new() -> Self {
+        Self {
+            totals: Merged::new(),
+            windows: BTreeMap::new(),
+            heatmap: LatencyHeatmap::default(),
+            imbalance: ImbalanceDetector::default(),
+            archives: 0,
+        }
+    }
+
+    /// Loads a `.rewrk` archive or a file of `rewrk --json` results.
+    fn load(&mut self, file: &str) -> Result<()> {
+        match ArchiveReader::open(file) {
+            Ok(archive) => self.add_archive(file, archive),
+            Err(ArchiveError::InvalidMagic) => {
+                let content = fs::read_to_string(file).with_context(|| {
+                    format!("failed to read results file {:?}", file)
+                })?;
+                self.add_results(file, &content)
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn add_archive<R: std::io::Read>(
+        &mut self,
+        file: &str,
+        archive: ArchiveReader<R>,
+    ) -> Result<()> {
+        let header = archive.header().clone();
+        let windows = read_archive(archive, |sample| {
+            self.heatmap.add_sample(sample);
+            self.imbalance.add_sample(sample);
+        })?;
+        self.totals.add_archive(file, &header, &windows)?;
+
+        for (key, window) in windows {
+            self.windows
+                .entry((WindowSource::Archive, key))
+                .or_insert_with(Window::new)
+                .add(
+                    Some(&window.histogram),
+                    window.requests,
+                    window.errors,
+                    window.duration_secs,
+                )?;
+        }
+
+        self.archives += 1;
+        Ok(())
+    }
+
+    fn add_results(&mut self, file: &str, content: &str) -> Result<()> {
+        for (index, (line_no, result)) in
+            read_results(file, content)?.into_iter().enumerate()
+        {
+            let context = || format!("invalid result in {:?} on line {}", file, line_no);
+
+            let histogram = result["latency_histogram"]
+                .as_str()
+                .map(decode_histogram)
+                .transpose()
+                .with_context(context)?;
+            self.windows
+                .entry((WindowSource::Results, (0, 0, index)))
+                .or_insert_with(Window::new)
+                .add(
+                    histogram.as_ref(),
+                    result["requests_total"].as_u64().unwrap_or_default(),
+                    result["errors_total"].as_u64().unwrap_or_default(),
+                    result["duration_secs"].as_f64().unwrap_or_default(),
+                )
+                .with_context(context)?;
+            self.totals.add(file, &result).with_context(context)?;
+        }
+
+        Ok(())
+    }
+
+    fn display_timeline(&self) {
+        println!(
+            "+ {:-^9} + {:-^9} + {:-^9} + {:-^11} + {:-^11} + {:-^11} + {:-^7} +",
+            "", "", "", "", "", "", ""
+        );
+        println!(
+            "| {:^9} | {:^9} | {:^9} | {:^11} | {:^11} | {:^11} | {:^7} |",
+            "Start".bright_cyan(),
+            "Duration".bright_cyan(),
+            "Requests".bright_cyan(),
+            "Req/Sec".bright_cyan(),
+            "p50".bright_yellow(),
+            "p99".bright_red(),
+            "Errors".bright_red(),
+        );
+        println!(
+            "+ {:-^9} + {:-^9} + {:-^9} + {:-^11} + {:-^11} + {:-^11} + {:-^7} +",
+            "", "", "", "", "", "", ""
+        );
+        for row in self.timeline_rows() {
+            println!(
+                "| {:^9} | {:^9} | {:^9} | {:^11} | {:^11} | {:^11} | {:^7} |",
+                row[0], row[1], row[2], row[3], row[4], row[5], row[6],
+            );
+        }
+        println!(
+            "+ {:-^9} + {:-^9} + {:-^9} + {:-^11} + {:-^11} + {:-^11} + {:-^7} +",
+            "", "", "", "", "", "", ""
+        );
+    }
+
+    fn timeline_rows(&self) -> Vec<Vec<String>> {
+        let mut start = 0.0;
+        let mut rows = Vec::with_capacity(self.windows.len());
+        for window in self.windows.values() {
+            rows.push(vec![
+                format!("{:.2}s", start),
+                format!("{:.2}s", window.duration_secs),
+                window.requests.to_string(),
+                format!("{:.2}", window.requests_per_sec()),
+                window.latency(0.5),
+                window.latency(0.99),
+                window.errors.to_string(),
+            ]);
+            start += window.duration_secs;
+        }
+        rows
+    }
+
+    /// The tables shared by the markdown and html reports.
+    fn tables(&self) -> Vec<Table> {
+        let totals = &self.totals;
+
+        let mut summary = vec![
+            vec!["Requests".to_string(), totals.requests.to_string()],
+            vec![
+                "Duration".to_string(),
+                format!("{:.2}s", totals.duration_secs),
+            ],
+            vec![
+                "Req/Sec".to_string(),
+                format!("{:.2}", totals.requests_per_sec()),
+            ],
+            vec!["Transfer".to_string(), format_data(totals.transfer)],
+            vec![
+                "Transfer Rate".to_string(),
+                format!("{}/Sec", format_data(totals.transfer_rate())),
+            ],
+            vec!["Errors".to_string(), totals.errors.to_string()],
+        ];
+
+        let mut percentiles = Vec::new();
+        if totals.requests > 0 {
+            let histogram = &totals.histogram;
+            summary.extend([
+                vec![
+                    "Latency Avg".to_string(),
+                    format!("{:.2}ms", histogram.mean() / 1000.0),
+                ],
+                vec![
+                    "Latency Stdev".to_string(),
+                    format!("{:.2}ms", histogram.stdev() / 1000.0),
+                ],
+                vec![
+                    "Latency Min".to_string(),
+                    format!("{:.2}ms", Merged::millis(histogram.min())),
+                ],
+                vec![
+                    "Latency Max".to_string(),
+                    format!("{:.2}ms", Merged::millis(histogram.max())),
+                ],
+            ]);
+
+            for (name, quantile) in PERCENTILES {
+                let latency = Merged::millis(histogram.value_at_quantile(quantile));
+                percentiles.push(vec![name.to_string(), format!("{:.2}ms", latency)]);
+            }
+        }
+
+        let mut tables = vec![Table {
+            title: "Summary",
+            headers: vec!["Metric", "Value"],
+            rows: summary,
+        }];
+        if !percentiles.is_empty() {
+            tables.push(Table {
+                title: "Percentiles",
+                headers: vec!["Percentile", "Latency"],
+                rows: percentiles,
+            });
+        }
+        if self.windows.len() > 1 {
+            tables.push(Table {
+                title: "Timeline",
+                headers: vec![
+                    "Start", "Duration", "Requests", "Req/Sec", "p50", "p99", "Errors",
+                ],
+                rows: self.timeline_rows(),
+            });
+        }
+
+        tables
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::from("# Benchmark Report\n");
+        for table in self.tables() {
+            let _ = write!(out, "\n## {}\n\n", table.title);
+            let _ = writeln!(out, "| {} |", table.headers.join(" | "));
+            let _ = writeln!(out, "|{}", " --- |".repeat(table.headers.len()));
+            for row in table.rows {
+                let _ = writeln!(out, "| {} |", row.join(" | "));
+            }
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Benchmark Report</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 2em; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: right; }\n\
+             </style>\n</head>\n<body>\n<h1>Benchmark Report</h1>\n",
+        );
+        for table in self.tables() {
+            let _ = writeln!(out, "<h2>{}</h2>\n<table>", table.title);
+            let _ = writeln!(
+                out,
+                "<tr>{}</tr>",
+                table
+                    .headers
+                    .iter()
+                    .map(|header| format!("<th>{}</th>", header))
+                    .collect::<String>()
+            );
+            for row in table.rows {
+                let _ = writeln!(
+                    out,
+                    "<tr>{}</tr>",
+                    row.iter()
+                        .map(|cell| format!("<td>{}</td>", cell))
+                        .collect::<String>()
+                );
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("</body>\n</html>");
+        out
+    }
+}
+
+/// Renders a report from archived samples or json results without re-running
+/// the benchmark.
+///
+/// The files can be `.rewrk` archives or files of `--json` results, the results
+/// of every file are combined into the same report.
+pub fn run(files: &[&str], format: Format) -> Result<()> {
+    let mut report = Report::new();
+    for file in files {
+        report
+            .load(file)
+            .with_context(|| format!("failed to load {:?}", file))?;
+    }
+
+    if report.totals.results == 0 {
+        return Err(anyhow!("no results found in the given files"));
+    }
+
+    for warning in report.imbalance.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+
+    match format {
+        Format::Table | Format::Percentiles => {
+            println!(
+                "Report of {} results ({} across {:.2}s)",
+                report.totals.results.to_string().cyan(),
+                format!("{} requests", report.totals.requests).cyan(),
+                report.totals.duration_secs,
+            );
+            report.totals.display_summary();
+
+            if matches!(format, Format::Percentiles) && report.totals.requests > 0 {
+                println!();
+                report.totals.display_percentile_table();
+            }
+
+            if report.totals.errors > 0 {
+                println!();
+                println!("{} Errors", report.totals.errors);
+            }
+        },
+        Format::Timeline => report.display_timeline(),
+        Format::Markdown => println!("{}", report.markdown()),
+        Format::Html => println!("{}", report.html()),
+        Format::HeatmapCsv | Format::HeatmapJson => {
+            if report.archives == 0 {
+                return Err(anyhow!(
+                    "heatmaps require the sample windows of '.rewrk' archives"
+                ));
+            }
+
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            if matches!(format, Format::HeatmapCsv) {
+                report.heatmap.write_csv(&mut stdout)?;
+            } else {
+                report.heatmap.write_json(&mut stdout)?;
+                writeln!(stdout)?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rewrk_core::{ArchiveHeader, ArchiveWriter};
+    use serde_json::json;
+
+    use super::*;
+    use crate::results::WorkerResult;
+    use crate::utils::encode_histogram;
+
+    fn results(requests: &[u64]) -> String {
+        requests
+            .iter()
+            .map(|&requests| {
+                let mut histogram = Histogram::<u64>::new(3).unwrap();
+                histogram.record_n(2_000, requests).unwrap();
+                json!({
+                    "requests_total": requests,
+                    "transfer_total": 100.0,
+                    "errors_total": 0,
+                    "duration_secs": 1.0,
+                    "latency_histogram": encode_histogram(&histogram),
+                })
+                .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn archive(rounds: usize, requests: usize) -> Vec<u8> {
+        let mut result = WorkerResult::default();
+        result.total_times.push(Duration::from_secs(2));
+        result.request_times = vec![Duration::from_millis(1); requests];
+
+        let mut writer = ArchiveWriter::new(Vec::new(), &ArchiveHeader::default())
+            .expect("Create archive");
+        for round in 0..rounds {
+            writer.write_sample(&result.to_sample(round, 0)).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_parse_format() {
+        for name in FORMATS {
+            assert!(name.parse::<Format>().is_ok(), "{}", name);
+        }
+        assert!("pdf".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_report_results() {
+        let mut report = Report::new();
+        report.add_results("a.json", &results(&[10, 20])).unwrap();
+        report.add_results("b.json", &results(&[30, 40])).unwrap();
+
+        assert_eq!(report.totals.results, 4);
+        assert_eq!(report.totals.requests, 100);
+        assert_eq!(report.archives, 0);
+
+        // Results at the same position in each file ran concurrently.
+        let rows = report.timeline_rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][2], "40");
+        assert_eq!(rows[1][0], "1.00s");
+        assert_eq!(rows[1][2], "60");
+        assert_eq!(rows[1][4], "2.00ms");
+    }
+
+    #[test]
+    fn test_report_archive_and_results() {
+        let mut report = Report::new();
+        let buffer = archive(2, 10);
+        let archive = ArchiveReader::new(&buffer[..]).unwrap();
+        report.add_archive("a.rewrk", archive).unwrap();
+        report.add_results("b.json", &results(&[30])).unwrap();
+
+        assert_eq!(report.archives, 1);
+        assert_eq!(report.totals.results, 2);
+        assert_eq!(report.totals.requests, 50);
+
+        // The first archive window and the first result aren't combined.
+        let rows = report.timeline_rows();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][2], "10");
+        assert_eq!(rows[1][2], "10");
+        assert_eq!(rows[2][2], "30");
+        assert_eq!(rows[2][0], "4.00s");
+    }
+
+    #[test]
+    fn test_report_tables() {
+        let mut report = Report::new();
+        report.add_results("a.json", &results(&[10, 20])).unwrap();
+
+        let titles: Vec<_> = report.tables().iter().map(|table| table.title).collect();
+        assert_eq!(titles, ["Summary", "Percentiles", "Timeline"]);
+
+        let markdown = report.markdown();
+        assert!(markdown.starts_with("# Benchmark Report\n"));
+        assert!(markdown.contains("| Requests | 30 |"), "{}", markdown);
+        assert!(markdown.contains("| 50% | 2.00ms |"), "{}", markdown);
+
+        let html = report.html();
+        assert!(html.contains("<h2>Timeline</h2>"));
+        assert!(
+            html.contains("<tr><td>Requests</td><td>30</td></tr>"),
+            "{}",
+            html
+        );
+        assert!(html.ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_report_single_result_has_no_timeline() {
+        let mut report = Report::new();
+        report.add_results("a.json", &results(&[10])).unwrap();
+
+        let titles: Vec<_> = report.tables().iter().map(|table| table.title).collect();
+        assert_eq!(titles, ["Summary", "Percentiles"]);
+    }
+}
@@ -3,10 +3,13 @@
 use std::collections::HashMap;
 
 use colored::Colorize;
-use serde_json::json;
+use hdrhistogram::Histogram;
+use rewrk_core::{Sample, SampleMetadata, ValidationError};
+use serde_json::{json, Value};
 use tokio::time::Duration;
 
-use crate::utils::format_data;
+use crate::shard::Shard;
+use crate::utils::{encode_histogram, format_data};
 
 fn get_percentile(request_times: &[Duration], pct: f64) -> Duration {
     let mut len = request_times.len() as f64 * pct;
@@ -33,11 +36,20 @@ pub struct WorkerResult {
     /// The vec of latencies per request stored.
     pub request_times: Vec<Duration>,
 
+    /// The vec of connection setup times when keep-alive is disabled.
+    pub connect_times: Vec<Duration>,
+
+    /// The number of requests served by each connection.
+    pub connection_requests: Vec<usize>,
+
     /// The amount of data read from each worker.
     pub buffer_sizes: Vec<usize>,
 
     /// Error counting map.
     pub error_map: HashMap<String, usize>,
+
+    /// The number of connects which failed due to ephemeral port exhaustion.
+    pub port_exhaustion_errors: usize,
 }
 
 impl WorkerResult {
@@ -47,16 +59,22 @@ impl WorkerResult {
         Self {
             total_times: vec![],
             request_times: vec![],
+            connect_times: vec![],
+            connection_requests: vec![],
             buffer_sizes: vec![],
             error_map: HashMap::new(),
+            port_exhaustion_errors: 0,
         }
     }
 
     /// Consumes both self and other producing a combined result.
     pub fn combine(mut self, other: Self) -> Self {
         self.request_times.extend(other.request_times);
+        self.connect_times.extend(other.connect_times);
+        self.connection_requests.extend(other.connection_requests);
         self.total_times.extend(other.total_times);
         self.buffer_sizes.extend(other.buffer_sizes);
+        self.port_exhaustion_errors += other.port_exhaustion_errors;
 
         // Insert/add new errors to current error map.
         for (message, count) in other.error_map {
@@ -71,6 +89,51 @@ impl WorkerResult {
         self
     }
 
+    /// Converts the result of a round, or of one of its connections, into a
+    /// sample so it can be written to a `.rewrk` archive or compared to the
+    /// other connections.
+    pub fn to_sample(&self, round: usize, connection_id: usize) -> Sample {
+        let metadata = SampleMetadata {
+            worker_id: 0,
+            connection_id,
+            round,
+            phase: 0,
+            sample_window: self.avg_total_time(),
+            peer_addr: None,
+        };
+
+        let mut errors: Vec<_> = self
+            .error_map
+            .iter()
+            .map(|(message, count)| {
+                (
+                    ValidationError::Other(message.clone().into()),
+                    *count as u64,
+                )
+            })
+            .collect();
+        if self.port_exhaustion_errors != 0 {
+            errors.push((
+                ValidationError::Other(
+                    "ephemeral port exhaustion (address not available)".into(),
+                ),
+                self.port_exhaustion_errors as u64,
+            ));
+        }
+
+        Sample::from_requests(
+            metadata,
+            &self.request_times,
+            self.total_transfer() as u64,
+            errors,
+        )
+    }
+
+    /// The number of requests which failed, including failed connects.
+    pub fn total_errors(&self) -> usize {
+        self.error_map.values().sum::<usize>() + self.port_exhaustion_errors
+    }
+
     /// Simple helper returning the amount of requests overall.
     pub fn total_requests(&self) -> usize {
         self.request_times.len()
@@ -123,6 +186,18 @@ impl WorkerResult {
         self.request_times.iter().min().copied().unwrap_or_default()
     }
 
+    /// Records the request latencies in microseconds into a histogram
+    /// which can be merged with the results of other runs.
+    pub fn latency_histogram(&self) -> Histogram<u64> {
+        let mut histogram = Histogram::new(3).expect("Create histogram");
+        for time in &self.request_times {
+            histogram
+                .record(time.as_micros() as u64)
+                .expect("Record latency");
+        }
+        histogram
+    }
+
     /// Calculates the variance between all requests
     pub fn variance(&self) -> f64 {
         let mean = self.avg_request_latency().as_secs_f64();
@@ -154,6 +229,15 @@ impl WorkerResult {
         self.request_times.sort_by(|a, b| b.partial_cmp(a).unwrap());
     }
 
+    /// Works out the latency at the 99th percentile.
+    ///
+    /// Unlike [WorkerResult::p99_avg_latency] this is the quantile itself
+    /// rather than the average of the slowest 1% of requests.
+    pub fn p99_latency(&self) -> Duration {
+        let histogram = self.latency_histogram();
+        Duration::from_micros(histogram.value_at_quantile(0.99))
+    }
+
     /// Works out the average latency of the 99.9 percentile.
     pub fn p999_avg_latency(&self) -> Duration {
         get_percentile(&self.request_times, 0.001)
@@ -208,6 +292,110 @@ impl WorkerResult {
         );
     }
 
+    /// Calculates the average connection setup time.
+    pub fn avg_connect_latency(&self) -> Duration {
+        let total: f64 = self.connect_times.iter().map(|dur| dur.as_secs_f64()).sum();
+
+        let len = self.connect_times.len() as f64;
+        Duration::from_secs_f64(total / len)
+    }
+
+    /// Calculates the max connection setup time.
+    pub fn max_connect_latency(&self) -> Duration {
+        self.connect_times.iter().max().copied().unwrap_or_default()
+    }
+
+    /// Calculates the min connection setup time.
+    pub fn min_connect_latency(&self) -> Duration {
+        self.connect_times.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn display_connect_latencies(&mut self) {
+        if self.connect_times.is_empty() {
+            return;
+        }
+
+        let modified = 1000_f64;
+        let avg = self.avg_connect_latency().as_secs_f64() * modified;
+        let max = self.max_connect_latency().as_secs_f64() * modified;
+        let min = self.min_connect_latency().as_secs_f64() * modified;
+
+        println!("  Connect (included in latencies):");
+        println!(
+            "    {:<7}  {:<7}  {:<7}  {:<7}  ",
+            "Avg".bright_yellow(),
+            "Min".bright_green(),
+            "Max".bright_red(),
+            "Total".bright_cyan(),
+        );
+        println!(
+            "    {:<7}  {:<7}  {:<7}  {:<7}  ",
+            format!("{:.2}ms", avg),
+            format!("{:.2}ms", min),
+            format!("{:.2}ms", max),
+            self.connect_times.len(),
+        );
+    }
+
+    /// The share of requests sent on a connection which already served
+    /// a request, between `0.0` and `1.0`.
+    pub fn connection_reuse_ratio(&self) -> f64 {
+        let requests: usize = self.connection_requests.iter().sum();
+        if requests == 0 {
+            return 0.0;
+        }
+
+        let reused = requests.saturating_sub(self.connection_requests.len());
+        reused as f64 / requests as f64
+    }
+
+    /// The distribution of the number of requests served by each connection.
+    pub fn requests_per_connection(&self) -> Histogram<u64> {
+        let mut histogram = Histogram::new(3).expect("Create histogram");
+        for requests in &self.connection_requests {
+            histogram
+                .record(*requests as u64)
+                .expect("Record requests per connection");
+        }
+        histogram
+    }
+
+    pub fn display_connections(&self) {
+        if self.connection_requests.is_empty() {
+            return;
+        }
+
+        let distribution = self.requests_per_connection();
+
+        println!("  Connections:");
+        println!(
+            "    Total: {:^7} Reuse: {:^7}",
+            format!("{}", self.connection_requests.len())
+                .as_str()
+                .bright_cyan(),
+            format!("{:.2}%", self.connection_reuse_ratio() * 100.0)
+                .as_str()
+                .bright_cyan(),
+        );
+        println!("    Requests per connection:");
+        println!(
+            "      {:<7}  {:<7}  {:<7}  {:<7}  {:<7}  ",
+            "Avg".bright_yellow(),
+            "Min".bright_green(),
+            "P50".bright_cyan(),
+            "P99".bright_magenta(),
+            "Max".bright_red(),
+        );
+        println!(
+            "      {:<7}  {:<7}  {:<7}  {:<7}  {:<7}  ",
+            format!("{:.2}", distribution.mean()),
+            distribution.min(),
+            distribution.value_at_quantile(0.5),
+            distribution.value_at_quantile(0.99),
+            distribution.max(),
+        );
+    }
+
     pub fn display_requests(&mut self) {
         let total = self.total_requests();
         let avg = self.avg_request_per_sec();
@@ -224,7 +412,7 @@ impl WorkerResult {
         let total = self.total_transfer() as f64;
         let rate = self.avg_transfer();
 
-        let display_total = format_data(total as f64);
+        let display_total = format_data(total);
         let display_rate = format_data(rate);
 
         println!("  Transfer:");
@@ -291,9 +479,24 @@ impl WorkerResult {
                 println!("{} Errors: {}", count, message);
             }
         }
+
+        if self.port_exhaustion_errors != 0 {
+            println!();
+            println!(
+                "{} Errors: {}",
+                self.port_exhaustion_errors,
+                "ephemeral port exhaustion (address not available)".bright_red(),
+            );
+            println!(
+                "  The OS ran out of local ports for new connections. Enable keep-alive, \
+                 reduce the number of connections, widen the local port range \
+                 (e.g. 'net.ipv4.ip_local_port_range'), enable 'net.ipv4.tcp_tw_reuse' \
+                 or use '--adaptive-connect' to slow down reconnects."
+            );
+        }
     }
 
-    pub fn display_json(&self) {
+    pub fn display_json(&self, shard: Option<Shard>) {
         // prevent div-by-zero panics
         if self.total_requests() == 0 {
             let null = None::<()>;
@@ -309,9 +512,13 @@ impl WorkerResult {
 
                 "requests_total": 0,
                 "requests_avg": null,
+
+                "errors_total": self.total_errors(),
+                "duration_secs": self.avg_total_time().as_secs_f64(),
+                "latency_histogram": null,
             });
 
-            println!("{}", out.to_string());
+            println!("{}", with_shard(out, shard));
             return;
         }
 
@@ -327,7 +534,7 @@ impl WorkerResult {
         let total_requests = self.total_requests();
         let avg_request_per_sec = self.avg_request_per_sec();
 
-        let out = json!({
+        let mut out = json!({
             "latency_avg": avg,
             "latency_max": max,
             "latency_min": min,
@@ -338,8 +545,47 @@ impl WorkerResult {
 
             "requests_total": total_requests,
             "requests_avg": avg_request_per_sec,
+
+            "errors_total": self.total_errors(),
+            "duration_secs": self.avg_total_time().as_secs_f64(),
+            "latency_histogram": encode_histogram(&self.latency_histogram()),
         });
 
-        println!("{}", out.to_string())
+        if !self.connect_times.is_empty() {
+            out["connect_avg"] =
+                json!(self.avg_connect_latency().as_secs_f64() * modified);
+            out["connect_max"] =
+                json!(self.max_connect_latency().as_secs_f64() * modified);
+            out["connect_min"] =
+                json!(self.min_connect_latency().as_secs_f64() * modified);
+            out["connect_total"] = json!(self.connect_times.len());
+        }
+
+        if !self.connection_requests.is_empty() {
+            let distribution = self.requests_per_connection();
+            out["connections_total"] = json!(self.connection_requests.len());
+            out["connection_reuse_ratio"] = json!(self.connection_reuse_ratio());
+            out["requests_per_connection"] = json!({
+                "avg": distribution.mean(),
+                "min": distribution.min(),
+                "p50": distribution.value_at_quantile(0.5),
+                "p99": distribution.value_at_quantile(0.99),
+                "max": distribution.max(),
+            });
+        }
+
+        if self.port_exhaustion_errors != 0 {
+            out["port_exhaustion_errors"] = json!(self.port_exhaustion_errors);
+        }
+
+        println!("{}", with_shard(out, shard))
+    }
+}
+
+/// Tags the json results with the shard which produced them.
+fn with_shard(mut out: Value, shard: Option<Shard>) -> Value {
+    if let Some(shard) = shard {
+        out["shard"] = shard.to_json();
     }
+    out
 }
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+use serde_json::{json, Value};
+
+/// One of several independently launched benchmark processes which
+/// share a workload between them, e.g. the pods of a Kubernetes job.
+///
+/// Connection counts and request rates are given as totals across all
+/// shards and each shard takes a deterministic share of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// The zero based index of this shard.
+    pub index: usize,
+
+    /// The total number of shards.
+    pub count: usize,
+}
+
+impl Shard {
+    /// This shard's share of the given total.
+    ///
+    /// The remainder is given to the lowest indexed shards, so the
+    /// shares of all shards always add up to the total.
+    pub fn split(&self, total: usize) -> usize {
+        let share = total / self.count;
+        let remainder = total % self.count;
+        share + usize::from(self.index < remainder)
+    }
+
+    /// This shard's share of the given request rate in requests per second.
+    ///
+    /// The rate is split evenly rather than in whole requests, so the shares
+    /// add up to the total even when the rate is lower than the shard count.
+    /// A rate of `0` is unlimited, so every shard's share is unlimited.
+    pub fn split_rate(&self, rate: u64) -> f64 {
+        rate as f64 / self.count as f64
+    }
+
+    /// The shard as a json value for tagging results.
+    pub fn to_json(self) -> Value {
+        json!({
+            "index": self.index,
+            "count": self.count,
+        })
+    }
+}
+
+impl FromStr for Shard {
+    type Err = Error;
+
+    /// Parses a shard in the form `i/N`, e.g. `0/4`.
+    fn from_str(value: &str) -> Result<Self> {
+        let (index, count) = value
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| anyhow!("expected a shard in the form 'i/N' e.g. '0/4'"))?;
+
+        let index: usize = index
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("invalid shard index {:?}", index))?;
+        let count: usize = count
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("invalid shard count {:?}", count))?;
+
+        if count == 0 {
+            return Err(anyhow!("the shard count must be at least 1"));
+        }
+        if index >= count {
+            return Err(anyhow!(
+                "the shard index must be less than the shard count ({})",
+                count
+            ));
+        }
+
+        Ok(Self { index, count })
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shard(index: usize, count: usize) -> Shard {
+        Shard { index, count }
+    }
+
+    #[test]
+    fn test_parse_shard() {
+        assert_eq!("0/4".parse::<Shard>().unwrap(), shard(0, 4));
+        assert_eq!(" 3 / 4 ".parse::<Shard>().unwrap(), shard(3, 4));
+        assert_eq!("0/1".parse::<Shard>().unwrap().to_string(), "0/1");
+
+        assert!("4/4".parse::<Shard>().is_err());
+        assert!("0/0".parse::<Shard>().is_err());
+        assert!("-1/4".parse::<Shard>().is_err());
+        assert!("1".parse::<Shard>().is_err());
+        assert!("a/b".parse::<Shard>().is_err());
+    }
+
+    #[test]
+    fn test_split() {
+        let shares = (0..3).map(|i| shard(i, 3).split(10)).collect::<Vec<_>>();
+        assert_eq!(shares, [4, 3, 3]);
+
+        let shares = (0..4).map(|i| shard(i, 4).split(2)).collect::<Vec<_>>();
+        assert_eq!(shares, [1, 1, 0, 0]);
+        assert_eq!(shard(0, 1).split(7), 7);
+    }
+
+    #[test]
+    fn test_split_rate() {
+        assert_eq!(shard(0, 4).split_rate(0), 0.0);
+        assert_eq!(shard(1, 4).split_rate(100), 25.0);
+
+        // Rates lower than the shard count aren't rounded up.
+        let total = (0..4).map(|i| shard(i, 4).split_rate(2)).sum::<f64>();
+        assert_eq!(total, 2.0);
+        assert_eq!(shard(3, 4).split_rate(2), 0.5);
+    }
+}
@@ -0,0 +1,329 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use colored::*;
+use serde_json::json;
+use tokio::time::Duration;
+
+use crate::results::WorkerResult;
+
+/// The results of a single benchmark run within a concurrency sweep.
+#[derive(Clone, Debug)]
+pub struct SweepResult {
+    /// The number of concurrent connections used for the run.
+    pub connections: usize,
+
+    /// The round of the run at this concurrency level.
+    pub round: usize,
+
+    /// The average number of requests per second.
+    pub requests_per_sec: f64,
+
+    /// The average request latency.
+    pub avg_latency: Duration,
+
+    /// The latency at the 99th percentile.
+    pub p99_latency: Duration,
+
+    /// The number of errors which occurred during the run.
+    pub errors: usize,
+}
+
+impl SweepResult {
+    /// Extracts the sweep metrics from a set of worker results.
+    pub fn from_result(connections: usize, round: usize, result: &WorkerResult) -> Self {
+        let errors =
+            result.error_map.values().sum::<usize>() + result.port_exhaustion_errors;
+
+        // prevent div-by-zero panics
+        if result.total_requests() == 0 {
+            return Self {
+                connections,
+                round,
+                requests_per_sec: 0.0,
+                avg_latency: Duration::default(),
+                p99_latency: Duration::default(),
+                errors,
+            };
+        }
+
+        Self {
+            connections,
+            round,
+            requests_per_sec: result.avg_request_per_sec(),
+            avg_latency: result.avg_request_latency(),
+            p99_latency: result.p99_latency(),
+            errors,
+        }
+    }
+}
+
+/// Steps the concurrency upward until the p99 latency breaches a target.
+#[derive(Clone, Copy, Debug)]
+pub struct GoalSeek {
+    /// The p99 latency every round of a concurrency level must stay within.
+    pub p99_target: Duration,
+
+    /// The maximum number of connections to step up to.
+    pub max_connections: usize,
+}
+
+impl GoalSeek {
+    /// The next concurrency level to run, doubling the connections until the
+    /// level breaches the target or the maximum number of connections is reached.
+    pub fn next_level(
+        &self,
+        connections: usize,
+        results: &[SweepResult],
+    ) -> Option<usize> {
+        if connections >= self.max_connections
+            || !self.meets_target(connections, results)
+        {
+            return None;
+        }
+
+        Some((connections * 2).min(self.max_connections))
+    }
+
+    /// The highest concurrency level which met the target along with its
+    /// average requests per second and worst p99 latency across rounds.
+    pub fn best_level(&self, results: &[SweepResult]) -> Option<(usize, f64, Duration)> {
+        let connections = results
+            .iter()
+            .map(|result| result.connections)
+            .filter(|connections| self.meets_target(*connections, results))
+            .max()?;
+
+        let level = results
+            .iter()
+            .filter(|result| result.connections == connections)
+            .collect::<Vec<_>>();
+        let requests_per_sec = level
+            .iter()
+            .map(|result| result.requests_per_sec)
+            .sum::<f64>()
+            / level.len() as f64;
+        let p99_latency = level
+            .iter()
+            .map(|result| result.p99_latency)
+            .max()
+            .unwrap_or_default();
+
+        Some((connections, requests_per_sec, p99_latency))
+    }
+
+    /// Checks if every round of a concurrency level completed
+    /// requests within the target.
+    fn meets_target(&self, connections: usize, results: &[SweepResult]) -> bool {
+        let mut level = results
+            .iter()
+            .filter(|result| result.connections == connections)
+            .peekable();
+
+        level.peek().is_some()
+            && level.all(|result| {
+                result.requests_per_sec > 0.0 && result.p99_latency <= self.p99_target
+            })
+    }
+}
+
+/// Displays the highest concurrency level which met the goal seek target.
+pub fn display_goal_seek(goal: &GoalSeek, results: &[SweepResult]) {
+    let modifier = 1000_f64;
+    let target = format!("{:.2}ms", goal.p99_target.as_secs_f64() * modifier);
+
+    match goal.best_level(results) {
+        Some((connections, requests_per_sec, p99_latency)) => println!(
+            "  Highest concurrency meeting a p99 of {}: {} connections @ {} req/sec (p99 {:.2}ms)",
+            target.bright_red(),
+            connections.to_string().bright_cyan(),
+            format!("{:.2}", requests_per_sec).bright_green(),
+            p99_latency.as_secs_f64() * modifier,
+        ),
+        None => println!(
+            "  No concurrency level met a p99 of {}",
+            target.bright_red()
+        ),
+    }
+}
+
+/// Displays the goal seek outcome as a json line.
+pub fn display_goal_seek_json(goal: &GoalSeek, results: &[SweepResult]) {
+    let modifier = 1000_f64;
+    let best = goal.best_level(results);
+
+    let out = json!({
+        "goal_seek": {
+            "p99_target_ms": goal.p99_target.as_secs_f64() * modifier,
+            "connections": best.map(|(connections, _, _)| connections),
+            "requests_per_sec": best.map(|(_, requests_per_sec, _)| requests_per_sec),
+            "latency_p99_ms": best.map(|(_, _, p99)| p99.as_secs_f64() * modifier),
+        }
+    });
+
+    println!("{}", out)
+}
+
+/// Displays the consolidated sweep results as a table.
+pub fn display_sweep_table(results: &[SweepResult]) {
+    println!(
+        "+ {:-^11} + {:-^7} + {:-^13} + {:-^13} + {:-^13} + {:-^9} +",
+        "", "", "", "", "", ""
+    );
+    println!(
+        "| {:^11} | {:^7} | {:^13} | {:^13} | {:^13} | {:^9} |",
+        "Connections".bright_cyan(),
+        "Round".bright_cyan(),
+        "Req/Sec".bright_green(),
+        "Avg Latency".bright_yellow(),
+        "P99 Latency".bright_red(),
+        "Errors".bright_magenta(),
+    );
+    println!(
+        "+ {:-^11} + {:-^7} + {:-^13} + {:-^13} + {:-^13} + {:-^9} +",
+        "", "", "", "", "", ""
+    );
+
+    let modifier = 1000_f64;
+    for result in results {
+        println!(
+            "| {:^11} | {:^7} | {:^13} | {:^13} | {:^13} | {:^9} |",
+            result.connections,
+            result.round + 1,
+            format!("{:.2}", result.requests_per_sec),
+            format!("{:.2}ms", result.avg_latency.as_secs_f64() * modifier),
+            format!("{:.2}ms", result.p99_latency.as_secs_f64() * modifier),
+            result.errors,
+        );
+    }
+
+    println!(
+        "+ {:-^11} + {:-^7} + {:-^13} + {:-^13} + {:-^13} + {:-^9} +",
+        "", "", "", "", "", ""
+    );
+}
+
+/// Writes the consolidated sweep results to a CSV file.
+///
+/// Latencies are written in milliseconds.
+pub fn write_csv(path: &Path, results: &[SweepResult]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(
+        writer,
+        "connections,round,requests_per_sec,latency_avg_ms,latency_p99_ms,errors"
+    )?;
+
+    let modifier = 1000_f64;
+    for result in results {
+        writeln!(
+            writer,
+            "{},{},{:.2},{:.3},{:.3},{}",
+            result.connections,
+            result.round + 1,
+            result.requests_per_sec,
+            result.avg_latency.as_secs_f64() * modifier,
+            result.p99_latency.as_secs_f64() * modifier,
+            result.errors,
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_result_p99_latency() {
+        let mut result = WorkerResult::default();
+        result.total_times = vec![Duration::from_secs(1)];
+        result.request_times = (1..=100).map(Duration::from_millis).collect();
+
+        let sweep = SweepResult::from_result(8, 0, &result);
+        assert_eq!(sweep.connections, 8);
+        assert_eq!(sweep.requests_per_sec, 100.0);
+        // The average of the slowest 1% is 100ms, the quantile is 99ms.
+        assert!(sweep.p99_latency >= Duration::from_millis(99));
+        assert!(sweep.p99_latency < Duration::from_millis(100));
+    }
+
+    fn result(connections: usize, round: usize, p99_ms: u64) -> SweepResult {
+        SweepResult {
+            connections,
+            round,
+            requests_per_sec: connections as f64 * 100.0,
+            avg_latency: Duration::from_millis(p99_ms / 2),
+            p99_latency: Duration::from_millis(p99_ms),
+            errors: 0,
+        }
+    }
+
+    fn goal() -> GoalSeek {
+        GoalSeek {
+            p99_target: Duration::from_millis(50),
+            max_connections: 64,
+        }
+    }
+
+    #[test]
+    fn test_goal_seek_next_level() {
+        let goal = goal();
+        let results = vec![result(8, 0, 20), result(8, 1, 30)];
+        assert_eq!(goal.next_level(8, &results), Some(16));
+
+        // Any round breaching the target stops the seek.
+        let results = vec![result(8, 0, 20), result(8, 1, 80)];
+        assert_eq!(goal.next_level(8, &results), None);
+
+        // The doubling is capped at the maximum.
+        let results = vec![result(48, 0, 20)];
+        assert_eq!(goal.next_level(48, &results), Some(64));
+        let results = vec![result(64, 0, 20)];
+        assert_eq!(goal.next_level(64, &results), None);
+
+        // A level without results never meets the target.
+        assert_eq!(goal.next_level(8, &[]), None);
+    }
+
+    #[test]
+    fn test_goal_seek_rejects_failed_rounds() {
+        let goal = goal();
+        let mut failed = result(8, 0, 0);
+        failed.requests_per_sec = 0.0;
+        assert_eq!(goal.next_level(8, &[failed]), None);
+    }
+
+    #[test]
+    fn test_goal_seek_best_level() {
+        let goal = goal();
+        let results = vec![
+            result(1, 0, 10),
+            result(2, 0, 20),
+            result(2, 1, 40),
+            result(4, 0, 60),
+        ];
+        let (connections, requests_per_sec, p99_latency) =
+            goal.best_level(&results).unwrap();
+        assert_eq!(connections, 2);
+        assert_eq!(requests_per_sec, 200.0);
+        assert_eq!(p99_latency, Duration::from_millis(40));
+
+        assert!(goal.best_level(&[result(4, 0, 60)]).is_none());
+    }
+
+    #[test]
+    fn test_from_result_empty() {
+        let mut result = WorkerResult::default();
+        result.port_exhaustion_errors = 2;
+
+        let sweep = SweepResult::from_result(1, 0, &result);
+        assert_eq!(sweep.requests_per_sec, 0.0);
+        assert_eq!(sweep.p99_latency, Duration::default());
+        assert_eq!(sweep.errors, 2);
+    }
+}
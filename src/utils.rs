@@ -1,3 +1,9 @@
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hdrhistogram::serialization::{Deserializer, Serializer, V2Serializer};
+use hdrhistogram::Histogram;
+
 const GIGABYTE: f64 = (1024 * 1024 * 1024) as f64;
 const MEGABYTE: f64 = (1024 * 1024) as f64;
 const KILOBYTE: f64 = 1024_f64;
@@ -11,13 +17,32 @@ pub fn div_mod(main: u64, divider: u64) -> (u64, u64) {
 }
 
 pub fn format_data(data_size: f64) -> String {
-    if data_size > GIGABYTE as f64 {
+    if data_size > GIGABYTE {
         format!("{:.2} GB", data_size / GIGABYTE)
-    } else if data_size > MEGABYTE as f64 {
+    } else if data_size > MEGABYTE {
         format!("{:.2} MB", data_size / MEGABYTE)
-    } else if data_size > KILOBYTE as f64 {
+    } else if data_size > KILOBYTE {
         format!("{:.2} KB", data_size / KILOBYTE)
     } else {
         format!("{:.2} B", data_size)
     }
 }
+
+/// Encodes a histogram as base64 so it can be embedded in the json results.
+pub fn encode_histogram(histogram: &Histogram<u64>) -> String {
+    let mut buf = Vec::new();
+    V2Serializer::new()
+        .serialize(histogram, &mut buf)
+        .expect("Serialize histogram");
+    STANDARD.encode(buf)
+}
+
+/// Decodes a histogram encoded by [encode_histogram].
+pub fn decode_histogram(encoded: &str) -> Result<Histogram<u64>> {
+    let buf = STANDARD
+        .decode(encoded)
+        .map_err(|e| anyhow!("invalid histogram encoding: {}", e))?;
+    Deserializer::new()
+        .deserialize(&mut buf.as_slice())
+        .map_err(|e| anyhow!("invalid histogram: {:?}", e))
+}